@@ -71,6 +71,37 @@ pub mod amd64;
 pub mod arm64;
 pub mod x86;
 
+impl Arch {
+    /// Decodes the "local base pointer" or "param base pointer" fields of the flags in
+    /// `S_FRAMEPROC` (see [`crate::syms::FrameProc`]) into the register that is used as the frame
+    /// pointer for this architecture.
+    ///
+    /// | Encoded | AMD64 | X86      | ARM64 |
+    /// |---------|-------|----------|-------|
+    /// | 0       | none  | none     | none  |
+    /// | 1       | `RSP` | `VFRAME` | `SP`  |
+    /// | 2       | `RBP` | `EBP`    | `FP`  |
+    /// | 3       | `R13` | `EBX`    | `X19` |
+    ///
+    /// On X86, `VFRAME` is a pseudo-register whose value must be computed from FPO data.
+    pub fn frame_pointer_reg(self, encoded: u32) -> Option<ArchReg> {
+        let reg = match (self, encoded) {
+            (_, 0) => return None,
+            (Arch::AMD64, 1) => amd64::Amd64Reg::RSP.0,
+            (Arch::AMD64, 2) => amd64::Amd64Reg::RBP.0,
+            (Arch::AMD64, 3) => amd64::Amd64Reg::R13.0,
+            (Arch::X86, 1) => x86::X86Reg::VFRAME.0,
+            (Arch::X86, 2) => x86::X86Reg::EBP.0,
+            (Arch::X86, 3) => x86::X86Reg::EBX.0,
+            (Arch::ARM64, 1) => arm64::Arm64Reg::SP.0,
+            (Arch::ARM64, 2) => arm64::Arm64Reg::FP.0,
+            (Arch::ARM64, 3) => arm64::Arm64Reg::X19.0,
+            _ => return None,
+        };
+        Some(ArchReg::new(self, reg))
+    }
+}
+
 /// Identifies a register in a specific architecture
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ArchReg {
@@ -355,4 +355,8 @@ register_set! {
     BND1       =    397,
     BND2       =    398,
     BND3       =    399,
+
+    // CV_ALLREG_VFRAME: the "virtual frame" pseudo-register. Its value is computed from FPO
+    // data (FRAMEDATA).
+    VFRAME     =  30006,
 }
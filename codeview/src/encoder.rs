@@ -1,6 +1,8 @@
 //! Support for encoding primitives and blittable types into output buffers.
 #![allow(missing_docs)]
 
+use crate::types::Leaf;
+use crate::types::number::Number;
 use bstr::BStr;
 use uuid::Uuid;
 use zerocopy::{Immutable, IntoBytes};
@@ -51,4 +53,48 @@ impl<'a> Encoder<'a> {
     pub fn uuid(&mut self, u: &Uuid) {
         self.bytes(&u.to_bytes_le())
     }
+
+    /// Encodes a signed integer as a numeric leaf, using the smallest representation.
+    ///
+    /// See "Numeric Leaves" in the PDB specification.
+    pub fn number_i64(&mut self, x: i64) {
+        if (0..0x8000).contains(&x) {
+            self.u16(x as u16);
+        } else if x >= 0 {
+            self.number_u64(x as u64);
+        } else if x >= i8::MIN as i64 {
+            self.u16(Leaf::LF_CHAR.0);
+            self.u8(x as u8);
+        } else if x >= i16::MIN as i64 {
+            self.u16(Leaf::LF_SHORT.0);
+            self.u16(x as u16);
+        } else if x >= i32::MIN as i64 {
+            self.u16(Leaf::LF_LONG.0);
+            self.u32(x as u32);
+        } else {
+            self.u16(Leaf::LF_QUADWORD.0);
+            self.bytes(&x.to_le_bytes());
+        }
+    }
+
+    /// Encodes an unsigned integer as a numeric leaf, using the smallest representation.
+    pub fn number_u64(&mut self, x: u64) {
+        if x < 0x8000 {
+            self.u16(x as u16);
+        } else if x <= u16::MAX as u64 {
+            self.u16(Leaf::LF_USHORT.0);
+            self.u16(x as u16);
+        } else if x <= u32::MAX as u64 {
+            self.u16(Leaf::LF_ULONG.0);
+            self.u32(x as u32);
+        } else {
+            self.u16(Leaf::LF_UQUADWORD.0);
+            self.bytes(&x.to_le_bytes());
+        }
+    }
+
+    /// Copies an existing numeric leaf.
+    pub fn number(&mut self, n: &Number) {
+        self.bytes(n.as_bytes());
+    }
 }
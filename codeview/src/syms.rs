@@ -5,14 +5,26 @@
 //! * [`cvinfo.h`](https://github.com/microsoft/microsoft-pdb/blob/805655a28bd8198004be2ac27e6e0290121a5e89/include/cvinfo.h)
 //! * [CodeView Symbols](https://llvm.org/docs/PDB/CodeViewSymbols.html)
 
+mod annotations;
 pub mod builder;
+mod compile;
+mod editor;
 mod iter;
 mod kind;
+mod location;
 mod offset_segment;
+mod tree;
+
+#[cfg(test)]
+mod tests;
 
 #[doc(inline)]
-pub use self::{iter::*, kind::SymKind, offset_segment::*};
+pub use self::{
+    annotations::*, compile::*, editor::*, iter::*, kind::SymKind, location::*, offset_segment::*,
+    tree::*,
+};
 
+use crate::arch::{Arch, ArchReg};
 use crate::parser::{Number, Parse, Parser, ParserError, ParserMut};
 use crate::types::{ItemId, ItemIdLe, TypeIndex, TypeIndexLe};
 use bitflags::bitflags;
@@ -404,7 +416,7 @@ impl<'a> Parse<'a> for ManagedConstant<'a> {
     }
 }
 
-/// Several symbols use this structure: `S_PROCREF`, `S_LPROCREF`, `S_DATAREF`. These symbols
+/// Several symbols use this structure: `S_PROCREF`, `S_LPROCREF`, `S_DATAREF`, `S_TOKENREF`. These symbols
 /// are present in the Global Symbol Stream, not in module symbol streams.
 ///
 /// These `S_*REF` symbols tell you where to find a specific global symbol, but they do not directly
@@ -594,17 +606,96 @@ impl<'a> Parse<'a> for Compile3<'a> {
 #[allow(missing_docs)]
 pub struct FrameProc {
     /// Count of bytes in the whole stack frame.
-    frame_size: U32<LE>,
+    pub frame_size: U32<LE>,
     /// Count of bytes in the frame allocated as padding.
-    pad_size: U32<LE>,
+    pub pad_size: U32<LE>,
     /// Offset of pad bytes from the base of the frame.
-    pad_offset: U32<LE>,
+    pub pad_offset: U32<LE>,
     /// Count of bytes in frame allocated for saved callee-save registers.
-    save_regs_size: U32<LE>,
-    offset_exception_handler: U32<LE>,
-    exception_handler_section: U16<LE>,
-    padding: U16<LE>,
-    flags: U32<LE>,
+    pub save_regs_size: U32<LE>,
+    pub offset_exception_handler: U32<LE>,
+    pub exception_handler_section: U16<LE>,
+    pub padding: U16<LE>,
+    pub flags: U32<LE>,
+}
+
+bitflags! {
+    /// Flags for `S_FRAMEPROC`. See `CV_FRAMEPROC` in `cvinfo.h`.
+    ///
+    /// Bits 14-15 and 16-17 are not flags; they are the encoded local and param base pointer
+    /// registers. Use [`FrameProc::local_base_pointer`] and [`FrameProc::param_base_pointer`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FrameProcFlags: u32 {
+        /// function uses `_alloca()`
+        const HAS_ALLOCA = 1 << 0;
+        /// function uses `setjmp()`
+        const HAS_SET_JMP = 1 << 1;
+        /// function uses `longjmp()`
+        const HAS_LONG_JMP = 1 << 2;
+        /// function uses inline asm
+        const HAS_INL_ASM = 1 << 3;
+        /// function has EH states
+        const HAS_EH = 1 << 4;
+        /// function was specified as inline
+        const INL_SPEC = 1 << 5;
+        /// function has SEH
+        const HAS_SEH = 1 << 6;
+        /// function is `__declspec(naked)`
+        const NAKED = 1 << 7;
+        /// function has buffer security check introduced by `/GS`
+        const SECURITY_CHECKS = 1 << 8;
+        /// function compiled with `/EHa`
+        const ASYNC_EH = 1 << 9;
+        /// function has `/GS` buffer checks, but stack ordering couldn't be done
+        const GS_NO_STACK_ORDERING = 1 << 10;
+        /// function was inlined within another function
+        const WAS_INLINED = 1 << 11;
+        /// function is `__declspec(strict_gs_check)`
+        const GS_CHECK = 1 << 12;
+        /// function is `__declspec(safebuffers)`
+        const SAFE_BUFFERS = 1 << 13;
+        /// function was compiled with PGO/PGU
+        const POGO_ON = 1 << 18;
+        /// Do we have valid Pogo counts?
+        const VALID_COUNTS = 1 << 19;
+        /// Did we optimize for speed?
+        const OPT_SPEED = 1 << 20;
+        /// function contains CFG checks (and no write checks)
+        const GUARD_CF = 1 << 21;
+        /// function contains CFW checks and/or instrumentation
+        const GUARD_CFW = 1 << 22;
+    }
+}
+
+impl FrameProc {
+    /// The flags, excluding the encoded base pointer registers.
+    pub fn flags(&self) -> FrameProcFlags {
+        FrameProcFlags::from_bits_truncate(self.flags.get())
+    }
+
+    /// The encoded register that is used as the base pointer for local variables (bits 14-15).
+    /// Use [`Arch::frame_pointer_reg`] to decode this.
+    pub fn local_base_pointer(&self) -> u32 {
+        (self.flags.get() >> 14) & 3
+    }
+
+    /// The encoded register that is used as the base pointer for parameters (bits 16-17).
+    /// Use [`Arch::frame_pointer_reg`] to decode this.
+    pub fn param_base_pointer(&self) -> u32 {
+        (self.flags.get() >> 16) & 3
+    }
+
+    /// The register that `S_DEFRANGE_FRAMEPOINTER_REL` and
+    /// `S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE` records are relative to, for a local variable
+    /// within this procedure. Parameters (`S_LOCAL` records whose flags contain `fIsParam`) use
+    /// the param base pointer; all other locals use the local base pointer.
+    pub fn frame_register(&self, arch: Arch, is_param: bool) -> Option<ArchReg> {
+        arch.frame_pointer_reg(if is_param {
+            self.param_base_pointer()
+        } else {
+            self.local_base_pointer()
+        })
+    }
 }
 
 #[repr(C)]
@@ -804,6 +895,8 @@ pub struct DefRangeRegisterFixed {
     pub reg: U16<LE>,
     // Attribute of the register range.
     pub attr: RangeAttrLe,
+    /// Range of addresses where this program is valid
+    pub range: LVarAddrRange,
 }
 
 /// `S_DEFRANGE_REGISTER`
@@ -1515,110 +1608,1218 @@ impl<'a> Parse<'a> for Section<'a> {
     }
 }
 
-/// Parsed data from a symbol record
+/// `S_COMPILE2`
+///
+/// See `COMPILESYM` in `cvinfo.h`.
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[repr(C)]
+#[allow(missing_docs)]
+pub struct Compile2Fixed {
+    pub flags: U32<LE>,
+    pub machine: U16<LE>,
+    pub frontend_major: U16<LE>,
+    pub frontend_minor: U16<LE>,
+    pub frontend_build: U16<LE>,
+    pub ver_major: U16<LE>,
+    pub ver_minor: U16<LE>,
+    pub ver_build: U16<LE>,
+    // name: strz
+    // extra strings: sequence of strz, terminated by an empty string
+}
+
+/// `S_COMPILE2`
+#[allow(missing_docs)]
+#[derive(Clone, Debug)]
+pub struct Compile2<'a> {
+    pub fixed: &'a Compile2Fixed,
+    /// The compiler version string.
+    pub name: &'a BStr,
+    /// An optional block of NUL-terminated strings, terminated by an empty string.
+    pub extra_strings: &'a [u8],
+}
+
+impl<'a> Parse<'a> for Compile2<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+            extra_strings: p.take_rest(),
+        })
+    }
+}
+
+impl<'a> Compile2<'a> {
+    /// Iterates the extra strings that follow the version string.
+    pub fn iter_extra_strings(&self) -> IterStrings<'a> {
+        IterStrings::new(self.extra_strings)
+    }
+}
+
+/// Iterates a sequence of NUL-terminated strings. The sequence ends at an empty string or at the
+/// end of the data.
+#[derive(Clone)]
+pub struct IterStrings<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> IterStrings<'a> {
+    /// Starts iterating strings in `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl<'a> Iterator for IterStrings<'a> {
+    type Item = &'a BStr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut p = Parser::new(self.bytes);
+        let s = p.strz().ok()?;
+        if s.is_empty() {
+            self.bytes = &[];
+            return None;
+        }
+        self.bytes = p.into_rest();
+        Some(s)
+    }
+}
+
+/// `S_ENVBLOCK`: A block of environment strings, stored as name/value pairs.
+///
+/// See `ENVBLOCKSYM` in `cvinfo.h`.
 #[derive(Clone, Debug)]
+pub struct EnvBlock<'a> {
+    /// Bit 0 is `rev`. The remaining bits are reserved.
+    pub flags: u8,
+    /// A sequence of NUL-terminated strings. Use [`Self::iter_strings`] to read them.
+    pub strings: &'a [u8],
+}
+
+impl<'a> Parse<'a> for EnvBlock<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            flags: p.u8()?,
+            strings: p.take_rest(),
+        })
+    }
+}
+
+impl<'a> EnvBlock<'a> {
+    /// Iterates the strings in the block. Strings alternate between names and values,
+    /// e.g. `cwd`, `d:\src`, `cl`, `cl.exe`.
+    pub fn iter_strings(&self) -> IterStrings<'a> {
+        IterStrings::new(self.strings)
+    }
+}
+
+/// `S_REGISTER`
+///
+/// See `REGSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
 #[allow(missing_docs)]
-pub enum SymData<'a> {
-    Unknown,
-    ObjName(ObjectName<'a>),
-    Compile3(Compile3<'a>),
-    Proc(Proc<'a>),
-    Thunk(Thunk<'a>),
-    Udt(Udt<'a>),
-    Constant(Constant<'a>),
-    ManagedConstant(ManagedConstant<'a>),
-    RefSym2(RefSym2<'a>),
-    Data(Data<'a>),
-    ThreadData(ThreadStorageData<'a>),
-    Pub(Pub<'a>),
-    End,
-    FrameProc(&'a FrameProc),
-    RegRel(RegRel<'a>),
-    Block(Block<'a>),
-    Local(Local<'a>),
-    DefRange(DefRange<'a>),
-    DefRangeFramePointerRel(DefRangeSymFramePointerRel<'a>),
-    DefRangeRegister(DefRangeRegister<'a>),
-    DefRangeRegisterRel(DefRangeRegisterRel<'a>),
-    DefRangeFramePointerRelFullScope(&'a DefRangeFramePointerRelFullScope),
-    DefRangeSubFieldRegister(DefRangeSubFieldRegister<'a>),
-    Trampoline(Trampoline<'a>),
-    BuildInfo(BuildInfo),
-    UsingNamespace(UsingNamespace<'a>),
-    InlineSiteEnd,
-    Label(Label<'a>),
-    FunctionList(FunctionList<'a>),
-    InlineSite(InlineSite<'a>),
-    InlineSite2(InlineSite2<'a>),
-    FrameCookie(&'a FrameCookie),
-    CallSiteInfo(&'a CallSiteInfo),
-    HeapAllocSite(&'a HeapAllocSite),
-    ManagedProc(ManagedProc<'a>),
-    Annotation(Annotation<'a>),
-    HotPatchFunc(HotPatchFunc<'a>),
-    CoffGroup(CoffGroup<'a>),
-    ArmSwitchTable(&'a ArmSwitchTable),
-    Section(Section<'a>),
+pub struct RegisterFixed {
+    pub ty: TypeIndexLe,
+    pub register: U16<LE>,
+    // name: strz
 }
 
-impl<'a> SymData<'a> {
-    /// Parses a symbol record. The caller has already parsed the length and kind of the record.
-    /// The `data` parameter does not include the length or kind.
-    pub fn parse(kind: SymKind, data: &'a [u8]) -> Result<Self, ParserError> {
-        let mut p = Parser::new(data);
-        Self::from_parser(kind, &mut p)
+/// `S_REGISTER`: A variable that is stored in a register.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct Register<'a> {
+    pub fixed: &'a RegisterFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for Register<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
     }
+}
 
-    /// Parses a symbol record. The caller has already parsed the length and kind of the record.
-    /// The `p` parameter does not include the length or kind.
+/// `S_BPREL32`
+///
+/// See `BPRELSYM32` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct BpRelFixed {
+    /// Offset relative to the frame pointer (`EBP` on x86).
+    pub offset: I32<LE>,
+    pub ty: TypeIndexLe,
+    // name: strz
+}
+
+/// `S_BPREL32`: A variable that is stored relative to the frame pointer.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct BpRel<'a> {
+    pub fixed: &'a BpRelFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for BpRel<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_SEPCODE`: A separated code block, such as a block of code that was moved out of its parent
+/// procedure by the optimizer.
+///
+/// See `SEPCODESYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct SepCode {
+    pub block: BlockHeader,
+    /// Length in bytes of the separated code.
+    pub length: U32<LE>,
+    /// See `CV_SEPCODEFLAGS`. Bit 0 is `fIsLexicalScope`, bit 1 is `fReturnsToParent`.
+    pub flags: U32<LE>,
+    pub offset: U32<LE>,
+    pub parent_offset: U32<LE>,
+    pub section: U16<LE>,
+    pub parent_section: U16<LE>,
+}
+
+impl SepCode {
+    /// The location of the separated code.
+    pub fn offset_segment(&self) -> OffsetSegment {
+        OffsetSegment {
+            offset: self.offset,
+            segment: self.section,
+        }
+    }
+
+    /// The location of the parent procedure.
+    pub fn parent_offset_segment(&self) -> OffsetSegment {
+        OffsetSegment {
+            offset: self.parent_offset,
+            segment: self.parent_section,
+        }
+    }
+}
+
+/// `S_EXPORT`
+///
+/// See `EXPORTSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct ExportFixed {
+    pub ordinal: U16<LE>,
+    pub flags: U16<LE>,
+    // name: strz
+}
+
+bitflags! {
+    /// Flags for `S_EXPORT`.
     ///
-    /// This function allows the caller to observe how many bytes were actually consumed from
-    /// the input stream.
-    pub fn from_parser(kind: SymKind, p: &mut Parser<'a>) -> Result<Self, ParserError> {
-        Ok(match kind {
-            SymKind::S_OBJNAME => Self::ObjName(p.parse()?),
-            SymKind::S_GPROC32 | SymKind::S_LPROC32 => Self::Proc(p.parse()?),
-            SymKind::S_THUNK32 => Self::Thunk(p.parse()?),
-            SymKind::S_COMPILE3 => Self::Compile3(p.parse()?),
-            SymKind::S_UDT => Self::Udt(p.parse()?),
-            SymKind::S_CONSTANT => Self::Constant(p.parse()?),
-            SymKind::S_MANCONSTANT => Self::Constant(p.parse()?),
-            SymKind::S_PUB32 => Self::Pub(p.parse()?),
-            SymKind::S_PUB32_ST => Self::Pub(Pub::parse_st(p)?),
+    /// See `EXPORTSYM` in `cvinfo.h`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ExportFlags: u16 {
+        /// The export is a constant.
+        const CONSTANT = 1 << 0;
+        /// The export is data.
+        const DATA = 1 << 1;
+        /// The export is private.
+        const PRIVATE = 1 << 2;
+        /// The export has no name.
+        const NONAME = 1 << 3;
+        /// The ordinal was explicitly assigned.
+        const ORDINAL = 1 << 4;
+        /// The export is a forwarder.
+        const FORWARDER = 1 << 5;
+    }
+}
 
-            SymKind::S_PROCREF
-            | SymKind::S_LPROCREF
-            | SymKind::S_DATAREF
-            | SymKind::S_ANNOTATIONREF => Self::RefSym2(p.parse()?),
+/// `S_EXPORT`: An exported function or data, found in the `* Linker *` module.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct Export<'a> {
+    pub fixed: &'a ExportFixed,
+    pub name: &'a BStr,
+}
 
-            SymKind::S_LDATA32 | SymKind::S_GDATA32 | SymKind::S_LMANDATA | SymKind::S_GMANDATA => {
-                Self::Data(p.parse()?)
-            }
+impl<'a> Export<'a> {
+    /// View the `flags` field as bit flags.
+    pub fn flags(&self) -> ExportFlags {
+        ExportFlags::from_bits_retain(self.fixed.flags.get())
+    }
+}
 
-            SymKind::S_LTHREAD32 | SymKind::S_GTHREAD32 => Self::ThreadData(p.parse()?),
-            SymKind::S_END => Self::End,
-            SymKind::S_FRAMEPROC => Self::FrameProc(p.get()?),
-            SymKind::S_REGREL32 => Self::RegRel(p.parse()?),
-            SymKind::S_BLOCK32 => Self::Block(p.parse()?),
-            SymKind::S_LOCAL => Self::Local(p.parse()?),
-            SymKind::S_DEFRANGE => Self::DefRange(p.parse()?),
-            SymKind::S_DEFRANGE_FRAMEPOINTER_REL => Self::DefRangeFramePointerRel(p.parse()?),
-            SymKind::S_DEFRANGE_REGISTER => Self::DefRangeRegister(p.parse()?),
-            SymKind::S_DEFRANGE_REGISTER_REL => Self::DefRangeRegisterRel(p.parse()?),
-            SymKind::S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE => {
-                Self::DefRangeFramePointerRelFullScope(p.get()?)
-            }
-            SymKind::S_DEFRANGE_SUBFIELD_REGISTER => Self::DefRangeSubFieldRegister(p.parse()?),
-            SymKind::S_TRAMPOLINE => Self::Trampoline(p.parse()?),
-            SymKind::S_BUILDINFO => Self::BuildInfo(p.parse()?),
-            SymKind::S_UNAMESPACE => Self::UsingNamespace(p.parse()?),
-            SymKind::S_INLINESITE_END => Self::InlineSiteEnd,
-            SymKind::S_LABEL32 => Self::Label(p.parse()?),
-            SymKind::S_CALLEES | SymKind::S_CALLERS => Self::FunctionList(p.parse()?),
-            SymKind::S_INLINESITE => Self::InlineSite(p.parse()?),
-            SymKind::S_INLINESITE2 => Self::InlineSite2(p.parse()?),
-            SymKind::S_INLINEES => Self::FunctionList(p.parse()?),
-            SymKind::S_FRAMECOOKIE => Self::FrameCookie(p.get()?),
+impl<'a> Parse<'a> for Export<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_FILESTATIC`
+///
+/// See `FILESTATICSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct FileStaticFixed {
+    pub ty: TypeIndexLe,
+    /// Index of the module filename in the string table.
+    pub module_filename: U32<LE>,
+    /// Local variable flags. See `CV_LVARFLAGS`.
+    pub flags: U16<LE>,
+    // name: strz
+}
+
+/// `S_FILESTATIC`: A static variable whose scope is a file, used with `S_DEFRANGE*` records.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct FileStatic<'a> {
+    pub fixed: &'a FileStaticFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for FileStatic<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_LOCALSLOT` and `S_PARAMSLOT`
+///
+/// See `SLOTSYM32` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct SlotFixed {
+    /// Slot index
+    pub slot: U32<LE>,
+    pub ty: TypeIndexLe,
+    // name: strz
+}
+
+/// `S_LOCALSLOT` and `S_PARAMSLOT`: A managed local variable or parameter stored in a slot.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct Slot<'a> {
+    pub fixed: &'a SlotFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for Slot<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANYREG`: A variable that is stored in more than one register. Each register is identified
+/// by an 8-bit register number.
+///
+/// See `MANYREGSYM` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct ManyReg<'a> {
+    pub ty: TypeIndex,
+    /// Registers, listed from the most significant to the least significant.
+    pub regs: &'a [u8],
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for ManyReg<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let ty = p.type_index()?;
+        let count = p.u8()?;
+        Ok(Self {
+            ty,
+            regs: p.bytes(count as usize)?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANYREG2`: A variable that is stored in more than one register. Each register is
+/// identified by a 16-bit register number.
+///
+/// See `MANYREGSYM2` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct ManyReg2<'a> {
+    pub ty: TypeIndex,
+    /// Registers, listed from the most significant to the least significant.
+    pub regs: &'a [U16<LE>],
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for ManyReg2<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let ty = p.type_index()?;
+        let count = p.u16()?;
+        Ok(Self {
+            ty,
+            regs: p.slice(count as usize)?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_DEFRANGE_SUBFIELD`
+///
+/// See `DEFRANGESYMSUBFIELD` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct DefRangeSubFieldFixed {
+    /// DIA program to evaluate the value of the symbol
+    pub program: U32<LE>,
+    /// Offset in the parent variable.
+    pub offset_parent: U32<LE>,
+    /// Range of addresses where this program is valid
+    pub range: LVarAddrRange,
+}
+
+/// `S_DEFRANGE_SUBFIELD`: A live range of a sub-field of a variable.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct DefRangeSubField<'a> {
+    pub fixed: &'a DefRangeSubFieldFixed,
+    pub gaps: &'a [LVarAddrGap],
+}
+
+impl<'a> Parse<'a> for DefRangeSubField<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let fixed = p.get()?;
+        let gaps = p.slice(p.len() / size_of::<LVarAddrGap>())?;
+        Ok(Self { fixed, gaps })
+    }
+}
+
+/// `S_POGODATA`: Profile-guided optimization data for the enclosing procedure.
+///
+/// See `POGOINFO` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct PogoData {
+    /// Number of times the function was called.
+    pub invocations: U32<LE>,
+    /// Dynamic instruction count
+    pub dynamic_count: zerocopy::I64<LE>,
+    /// Static instruction count
+    pub num_instrs: U32<LE>,
+    /// Final static instruction count (after inlining)
+    pub num_live_instrs: U32<LE>,
+}
+
+/// `S_MOD_TYPEREF`: Describes how the types of a module are stored.
+///
+/// See `MODTYPEREF` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct ModTypeRef {
+    /// See [`ModTypeRefFlags`].
+    pub flags: U32<LE>,
+    /// The meaning of this field depends on `flags`. It is either the stream number of the
+    /// type map (`fOwnTMR`, `fOwnTM`) or the module index of the module that owns the type map
+    /// (`fRefTMPCT`, `fRefTM`).
+    pub word0: U16<LE>,
+    /// The stream number of the type map for PCT (`fOwnTMPCT`).
+    pub word1: U16<LE>,
+}
+
+impl ModTypeRef {
+    /// View the `flags` field as bit flags.
+    pub fn flags(&self) -> ModTypeRefFlags {
+        ModTypeRefFlags::from_bits_retain(self.flags.get())
+    }
+}
+
+bitflags! {
+    /// Flags for `S_MOD_TYPEREF`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ModTypeRefFlags: u32 {
+        /// This module has no types.
+        const NONE = 1 << 0;
+        /// This module references a type map of PCT (precompiled types) owned by another module.
+        const REF_TMPCT = 1 << 1;
+        /// This module owns a type map of PCT.
+        const OWN_TMPCT = 1 << 2;
+        /// This module owns a type map referenced by other modules.
+        const OWN_TMR = 1 << 3;
+        /// This module owns a type map.
+        const OWN_TM = 1 << 4;
+        /// This module references a type map owned by another module.
+        const REF_TM = 1 << 5;
+    }
+}
+
+/// `S_REF_MINIPDB`
+///
+/// See `REFMINIPDB` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct RefMiniPdbFixed {
+    /// If `flags` contains `UDT`, this is a `TypeIndex`. Otherwise, it is the COFF section index.
+    pub coff_section_or_type: U32<LE>,
+    /// The module index of the module that contains the symbol.
+    pub module: U16<LE>,
+    /// See [`RefMiniPdbFlags`].
+    pub flags: U16<LE>,
+    // name: strz
+}
+
+bitflags! {
+    /// Flags for `S_REF_MINIPDB`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RefMiniPdbFlags: u16 {
+        /// Reference to a local (static) symbol.
+        const LOCAL = 1 << 0;
+        /// Reference to data.
+        const DATA = 1 << 1;
+        /// Reference to a UDT.
+        const UDT = 1 << 2;
+        /// Reference to a label.
+        const LABEL = 1 << 3;
+        /// Reference to a constant.
+        const CONST = 1 << 4;
+    }
+}
+
+/// `S_REF_MINIPDB`: A reference to a symbol in a "mini PDB" (`/DEBUG:FASTLINK`).
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct RefMiniPdb<'a> {
+    pub fixed: &'a RefMiniPdbFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> RefMiniPdb<'a> {
+    /// View the `flags` field as bit flags.
+    pub fn flags(&self) -> RefMiniPdbFlags {
+        RefMiniPdbFlags::from_bits_retain(self.fixed.flags.get())
+    }
+}
+
+impl<'a> Parse<'a> for RefMiniPdb<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_PDBMAP`: Maps the name of one PDB to another, for `/DEBUG:FASTLINK`.
+///
+/// See `PDBMAP` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+pub struct PdbMap<'a> {
+    /// The name of the source PDB
+    pub from: &'a BStr,
+    /// The name of the destination PDB
+    pub to: &'a BStr,
+}
+
+impl<'a> Parse<'a> for PdbMap<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            from: p.strz()?,
+            to: p.strz()?,
+        })
+    }
+}
+
+/// `S_FASTLINK`
+///
+/// See `FASTLINKSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct FastLinkFixed {
+    pub ty: TypeIndexLe,
+    /// Bit 0 is `fIsData`, bit 1 is `fIsUDT`, bit 2 is `fIsConst`, bit 3 is `fIsLabel`.
+    pub flags: U16<LE>,
+    // name: strz
+}
+
+/// `S_FASTLINK`: A symbol that is defined in a "mini PDB" (`/DEBUG:FASTLINK`).
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct FastLink<'a> {
+    pub fixed: &'a FastLinkFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for FastLink<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_DISCARDED`
+///
+/// See `DISCARDEDSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct DiscardedFixed {
+    /// Bits 0-7 contain the discard reason. See [`DISCARDED_UNKNOWN`] and friends.
+    pub flags: U32<LE>,
+    /// The file that contains the discarded symbol, as an offset into the file checksums
+    /// subsection.
+    pub file_id: U32<LE>,
+    /// The line number of the discarded symbol.
+    pub line: U32<LE>,
+    // data: [u8]
+}
+
+/// Discard reason for `S_DISCARDED`: unknown.
+pub const DISCARDED_UNKNOWN: u8 = 0;
+/// Discard reason for `S_DISCARDED`: the COMDAT was not selected.
+pub const DISCARDED_NOT_SELECTED: u8 = 1;
+/// Discard reason for `S_DISCARDED`: the symbol was not referenced.
+pub const DISCARDED_NOT_REFERENCED: u8 = 2;
+
+/// `S_DISCARDED`: A symbol that was discarded by the linker. The original symbol record is
+/// stored in `data`.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct Discarded<'a> {
+    pub fixed: &'a DiscardedFixed,
+    /// The original symbol record, including its length and kind.
+    pub data: &'a [u8],
+}
+
+impl<'a> Discarded<'a> {
+    /// The reason the symbol was discarded.
+    pub fn discarded_type(&self) -> u8 {
+        self.fixed.flags.get() as u8
+    }
+}
+
+impl<'a> Parse<'a> for Discarded<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            data: p.take_rest(),
+        })
+    }
+}
+
+/// `S_VFTABLE32`: The location of a virtual function table.
+///
+/// See `VPATHSYM32` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct VFTable32 {
+    /// The type index of the root of the path
+    pub root: TypeIndexLe,
+    /// The type index of the path record
+    pub path: TypeIndexLe,
+    pub offset_segment: OffsetSegment,
+}
+
+/// `S_GDATA_HLSL`, `S_LDATA_HLSL`
+///
+/// See `DATASYMHLSL` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct DataHlslFixed {
+    pub ty: TypeIndexLe,
+    /// See `CV_HLSLREG_e`.
+    pub reg_type: U16<LE>,
+    pub data_slot: U16<LE>,
+    pub data_offset: U16<LE>,
+    pub texture_slot: U16<LE>,
+    pub sampler_slot: U16<LE>,
+    pub uav_slot: U16<LE>,
+    // name: strz
+}
+
+/// `S_GDATA_HLSL`, `S_LDATA_HLSL`
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct DataHlsl<'a> {
+    pub fixed: &'a DataHlslFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for DataHlsl<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_GDATA_HLSL32`, `S_LDATA_HLSL32`
+///
+/// See `DATASYMHLSL32` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct DataHlsl32Fixed {
+    pub ty: TypeIndexLe,
+    pub data_slot: U32<LE>,
+    pub data_offset: U32<LE>,
+    pub texture_slot: U32<LE>,
+    pub sampler_slot: U32<LE>,
+    pub uav_slot: U32<LE>,
+    /// See `CV_HLSLREG_e`.
+    pub reg_type: U16<LE>,
+    // name: strz
+}
+
+/// `S_GDATA_HLSL32`, `S_LDATA_HLSL32`
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct DataHlsl32<'a> {
+    pub fixed: &'a DataHlsl32Fixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for DataHlsl32<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_GDATA_HLSL32_EX`, `S_LDATA_HLSL32_EX`
+///
+/// See `DATASYMHLSL32_EX` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct DataHlsl32ExFixed {
+    pub ty: TypeIndexLe,
+    pub reg_id: U32<LE>,
+    pub data_offset: U32<LE>,
+    pub bind_space: U32<LE>,
+    pub bind_slot: U32<LE>,
+    /// See `CV_HLSLREG_e`.
+    pub reg_type: U16<LE>,
+    // name: strz
+}
+
+/// `S_GDATA_HLSL32_EX`, `S_LDATA_HLSL32_EX`
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct DataHlsl32Ex<'a> {
+    pub fixed: &'a DataHlsl32ExFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for DataHlsl32Ex<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_DEFRANGE_HLSL`
+///
+/// See `DEFRANGESYMHLSL` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct DefRangeHlslFixed {
+    /// See `CV_HLSLREG_e`.
+    pub reg_type: U16<LE>,
+    /// ```text
+    /// unsigned short  regIndices       : 2;   // 0, 1 or 2, dimensionality of register space
+    /// unsigned short  spilledUdtMember : 1;   // this is a spilled member
+    /// unsigned short  memorySpace      : 4;   // memory space
+    /// unsigned short  padding          : 9;   // for future use
+    /// ```
+    pub flags: U16<LE>,
+    /// Offset in parent variable
+    pub offset_parent: U16<LE>,
+    /// Size of enregistered portion
+    pub size_in_parent: U16<LE>,
+    /// Range of addresses where this program is valid
+    pub range: LVarAddrRange,
+}
+
+/// `S_DEFRANGE_HLSL`: A live range of a variable in an HLSL program.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct DefRangeHlsl<'a> {
+    pub fixed: &'a DefRangeHlslFixed,
+    /// The gaps (`CV_LVAR_ADDR_GAP`), followed by the register indices (one `u32` for each
+    /// dimension of `regIndices`).
+    pub rest: &'a [u8],
+}
+
+impl<'a> DefRangeHlsl<'a> {
+    /// The number of register indices, which is the dimensionality of the register space.
+    pub fn num_reg_indices(&self) -> usize {
+        (self.fixed.flags.get() & 3) as usize
+    }
+
+    /// The gaps in the live range.
+    pub fn gaps(&self) -> &'a [LVarAddrGap] {
+        let gaps_len = self.rest.len().saturating_sub(self.num_reg_indices() * 4);
+        <[LVarAddrGap]>::ref_from_bytes(&self.rest[..gaps_len - gaps_len % 4]).unwrap_or(&[])
+    }
+
+    /// The register indices.
+    pub fn reg_indices(&self) -> &'a [U32<LE>] {
+        let n = self.num_reg_indices() * 4;
+        if self.rest.len() < n {
+            return &[];
+        }
+        <[U32<LE>]>::ref_from_bytes(&self.rest[self.rest.len() - n..]).unwrap_or(&[])
+    }
+}
+
+impl<'a> Parse<'a> for DefRangeHlsl<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            rest: p.take_rest(),
+        })
+    }
+}
+
+/// A CLR metadata token. The high 8 bits identify a metadata table and the low 24 bits are a
+/// row index (RID) within that table.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MetadataToken(pub u32);
+
+#[allow(missing_docs)]
+impl MetadataToken {
+    pub const TABLE_TYPE_REF: u8 = 0x01;
+    pub const TABLE_TYPE_DEF: u8 = 0x02;
+    pub const TABLE_FIELD: u8 = 0x04;
+    pub const TABLE_METHOD_DEF: u8 = 0x06;
+    pub const TABLE_MEMBER_REF: u8 = 0x0a;
+    pub const TABLE_TYPE_SPEC: u8 = 0x1b;
+    pub const TABLE_METHOD_SPEC: u8 = 0x2b;
+
+    /// The metadata table
+    pub fn table(self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    /// The row index within the table
+    pub fn rid(self) -> u32 {
+        self.0 & 0x00ff_ffff
+    }
+}
+
+impl std::fmt::Display for MetadataToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "0x{:08x}", self.0)
+    }
+}
+
+impl Debug for MetadataToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        <Self as std::fmt::Display>::fmt(self, f)
+    }
+}
+
+impl ManagedProcFixed {
+    /// The metadata token of the method (normally a `MethodDef`)
+    pub fn token(&self) -> MetadataToken {
+        MetadataToken(self.token.get())
+    }
+}
+
+/// The location and attributes of an attributed variable.
+///
+/// The `S_MAN*` records (managed variables) and the `S_ATTR_*` records use the same layouts. In
+/// `S_MAN*` records, the `typind` field is a CLR metadata token (see [`MetadataToken`]) rather
+/// than a type index.
+///
+/// See `CV_lvar_attr` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Clone, Debug)]
+pub struct LVarAttr {
+    /// First code address where the variable is valid
+    pub offset_segment: OffsetSegment,
+    /// Local variable flags. See `CV_LVARFLAGS`.
+    pub flags: U16<LE>,
+}
+
+/// `S_MANSLOT`
+///
+/// See `ATTRSLOTSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct AttrSlotFixed {
+    /// Slot index
+    pub slot: U32<LE>,
+    /// Type index, or a metadata token in `S_MAN*` records
+    pub ty: TypeIndexLe,
+    pub attr: LVarAttr,
+    // name: strz
+}
+
+/// `S_MANSLOT`: A managed local variable stored in a slot.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct AttrSlot<'a> {
+    pub fixed: &'a AttrSlotFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for AttrSlot<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANREGISTER` and `S_ATTR_REGISTER`
+///
+/// See `ATTRREGSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct AttrRegisterFixed {
+    /// Type index, or a metadata token in `S_MAN*` records
+    pub ty: TypeIndexLe,
+    pub attr: LVarAttr,
+    pub register: U16<LE>,
+    // name: strz
+}
+
+/// `S_MANREGISTER` and `S_ATTR_REGISTER`: An attributed variable stored in a register.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct AttrRegister<'a> {
+    pub fixed: &'a AttrRegisterFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for AttrRegister<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANFRAMEREL` and `S_ATTR_FRAMEREL`
+///
+/// See `ATTRFRAMERELSYM` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct AttrFrameRelFixed {
+    /// Offset relative to the frame pointer
+    pub offset: I32<LE>,
+    /// Type index, or a metadata token in `S_MAN*` records
+    pub ty: TypeIndexLe,
+    pub attr: LVarAttr,
+    // name: strz
+}
+
+/// `S_MANFRAMEREL` and `S_ATTR_FRAMEREL`: An attributed variable stored relative to the frame
+/// pointer.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct AttrFrameRel<'a> {
+    pub fixed: &'a AttrFrameRelFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for AttrFrameRel<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANREGREL` and `S_ATTR_REGREL`
+///
+/// See `ATTRREGREL` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct AttrRegRelFixed {
+    /// Offset relative to the register
+    pub offset: U32<LE>,
+    /// Type index, or a metadata token in `S_MAN*` records
+    pub ty: TypeIndexLe,
+    pub register: U16<LE>,
+    pub attr: LVarAttr,
+    // name: strz
+}
+
+/// `S_MANREGREL` and `S_ATTR_REGREL`: An attributed variable stored relative to a register.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct AttrRegRel<'a> {
+    pub fixed: &'a AttrRegRelFixed,
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for AttrRegRel<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANMANYREG` and `S_ATTR_MANYREG`: An attributed variable stored in more than one
+/// register. Each register is identified by an 8-bit register number.
+///
+/// See `ATTRMANYREGSYM` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct AttrManyReg<'a> {
+    /// Type index, or a metadata token in `S_MAN*` records
+    pub ty: TypeIndex,
+    pub attr: &'a LVarAttr,
+    /// Registers, listed from the most significant to the least significant.
+    pub regs: &'a [u8],
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for AttrManyReg<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let ty = p.type_index()?;
+        let attr = p.get()?;
+        let count = p.u8()?;
+        Ok(Self {
+            ty,
+            attr,
+            regs: p.bytes(count as usize)?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_MANMANYREG2`: An attributed variable stored in more than one register. Each register is
+/// identified by a 16-bit register number.
+///
+/// See `ATTRMANYREGSYM2` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct AttrManyReg2<'a> {
+    /// Type index, or a metadata token in `S_MAN*` records
+    pub ty: TypeIndex,
+    pub attr: &'a LVarAttr,
+    /// Registers, listed from the most significant to the least significant.
+    pub regs: &'a [U16<LE>],
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for AttrManyReg2<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let ty = p.type_index()?;
+        let attr = p.get()?;
+        let count = p.u16()?;
+        Ok(Self {
+            ty,
+            attr,
+            regs: p.slice(count as usize)?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `S_OEM`
+///
+/// See `OEMSYMBOL` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+#[allow(missing_docs)]
+pub struct OemFixed {
+    /// Identifies the OEM that defined this record
+    pub oem_id: [u8; 16],
+    pub ty: TypeIndexLe,
+}
+
+/// `S_OEM`: A record whose contents are defined by an OEM.
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub struct Oem<'a> {
+    pub fixed: &'a OemFixed,
+    /// OEM-defined data
+    pub data: &'a [u8],
+}
+
+impl<'a> Parse<'a> for Oem<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            data: p.take_rest(),
+        })
+    }
+}
+
+/// Parsed data from a symbol record
+#[derive(Clone, Debug)]
+#[allow(missing_docs)]
+pub enum SymData<'a> {
+    Unknown,
+    ObjName(ObjectName<'a>),
+    Compile3(Compile3<'a>),
+    Proc(Proc<'a>),
+    Thunk(Thunk<'a>),
+    Udt(Udt<'a>),
+    Constant(Constant<'a>),
+    ManagedConstant(ManagedConstant<'a>),
+    RefSym2(RefSym2<'a>),
+    Data(Data<'a>),
+    ThreadData(ThreadStorageData<'a>),
+    Pub(Pub<'a>),
+    End,
+    FrameProc(&'a FrameProc),
+    RegRel(RegRel<'a>),
+    Block(Block<'a>),
+    Local(Local<'a>),
+    DefRange(DefRange<'a>),
+    DefRangeFramePointerRel(DefRangeSymFramePointerRel<'a>),
+    DefRangeRegister(DefRangeRegister<'a>),
+    DefRangeRegisterRel(DefRangeRegisterRel<'a>),
+    DefRangeFramePointerRelFullScope(&'a DefRangeFramePointerRelFullScope),
+    DefRangeSubFieldRegister(DefRangeSubFieldRegister<'a>),
+    Trampoline(Trampoline<'a>),
+    BuildInfo(BuildInfo),
+    UsingNamespace(UsingNamespace<'a>),
+    InlineSiteEnd,
+    Label(Label<'a>),
+    FunctionList(FunctionList<'a>),
+    InlineSite(InlineSite<'a>),
+    InlineSite2(InlineSite2<'a>),
+    FrameCookie(&'a FrameCookie),
+    CallSiteInfo(&'a CallSiteInfo),
+    HeapAllocSite(&'a HeapAllocSite),
+    ManagedProc(ManagedProc<'a>),
+    Annotation(Annotation<'a>),
+    HotPatchFunc(HotPatchFunc<'a>),
+    CoffGroup(CoffGroup<'a>),
+    ArmSwitchTable(&'a ArmSwitchTable),
+    Section(Section<'a>),
+    Compile2(Compile2<'a>),
+    EnvBlock(EnvBlock<'a>),
+    Register(Register<'a>),
+    BpRel(BpRel<'a>),
+    SepCode(&'a SepCode),
+    Export(Export<'a>),
+    FileStatic(FileStatic<'a>),
+    Slot(Slot<'a>),
+    ManyReg(ManyReg<'a>),
+    ManyReg2(ManyReg2<'a>),
+    DefRangeSubField(DefRangeSubField<'a>),
+    PogoData(&'a PogoData),
+    ModTypeRef(&'a ModTypeRef),
+    RefMiniPdb(RefMiniPdb<'a>),
+    PdbMap(PdbMap<'a>),
+    FastLink(FastLink<'a>),
+    Discarded(Discarded<'a>),
+    VFTable32(&'a VFTable32),
+    DataHlsl(DataHlsl<'a>),
+    DataHlsl32(DataHlsl32<'a>),
+    DataHlsl32Ex(DataHlsl32Ex<'a>),
+    DefRangeHlsl(DefRangeHlsl<'a>),
+    AttrSlot(AttrSlot<'a>),
+    AttrRegister(AttrRegister<'a>),
+    AttrFrameRel(AttrFrameRel<'a>),
+    AttrRegRel(AttrRegRel<'a>),
+    AttrManyReg(AttrManyReg<'a>),
+    AttrManyReg2(AttrManyReg2<'a>),
+    Oem(Oem<'a>),
+}
+
+impl<'a> SymData<'a> {
+    /// Parses a symbol record. The caller has already parsed the length and kind of the record.
+    /// The `data` parameter does not include the length or kind.
+    pub fn parse(kind: SymKind, data: &'a [u8]) -> Result<Self, ParserError> {
+        let mut p = Parser::new(data);
+        Self::from_parser(kind, &mut p)
+    }
+
+    /// Parses a symbol record. The caller has already parsed the length and kind of the record.
+    /// The `p` parameter does not include the length or kind.
+    ///
+    /// This function allows the caller to observe how many bytes were actually consumed from
+    /// the input stream.
+    pub fn from_parser(kind: SymKind, p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(match kind {
+            SymKind::S_OBJNAME => Self::ObjName(p.parse()?),
+            SymKind::S_GPROC32 | SymKind::S_LPROC32 => Self::Proc(p.parse()?),
+            SymKind::S_THUNK32 => Self::Thunk(p.parse()?),
+            SymKind::S_COMPILE3 => Self::Compile3(p.parse()?),
+            SymKind::S_UDT => Self::Udt(p.parse()?),
+            SymKind::S_CONSTANT => Self::Constant(p.parse()?),
+            SymKind::S_MANCONSTANT => Self::Constant(p.parse()?),
+            SymKind::S_PUB32 => Self::Pub(p.parse()?),
+            SymKind::S_PUB32_ST => Self::Pub(Pub::parse_st(p)?),
+
+            SymKind::S_PROCREF
+            | SymKind::S_LPROCREF
+            | SymKind::S_DATAREF
+            | SymKind::S_TOKENREF
+            | SymKind::S_ANNOTATIONREF => Self::RefSym2(p.parse()?),
+
+            SymKind::S_LDATA32 | SymKind::S_GDATA32 | SymKind::S_LMANDATA | SymKind::S_GMANDATA => {
+                Self::Data(p.parse()?)
+            }
+
+            SymKind::S_LTHREAD32 | SymKind::S_GTHREAD32 => Self::ThreadData(p.parse()?),
+            SymKind::S_END => Self::End,
+            SymKind::S_FRAMEPROC => Self::FrameProc(p.get()?),
+            SymKind::S_REGREL32 => Self::RegRel(p.parse()?),
+            SymKind::S_BLOCK32 => Self::Block(p.parse()?),
+            SymKind::S_LOCAL => Self::Local(p.parse()?),
+            SymKind::S_DEFRANGE => Self::DefRange(p.parse()?),
+            SymKind::S_DEFRANGE_FRAMEPOINTER_REL => Self::DefRangeFramePointerRel(p.parse()?),
+            SymKind::S_DEFRANGE_REGISTER => Self::DefRangeRegister(p.parse()?),
+            SymKind::S_DEFRANGE_REGISTER_REL => Self::DefRangeRegisterRel(p.parse()?),
+            SymKind::S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE => {
+                Self::DefRangeFramePointerRelFullScope(p.get()?)
+            }
+            SymKind::S_DEFRANGE_SUBFIELD_REGISTER => Self::DefRangeSubFieldRegister(p.parse()?),
+            SymKind::S_TRAMPOLINE => Self::Trampoline(p.parse()?),
+            SymKind::S_BUILDINFO => Self::BuildInfo(p.parse()?),
+            SymKind::S_UNAMESPACE => Self::UsingNamespace(p.parse()?),
+            SymKind::S_INLINESITE_END => Self::InlineSiteEnd,
+            SymKind::S_LABEL32 => Self::Label(p.parse()?),
+            SymKind::S_CALLEES | SymKind::S_CALLERS => Self::FunctionList(p.parse()?),
+            SymKind::S_INLINESITE => Self::InlineSite(p.parse()?),
+            SymKind::S_INLINESITE2 => Self::InlineSite2(p.parse()?),
+            SymKind::S_INLINEES => Self::FunctionList(p.parse()?),
+            SymKind::S_FRAMECOOKIE => Self::FrameCookie(p.get()?),
             SymKind::S_CALLSITEINFO => Self::CallSiteInfo(p.get()?),
             SymKind::S_HEAPALLOCSITE => Self::HeapAllocSite(p.get()?),
             SymKind::S_GMANPROC | SymKind::S_LMANPROC => Self::ManagedProc(p.parse()?),
@@ -1627,6 +2828,37 @@ impl<'a> SymData<'a> {
             SymKind::S_ARMSWITCHTABLE => Self::ArmSwitchTable(p.get()?),
             SymKind::S_COFFGROUP => Self::CoffGroup(p.parse()?),
             SymKind::S_SECTION => Self::Section(p.parse()?),
+            SymKind::S_COMPILE2 => Self::Compile2(p.parse()?),
+            SymKind::S_ENVBLOCK => Self::EnvBlock(p.parse()?),
+            SymKind::S_REGISTER => Self::Register(p.parse()?),
+            SymKind::S_BPREL32 => Self::BpRel(p.parse()?),
+            SymKind::S_SEPCODE => Self::SepCode(p.get()?),
+            SymKind::S_EXPORT => Self::Export(p.parse()?),
+            SymKind::S_FILESTATIC => Self::FileStatic(p.parse()?),
+            SymKind::S_LOCALSLOT | SymKind::S_PARAMSLOT => Self::Slot(p.parse()?),
+            SymKind::S_MANYREG => Self::ManyReg(p.parse()?),
+            SymKind::S_MANYREG2 => Self::ManyReg2(p.parse()?),
+            SymKind::S_DEFRANGE_SUBFIELD => Self::DefRangeSubField(p.parse()?),
+            SymKind::S_POGODATA => Self::PogoData(p.get()?),
+            SymKind::S_MOD_TYPEREF => Self::ModTypeRef(p.get()?),
+            SymKind::S_REF_MINIPDB => Self::RefMiniPdb(p.parse()?),
+            SymKind::S_PDBMAP => Self::PdbMap(p.parse()?),
+            SymKind::S_FASTLINK => Self::FastLink(p.parse()?),
+            SymKind::S_DISCARDED => Self::Discarded(p.parse()?),
+            SymKind::S_VFTABLE32 => Self::VFTable32(p.get()?),
+            SymKind::S_GDATA_HLSL | SymKind::S_LDATA_HLSL => Self::DataHlsl(p.parse()?),
+            SymKind::S_GDATA_HLSL32 | SymKind::S_LDATA_HLSL32 => Self::DataHlsl32(p.parse()?),
+            SymKind::S_GDATA_HLSL32_EX | SymKind::S_LDATA_HLSL32_EX => {
+                Self::DataHlsl32Ex(p.parse()?)
+            }
+            SymKind::S_DEFRANGE_HLSL => Self::DefRangeHlsl(p.parse()?),
+            SymKind::S_MANSLOT => Self::AttrSlot(p.parse()?),
+            SymKind::S_MANREGISTER | SymKind::S_ATTR_REGISTER => Self::AttrRegister(p.parse()?),
+            SymKind::S_MANFRAMEREL | SymKind::S_ATTR_FRAMEREL => Self::AttrFrameRel(p.parse()?),
+            SymKind::S_MANREGREL | SymKind::S_ATTR_REGREL => Self::AttrRegRel(p.parse()?),
+            SymKind::S_MANMANYREG | SymKind::S_ATTR_MANYREG => Self::AttrManyReg(p.parse()?),
+            SymKind::S_MANMANYREG2 => Self::AttrManyReg2(p.parse()?),
+            SymKind::S_OEM => Self::Oem(p.parse()?),
 
             _ => Self::Unknown,
         })
@@ -1643,6 +2875,25 @@ impl<'a> SymData<'a> {
             Self::RefSym2(refsym) => Some(refsym.name),
             Self::Constant(c) => Some(c.name),
             Self::ManagedConstant(c) => Some(c.name),
+            Self::Register(r) => Some(r.name),
+            Self::BpRel(b) => Some(b.name),
+            Self::Export(e) => Some(e.name),
+            Self::FileStatic(f) => Some(f.name),
+            Self::Slot(s) => Some(s.name),
+            Self::ManyReg(r) => Some(r.name),
+            Self::ManyReg2(r) => Some(r.name),
+            Self::RefMiniPdb(r) => Some(r.name),
+            Self::FastLink(f) => Some(f.name),
+            Self::DataHlsl(d) => Some(d.name),
+            Self::DataHlsl32(d) => Some(d.name),
+            Self::DataHlsl32Ex(d) => Some(d.name),
+            Self::ManagedProc(p) => Some(p.name),
+            Self::AttrSlot(s) => Some(s.name),
+            Self::AttrRegister(r) => Some(r.name),
+            Self::AttrFrameRel(r) => Some(r.name),
+            Self::AttrRegRel(r) => Some(r.name),
+            Self::AttrManyReg(r) => Some(r.name),
+            Self::AttrManyReg2(r) => Some(r.name),
             _ => None,
         }
     }
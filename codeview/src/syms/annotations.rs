@@ -0,0 +1,704 @@
+//! Decodes the "binary annotations" that are stored in `S_INLINESITE` and `S_INLINESITE2` records.
+//!
+//! Binary annotations are a compact program that describes which code ranges of the parent
+//! procedure belong to an inlined call site, and which source lines those ranges map to.
+//! Each annotation is an opcode (`BA_OP_*` in `cvinfo.h`) followed by zero or more operands.
+//! Opcodes and operands are encoded using the CodeView compressed integer encoding
+//! (`CVCompressData` / `CVUncompressData` in `cvinfo.h`).
+//!
+//! [`BinaryAnnotationsIter`] decodes the opcodes. [`InlineLinesIter`] runs the program and
+//! produces [`InlineLineRow`] values, which describe code ranges relative to the start of the
+//! parent procedure.
+
+use anyhow::{Context, bail};
+use std::ops::Range;
+
+/// Decodes an unsigned integer using the CodeView compressed integer encoding.
+///
+/// See `CVUncompressData` in `cvinfo.h`. On success, returns the decoded value and advances
+/// `bytes` past it.
+pub fn uncompress_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let b: &[u8] = bytes;
+    let b0 = *b.first()? as u32;
+    let (value, len) = if b0 & 0x80 == 0 {
+        (b0, 1)
+    } else if b0 & 0xc0 == 0x80 {
+        if b.len() < 2 {
+            return None;
+        }
+        (((b0 & 0x3f) << 8) | b[1] as u32, 2)
+    } else if b0 & 0xe0 == 0xc0 {
+        if b.len() < 4 {
+            return None;
+        }
+        (
+            ((b0 & 0x1f) << 24) | ((b[1] as u32) << 16) | ((b[2] as u32) << 8) | b[3] as u32,
+            4,
+        )
+    } else {
+        return None;
+    };
+    *bytes = &b[len..];
+    Some(value)
+}
+
+/// Converts a value that was decoded by [`uncompress_u32`] into a signed value.
+///
+/// The sign is stored in the low bit. See `DecodeSignedInt32` in `cvinfo.h`.
+pub fn decode_signed_i32(value: u32) -> i32 {
+    if value & 1 != 0 {
+        -((value >> 1) as i32)
+    } else {
+        (value >> 1) as i32
+    }
+}
+
+/// Encodes an unsigned integer using the CodeView compressed integer encoding.
+///
+/// See `CVCompressData` in `cvinfo.h`. Values larger than `0x1fff_ffff` cannot be represented,
+/// and return an error without writing anything to `out`.
+pub fn compress_u32(value: u32, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    if value < 0x80 {
+        out.push(value as u8);
+    } else if value < 0x4000 {
+        out.extend_from_slice(&[0x80 | (value >> 8) as u8, value as u8]);
+    } else if value <= MAX_COMPRESSED_U32 {
+        out.extend_from_slice(&[
+            0xc0 | (value >> 24) as u8,
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ]);
+    } else {
+        bail!("Value 0x{value:x} is too large to compress");
+    }
+    Ok(())
+}
+
+/// The largest value that [`compress_u32`] can encode.
+pub const MAX_COMPRESSED_U32: u32 = 0x1fff_ffff;
+
+/// Converts a signed value into the form used by [`compress_u32`]. This is the inverse of
+/// [`decode_signed_i32`].
+///
+/// See `EncodeSignedInt32` in `cvinfo.h`. Returns `None` for `i32::MIN`, whose magnitude does not
+/// fit in 31 bits.
+pub fn encode_signed_i32(value: i32) -> Option<u32> {
+    let magnitude = value.unsigned_abs().checked_mul(2)?;
+    Some(if value >= 0 { magnitude } else { magnitude | 1 })
+}
+
+/// The opcode values of binary annotations. See `BinaryAnnotationOpcode` in `cvinfo.h`.
+#[allow(missing_docs)]
+pub mod opcodes {
+    pub const BA_OP_INVALID: u32 = 0;
+    pub const BA_OP_CODE_OFFSET: u32 = 1;
+    pub const BA_OP_CHANGE_CODE_OFFSET_BASE: u32 = 2;
+    pub const BA_OP_CHANGE_CODE_OFFSET: u32 = 3;
+    pub const BA_OP_CHANGE_CODE_LENGTH: u32 = 4;
+    pub const BA_OP_CHANGE_FILE: u32 = 5;
+    pub const BA_OP_CHANGE_LINE_OFFSET: u32 = 6;
+    pub const BA_OP_CHANGE_LINE_END_DELTA: u32 = 7;
+    pub const BA_OP_CHANGE_RANGE_KIND: u32 = 8;
+    pub const BA_OP_CHANGE_COLUMN_START: u32 = 9;
+    pub const BA_OP_CHANGE_COLUMN_END_DELTA: u32 = 10;
+    pub const BA_OP_CHANGE_CODE_OFFSET_AND_LINE_OFFSET: u32 = 11;
+    pub const BA_OP_CHANGE_CODE_LENGTH_AND_CODE_OFFSET: u32 = 12;
+    pub const BA_OP_CHANGE_COLUMN_END: u32 = 13;
+}
+
+/// A single decoded binary annotation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BinaryAnnotation {
+    /// `BA_OP_CodeOffset`: sets the current code offset.
+    CodeOffset(u32),
+    /// `BA_OP_ChangeCodeOffsetBase`: sets the segment number of the code offsets.
+    ChangeCodeOffsetBase(u32),
+    /// `BA_OP_ChangeCodeOffset`: advances the current code offset and starts a new range.
+    ChangeCodeOffset(u32),
+    /// `BA_OP_ChangeCodeLength`: sets the length of the current range.
+    ChangeCodeLength(u32),
+    /// `BA_OP_ChangeFile`: sets the current file. The value is the byte offset of the file's
+    /// record within the `DEBUG_S_FILECHKSMS` subsection.
+    ChangeFile(u32),
+    /// `BA_OP_ChangeLineOffset`: adds a signed delta to the current line number.
+    ChangeLineOffset(i32),
+    /// `BA_OP_ChangeLineEndDelta`: sets the number of lines covered by the current range.
+    ChangeLineEndDelta(u32),
+    /// `BA_OP_ChangeRangeKind`: 1 for a statement (the default), 0 for an expression.
+    ChangeRangeKind(u32),
+    /// `BA_OP_ChangeColumnStart`: sets the starting column.
+    ChangeColumnStart(u32),
+    /// `BA_OP_ChangeColumnEndDelta`: sets the ending column, relative to the starting column.
+    ChangeColumnEndDelta(i32),
+    /// `BA_OP_ChangeCodeOffsetAndLineOffset`: a compact combination of `ChangeCodeOffset` and
+    /// `ChangeLineOffset`.
+    ChangeCodeOffsetAndLineOffset {
+        /// The amount to add to the code offset.
+        code_delta: u32,
+        /// The amount to add to the line number.
+        line_delta: i32,
+    },
+    /// `BA_OP_ChangeCodeLengthAndCodeOffset`: a compact combination of `ChangeCodeOffset` and
+    /// `ChangeCodeLength`.
+    ChangeCodeLengthAndCodeOffset {
+        /// The length of the new range.
+        code_length: u32,
+        /// The amount to add to the code offset.
+        code_delta: u32,
+    },
+    /// `BA_OP_ChangeColumnEnd`: sets the ending column.
+    ChangeColumnEnd(u32),
+}
+
+impl BinaryAnnotation {
+    /// The `BA_OP_*` opcode of this annotation.
+    pub fn opcode(&self) -> u32 {
+        use opcodes::*;
+        match self {
+            Self::CodeOffset(_) => BA_OP_CODE_OFFSET,
+            Self::ChangeCodeOffsetBase(_) => BA_OP_CHANGE_CODE_OFFSET_BASE,
+            Self::ChangeCodeOffset(_) => BA_OP_CHANGE_CODE_OFFSET,
+            Self::ChangeCodeLength(_) => BA_OP_CHANGE_CODE_LENGTH,
+            Self::ChangeFile(_) => BA_OP_CHANGE_FILE,
+            Self::ChangeLineOffset(_) => BA_OP_CHANGE_LINE_OFFSET,
+            Self::ChangeLineEndDelta(_) => BA_OP_CHANGE_LINE_END_DELTA,
+            Self::ChangeRangeKind(_) => BA_OP_CHANGE_RANGE_KIND,
+            Self::ChangeColumnStart(_) => BA_OP_CHANGE_COLUMN_START,
+            Self::ChangeColumnEndDelta(_) => BA_OP_CHANGE_COLUMN_END_DELTA,
+            Self::ChangeCodeOffsetAndLineOffset { .. } => BA_OP_CHANGE_CODE_OFFSET_AND_LINE_OFFSET,
+            Self::ChangeCodeLengthAndCodeOffset { .. } => BA_OP_CHANGE_CODE_LENGTH_AND_CODE_OFFSET,
+            Self::ChangeColumnEnd(_) => BA_OP_CHANGE_COLUMN_END,
+        }
+    }
+}
+
+impl BinaryAnnotation {
+    /// Encodes this annotation and appends it to `out`.
+    ///
+    /// Returns an error if an operand cannot be represented. This happens if an operand is larger
+    /// than [`MAX_COMPRESSED_U32`], or if the `code_delta` of `ChangeCodeOffsetAndLineOffset` is
+    /// larger than `0xf`. Nothing is written to `out` in that case.
+    pub fn encode(&self, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let start = out.len();
+        let result = self.encode_operands(out);
+        if result.is_err() {
+            out.truncate(start);
+        }
+        result
+    }
+
+    fn encode_operands(&self, out: &mut Vec<u8>) -> anyhow::Result<()> {
+        let signed = |x: i32| {
+            encode_signed_i32(x).with_context(|| format!("Value {x} is too large to encode"))
+        };
+
+        compress_u32(self.opcode(), out)?;
+        match *self {
+            Self::CodeOffset(x)
+            | Self::ChangeCodeOffsetBase(x)
+            | Self::ChangeCodeOffset(x)
+            | Self::ChangeCodeLength(x)
+            | Self::ChangeFile(x)
+            | Self::ChangeLineEndDelta(x)
+            | Self::ChangeRangeKind(x)
+            | Self::ChangeColumnStart(x)
+            | Self::ChangeColumnEnd(x) => compress_u32(x, out),
+            Self::ChangeLineOffset(x) | Self::ChangeColumnEndDelta(x) => {
+                compress_u32(signed(x)?, out)
+            }
+            Self::ChangeCodeOffsetAndLineOffset {
+                code_delta,
+                line_delta,
+            } => {
+                // The code delta is stored in the low 4 bits, and the line delta above it.
+                if code_delta > 0xf {
+                    bail!(
+                        "Code delta 0x{code_delta:x} of ChangeCodeOffsetAndLineOffset is too large"
+                    );
+                }
+                let line_delta = signed(line_delta)?;
+                if line_delta > MAX_COMPRESSED_U32 >> 4 {
+                    bail!("Line delta of ChangeCodeOffsetAndLineOffset is too large");
+                }
+                compress_u32((line_delta << 4) | code_delta, out)
+            }
+            Self::ChangeCodeLengthAndCodeOffset {
+                code_length,
+                code_delta,
+            } => {
+                compress_u32(code_length, out)?;
+                compress_u32(code_delta, out)
+            }
+        }
+    }
+}
+
+/// Encodes a sequence of annotations and appends them to `out`.
+///
+/// Returns an error if any annotation cannot be encoded (see [`BinaryAnnotation::encode`]).
+/// Nothing is written to `out` in that case.
+pub fn encode_annotations(
+    annotations: &[BinaryAnnotation],
+    out: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let start = out.len();
+    for a in annotations {
+        if let Err(e) = a.encode(out) {
+            out.truncate(start);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Decodes [`BinaryAnnotation`] values from the `binary_annotations` field of an inline site.
+///
+/// Iteration stops at the first `BA_OP_Invalid` opcode (which is also used as trailing padding),
+/// at the end of the data, or at the first malformed annotation. Use [`Self::rest`] to check
+/// whether all of the data was consumed.
+#[derive(Clone)]
+pub struct BinaryAnnotationsIter<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BinaryAnnotationsIter<'a> {
+    /// Starts decoding annotations.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// The bytes that have not yet been decoded.
+    pub fn rest(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    fn decode_one(bytes: &mut &'a [u8]) -> Option<BinaryAnnotation> {
+        use opcodes::*;
+
+        let op = uncompress_u32(bytes)?;
+        let mut arg = || uncompress_u32(bytes);
+
+        Some(match op {
+            BA_OP_CODE_OFFSET => BinaryAnnotation::CodeOffset(arg()?),
+            BA_OP_CHANGE_CODE_OFFSET_BASE => BinaryAnnotation::ChangeCodeOffsetBase(arg()?),
+            BA_OP_CHANGE_CODE_OFFSET => BinaryAnnotation::ChangeCodeOffset(arg()?),
+            BA_OP_CHANGE_CODE_LENGTH => BinaryAnnotation::ChangeCodeLength(arg()?),
+            BA_OP_CHANGE_FILE => BinaryAnnotation::ChangeFile(arg()?),
+            BA_OP_CHANGE_LINE_OFFSET => {
+                BinaryAnnotation::ChangeLineOffset(decode_signed_i32(arg()?))
+            }
+            BA_OP_CHANGE_LINE_END_DELTA => BinaryAnnotation::ChangeLineEndDelta(arg()?),
+            BA_OP_CHANGE_RANGE_KIND => BinaryAnnotation::ChangeRangeKind(arg()?),
+            BA_OP_CHANGE_COLUMN_START => BinaryAnnotation::ChangeColumnStart(arg()?),
+            BA_OP_CHANGE_COLUMN_END_DELTA => {
+                BinaryAnnotation::ChangeColumnEndDelta(decode_signed_i32(arg()?))
+            }
+            BA_OP_CHANGE_CODE_OFFSET_AND_LINE_OFFSET => {
+                let v = arg()?;
+                BinaryAnnotation::ChangeCodeOffsetAndLineOffset {
+                    code_delta: v & 0xf,
+                    line_delta: decode_signed_i32(v >> 4),
+                }
+            }
+            BA_OP_CHANGE_CODE_LENGTH_AND_CODE_OFFSET => {
+                let code_length = arg()?;
+                let code_delta = arg()?;
+                BinaryAnnotation::ChangeCodeLengthAndCodeOffset {
+                    code_length,
+                    code_delta,
+                }
+            }
+            BA_OP_CHANGE_COLUMN_END => BinaryAnnotation::ChangeColumnEnd(arg()?),
+            _ => return None,
+        })
+    }
+}
+
+impl<'a> Iterator for BinaryAnnotationsIter<'a> {
+    type Item = BinaryAnnotation;
+
+    fn next(&mut self) -> Option<BinaryAnnotation> {
+        let mut bytes = self.bytes;
+        let a = Self::decode_one(&mut bytes)?;
+        self.bytes = bytes;
+        Some(a)
+    }
+}
+
+/// One row of line information that was produced by evaluating binary annotations.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InlineLineRow {
+    /// The range of code offsets covered by this row. Offsets are relative to the start of the
+    /// parent procedure.
+    ///
+    /// If the annotations end without specifying the length of the last range, then the range
+    /// of the last row is empty.
+    pub code_offset_range: Range<u32>,
+    /// The byte offset of the file's record within the `DEBUG_S_FILECHKSMS` subsection.
+    pub file_id: u32,
+    /// The line number.
+    pub line: u32,
+    /// The number of lines covered by this row, beyond `line`. This is usually zero.
+    pub line_end_delta: u32,
+    /// The starting column, or 0 if no column information is present.
+    pub column: u32,
+    /// The ending column, or 0 if no column information is present.
+    pub column_end: u32,
+    /// True if this row describes a statement, false if it describes an expression.
+    pub is_statement: bool,
+}
+
+/// Evaluates binary annotations and produces [`InlineLineRow`] values.
+///
+/// Line numbers in binary annotations are relative to the starting line of the inlinee, which
+/// is stored in the `DEBUG_S_INLINEELINES` subsection. The file is also initialized from that
+/// subsection. If that information is not available, pass 0 for both and treat the resulting
+/// line numbers as deltas.
+#[derive(Clone)]
+pub struct InlineLinesIter<'a> {
+    annotations: BinaryAnnotationsIter<'a>,
+    code_offset: u32,
+    file_id: u32,
+    line: i64,
+    line_end_delta: u32,
+    column: u32,
+    column_end: u32,
+    is_statement: bool,
+    /// The row that has been started but whose length is not yet known.
+    pending: Option<InlineLineRow>,
+    /// A finished row that has not yet been returned.
+    ready: Option<InlineLineRow>,
+}
+
+impl<'a> InlineLinesIter<'a> {
+    /// Starts evaluating `binary_annotations`.
+    pub fn new(binary_annotations: &'a [u8], start_file_id: u32, start_line: u32) -> Self {
+        Self {
+            annotations: BinaryAnnotationsIter::new(binary_annotations),
+            code_offset: 0,
+            file_id: start_file_id,
+            line: start_line as i64,
+            line_end_delta: 0,
+            column: 0,
+            column_end: 0,
+            is_statement: true,
+            pending: None,
+            ready: None,
+        }
+    }
+
+    /// Starts a new row at the current code offset. Returns the previous row, if any, which ends
+    /// where the new row begins.
+    fn start_row(&mut self) -> Option<InlineLineRow> {
+        let prev = self.pending.take().map(|mut row| {
+            row.code_offset_range.end = self.code_offset.max(row.code_offset_range.start);
+            row
+        });
+        self.pending = Some(InlineLineRow {
+            code_offset_range: self.code_offset..self.code_offset,
+            file_id: self.file_id,
+            line: self.line as u32,
+            line_end_delta: self.line_end_delta,
+            column: self.column,
+            column_end: self.column_end,
+            is_statement: self.is_statement,
+        });
+        prev
+    }
+
+    /// Sets the length of the current row and finishes it.
+    fn finish_row(&mut self, code_length: u32) -> Option<InlineLineRow> {
+        let mut row = self.pending.take()?;
+        let end = row.code_offset_range.start.wrapping_add(code_length);
+        row.code_offset_range.end = end;
+        self.code_offset = end;
+        Some(row)
+    }
+}
+
+impl<'a> Iterator for InlineLinesIter<'a> {
+    type Item = InlineLineRow;
+
+    fn next(&mut self) -> Option<InlineLineRow> {
+        if let Some(row) = self.ready.take() {
+            return Some(row);
+        }
+
+        loop {
+            let Some(a) = self.annotations.next() else {
+                // Flush the last row, which has an unknown length.
+                return self.pending.take();
+            };
+
+            let out = match a {
+                BinaryAnnotation::CodeOffset(offset) => {
+                    self.code_offset = offset;
+                    self.start_row()
+                }
+                BinaryAnnotation::ChangeCodeOffsetBase(_) => None,
+                BinaryAnnotation::ChangeCodeOffset(delta) => {
+                    self.code_offset = self.code_offset.wrapping_add(delta);
+                    self.start_row()
+                }
+                BinaryAnnotation::ChangeCodeLength(len) => self.finish_row(len),
+                BinaryAnnotation::ChangeFile(file_id) => {
+                    self.file_id = file_id;
+                    None
+                }
+                BinaryAnnotation::ChangeLineOffset(delta) => {
+                    self.line += delta as i64;
+                    None
+                }
+                BinaryAnnotation::ChangeLineEndDelta(delta) => {
+                    self.line_end_delta = delta;
+                    None
+                }
+                BinaryAnnotation::ChangeRangeKind(kind) => {
+                    self.is_statement = kind != 0;
+                    None
+                }
+                BinaryAnnotation::ChangeColumnStart(column) => {
+                    self.column = column;
+                    None
+                }
+                BinaryAnnotation::ChangeColumnEndDelta(delta) => {
+                    self.column_end = self.column.wrapping_add_signed(delta);
+                    None
+                }
+                BinaryAnnotation::ChangeColumnEnd(column_end) => {
+                    self.column_end = column_end;
+                    None
+                }
+                BinaryAnnotation::ChangeCodeOffsetAndLineOffset {
+                    code_delta,
+                    line_delta,
+                } => {
+                    self.line += line_delta as i64;
+                    self.code_offset = self.code_offset.wrapping_add(code_delta);
+                    self.start_row()
+                }
+                BinaryAnnotation::ChangeCodeLengthAndCodeOffset {
+                    code_length,
+                    code_delta,
+                } => {
+                    self.code_offset = self.code_offset.wrapping_add(code_delta);
+                    let prev = self.start_row();
+                    let this = self.finish_row(code_length);
+                    match prev {
+                        Some(prev) => {
+                            self.ready = this;
+                            Some(prev)
+                        }
+                        None => this,
+                    }
+                }
+            };
+
+            if out.is_some() {
+                return out;
+            }
+        }
+    }
+}
+
+impl<'a> super::InlineSite<'a> {
+    /// Decodes the binary annotations of this inline site.
+    pub fn iter_annotations(&self) -> BinaryAnnotationsIter<'a> {
+        BinaryAnnotationsIter::new(self.binary_annotations)
+    }
+
+    /// Evaluates the binary annotations of this inline site. See [`InlineLinesIter`].
+    pub fn iter_lines(&self, start_file_id: u32, start_line: u32) -> InlineLinesIter<'a> {
+        InlineLinesIter::new(self.binary_annotations, start_file_id, start_line)
+    }
+}
+
+impl<'a> super::InlineSite2<'a> {
+    /// Decodes the binary annotations of this inline site.
+    pub fn iter_annotations(&self) -> BinaryAnnotationsIter<'a> {
+        BinaryAnnotationsIter::new(self.binary_annotations)
+    }
+
+    /// Evaluates the binary annotations of this inline site. See [`InlineLinesIter`].
+    pub fn iter_lines(&self, start_file_id: u32, start_line: u32) -> InlineLinesIter<'a> {
+        InlineLinesIter::new(self.binary_annotations, start_file_id, start_line)
+    }
+}
+
+#[cfg(test)]
+#[rustfmt::skip]
+static TEST_ANNOTATIONS: &[u8] = &[
+    0x06, 0x02,             // ChangeLineOffset(1)
+    0x03, 0x10,             // ChangeCodeOffset(0x10)
+    0x0b, 0x43,             // ChangeCodeOffsetAndLineOffset(3, 2)
+    0x05, 0x18,             // ChangeFile(0x18)
+    0x0c, 0x05, 0x02,       // ChangeCodeLengthAndCodeOffset(5, 2)
+    0x06, 0x03,             // ChangeLineOffset(-1)
+    0x03, 0x82, 0x00,       // ChangeCodeOffset(0x200)
+    0x04, 0x07,             // ChangeCodeLength(7)
+    0x00,                   // padding
+];
+
+#[test]
+fn test_uncompress() {
+    let mut b: &[u8] = &[0x7f, 0x81, 0x23, 0xc1, 0x02, 0x03, 0x04, 0xe0];
+    assert_eq!(uncompress_u32(&mut b), Some(0x7f));
+    assert_eq!(uncompress_u32(&mut b), Some(0x123));
+    assert_eq!(uncompress_u32(&mut b), Some(0x0102_0304));
+    assert_eq!(uncompress_u32(&mut b), None);
+    assert_eq!(b, &[0xe0]);
+
+    assert_eq!(decode_signed_i32(4), 2);
+    assert_eq!(decode_signed_i32(3), -1);
+}
+
+#[test]
+fn test_compress() {
+    for x in [0, 0x7f, 0x80, 0x3fff, 0x4000, 0x1fff_ffff] {
+        let mut buf = Vec::new();
+        compress_u32(x, &mut buf).unwrap();
+        let mut b: &[u8] = &buf;
+        assert_eq!(uncompress_u32(&mut b), Some(x));
+        assert!(b.is_empty());
+    }
+
+    let mut buf = Vec::new();
+    assert!(compress_u32(MAX_COMPRESSED_U32 + 1, &mut buf).is_err());
+    assert!(compress_u32(u32::MAX, &mut buf).is_err());
+    assert!(buf.is_empty());
+
+    for x in [
+        0,
+        1,
+        -1,
+        100,
+        -100,
+        0x0fff_ffff,
+        -0x0fff_ffff,
+        i32::MAX,
+        i32::MIN + 1,
+    ] {
+        assert_eq!(decode_signed_i32(encode_signed_i32(x).unwrap()), x);
+    }
+    assert_eq!(encode_signed_i32(i32::MIN), None);
+}
+
+#[test]
+fn test_encode_annotations() {
+    let ops: Vec<BinaryAnnotation> = BinaryAnnotationsIter::new(TEST_ANNOTATIONS).collect();
+    let mut buf = Vec::new();
+    encode_annotations(&ops, &mut buf).unwrap();
+    assert_eq!(buf, TEST_ANNOTATIONS[..TEST_ANNOTATIONS.len() - 1]);
+}
+
+#[test]
+fn test_encode_annotation_limits() {
+    let encode = |a: BinaryAnnotation| {
+        let mut buf = vec![0xaa];
+        let result = a.encode(&mut buf);
+        let decoded: Vec<BinaryAnnotation> = BinaryAnnotationsIter::new(&buf[1..]).collect();
+        match result {
+            Ok(()) => {
+                assert_eq!(decoded, [a]);
+                true
+            }
+            Err(_) => {
+                assert_eq!(buf, [0xaa]);
+                false
+            }
+        }
+    };
+
+    let both = |code_delta, line_delta| BinaryAnnotation::ChangeCodeOffsetAndLineOffset {
+        code_delta,
+        line_delta,
+    };
+    assert!(encode(both(0xf, 0)));
+    assert!(!encode(both(0x10, 0)));
+    assert!(encode(both(0, 0xff_ffff)));
+    assert!(encode(both(0, -0xff_ffff)));
+    assert!(!encode(both(0, 0x100_0000)));
+    assert!(!encode(both(0, -0x100_0000)));
+
+    assert!(encode(BinaryAnnotation::ChangeCodeOffset(
+        MAX_COMPRESSED_U32
+    )));
+    assert!(!encode(BinaryAnnotation::ChangeCodeOffset(
+        MAX_COMPRESSED_U32 + 1
+    )));
+    assert!(encode(BinaryAnnotation::ChangeLineOffset(-0x0fff_ffff)));
+    assert!(!encode(BinaryAnnotation::ChangeLineOffset(0x1000_0000)));
+    assert!(!encode(BinaryAnnotation::ChangeLineOffset(i32::MIN)));
+    assert!(!encode(BinaryAnnotation::ChangeCodeLengthAndCodeOffset {
+        code_length: 1,
+        code_delta: u32::MAX,
+    }));
+
+    // A failure discards the annotations that were already encoded.
+    let mut buf = Vec::new();
+    let ops = [BinaryAnnotation::ChangeCodeOffset(1), both(0x10, 0)];
+    assert!(encode_annotations(&ops, &mut buf).is_err());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn test_decode_annotations() {
+    let mut iter = BinaryAnnotationsIter::new(TEST_ANNOTATIONS);
+    let ops: Vec<BinaryAnnotation> = iter.by_ref().collect();
+    assert_eq!(
+        ops,
+        [
+            BinaryAnnotation::ChangeLineOffset(1),
+            BinaryAnnotation::ChangeCodeOffset(0x10),
+            BinaryAnnotation::ChangeCodeOffsetAndLineOffset {
+                code_delta: 3,
+                line_delta: 2
+            },
+            BinaryAnnotation::ChangeFile(0x18),
+            BinaryAnnotation::ChangeCodeLengthAndCodeOffset {
+                code_length: 5,
+                code_delta: 2
+            },
+            BinaryAnnotation::ChangeLineOffset(-1),
+            BinaryAnnotation::ChangeCodeOffset(0x200),
+            BinaryAnnotation::ChangeCodeLength(7),
+        ]
+    );
+    assert_eq!(iter.rest(), &[0]);
+}
+
+#[test]
+fn test_eval_annotations() {
+    let row = |range: Range<u32>, file_id: u32, line: u32| InlineLineRow {
+        code_offset_range: range,
+        file_id,
+        line,
+        line_end_delta: 0,
+        column: 0,
+        column_end: 0,
+        is_statement: true,
+    };
+
+    let rows: Vec<InlineLineRow> = InlineLinesIter::new(TEST_ANNOTATIONS, 0, 100).collect();
+    assert_eq!(
+        rows,
+        [
+            row(0x10..0x13, 0, 101),
+            row(0x13..0x15, 0, 103),
+            row(0x15..0x1a, 0x18, 103),
+            row(0x21a..0x221, 0x18, 102),
+        ]
+    );
+}
+
+#[test]
+fn test_eval_unterminated() {
+    // ChangeColumnStart(4), ChangeColumnEndDelta(3), ChangeCodeOffset(8)
+    let rows: Vec<InlineLineRow> =
+        InlineLinesIter::new(&[0x09, 0x04, 0x0a, 0x06, 0x03, 0x08], 0, 0).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].code_offset_range, 8..8);
+    assert_eq!(rows[0].column, 4);
+    assert_eq!(rows[0].column_end, 7);
+}
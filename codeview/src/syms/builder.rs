@@ -1,28 +1,65 @@
 //! Supports building new symbol streams
 
-use super::SymKind;
+use super::*;
 use crate::encoder::Encoder;
 use crate::types::TypeIndex;
 use bstr::BStr;
 
 /// Writes symbol records into a buffer.
+///
+/// Records that start a scope (procedures, blocks, thunks, inline sites, etc.) are written with
+/// the `begin_*` methods. Each of these must be matched by a call to [`SymBuilder::end_scope`],
+/// which writes the matching `S_END` (or `S_INLINESITE_END`, `S_PROC_ID_END`) record and fixes up
+/// the `p_parent` and `p_end` fields of the scope record. [`SymBuilder::finish`] closes any
+/// scopes that are still open.
 #[derive(Default)]
 pub struct SymBuilder {
     /// Contains the symbol stream
     pub buffer: Vec<u8>,
+
+    /// The stream offset of `buffer[0]`. Module symbol streams begin with a 4-byte signature,
+    /// which is counted in the `p_parent` and `p_end` fields but which is not written by this
+    /// builder.
+    base_offset: u32,
+
+    /// The open scopes. Each entry gives the stream offset of the record that started the scope
+    /// and the kind of that record.
+    scopes: Vec<(u32, SymKind)>,
 }
 
 impl SymBuilder {
     /// Creates a new empty symbol stream builder.
     pub fn new() -> Self {
-        Self { buffer: Vec::new() }
+        Self::default()
     }
 
-    /// Consumes this builder and returns the symbol stream.
-    pub fn finish(self) -> Vec<u8> {
+    /// Creates a new empty symbol stream builder, whose records will be placed at `base_offset`
+    /// within the symbol stream. Use 4 for module symbol streams, to account for the signature.
+    pub fn with_base_offset(base_offset: u32) -> Self {
+        Self {
+            base_offset,
+            ..Self::default()
+        }
+    }
+
+    /// Consumes this builder and returns the symbol stream. Any open scopes are closed first.
+    pub fn finish(mut self) -> Vec<u8> {
+        while !self.scopes.is_empty() {
+            self.end_scope();
+        }
         self.buffer
     }
 
+    /// The stream offset where the next record will be written.
+    pub fn stream_offset(&self) -> u32 {
+        self.base_offset + self.buffer.len() as u32
+    }
+
+    /// The number of scopes that are currently open.
+    pub fn scope_depth(&self) -> usize {
+        self.scopes.len()
+    }
+
     /// Starts adding a new record to the builder.
     pub fn record(&mut self, kind: SymKind) -> RecordBuilder<'_> {
         let record_start = self.buffer.len();
@@ -34,6 +71,60 @@ impl SymBuilder {
         }
     }
 
+    /// Adds a record whose payload is a fixed-size structure.
+    fn fixed<T: IntoBytes + Immutable>(&mut self, kind: SymKind, fixed: &T) {
+        let mut r = self.record(kind);
+        r.enc.t(fixed);
+    }
+
+    /// Adds a record whose payload is a fixed-size structure followed by a name.
+    fn fixed_name<T: IntoBytes + Immutable>(&mut self, kind: SymKind, fixed: &T, name: &BStr) {
+        let mut r = self.record(kind);
+        r.enc.t(fixed);
+        r.enc.strz(name);
+    }
+
+    /// Adds a record whose payload is a fixed-size structure followed by unstructured data.
+    fn fixed_rest<T: IntoBytes + Immutable>(&mut self, kind: SymKind, fixed: &T, rest: &[u8]) {
+        let mut r = self.record(kind);
+        r.enc.t(fixed);
+        r.enc.bytes(rest);
+    }
+
+    /// Writes a record that starts a scope. The payload of the record must begin with a
+    /// `BlockHeader`, whose contents will be set by this function and by `end_scope`.
+    fn begin_scope(&mut self, kind: SymKind, payload: impl FnOnce(&mut Encoder)) {
+        let record_offset = self.stream_offset();
+        let p_parent = self.scopes.last().map(|&(offset, _)| offset).unwrap_or(0);
+
+        let header_pos = self.buffer.len() + 4;
+        payload(&mut self.record(kind).enc);
+        self.buffer[header_pos..header_pos + 4].copy_from_slice(&p_parent.to_le_bytes());
+        self.buffer[header_pos + 4..header_pos + 8].copy_from_slice(&0u32.to_le_bytes());
+
+        self.scopes.push((record_offset, kind));
+    }
+
+    /// Closes the innermost open scope.
+    ///
+    /// This writes the end record given by [`SymKind::scope_end_kind`] and sets the `p_end` field
+    /// of the record that started the scope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no open scope.
+    pub fn end_scope(&mut self) {
+        let (scope_offset, scope_kind) = self.scopes.pop().expect("no open scope");
+
+        let end_kind = scope_kind.scope_end_kind();
+
+        let end_offset = self.stream_offset();
+        self.record(end_kind);
+
+        let p_end_pos = (scope_offset - self.base_offset) as usize + 8;
+        self.buffer[p_end_pos..p_end_pos + 4].copy_from_slice(&end_offset.to_le_bytes());
+    }
+
     /// Adds an `S_UDT` record.
     pub fn udt(&mut self, ty: TypeIndex, name: &BStr) {
         let mut r = self.record(SymKind::S_UDT);
@@ -49,6 +140,491 @@ impl SymBuilder {
         r.enc.u16(segment);
         r.enc.strz(name.into());
     }
+
+    /// Adds an `S_OBJNAME` record.
+    pub fn obj_name(&mut self, signature: u32, name: &BStr) {
+        let mut r = self.record(SymKind::S_OBJNAME);
+        r.enc.u32(signature);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_COMPILE3` record.
+    pub fn compile3(&mut self, fixed: &Compile3Fixed, name: &BStr) {
+        self.fixed_name(SymKind::S_COMPILE3, fixed, name);
+    }
+
+    /// Adds an `S_COMPILE2` record. `extra_strings` may be empty.
+    pub fn compile2(&mut self, fixed: &Compile2Fixed, name: &BStr, extra_strings: &[&BStr]) {
+        let mut r = self.record(SymKind::S_COMPILE2);
+        r.enc.t(fixed);
+        r.enc.strz(name);
+        if !extra_strings.is_empty() {
+            for s in extra_strings {
+                r.enc.strz(s);
+            }
+            r.enc.u8(0);
+        }
+    }
+
+    /// Adds an `S_ENVBLOCK` record.
+    pub fn env_block(&mut self, flags: u8, strings: &[&BStr]) {
+        let mut r = self.record(SymKind::S_ENVBLOCK);
+        r.enc.u8(flags);
+        for s in strings {
+            r.enc.strz(s);
+        }
+        r.enc.u8(0);
+    }
+
+    /// Adds an `S_BUILDINFO` record.
+    pub fn build_info(&mut self, item: ItemId) {
+        self.record(SymKind::S_BUILDINFO).enc.u32(item);
+    }
+
+    /// Adds an `S_UNAMESPACE` record.
+    pub fn using_namespace(&mut self, namespace: &BStr) {
+        self.record(SymKind::S_UNAMESPACE).enc.strz(namespace);
+    }
+
+    /// Starts a procedure scope. `kind` is one of `S_GPROC32`, `S_LPROC32`, `S_GPROC32_ID`, etc.
+    ///
+    /// The `p_parent` and `p_end` fields of `fixed` are ignored.
+    pub fn begin_proc(&mut self, kind: SymKind, fixed: &ProcFixed, name: &BStr) {
+        debug_assert!(kind.is_proc());
+        self.begin_scope(kind, |e| {
+            e.t(fixed);
+            e.strz(name);
+        });
+    }
+
+    /// Starts a managed procedure scope (`S_GMANPROC` or `S_LMANPROC`).
+    ///
+    /// The `p_parent` and `p_end` fields of `fixed` are ignored.
+    pub fn begin_managed_proc(&mut self, kind: SymKind, fixed: &ManagedProcFixed, name: &BStr) {
+        self.begin_scope(kind, |e| {
+            e.t(fixed);
+            e.strz(name);
+        });
+    }
+
+    /// Starts an `S_THUNK32` scope. The `block` field of `fixed` is ignored.
+    pub fn begin_thunk(&mut self, fixed: &ThunkFixed, name: &BStr, variant: &[u8]) {
+        self.begin_scope(SymKind::S_THUNK32, |e| {
+            e.t(fixed);
+            e.strz(name);
+            e.bytes(variant);
+        });
+    }
+
+    /// Starts an `S_BLOCK32` scope.
+    pub fn begin_block(&mut self, length: u32, offset_segment: OffsetSegment, name: &BStr) {
+        self.begin_scope(SymKind::S_BLOCK32, |e| {
+            e.t(&BlockHeader::default());
+            e.u32(length);
+            e.t(&offset_segment);
+            e.strz(name);
+        });
+    }
+
+    /// Starts an `S_SEPCODE` scope. The `block` field of `fixed` is ignored.
+    pub fn begin_sep_code(&mut self, fixed: &SepCode) {
+        self.begin_scope(SymKind::S_SEPCODE, |e| e.t(fixed));
+    }
+
+    /// Starts an `S_INLINESITE` scope.
+    ///
+    /// Returns an error, and does not start the scope, if `annotations` cannot be encoded.
+    pub fn begin_inline_site(
+        &mut self,
+        inlinee: ItemId,
+        annotations: &[BinaryAnnotation],
+    ) -> anyhow::Result<()> {
+        let annotations = encode_annotations_padded(annotations)?;
+        self.begin_scope(SymKind::S_INLINESITE, |e| {
+            e.t(&BlockHeader::default());
+            e.u32(inlinee);
+            e.bytes(&annotations);
+        });
+        Ok(())
+    }
+
+    /// Starts an `S_INLINESITE2` scope.
+    ///
+    /// Returns an error, and does not start the scope, if `annotations` cannot be encoded.
+    pub fn begin_inline_site2(
+        &mut self,
+        inlinee: ItemId,
+        invocations: u32,
+        annotations: &[BinaryAnnotation],
+    ) -> anyhow::Result<()> {
+        let annotations = encode_annotations_padded(annotations)?;
+        self.begin_scope(SymKind::S_INLINESITE2, |e| {
+            e.t(&BlockHeader::default());
+            e.u32(inlinee);
+            e.u32(invocations);
+            e.bytes(&annotations);
+        });
+        Ok(())
+    }
+
+    /// Adds an `S_FRAMEPROC` record.
+    pub fn frame_proc(&mut self, frame_proc: &FrameProc) {
+        self.fixed(SymKind::S_FRAMEPROC, frame_proc);
+    }
+
+    /// Adds an `S_REGREL32` record.
+    pub fn reg_rel(&mut self, fixed: &RegRelFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_REGREL32, fixed, name);
+    }
+
+    /// Adds an `S_LOCAL` record. This should be followed by `S_DEFRANGE_*` records.
+    pub fn local(&mut self, ty: TypeIndex, flags: u16, name: &BStr) {
+        let mut r = self.record(SymKind::S_LOCAL);
+        r.enc.u32(ty.0);
+        r.enc.u16(flags);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_DEFRANGE` record.
+    pub fn def_range(&mut self, fixed: &DefRangeFixed, gaps: &[LVarAddrGap]) {
+        self.fixed_rest(SymKind::S_DEFRANGE, fixed, gaps.as_bytes());
+    }
+
+    /// Adds an `S_DEFRANGE_SUBFIELD` record.
+    pub fn def_range_subfield(&mut self, fixed: &DefRangeSubFieldFixed, gaps: &[LVarAddrGap]) {
+        self.fixed_rest(SymKind::S_DEFRANGE_SUBFIELD, fixed, gaps.as_bytes());
+    }
+
+    /// Adds an `S_DEFRANGE_FRAMEPOINTER_REL` record.
+    pub fn def_range_frame_pointer_rel(
+        &mut self,
+        fixed: &DefRangeSymFramePointerRelFixed,
+        gaps: &[LVarAddrGap],
+    ) {
+        self.fixed_rest(SymKind::S_DEFRANGE_FRAMEPOINTER_REL, fixed, gaps.as_bytes());
+    }
+
+    /// Adds an `S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE` record.
+    pub fn def_range_frame_pointer_rel_full_scope(&mut self, frame_pointer_offset: i32) {
+        let mut r = self.record(SymKind::S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE);
+        r.enc.u32(frame_pointer_offset as u32);
+    }
+
+    /// Adds an `S_DEFRANGE_REGISTER` record.
+    pub fn def_range_register(&mut self, fixed: &DefRangeRegisterFixed, gaps: &[LVarAddrGap]) {
+        self.fixed_rest(SymKind::S_DEFRANGE_REGISTER, fixed, gaps.as_bytes());
+    }
+
+    /// Adds an `S_DEFRANGE_REGISTER_REL` record.
+    pub fn def_range_register_rel(
+        &mut self,
+        fixed: &DefRangeRegisterRelFixed,
+        gaps: &[LVarAddrGap],
+    ) {
+        self.fixed_rest(SymKind::S_DEFRANGE_REGISTER_REL, fixed, gaps.as_bytes());
+    }
+
+    /// Adds an `S_DEFRANGE_SUBFIELD_REGISTER` record.
+    pub fn def_range_subfield_register(
+        &mut self,
+        fixed: &DefRangeSubFieldRegisterFixed,
+        gaps: &[LVarAddrGap],
+    ) {
+        self.fixed_rest(
+            SymKind::S_DEFRANGE_SUBFIELD_REGISTER,
+            fixed,
+            gaps.as_bytes(),
+        );
+    }
+
+    /// Adds an `S_DEFRANGE_HLSL` record. `rest` contains the gaps and the register indices.
+    pub fn def_range_hlsl(&mut self, fixed: &DefRangeHlslFixed, rest: &[u8]) {
+        self.fixed_rest(SymKind::S_DEFRANGE_HLSL, fixed, rest);
+    }
+
+    /// Adds an `S_CONSTANT` record. The value is encoded as a numeric leaf.
+    pub fn constant(&mut self, ty: TypeIndex, value: i64, name: &BStr) {
+        let mut r = self.record(SymKind::S_CONSTANT);
+        r.enc.u32(ty.0);
+        r.enc.number_i64(value);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_MANCONSTANT` record. The value is encoded as a numeric leaf.
+    pub fn managed_constant(&mut self, token: u32, value: i64, name: &BStr) {
+        let mut r = self.record(SymKind::S_MANCONSTANT);
+        r.enc.u32(token);
+        r.enc.number_i64(value);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_PROCREF`, `S_LPROCREF`, `S_DATAREF`, or `S_ANNOTATIONREF` record.
+    pub fn ref_sym2(&mut self, kind: SymKind, fixed: &RefSym2Fixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_GDATA32`, `S_LDATA32`, `S_GMANDATA`, or `S_LMANDATA` record.
+    pub fn data(
+        &mut self,
+        kind: SymKind,
+        ty: TypeIndex,
+        offset_segment: OffsetSegment,
+        name: &BStr,
+    ) {
+        let mut r = self.record(kind);
+        r.enc.u32(ty.0);
+        r.enc.t(&offset_segment);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_GTHREAD32` or `S_LTHREAD32` record.
+    pub fn thread_data(
+        &mut self,
+        kind: SymKind,
+        ty: TypeIndex,
+        offset_segment: OffsetSegment,
+        name: &BStr,
+    ) {
+        self.data(kind, ty, offset_segment, name);
+    }
+
+    /// Adds an `S_LABEL32` record.
+    pub fn label(&mut self, offset_segment: OffsetSegment, flags: u8, name: &BStr) {
+        let mut r = self.record(SymKind::S_LABEL32);
+        r.enc.t(&offset_segment);
+        r.enc.u8(flags);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_TRAMPOLINE` record.
+    pub fn trampoline(&mut self, fixed: &TrampolineFixed, rest: &[u8]) {
+        self.fixed_rest(SymKind::S_TRAMPOLINE, fixed, rest);
+    }
+
+    /// Adds an `S_CALLERS`, `S_CALLEES`, or `S_INLINEES` record. `counts` may be shorter than
+    /// `funcs`, and is always empty for `S_INLINEES`.
+    pub fn function_list(&mut self, kind: SymKind, funcs: &[ItemId], counts: &[u32]) {
+        let mut r = self.record(kind);
+        r.enc.u32(funcs.len() as u32);
+        for &f in funcs {
+            r.enc.u32(f);
+        }
+        for &c in counts {
+            r.enc.u32(c);
+        }
+    }
+
+    /// Adds an `S_FRAMECOOKIE` record.
+    pub fn frame_cookie(&mut self, cookie: &FrameCookie) {
+        self.fixed(SymKind::S_FRAMECOOKIE, cookie);
+    }
+
+    /// Adds an `S_CALLSITEINFO` record.
+    pub fn call_site_info(&mut self, info: &CallSiteInfo) {
+        self.fixed(SymKind::S_CALLSITEINFO, info);
+    }
+
+    /// Adds an `S_HEAPALLOCSITE` record.
+    pub fn heap_alloc_site(&mut self, site: &HeapAllocSite) {
+        self.fixed(SymKind::S_HEAPALLOCSITE, site);
+    }
+
+    /// Adds an `S_ANNOTATION` record.
+    pub fn annotation(&mut self, offset: OffsetSegment, strings: &[&BStr]) {
+        let mut r = self.record(SymKind::S_ANNOTATION);
+        r.enc.t(&offset);
+        r.enc.u16(strings.len() as u16);
+        for s in strings {
+            r.enc.strz(s);
+        }
+    }
+
+    /// Adds an `S_HOTPATCHFUNC` record.
+    pub fn hot_patch_func(&mut self, func: ItemId, name: &BStr) {
+        let mut r = self.record(SymKind::S_HOTPATCHFUNC);
+        r.enc.u32(func);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_COFFGROUP` record.
+    pub fn coff_group(&mut self, fixed: &CoffGroupFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_COFFGROUP, fixed, name);
+    }
+
+    /// Adds an `S_ARMSWITCHTABLE` record.
+    pub fn arm_switch_table(&mut self, table: &ArmSwitchTable) {
+        self.fixed(SymKind::S_ARMSWITCHTABLE, table);
+    }
+
+    /// Adds an `S_SECTION` record.
+    pub fn section(&mut self, fixed: &SectionFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_SECTION, fixed, name);
+    }
+
+    /// Adds an `S_REGISTER` record.
+    pub fn register(&mut self, fixed: &RegisterFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_REGISTER, fixed, name);
+    }
+
+    /// Adds an `S_BPREL32` record.
+    pub fn bp_rel(&mut self, fixed: &BpRelFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_BPREL32, fixed, name);
+    }
+
+    /// Adds an `S_EXPORT` record.
+    pub fn export(&mut self, fixed: &ExportFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_EXPORT, fixed, name);
+    }
+
+    /// Adds an `S_FILESTATIC` record.
+    pub fn file_static(&mut self, fixed: &FileStaticFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_FILESTATIC, fixed, name);
+    }
+
+    /// Adds an `S_LOCALSLOT` or `S_PARAMSLOT` record.
+    pub fn slot(&mut self, kind: SymKind, fixed: &SlotFixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_MANYREG` record.
+    pub fn many_reg(&mut self, ty: TypeIndex, regs: &[u8], name: &BStr) {
+        let mut r = self.record(SymKind::S_MANYREG);
+        r.enc.u32(ty.0);
+        r.enc.u8(regs.len() as u8);
+        r.enc.bytes(regs);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_MANYREG2` record.
+    pub fn many_reg2(&mut self, ty: TypeIndex, regs: &[u16], name: &BStr) {
+        let mut r = self.record(SymKind::S_MANYREG2);
+        r.enc.u32(ty.0);
+        r.enc.u16(regs.len() as u16);
+        for &reg in regs {
+            r.enc.u16(reg);
+        }
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_POGODATA` record.
+    pub fn pogo_data(&mut self, pogo: &PogoData) {
+        self.fixed(SymKind::S_POGODATA, pogo);
+    }
+
+    /// Adds an `S_MOD_TYPEREF` record.
+    pub fn mod_type_ref(&mut self, type_ref: &ModTypeRef) {
+        self.fixed(SymKind::S_MOD_TYPEREF, type_ref);
+    }
+
+    /// Adds an `S_REF_MINIPDB` record.
+    pub fn ref_mini_pdb(&mut self, fixed: &RefMiniPdbFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_REF_MINIPDB, fixed, name);
+    }
+
+    /// Adds an `S_PDBMAP` record.
+    pub fn pdb_map(&mut self, from: &BStr, to: &BStr) {
+        let mut r = self.record(SymKind::S_PDBMAP);
+        r.enc.strz(from);
+        r.enc.strz(to);
+    }
+
+    /// Adds an `S_FASTLINK` record.
+    pub fn fast_link(&mut self, fixed: &FastLinkFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_FASTLINK, fixed, name);
+    }
+
+    /// Adds an `S_DISCARDED` record. `data` is the original record, including its header.
+    pub fn discarded(&mut self, fixed: &DiscardedFixed, data: &[u8]) {
+        self.fixed_rest(SymKind::S_DISCARDED, fixed, data);
+    }
+
+    /// Adds an `S_VFTABLE32` record.
+    pub fn vftable32(&mut self, vftable: &VFTable32) {
+        self.fixed(SymKind::S_VFTABLE32, vftable);
+    }
+
+    /// Adds an `S_GDATA_HLSL` or `S_LDATA_HLSL` record.
+    pub fn data_hlsl(&mut self, kind: SymKind, fixed: &DataHlslFixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_GDATA_HLSL32` or `S_LDATA_HLSL32` record.
+    pub fn data_hlsl32(&mut self, kind: SymKind, fixed: &DataHlsl32Fixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_GDATA_HLSL32_EX` or `S_LDATA_HLSL32_EX` record.
+    pub fn data_hlsl32_ex(&mut self, kind: SymKind, fixed: &DataHlsl32ExFixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_MANSLOT` record.
+    pub fn attr_slot(&mut self, fixed: &AttrSlotFixed, name: &BStr) {
+        self.fixed_name(SymKind::S_MANSLOT, fixed, name);
+    }
+
+    /// Adds an `S_MANREGISTER` or `S_ATTR_REGISTER` record.
+    pub fn attr_register(&mut self, kind: SymKind, fixed: &AttrRegisterFixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_MANFRAMEREL` or `S_ATTR_FRAMEREL` record.
+    pub fn attr_frame_rel(&mut self, kind: SymKind, fixed: &AttrFrameRelFixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_MANREGREL` or `S_ATTR_REGREL` record.
+    pub fn attr_reg_rel(&mut self, kind: SymKind, fixed: &AttrRegRelFixed, name: &BStr) {
+        self.fixed_name(kind, fixed, name);
+    }
+
+    /// Adds an `S_MANMANYREG` or `S_ATTR_MANYREG` record.
+    pub fn attr_many_reg(
+        &mut self,
+        kind: SymKind,
+        ty: TypeIndex,
+        attr: &LVarAttr,
+        regs: &[u8],
+        name: &BStr,
+    ) {
+        let mut r = self.record(kind);
+        r.enc.u32(ty.0);
+        r.enc.t(attr);
+        r.enc.u8(regs.len() as u8);
+        r.enc.bytes(regs);
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_MANMANYREG2` record.
+    pub fn attr_many_reg2(&mut self, ty: TypeIndex, attr: &LVarAttr, regs: &[u16], name: &BStr) {
+        let mut r = self.record(SymKind::S_MANMANYREG2);
+        r.enc.u32(ty.0);
+        r.enc.t(attr);
+        r.enc.u16(regs.len() as u16);
+        for &reg in regs {
+            r.enc.u16(reg);
+        }
+        r.enc.strz(name);
+    }
+
+    /// Adds an `S_OEM` record.
+    pub fn oem(&mut self, fixed: &OemFixed, data: &[u8]) {
+        self.fixed_rest(SymKind::S_OEM, fixed, data);
+    }
+}
+
+/// Encodes binary annotations, padded with `BA_OP_Invalid` to a 4-byte boundary so that the
+/// record padding is not decoded as annotations.
+///
+/// The fixed part of `S_INLINESITE` and `S_INLINESITE2` records is a multiple of 4 bytes long,
+/// so padding the annotations themselves also aligns the record.
+fn encode_annotations_padded(annotations: &[BinaryAnnotation]) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    encode_annotations(annotations, &mut bytes)?;
+    while bytes.len() & 3 != 0 {
+        bytes.push(0);
+    }
+    Ok(bytes)
 }
 
 /// State for writing a single record. When this is dropped, it will terminate the record.
@@ -62,11 +638,12 @@ pub struct RecordBuilder<'a> {
 
 impl<'a> Drop for RecordBuilder<'a> {
     fn drop(&mut self) {
-        // Align the buffer to a 4-byte boundary
+        // Align the buffer to a 4-byte boundary. The number of padding bytes is the distance to
+        // the next boundary, so a buffer that is 1 byte past a boundary needs 3 bytes.
         match self.enc.buf.len() & 3 {
-            1 => self.enc.buf.push(0xf1),
+            1 => self.enc.buf.extend_from_slice(&[0xf1, 0xf2, 0xf3]),
             2 => self.enc.buf.extend_from_slice(&[0xf1, 0xf2]),
-            3 => self.enc.buf.extend_from_slice(&[0xf1, 0xf2, 0xf3]),
+            3 => self.enc.buf.push(0xf1),
             _ => {}
         }
 
@@ -76,3 +653,111 @@ impl<'a> Drop for RecordBuilder<'a> {
         record_field[1] = (record_len >> 8) as u8;
     }
 }
+
+#[test]
+fn test_build_scopes() {
+    use crate::IteratorWithRangesExt;
+    use zerocopy::FromZeros;
+
+    let mut b = SymBuilder::with_base_offset(4);
+    b.obj_name(0, "foo.obj".into());
+
+    let mut proc = ProcFixed::new_zeroed();
+    proc.proc_len = U32::new(0x40);
+    proc.proc_type = TypeIndexLe(U32::new(0x1001));
+    proc.offset_segment = OffsetSegment::new(0x1000, 1);
+    b.begin_proc(SymKind::S_GPROC32_ID, &proc, "main".into());
+
+    let mut frame = FrameProc::new_zeroed();
+    frame.frame_size = U32::new(0x28);
+    b.frame_proc(&frame);
+    b.local(TypeIndex(0x74), 1, "argc".into());
+    b.def_range_frame_pointer_rel_full_scope(8);
+    b.begin_block(0x10, OffsetSegment::new(0x1010, 1), "".into());
+    b.constant(TypeIndex(0x74), -5, "kMinus".into());
+    b.begin_inline_site(
+        0x1234,
+        &[
+            BinaryAnnotation::ChangeCodeOffset(0x12),
+            BinaryAnnotation::ChangeCodeLength(4),
+        ],
+    )
+    .unwrap();
+    let stream = b.finish();
+
+    let recs: Vec<(u32, Sym)> = SymIter::new(&stream)
+        .with_ranges()
+        .map(|(r, sym)| (r.start as u32 + 4, sym))
+        .collect();
+    let kinds: Vec<SymKind> = recs.iter().map(|(_, s)| s.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            SymKind::S_OBJNAME,
+            SymKind::S_GPROC32_ID,
+            SymKind::S_FRAMEPROC,
+            SymKind::S_LOCAL,
+            SymKind::S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE,
+            SymKind::S_BLOCK32,
+            SymKind::S_CONSTANT,
+            SymKind::S_INLINESITE,
+            SymKind::S_INLINESITE_END,
+            SymKind::S_END,
+            SymKind::S_PROC_ID_END,
+        ]
+    );
+
+    let proc_offset = recs[1].0;
+    let block_offset = recs[5].0;
+    let header = |i: usize| BlockHeader::read_from_prefix(recs[i].1.data).unwrap().0;
+
+    assert_eq!(header(1).p_parent.get(), 0);
+    assert_eq!(header(1).p_end.get(), recs[10].0);
+    assert_eq!(header(5).p_parent.get(), proc_offset);
+    assert_eq!(header(5).p_end.get(), recs[9].0);
+    assert_eq!(header(7).p_parent.get(), block_offset);
+    assert_eq!(header(7).p_end.get(), recs[8].0);
+
+    let SymData::Constant(c) = recs[6].1.parse().unwrap() else {
+        panic!()
+    };
+    assert_eq!(i64::try_from(c.value).unwrap(), -5);
+    assert_eq!(c.name, "kMinus");
+
+    let SymData::InlineSite(site) = recs[7].1.parse().unwrap() else {
+        panic!()
+    };
+    assert_eq!(site.fixed.inlinee.get(), 0x1234);
+    let rows: Vec<InlineLineRow> = site.iter_lines(0, 10).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].code_offset_range, 0x12..0x16);
+
+    let SymData::Proc(p) = SymData::parse(SymKind::S_GPROC32, recs[1].1.data).unwrap() else {
+        panic!()
+    };
+    assert_eq!(p.name, "main");
+    assert_eq!(p.fixed.proc_len.get(), 0x40);
+}
+
+#[test]
+fn test_record_padding() {
+    for payload_len in 0..8 {
+        let mut b = SymBuilder::new();
+        b.record(SymKind::S_ANNOTATIONREF)
+            .enc
+            .bytes(&[0xaa; 8][..payload_len]);
+        let stream = b.finish();
+
+        let expected_len = (4 + payload_len).next_multiple_of(4);
+        assert_eq!(stream.len(), expected_len, "payload_len = {payload_len}");
+        assert_eq!(
+            u16::from_le_bytes([stream[0], stream[1]]) as usize,
+            expected_len - 2
+        );
+        let padding = &stream[4 + payload_len..];
+        assert_eq!(padding, &[0xf1, 0xf2, 0xf3][..padding.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests;
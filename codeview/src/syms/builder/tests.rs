@@ -0,0 +1,540 @@
+//! Round-trip tests for the record encoders of `SymBuilder`. Each test encodes records with the
+//! builder and then decodes them with `Sym::parse_as`.
+
+use super::*;
+use zerocopy::FromZeros;
+
+/// Decodes all records in a stream.
+fn records(stream: &[u8]) -> Vec<Sym<'_>> {
+    assert_eq!(stream.len() % 4, 0);
+    SymIter::new(stream).collect()
+}
+
+fn parse<'a, T: Parse<'a>>(sym: &Sym<'a>, kind: SymKind) -> T {
+    assert_eq!(sym.kind, kind);
+    sym.parse_as::<T>().unwrap()
+}
+
+fn range(offset: u32, size: u16) -> LVarAddrRange {
+    LVarAddrRange {
+        start: OffsetSegment::new(offset, 1),
+        range_size: U16::new(size),
+    }
+}
+
+fn gap(start: u16, size: u16) -> LVarAddrGap {
+    LVarAddrGap {
+        gap_start_offset: U16::new(start),
+        range_size: U16::new(size),
+    }
+}
+
+#[test]
+fn compile_records() {
+    let mut b = SymBuilder::new();
+    b.obj_name(0x1234, "foo.obj".into());
+
+    let mut c2 = Compile2Fixed::new_zeroed();
+    c2.flags = U32::new(0x2001);
+    c2.machine = U16::new(0xd0);
+    c2.ver_major = U16::new(19);
+    b.compile2(&c2, "cl 1".into(), &["-Zi".into(), "-O2".into()]);
+    b.compile2(&c2, "cl 2".into(), &[]);
+
+    let mut c3 = Compile3Fixed::new_zeroed();
+    c3.flags = U32::new(0x4001);
+    c3.ver_build = U16::new(30000);
+    c3.ver_qfe = U16::new(7);
+    b.compile3(&c3, "Microsoft (R) Optimizing Compiler".into());
+
+    b.env_block(0, &["cwd".into(), "d:\\src".into()]);
+    b.build_info(0x1005);
+    b.using_namespace("std".into());
+    let stream = b.finish();
+    let recs = records(&stream);
+    assert_eq!(recs.len(), 7);
+
+    let obj: ObjectName = parse(&recs[0], SymKind::S_OBJNAME);
+    assert_eq!(obj.signature, 0x1234);
+    assert_eq!(obj.name, "foo.obj");
+
+    let c: Compile2 = parse(&recs[1], SymKind::S_COMPILE2);
+    assert_eq!(c.fixed.flags.get(), 0x2001);
+    assert_eq!(c.fixed.machine.get(), 0xd0);
+    assert_eq!(c.fixed.ver_major.get(), 19);
+    assert_eq!(c.name, "cl 1");
+    let extra: Vec<&BStr> = c.iter_extra_strings().collect();
+    assert_eq!(extra, ["-Zi", "-O2"]);
+
+    let c: Compile2 = parse(&recs[2], SymKind::S_COMPILE2);
+    assert_eq!(c.name, "cl 2");
+    assert_eq!(c.iter_extra_strings().count(), 0);
+
+    let c: Compile3 = parse(&recs[3], SymKind::S_COMPILE3);
+    assert_eq!(c.fixed.flags.get(), 0x4001);
+    assert_eq!(c.fixed.ver_build.get(), 30000);
+    assert_eq!(c.fixed.ver_qfe.get(), 7);
+    assert_eq!(c.name, "Microsoft (R) Optimizing Compiler");
+
+    let env: EnvBlock = parse(&recs[4], SymKind::S_ENVBLOCK);
+    let strings: Vec<&BStr> = env.iter_strings().collect();
+    assert_eq!(strings, ["cwd", "d:\\src"]);
+
+    let bi: BuildInfo = parse(&recs[5], SymKind::S_BUILDINFO);
+    assert_eq!(bi.item, 0x1005);
+
+    let ns: UsingNamespace = parse(&recs[6], SymKind::S_UNAMESPACE);
+    assert_eq!(ns.namespace, "std");
+}
+
+#[test]
+fn frame_records() {
+    let mut b = SymBuilder::new();
+    let mut frame = FrameProc::new_zeroed();
+    frame.frame_size = U32::new(0x48);
+    frame.save_regs_size = U32::new(0x10);
+    frame.flags = U32::new(0x0011_4200);
+    b.frame_proc(&frame);
+
+    let mut rr = RegRelFixed::new_zeroed();
+    rr.offset = U32::new(0x20);
+    rr.ty = TypeIndexLe(U32::new(0x74));
+    rr.register = U16::new(335);
+    b.reg_rel(&rr, "x".into());
+
+    let mut reg = RegisterFixed::new_zeroed();
+    reg.ty = TypeIndexLe(U32::new(0x75));
+    reg.register = U16::new(17);
+    b.register(&reg, "r".into());
+
+    b.local(TypeIndex(0x1003), 1, "argc".into());
+    b.many_reg(TypeIndex(0x13), &[17, 18], "pair".into());
+    b.many_reg2(TypeIndex(0x13), &[330, 331], "pair2".into());
+    let stream = b.finish();
+    let recs = records(&stream);
+    assert_eq!(recs.len(), 6);
+
+    let f = recs[0].data;
+    assert_eq!(recs[0].kind, SymKind::S_FRAMEPROC);
+    let f = FrameProc::ref_from_prefix(f).unwrap().0;
+    assert_eq!(f.frame_size.get(), 0x48);
+    assert_eq!(f.save_regs_size.get(), 0x10);
+    assert_eq!(f.flags.get(), 0x0011_4200);
+
+    let rr: RegRel = parse(&recs[1], SymKind::S_REGREL32);
+    assert_eq!(rr.fixed.offset.get(), 0x20);
+    assert_eq!(rr.fixed.ty.get(), TypeIndex(0x74));
+    assert_eq!(rr.fixed.register.get(), 335);
+    assert_eq!(rr.name, "x");
+
+    let r: Register = parse(&recs[2], SymKind::S_REGISTER);
+    assert_eq!(r.fixed.register.get(), 17);
+    assert_eq!(r.name, "r");
+
+    let l: Local = parse(&recs[3], SymKind::S_LOCAL);
+    assert_eq!(l.fixed.ty.get(), TypeIndex(0x1003));
+    assert_eq!(l.fixed.flags.get(), 1);
+    assert_eq!(l.name, "argc");
+
+    let m: ManyReg = parse(&recs[4], SymKind::S_MANYREG);
+    assert_eq!(m.ty, TypeIndex(0x13));
+    assert_eq!(m.regs, [17, 18]);
+    assert_eq!(m.name, "pair");
+
+    let m: ManyReg2 = parse(&recs[5], SymKind::S_MANYREG2);
+    let regs: Vec<u16> = m.regs.iter().map(|r| r.get()).collect();
+    assert_eq!(regs, [330, 331]);
+    assert_eq!(m.name, "pair2");
+}
+
+#[test]
+fn def_range_records() {
+    let gaps = [gap(4, 2), gap(10, 1)];
+    let mut b = SymBuilder::new();
+
+    let mut dr = DefRangeFixed::new_zeroed();
+    dr.program = U32::new(7);
+    dr.range = range(0x100, 0x20);
+    b.def_range(&dr, &gaps);
+
+    let mut sf = DefRangeSubFieldFixed::new_zeroed();
+    sf.program = U32::new(8);
+    sf.offset_parent = U32::new(4);
+    sf.range = range(0x100, 0x20);
+    b.def_range_subfield(&sf, &gaps[..1]);
+
+    let mut fp = DefRangeSymFramePointerRelFixed::new_zeroed();
+    fp.offset_to_frame_pointer = U32::new(0x18);
+    fp.range = range(0x110, 0x8);
+    b.def_range_frame_pointer_rel(&fp, &[]);
+
+    b.def_range_frame_pointer_rel_full_scope(-8);
+
+    let mut reg = DefRangeRegisterFixed::new_zeroed();
+    reg.reg = U16::new(17);
+    reg.range = range(0x120, 0x4);
+    b.def_range_register(&reg, &gaps);
+
+    let mut rel = DefRangeRegisterRelFixed::new_zeroed();
+    rel.base_reg = U16::new(335);
+    rel.base_pointer_offset = I32::new(-0x10);
+    rel.range = range(0x130, 0x10);
+    b.def_range_register_rel(&rel, &[]);
+
+    let mut sfr = DefRangeSubFieldRegisterFixed::new_zeroed();
+    sfr.reg = U16::new(18);
+    sfr.flags = U32::new(8);
+    sfr.range = range(0x140, 0x2);
+    b.def_range_subfield_register(&sfr, &gaps[1..]);
+
+    let mut hlsl = DefRangeHlslFixed::new_zeroed();
+    hlsl.reg_type = U16::new(3);
+    hlsl.flags = U16::new(1);
+    hlsl.range = range(0x150, 0x6);
+    b.def_range_hlsl(&hlsl, &[1, 0, 0, 0]);
+
+    let stream = b.finish();
+    let recs = records(&stream);
+    assert_eq!(recs.len(), 8);
+
+    let d: DefRange = parse(&recs[0], SymKind::S_DEFRANGE);
+    assert_eq!(d.fixed.program.get(), 7);
+    assert_eq!(d.fixed.range.start.offset(), 0x100);
+    assert_eq!(d.fixed.range.range_size.get(), 0x20);
+    assert_eq!(d.gaps.len(), 2);
+    assert_eq!(d.gaps[1].gap_start_offset.get(), 10);
+
+    let d: DefRangeSubField = parse(&recs[1], SymKind::S_DEFRANGE_SUBFIELD);
+    assert_eq!(d.fixed.offset_parent.get(), 4);
+    assert_eq!(d.gaps.len(), 1);
+
+    let d: DefRangeSymFramePointerRel = parse(&recs[2], SymKind::S_DEFRANGE_FRAMEPOINTER_REL);
+    assert_eq!(d.fixed.offset_to_frame_pointer.get(), 0x18);
+    assert_eq!(d.fixed.range.start.offset(), 0x110);
+    assert!(d.gaps.is_empty());
+
+    assert_eq!(
+        recs[3].kind,
+        SymKind::S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE
+    );
+    assert_eq!(recs[3].data, (-8i32).to_le_bytes());
+
+    let d: DefRangeRegister = parse(&recs[4], SymKind::S_DEFRANGE_REGISTER);
+    assert_eq!(d.fixed.reg.get(), 17);
+    assert_eq!(d.fixed.range.start.offset(), 0x120);
+    assert_eq!(d.gaps, gaps.as_bytes());
+
+    let d: DefRangeRegisterRel = parse(&recs[5], SymKind::S_DEFRANGE_REGISTER_REL);
+    assert_eq!(d.fixed.base_reg.get(), 335);
+    assert_eq!(d.fixed.base_pointer_offset.get(), -0x10);
+    assert!(d.gaps.is_empty());
+
+    let d: DefRangeSubFieldRegister = parse(&recs[6], SymKind::S_DEFRANGE_SUBFIELD_REGISTER);
+    assert_eq!(d.fixed.reg.get(), 18);
+    assert_eq!(d.fixed.flags.get(), 8);
+    assert_eq!(d.gaps, gaps[1..].as_bytes());
+
+    let d: DefRangeHlsl = parse(&recs[7], SymKind::S_DEFRANGE_HLSL);
+    assert_eq!(d.fixed.reg_type.get(), 3);
+    assert_eq!(d.fixed.range.start.offset(), 0x150);
+    assert_eq!(d.rest, [1, 0, 0, 0]);
+}
+
+#[test]
+fn data_records() {
+    let os = OffsetSegment::new(0x2000, 3);
+    let mut b = SymBuilder::new();
+    b.data(SymKind::S_GDATA32, TypeIndex(0x1010), os, "g_data".into());
+    b.thread_data(SymKind::S_LTHREAD32, TypeIndex(0x74), os, "t_data".into());
+    b.label(os, 4, "label".into());
+    b.constant(TypeIndex(0x74), -5, "kMinus".into());
+    b.managed_constant(0x0600_0001, 1 << 40, "kBig".into());
+    b.udt(TypeIndex(0x1020), "Foo".into());
+    b.pub32(2, 0x2010, 3, "?pub@@YAXXZ");
+
+    let mut rs = RefSym2Fixed::new_zeroed();
+    rs.symbol_offset = U32::new(0x40);
+    rs.module_index = U16::new(5);
+    b.ref_sym2(SymKind::S_PROCREF, &rs, "main".into());
+
+    let mut hlsl = DataHlsl32Fixed::new_zeroed();
+    hlsl.ty = TypeIndexLe(U32::new(0x40));
+    hlsl.data_slot = U32::new(2);
+    hlsl.reg_type = U16::new(5);
+    b.data_hlsl32(SymKind::S_GDATA_HLSL32, &hlsl, "cb".into());
+
+    let stream = b.finish();
+    let recs = records(&stream);
+    assert_eq!(recs.len(), 9);
+
+    let d: Data = parse(&recs[0], SymKind::S_GDATA32);
+    assert_eq!(d.header.type_.get(), TypeIndex(0x1010));
+    assert_eq!(d.header.offset_segment, os);
+    assert_eq!(d.name, "g_data");
+
+    let d: ThreadStorageData = parse(&recs[1], SymKind::S_LTHREAD32);
+    assert_eq!(d.header.type_.get(), TypeIndex(0x74));
+    assert_eq!(d.name, "t_data");
+
+    let l: Label = parse(&recs[2], SymKind::S_LABEL32);
+    assert_eq!(l.fixed.offset_segment, os);
+    assert_eq!(l.fixed.flags, 4);
+    assert_eq!(l.name, "label");
+
+    let c: Constant = parse(&recs[3], SymKind::S_CONSTANT);
+    assert_eq!(c.type_, TypeIndex(0x74));
+    assert_eq!(i64::try_from(c.value).unwrap(), -5);
+    assert_eq!(c.name, "kMinus");
+
+    let c: ManagedConstant = parse(&recs[4], SymKind::S_MANCONSTANT);
+    assert_eq!(c.token, 0x0600_0001);
+    assert_eq!(i64::try_from(c.value).unwrap(), 1 << 40);
+    assert_eq!(c.name, "kBig");
+
+    let u: Udt = parse(&recs[5], SymKind::S_UDT);
+    assert_eq!(u.type_, TypeIndex(0x1020));
+    assert_eq!(u.name, "Foo");
+
+    let p: Pub = parse(&recs[6], SymKind::S_PUB32);
+    assert_eq!(p.fixed.flags.get(), 2);
+    assert_eq!(p.fixed.offset_segment.offset(), 0x2010);
+    assert_eq!(p.fixed.offset_segment.segment(), 3);
+    assert_eq!(p.name, "?pub@@YAXXZ");
+
+    let r: RefSym2 = parse(&recs[7], SymKind::S_PROCREF);
+    assert_eq!(r.header.symbol_offset.get(), 0x40);
+    assert_eq!(r.header.module_index.get(), 5);
+    assert_eq!(r.name, "main");
+
+    let h: DataHlsl32 = parse(&recs[8], SymKind::S_GDATA_HLSL32);
+    assert_eq!(h.fixed.data_slot.get(), 2);
+    assert_eq!(h.fixed.reg_type.get(), 5);
+    assert_eq!(h.name, "cb");
+}
+
+#[test]
+fn attributed_records() {
+    let mut attr = LVarAttr::new_zeroed();
+    attr.offset_segment = OffsetSegment::new(0x300, 1);
+    attr.flags = U16::new(1);
+
+    let mut b = SymBuilder::new();
+    let mut rr = AttrRegRelFixed::new_zeroed();
+    rr.offset = U32::new(0x28);
+    rr.ty = TypeIndexLe(U32::new(0x74));
+    rr.register = U16::new(335);
+    rr.attr = attr.clone();
+    b.attr_reg_rel(SymKind::S_ATTR_REGREL, &rr, "x".into());
+    b.attr_many_reg(
+        SymKind::S_ATTR_MANYREG,
+        TypeIndex(0x13),
+        &attr,
+        &[1, 2, 3],
+        "y".into(),
+    );
+    b.attr_many_reg2(TypeIndex(0x13), &attr, &[400], "z".into());
+
+    let mut slot = SlotFixed::new_zeroed();
+    slot.slot = U32::new(3);
+    slot.ty = TypeIndexLe(U32::new(0x1001));
+    b.slot(SymKind::S_LOCALSLOT, &slot, "s".into());
+
+    let stream = b.finish();
+    let recs = records(&stream);
+    assert_eq!(recs.len(), 4);
+
+    let r: AttrRegRel = parse(&recs[0], SymKind::S_ATTR_REGREL);
+    assert_eq!(r.fixed.offset.get(), 0x28);
+    assert_eq!(r.fixed.register.get(), 335);
+    assert_eq!(r.fixed.attr.offset_segment.offset(), 0x300);
+    assert_eq!(r.name, "x");
+
+    let m: AttrManyReg = parse(&recs[1], SymKind::S_ATTR_MANYREG);
+    assert_eq!(m.ty, TypeIndex(0x13));
+    assert_eq!(m.attr.flags.get(), 1);
+    assert_eq!(m.regs, [1, 2, 3]);
+    assert_eq!(m.name, "y");
+
+    let m: AttrManyReg2 = parse(&recs[2], SymKind::S_MANMANYREG2);
+    assert_eq!(m.regs.len(), 1);
+    assert_eq!(m.regs[0].get(), 400);
+    assert_eq!(m.name, "z");
+
+    let s: Slot = parse(&recs[3], SymKind::S_LOCALSLOT);
+    assert_eq!(s.fixed.slot.get(), 3);
+    assert_eq!(s.fixed.ty.get(), TypeIndex(0x1001));
+    assert_eq!(s.name, "s");
+}
+
+#[test]
+fn scope_records() {
+    let mut b = SymBuilder::new();
+
+    let mut thunk = ThunkFixed::new_zeroed();
+    thunk.offset_segment = OffsetSegment::new(0x10, 1);
+    thunk.length = U16::new(6);
+    thunk.thunk_ordinal = 1;
+    b.begin_thunk(&thunk, "thunk".into(), &[0xaa, 0xbb]);
+    b.end_scope();
+
+    let mut mp = ManagedProcFixed::new_zeroed();
+    mp.proc_len = U32::new(0x30);
+    mp.token = U32::new(0x0600_0002);
+    b.begin_managed_proc(SymKind::S_GMANPROC, &mp, "Managed".into());
+
+    let mut sep = SepCode::new_zeroed();
+    sep.length = U32::new(0x20);
+    sep.offset = U32::new(0x500);
+    b.begin_sep_code(&sep);
+    b.end_scope();
+
+    b.begin_inline_site2(
+        0x1010,
+        3,
+        &[
+            BinaryAnnotation::ChangeCodeOffset(4),
+            BinaryAnnotation::ChangeCodeLength(2),
+        ],
+    )
+    .unwrap();
+    b.end_scope();
+    b.end_scope();
+
+    let stream = b.finish();
+    let recs = records(&stream);
+    let kinds: Vec<SymKind> = recs.iter().map(|s| s.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            SymKind::S_THUNK32,
+            SymKind::S_END,
+            SymKind::S_GMANPROC,
+            SymKind::S_SEPCODE,
+            SymKind::S_END,
+            SymKind::S_INLINESITE2,
+            SymKind::S_INLINESITE_END,
+            SymKind::S_END,
+        ]
+    );
+
+    let t: Thunk = parse(&recs[0], SymKind::S_THUNK32);
+    assert_eq!(t.fixed.length.get(), 6);
+    assert_eq!(t.fixed.thunk_ordinal, 1);
+    assert_eq!(t.name, "thunk");
+    assert_eq!(&t.variant[..2], [0xaa, 0xbb]);
+    assert_ne!(t.fixed.block.p_end.get(), 0);
+
+    let p: ManagedProc = parse(&recs[2], SymKind::S_GMANPROC);
+    assert_eq!(p.fixed.proc_len.get(), 0x30);
+    assert_eq!(p.fixed.token.get(), 0x0600_0002);
+    assert_eq!(p.name, "Managed");
+    assert_ne!(p.fixed.p_end.get(), 0);
+
+    assert_eq!(recs[3].kind, SymKind::S_SEPCODE);
+    let s = SepCode::ref_from_prefix(recs[3].data).unwrap().0;
+    assert_eq!(s.length.get(), 0x20);
+    assert_eq!(s.offset.get(), 0x500);
+    assert_ne!(s.block.p_parent.get(), 0);
+
+    let i: InlineSite2 = parse(&recs[5], SymKind::S_INLINESITE2);
+    assert_eq!(i.fixed.inlinee.get(), 0x1010);
+    assert_eq!(i.fixed.invocations.get(), 3);
+    let annotations: Vec<BinaryAnnotation> = i.iter_annotations().collect();
+    assert_eq!(
+        annotations,
+        [
+            BinaryAnnotation::ChangeCodeOffset(4),
+            BinaryAnnotation::ChangeCodeLength(2),
+        ]
+    );
+}
+
+#[test]
+fn misc_records() {
+    let mut b = SymBuilder::new();
+    b.function_list(SymKind::S_CALLEES, &[0x1001, 0x1002], &[5]);
+    b.annotation(OffsetSegment::new(0x40, 1), &["a".into(), "bc".into()]);
+    b.pdb_map("from.pdb".into(), "to.pdb".into());
+
+    let mut disc = DiscardedFixed::new_zeroed();
+    disc.file_id = U32::new(8);
+    disc.line = U32::new(42);
+    b.discarded(&disc, &[4, 0, 0x4c, 0x11]);
+
+    let mut oem = OemFixed::new_zeroed();
+    oem.oem_id = [0x11; 16];
+    oem.ty = TypeIndexLe(U32::new(0x1005));
+    b.oem(&oem, &[1, 2, 3, 4]);
+
+    let mut tramp = TrampolineFixed::new_zeroed();
+    tramp.thunk_len = U16::new(5);
+    tramp.thunk_offset = U32::new(0x10);
+    tramp.target_offset = U32::new(0x90);
+    b.trampoline(&tramp, &[]);
+
+    let mut site = HeapAllocSite::new_zeroed();
+    site.instruction_size = U16::new(5);
+    site.func_type = TypeIndexLe(U32::new(0x1003));
+    b.heap_alloc_site(&site);
+
+    let mut pogo = PogoData::new_zeroed();
+    pogo.invocations = U32::new(9);
+    b.pogo_data(&pogo);
+
+    let stream = b.finish();
+    let recs = records(&stream);
+    assert_eq!(recs.len(), 8);
+
+    let f: FunctionList = parse(&recs[0], SymKind::S_CALLEES);
+    let funcs: Vec<ItemId> = f.funcs.iter().map(|f| f.get()).collect();
+    assert_eq!(funcs, [0x1001, 0x1002]);
+    assert_eq!(f.counts.len(), 1);
+    assert_eq!(f.counts[0].get(), 5);
+
+    let a: Annotation = parse(&recs[1], SymKind::S_ANNOTATION);
+    assert_eq!(a.fixed.offset.offset(), 0x40);
+    let strings: Vec<&BStr> = a.iter_strings().collect();
+    assert_eq!(strings, ["a", "bc"]);
+
+    let m: PdbMap = parse(&recs[2], SymKind::S_PDBMAP);
+    assert_eq!(m.from, "from.pdb");
+    assert_eq!(m.to, "to.pdb");
+
+    let d: Discarded = parse(&recs[3], SymKind::S_DISCARDED);
+    assert_eq!(d.fixed.file_id.get(), 8);
+    assert_eq!(d.fixed.line.get(), 42);
+    assert_eq!(&d.data[..4], [4, 0, 0x4c, 0x11]);
+
+    let o: Oem = parse(&recs[4], SymKind::S_OEM);
+    assert_eq!(o.fixed.oem_id, [0x11; 16]);
+    assert_eq!(o.fixed.ty.get(), TypeIndex(0x1005));
+    assert_eq!(o.data, [1, 2, 3, 4]);
+
+    let t: Trampoline = parse(&recs[5], SymKind::S_TRAMPOLINE);
+    assert_eq!(t.fixed.thunk_len.get(), 5);
+    assert_eq!(t.fixed.target_offset.get(), 0x90);
+
+    assert_eq!(recs[6].kind, SymKind::S_HEAPALLOCSITE);
+    let h = HeapAllocSite::ref_from_prefix(recs[6].data).unwrap().0;
+    assert_eq!(h.instruction_size.get(), 5);
+    assert_eq!(h.func_type.get(), TypeIndex(0x1003));
+
+    let SymData::PogoData(p) = recs[7].parse().unwrap() else {
+        panic!()
+    };
+    assert_eq!(p.invocations.get(), 9);
+}
+
+#[test]
+fn inline_site_with_unencodable_annotations() {
+    let mut b = SymBuilder::new();
+    let bad = BinaryAnnotation::ChangeCodeOffsetAndLineOffset {
+        code_delta: 0x10,
+        line_delta: 1,
+    };
+    assert!(b.begin_inline_site(0x1000, &[bad]).is_err());
+    assert!(b.begin_inline_site2(0x1000, 1, &[bad]).is_err());
+
+    // No scope was started, so the stream is still empty and balanced.
+    assert!(b.finish().is_empty());
+}
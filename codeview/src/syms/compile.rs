@@ -0,0 +1,339 @@
+//! Decodes the flags and version numbers of `S_COMPILE2` and `S_COMPILE3` records, and identifies
+//! the tool that produced a module.
+
+use super::*;
+use crate::types::{Leaf, TypesIter};
+
+/// Identifies the source language of a module. This is the low 8 bits of the flags of
+/// `S_COMPILE2` and `S_COMPILE3`.
+///
+/// See `CV_CFL_LANG` in `cvconst.h`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SourceLanguage(pub u8);
+
+macro_rules! source_languages {
+    ($($value:expr, $name:ident;)*) => {
+        #[allow(missing_docs)]
+        impl SourceLanguage {
+            $(
+                pub const $name: SourceLanguage = SourceLanguage($value);
+            )*
+
+            /// Gets the name of this language, if it is known.
+            pub fn get_name(self) -> Option<&'static str> {
+                match self {
+                    $(
+                        Self::$name => Some(stringify!($name)),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+source_languages! {
+    0x00, C;
+    0x01, CXX;
+    0x02, FORTRAN;
+    0x03, MASM;
+    0x04, PASCAL;
+    0x05, BASIC;
+    0x06, COBOL;
+    0x07, LINK;
+    0x08, CVTRES;
+    0x09, CVTPGD;
+    0x0a, CSHARP;
+    0x0b, VB;
+    0x0c, ILASM;
+    0x0d, JAVA;
+    0x0e, JSCRIPT;
+    0x0f, MSIL;
+    0x10, HLSL;
+    0x11, OBJC;
+    0x12, OBJCXX;
+    0x13, SWIFT;
+    0x14, ALIASOBJ;
+    0x15, RUST;
+    0x16, GO;
+    0x44, D;
+}
+
+impl std::fmt::Debug for SourceLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.get_name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "??(0x{:02x})", self.0),
+        }
+    }
+}
+
+impl std::fmt::Display for SourceLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+bitflags! {
+    /// Flags of `S_COMPILE2` and `S_COMPILE3`, excluding the language (bits 0-7).
+    ///
+    /// `S_COMPILE2` defines only the flags up to and including `MSIL_MODULE`.
+    ///
+    /// Whether a module was compiled with `/Zi` or `/Z7` is not recorded in these flags; see
+    /// [`DebugInfoFormat`]. `/ZI` (edit and continue) is recorded as `EDIT_AND_CONTINUE`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CompileFlags: u32 {
+        /// `fEC`: compiled for edit and continue (`/ZI`)
+        const EDIT_AND_CONTINUE = 1 << 8;
+        /// `fNoDbgInfo`: not compiled with debug info
+        const NO_DEBUG_INFO = 1 << 9;
+        /// `fLTCG`: compiled with link-time code generation (`/GL`)
+        const LTCG = 1 << 10;
+        /// `fNoDataAlign`: compiled with `-Bzalign`
+        const NO_DATA_ALIGN = 1 << 11;
+        /// `fManagedPresent`: managed code or data is present
+        const MANAGED_PRESENT = 1 << 12;
+        /// `fSecurityChecks`: compiled with `/GS`
+        const SECURITY_CHECKS = 1 << 13;
+        /// `fHotPatch`: compiled with `/hotpatch`
+        const HOT_PATCH = 1 << 14;
+        /// `fCVTCIL`: converted with CVTCIL
+        const CVTCIL = 1 << 15;
+        /// `fMSILModule`: MSIL netmodule
+        const MSIL_MODULE = 1 << 16;
+        /// `fSdl`: compiled with `/sdl`
+        const SDL = 1 << 17;
+        /// `fPGO`: compiled with `/ltcg:pgo` or `pgu`
+        const PGO = 1 << 18;
+        /// `fExp`: this is a `.exp` module
+        const EXP_MODULE = 1 << 19;
+    }
+}
+
+/// The compiler option that controlled how the debug information of a module was written.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DebugInfoFormat {
+    /// The module was compiled without debug information (`fNoDbgInfo`).
+    None,
+    /// `/Z7`: the type records are stored in the object file.
+    Z7,
+    /// `/Zi`: the type records are stored in a PDB (a type server), and the object file refers
+    /// to it with an `LF_TYPESERVER2` record.
+    Zi,
+    /// `/ZI`: like `/Zi`, and the module was also compiled for edit and continue.
+    ZiEditAndContinue,
+}
+
+impl DebugInfoFormat {
+    /// Determines the format from the flags of the `S_COMPILE2` or `S_COMPILE3` record of a
+    /// module and from whether the module's type records refer to a type server.
+    ///
+    /// The compile flags do not distinguish `/Zi` from `/Z7`, so the caller must determine
+    /// `uses_type_server`. For an object file, use [`types_use_type_server`] on the contents of
+    /// its `.debug$T` section. For a module in a linked PDB, the type server index in the
+    /// module's Module Info record is non-zero if the module used a type server.
+    pub fn new(flags: CompileFlags, uses_type_server: bool) -> Self {
+        if flags.contains(CompileFlags::NO_DEBUG_INFO) {
+            Self::None
+        } else if flags.contains(CompileFlags::EDIT_AND_CONTINUE) {
+            Self::ZiEditAndContinue
+        } else if uses_type_server {
+            Self::Zi
+        } else {
+            Self::Z7
+        }
+    }
+
+    /// The compiler option, e.g. `/Zi`.
+    pub fn option(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Z7 => Some("/Z7"),
+            Self::Zi => Some("/Zi"),
+            Self::ZiEditAndContinue => Some("/ZI"),
+        }
+    }
+}
+
+/// Checks whether the type records of an object file refer to a type server, which means that
+/// the object file was compiled with `/Zi` or `/ZI`.
+///
+/// `debug_types` is the contents of a `.debug$T` section. It may begin with the 4-byte CodeView
+/// signature. A type server is used if the first type record is `LF_TYPESERVER2` (or the older
+/// `LF_TYPESERVER`).
+pub fn types_use_type_server(debug_types: &[u8]) -> bool {
+    let records = match debug_types.get(..4) {
+        Some(signature) if u32::from_le_bytes(signature.try_into().unwrap()) < 0x1000 => {
+            &debug_types[4..]
+        }
+        _ => debug_types,
+    };
+
+    matches!(
+        TypesIter::new(records).next().map(|r| r.kind),
+        Some(Leaf::LF_TYPESERVER2 | Leaf::LF_TYPESERVER | Leaf::LF_TYPESERVER_ST)
+    )
+}
+
+/// The family of tools that produced a module. See [`Compile3::toolchain`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Toolchain {
+    /// The Microsoft C/C++ compiler (`cl.exe`)
+    Msvc,
+    /// `clang-cl` or another LLVM-based C/C++ compiler
+    Clang,
+    /// The Rust compiler
+    Rustc,
+    /// The Microsoft Macro Assembler (`ml.exe`, `ml64.exe`, `armasm64.exe`)
+    Masm,
+    /// The linker. This is the `* Linker *` module.
+    Linker,
+    /// The resource converter (`cvtres.exe`)
+    Cvtres,
+    /// Something else
+    Other,
+}
+
+/// A version number of a tool. Versions compare in the order major, minor, build, qfe.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct ToolVersion {
+    #[allow(missing_docs)]
+    pub major: u16,
+    #[allow(missing_docs)]
+    pub minor: u16,
+    #[allow(missing_docs)]
+    pub build: u16,
+    #[allow(missing_docs)]
+    pub qfe: u16,
+}
+
+impl ToolVersion {
+    /// Constructor
+    pub fn new(major: u16, minor: u16, build: u16, qfe: u16) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+            qfe,
+        }
+    }
+}
+
+impl std::fmt::Display for ToolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.qfe
+        )
+    }
+}
+
+/// Identifies a tool from the language and the version string of its `S_COMPILE*` record.
+fn classify_toolchain(language: SourceLanguage, name: &BStr) -> Toolchain {
+    let name: &[u8] = name;
+    let contains = |s: &str| {
+        name.windows(s.len())
+            .any(|w| w.eq_ignore_ascii_case(s.as_bytes()))
+    };
+
+    match language {
+        SourceLanguage::LINK => return Toolchain::Linker,
+        SourceLanguage::CVTRES => return Toolchain::Cvtres,
+        SourceLanguage::MASM => return Toolchain::Masm,
+        SourceLanguage::RUST => return Toolchain::Rustc,
+        _ => {}
+    }
+
+    if contains("clang") || contains("LLVM") {
+        Toolchain::Clang
+    } else if contains("rustc") {
+        Toolchain::Rustc
+    } else if contains("Macro Assembler") {
+        Toolchain::Masm
+    } else if contains("Optimizing Compiler") {
+        Toolchain::Msvc
+    } else if contains("LINK") {
+        Toolchain::Linker
+    } else {
+        Toolchain::Other
+    }
+}
+
+impl Compile3Fixed {
+    /// The source language
+    pub fn language(&self) -> SourceLanguage {
+        SourceLanguage(self.flags.get() as u8)
+    }
+
+    /// The flags, excluding the language
+    pub fn compile_flags(&self) -> CompileFlags {
+        CompileFlags::from_bits_truncate(self.flags.get())
+    }
+
+    /// The version of the front-end (the compiler, for most tools)
+    pub fn frontend_version(&self) -> ToolVersion {
+        ToolVersion::new(
+            self.frontend_major.get(),
+            self.frontend_minor.get(),
+            self.frontend_build.get(),
+            self.frontend_qfe.get(),
+        )
+    }
+
+    /// The version of the back-end (the code generator)
+    pub fn backend_version(&self) -> ToolVersion {
+        ToolVersion::new(
+            self.ver_major.get(),
+            self.ver_minor.get(),
+            self.ver_build.get(),
+            self.ver_qfe.get(),
+        )
+    }
+}
+
+impl<'a> Compile3<'a> {
+    /// Identifies the tool that produced this module.
+    pub fn toolchain(&self) -> Toolchain {
+        classify_toolchain(self.fixed.language(), self.name)
+    }
+}
+
+impl Compile2Fixed {
+    /// The source language
+    pub fn language(&self) -> SourceLanguage {
+        SourceLanguage(self.flags.get() as u8)
+    }
+
+    /// The flags, excluding the language
+    pub fn compile_flags(&self) -> CompileFlags {
+        CompileFlags::from_bits_truncate(self.flags.get())
+    }
+
+    /// The version of the front-end. `S_COMPILE2` does not record a QFE number, so it is 0.
+    pub fn frontend_version(&self) -> ToolVersion {
+        ToolVersion::new(
+            self.frontend_major.get(),
+            self.frontend_minor.get(),
+            self.frontend_build.get(),
+            0,
+        )
+    }
+
+    /// The version of the back-end. `S_COMPILE2` does not record a QFE number, so it is 0.
+    pub fn backend_version(&self) -> ToolVersion {
+        ToolVersion::new(
+            self.ver_major.get(),
+            self.ver_minor.get(),
+            self.ver_build.get(),
+            0,
+        )
+    }
+}
+
+impl<'a> Compile2<'a> {
+    /// Identifies the tool that produced this module.
+    pub fn toolchain(&self) -> Toolchain {
+        classify_toolchain(self.fixed.language(), self.name)
+    }
+}
@@ -0,0 +1,448 @@
+//! Supports editing existing symbol streams
+
+use super::builder::SymBuilder;
+use super::*;
+use crate::IteratorWithRangesExt;
+use std::borrow::Cow;
+
+/// Edits the records of an existing symbol stream.
+///
+/// The editor holds a list of records. Records can be deleted, inserted, or replaced, and then
+/// [`SymStreamEditor::finish`] writes a new symbol stream. While writing, the editor recomputes
+/// record offsets and alignment padding, and it rewrites the `p_parent`, `p_end`, and `p_next`
+/// fields of records that start scopes so that they agree with the new layout. Scope nesting is
+/// determined by the kinds of the records (see [`SymKind::starts_scope`] and
+/// [`SymKind::ends_scope`]), so a caller that removes a scope must remove its end record, too;
+/// [`SymStreamEditor::delete_scope`] does this.
+///
+/// `finish` also returns a [`SymOffsetMap`], which maps the offsets of the original records to
+/// their new offsets. Callers use this to fix up references into the stream that live elsewhere,
+/// such as `S_PROCREF` records in the global symbol stream or the global refs of a module.
+pub struct SymStreamEditor<'a> {
+    base_offset: u32,
+    records: Vec<EditRecord<'a>>,
+}
+
+struct EditRecord<'a> {
+    /// The stream offset of the record in the original stream. `None` for new records.
+    old_offset: Option<u32>,
+    kind: SymKind,
+    data: Cow<'a, [u8]>,
+}
+
+impl<'a> SymStreamEditor<'a> {
+    /// Loads the records of a symbol stream. `base_offset` is the stream offset of `data[0]`.
+    pub fn new(data: &'a [u8], base_offset: u32) -> Self {
+        let records = SymIter::new(data)
+            .with_ranges()
+            .map(|(range, sym)| EditRecord {
+                old_offset: Some(base_offset + range.start as u32),
+                kind: sym.kind,
+                data: Cow::Borrowed(sym.data),
+            })
+            .collect();
+        Self {
+            base_offset,
+            records,
+        }
+    }
+
+    /// Loads the records of a module symbol stream. The 4-byte signature at the start of `data`
+    /// is skipped; offsets (including those in the output of `finish`) are stream offsets that
+    /// include the signature.
+    pub fn for_module_syms(data: &'a [u8]) -> Self {
+        if data.len() < 4 {
+            return Self::new(&[], 4);
+        }
+        Self::new(&data[4..], 4)
+    }
+
+    /// The number of records.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns `true` if there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Gets the record at `index`.
+    pub fn sym(&self, index: usize) -> Sym<'_> {
+        let r = &self.records[index];
+        Sym {
+            kind: r.kind,
+            data: &r.data,
+        }
+    }
+
+    /// Gets the offset that the record at `index` had in the original stream, or `None` if the
+    /// record was inserted.
+    pub fn old_offset(&self, index: usize) -> Option<u32> {
+        self.records[index].old_offset
+    }
+
+    /// Finds the current index of the record that was at `old_offset` in the original stream.
+    pub fn find(&self, old_offset: u32) -> Option<usize> {
+        self.records
+            .iter()
+            .position(|r| r.old_offset == Some(old_offset))
+    }
+
+    /// Iterates the records.
+    pub fn iter(&self) -> impl Iterator<Item = Sym<'_>> + '_ {
+        self.records.iter().map(|r| Sym {
+            kind: r.kind,
+            data: &r.data,
+        })
+    }
+
+    /// Deletes the record at `index`.
+    pub fn delete(&mut self, index: usize) {
+        self.records.remove(index);
+    }
+
+    /// Deletes the record at `index` and, if it starts a scope, all of the records within the
+    /// scope and the record that ends it. Returns the number of records deleted.
+    pub fn delete_scope(&mut self, index: usize) -> usize {
+        let end = self.scope_end(index);
+        self.records.drain(index..end);
+        end - index
+    }
+
+    /// Inserts a new record before the record at `index`.
+    ///
+    /// `data` is the payload of the record, without the length and kind fields. If the record
+    /// starts a scope, then this also inserts the record that ends the scope (see
+    /// [`SymKind::scope_end_kind`]) at `index + 1`, so the new scope is empty. Records can be
+    /// added to the scope by inserting them at `index + 1`. The `BlockHeader` of the new record
+    /// will be set by `finish`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `kind` ends a scope. Scope end records are only inserted along with the record
+    /// that starts the scope.
+    pub fn insert(&mut self, index: usize, kind: SymKind, data: Vec<u8>) {
+        assert!(
+            !kind.ends_scope(),
+            "cannot insert a record that ends a scope: {kind:?}"
+        );
+
+        if kind.starts_scope() {
+            self.records.insert(
+                index,
+                EditRecord {
+                    old_offset: None,
+                    kind: kind.scope_end_kind(),
+                    data: Cow::Owned(Vec::new()),
+                },
+            );
+        }
+
+        self.records.insert(
+            index,
+            EditRecord {
+                old_offset: None,
+                kind,
+                data: Cow::Owned(data),
+            },
+        );
+    }
+
+    /// Replaces the record at `index`. The new record takes the place of the old record in the
+    /// offset map.
+    ///
+    /// Returns an error, and leaves the record unchanged, if the new record would change the
+    /// scope nesting: a record that starts (or ends) a scope can only be replaced by another
+    /// record that starts (or ends) a scope. Use [`Self::delete_scope`] and [`Self::insert`] to
+    /// replace a scope with a record that is not a scope.
+    pub fn replace(&mut self, index: usize, kind: SymKind, data: Vec<u8>) -> anyhow::Result<()> {
+        let r = &mut self.records[index];
+        if kind.starts_scope() != r.kind.starts_scope() || kind.ends_scope() != r.kind.ends_scope()
+        {
+            anyhow::bail!(
+                "Cannot replace {:?} with {kind:?}, because that would change the scope nesting",
+                r.kind
+            );
+        }
+        r.kind = kind;
+        r.data = Cow::Owned(data);
+        Ok(())
+    }
+
+    /// Gets the index one past the end of the scope that starts at `index`, including the record
+    /// that ends the scope. If the record at `index` does not start a scope, returns `index + 1`.
+    fn scope_end(&self, index: usize) -> usize {
+        let mut depth = 0usize;
+        for (i, r) in self.records.iter().enumerate().skip(index) {
+            if r.kind.starts_scope() {
+                depth += 1;
+            } else if r.kind.ends_scope() {
+                depth = depth.saturating_sub(1);
+            }
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+        self.records.len()
+    }
+
+    /// Writes the new symbol stream. The output does not contain a module signature; the first
+    /// record is at the `base_offset` that was given when the editor was created.
+    pub fn finish(self) -> (Vec<u8>, SymOffsetMap) {
+        let mut b = SymBuilder::with_base_offset(self.base_offset);
+        let mut new_offsets: Vec<u32> = Vec::with_capacity(self.records.len());
+        let mut map: Vec<(u32, u32)> = Vec::with_capacity(self.records.len());
+
+        for r in self.records.iter() {
+            let offset = b.stream_offset();
+            new_offsets.push(offset);
+            if let Some(old) = r.old_offset {
+                map.push((old, offset));
+            }
+            b.record(r.kind).enc.bytes(&r.data);
+        }
+
+        map.sort_unstable();
+        let map = SymOffsetMap { map };
+
+        let base_offset = self.base_offset;
+        let mut buffer = b.buffer;
+        let header_at = |offset: u32| -> usize { (offset - base_offset) as usize + 4 };
+
+        let mut stack: Vec<u32> = Vec::new();
+        for (r, &offset) in self.records.iter().zip(new_offsets.iter()) {
+            if r.kind.ends_scope() {
+                if let Some(start) = stack.pop()
+                    && let Some(h) = block_header_mut(&mut buffer, header_at(start))
+                {
+                    h.p_end = U32::new(offset);
+                }
+                continue;
+            }
+
+            if r.kind.starts_scope() {
+                let p_parent = stack.last().copied().unwrap_or(0);
+                if let Some(h) = block_header_mut(&mut buffer, header_at(offset)) {
+                    h.p_parent = U32::new(p_parent);
+                    h.p_end = U32::new(0);
+                }
+                stack.push(offset);
+
+                // Procedures (native and managed) and thunks have a p_next field, which
+                // immediately follows the BlockHeader. It points to another record in the same
+                // stream, so remap it.
+                if r.kind.is_proc()
+                    || matches!(
+                        r.kind,
+                        SymKind::S_THUNK32 | SymKind::S_GMANPROC | SymKind::S_LMANPROC
+                    )
+                {
+                    let pos = header_at(offset) + size_of::<BlockHeader>();
+                    if let Some(p_next) = buffer
+                        .get_mut(pos..pos + 4)
+                        .and_then(|b| U32::<LE>::mut_from_bytes(b).ok())
+                        && p_next.get() != 0
+                    {
+                        *p_next = U32::new(map.get(p_next.get()).unwrap_or(0));
+                    }
+                }
+            }
+        }
+
+        (buffer, map)
+    }
+}
+
+fn block_header_mut(buffer: &mut [u8], pos: usize) -> Option<&mut BlockHeader> {
+    BlockHeader::mut_from_prefix(buffer.get_mut(pos..)?)
+        .ok()
+        .map(|(h, _)| h)
+}
+
+/// Maps the offsets of records in an original symbol stream to the offsets of the same records
+/// in an edited symbol stream. Produced by [`SymStreamEditor::finish`].
+#[derive(Clone, Debug, Default)]
+pub struct SymOffsetMap {
+    /// (old, new) pairs, sorted by old offset
+    map: Vec<(u32, u32)>,
+}
+
+impl SymOffsetMap {
+    /// Gets the new offset of the record that was at `old_offset`. Returns `None` if there was no
+    /// record at `old_offset` or if the record was deleted.
+    pub fn get(&self, old_offset: u32) -> Option<u32> {
+        let i = self
+            .map
+            .binary_search_by_key(&old_offset, |&(old, _)| old)
+            .ok()?;
+        Some(self.map[i].1)
+    }
+
+    /// Iterates the `(old, new)` offset pairs, sorted by old offset.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.map.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TypeIndex;
+    use zerocopy::FromZeros;
+
+    fn build() -> Vec<u8> {
+        let mut b = SymBuilder::with_base_offset(4);
+        b.udt(TypeIndex(0x1000), "T".into());
+        let mut proc = ProcFixed::new_zeroed();
+        proc.proc_len = U32::new(0x100);
+        b.begin_proc(SymKind::S_GPROC32, &proc, "f".into());
+        b.local(TypeIndex(0x74), 0, "a".into());
+        b.begin_block(0x20, OffsetSegment::new(0x10, 1), "".into());
+        b.local(TypeIndex(0x74), 0, "b".into());
+        b.end_scope();
+        b.end_scope();
+        b.begin_proc(SymKind::S_GPROC32, &proc, "g".into());
+        b.end_scope();
+        b.finish()
+    }
+
+    #[test]
+    fn edit_delete_insert() {
+        let stream = build();
+        let old = SymTree::new(&stream, 4);
+        let old_g = old.roots().nth(2).unwrap();
+        let old_g_offset = old.node(old_g).offset;
+
+        let mut ed = SymStreamEditor::new(&stream, 4);
+        // Remove the S_UDT, and the block with everything in it.
+        ed.delete(0);
+        let block = (0..ed.len())
+            .find(|&i| ed.sym(i).kind == SymKind::S_BLOCK32)
+            .unwrap();
+        assert_eq!(ed.delete_scope(block), 3);
+
+        // Add a long local, at the start of `f`.
+        let mut b = SymBuilder::new();
+        b.local(TypeIndex(0x75), 0, "a_very_long_name".into());
+        let new_local = SymIter::one(&b.buffer).unwrap();
+        ed.insert(1, new_local.kind, new_local.data.to_vec());
+
+        let (new_stream, map) = ed.finish();
+        let tree = SymTree::new(&new_stream, 4);
+        assert!(tree.mismatches().is_empty(), "{:?}", tree.mismatches());
+        assert_eq!(new_stream.len() % 4, 0);
+
+        let kinds: Vec<SymKind> = tree.nodes().iter().map(|n| n.sym.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SymKind::S_GPROC32,
+                SymKind::S_LOCAL,
+                SymKind::S_LOCAL,
+                SymKind::S_END,
+                SymKind::S_GPROC32,
+                SymKind::S_END
+            ]
+        );
+
+        assert_eq!(map.get(4), None);
+        assert_eq!(map.get(old_g_offset), Some(tree.node(4).offset));
+        assert_eq!(map.get(old.node(1).offset), Some(4));
+    }
+
+    #[test]
+    fn insert_scope() {
+        let stream = build();
+        let mut ed = SymStreamEditor::new(&stream, 4);
+
+        // Insert an inline site at the start of `g`, and put a local in it.
+        let g = (0..ed.len())
+            .filter(|&i| ed.sym(i).kind == SymKind::S_GPROC32)
+            .nth(1)
+            .unwrap();
+        let mut b = SymBuilder::new();
+        b.begin_inline_site(0x1234, &[]).unwrap();
+        let site = SymIter::one(&b.buffer).unwrap();
+        ed.insert(g + 1, site.kind, site.data.to_vec());
+        assert_eq!(ed.sym(g + 2).kind, SymKind::S_INLINESITE_END);
+
+        let mut b = SymBuilder::new();
+        b.local(TypeIndex(0x74), 0, "c".into());
+        let local = SymIter::one(&b.buffer).unwrap();
+        ed.insert(g + 2, local.kind, local.data.to_vec());
+
+        let (new_stream, _) = ed.finish();
+        let tree = SymTree::new(&new_stream, 4);
+        assert!(tree.mismatches().is_empty(), "{:?}", tree.mismatches());
+
+        let kinds: Vec<SymKind> = tree.nodes().iter().map(|n| n.sym.kind).collect();
+        assert_eq!(
+            &kinds[kinds.len() - 5..],
+            [
+                SymKind::S_GPROC32,
+                SymKind::S_INLINESITE,
+                SymKind::S_LOCAL,
+                SymKind::S_INLINESITE_END,
+                SymKind::S_END
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_scope_end() {
+        let stream = build();
+        let mut ed = SymStreamEditor::new(&stream, 4);
+        ed.insert(1, SymKind::S_END, Vec::new());
+    }
+
+    #[test]
+    fn replace_keeps_scopes_balanced() {
+        let stream = build();
+        let mut ed = SymStreamEditor::new(&stream, 4);
+        let block = (0..ed.len())
+            .find(|&i| ed.sym(i).kind == SymKind::S_BLOCK32)
+            .unwrap();
+        let local = block + 1;
+
+        let mut b = SymBuilder::new();
+        b.begin_block(0x30, OffsetSegment::new(0x10, 1), "inner".into());
+        let new_block = SymIter::one(&b.buffer).unwrap();
+        let mut b = SymBuilder::new();
+        b.local(TypeIndex(0x75), 0, "z".into());
+        let new_local = SymIter::one(&b.buffer).unwrap();
+
+        // A scope can be replaced by another scope, and a non-scope by a non-scope.
+        ed.replace(block, new_block.kind, new_block.data.to_vec())
+            .unwrap();
+        ed.replace(local, new_local.kind, new_local.data.to_vec())
+            .unwrap();
+
+        // But not by a record that changes the nesting.
+        assert!(
+            ed.replace(block, new_local.kind, new_local.data.to_vec())
+                .is_err()
+        );
+        assert!(
+            ed.replace(local, new_block.kind, new_block.data.to_vec())
+                .is_err()
+        );
+        assert!(ed.replace(local, SymKind::S_END, Vec::new()).is_err());
+        assert_eq!(ed.sym(block).data, new_block.data);
+        assert_eq!(ed.sym(local).kind, SymKind::S_LOCAL);
+
+        let (new_stream, _) = ed.finish();
+        let tree = SymTree::new(&new_stream, 4);
+        assert!(tree.mismatches().is_empty(), "{:?}", tree.mismatches());
+    }
+
+    #[test]
+    fn edit_identity() {
+        let stream = build();
+        let ed = SymStreamEditor::new(&stream, 4);
+        let (new_stream, map) = ed.finish();
+        assert_eq!(new_stream, stream);
+        assert!(map.iter().all(|(old, new)| old == new));
+    }
+}
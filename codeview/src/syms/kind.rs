@@ -210,6 +210,7 @@ impl SymKind {
                 | SymKind::S_LPROC32_DPC
                 | SymKind::S_LPROC32_DPC_ID
                 | SymKind::S_GPROC32_ID
+                | SymKind::S_LPROC32_ID
                 | SymKind::S_BLOCK32
                 | SymKind::S_THUNK32
                 | SymKind::S_INLINESITE
@@ -220,6 +221,20 @@ impl SymKind {
         )
     }
 
+    /// The kind of the record that ends a scope that was started by a record of this kind.
+    ///
+    /// This is `S_INLINESITE_END` for inline sites, `S_PROC_ID_END` for `S_*PROC32_ID`
+    /// procedures, and `S_END` for everything else.
+    pub fn scope_end_kind(self) -> SymKind {
+        match self {
+            SymKind::S_INLINESITE | SymKind::S_INLINESITE2 => SymKind::S_INLINESITE_END,
+            SymKind::S_GPROC32_ID | SymKind::S_LPROC32_ID | SymKind::S_LPROC32_DPC_ID => {
+                SymKind::S_PROC_ID_END
+            }
+            _ => SymKind::S_END,
+        }
+    }
+
     /// True if this `SymKind` is a procedure definition.
     pub fn is_proc(self) -> bool {
         matches!(
@@ -0,0 +1,365 @@
+//! Evaluates the `S_DEFRANGE_*` records that follow an `S_LOCAL` record, to find where a local
+//! variable is stored at a given code address.
+
+use super::*;
+
+/// The location of a local variable (or of a piece of one) at a specific code address.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VarLocation {
+    /// The value is in a register.
+    Register(ArchReg),
+    /// The value is in memory, at `reg + offset`.
+    RegisterRelative {
+        /// The base register
+        reg: ArchReg,
+        /// The offset from the value of `reg`
+        offset: i32,
+    },
+    /// The value is in memory, at an offset from the frame pointer. The frame pointer register
+    /// is described by the `S_FRAMEPROC` record of the enclosing procedure.
+    FramePointerRelative {
+        /// The offset from the frame pointer
+        offset: i32,
+    },
+    /// The value is computed by a DIA program (`S_DEFRANGE`, `S_DEFRANGE_SUBFIELD`). These are
+    /// not evaluated.
+    Program(u32),
+    /// The variable is split into pieces, each of which is stored in a different place.
+    Pieces(Vec<VarPiece>),
+}
+
+impl VarLocation {
+    /// Replaces [`VarLocation::FramePointerRelative`] (including within pieces) with
+    /// [`VarLocation::RegisterRelative`], using `frame_reg` as the frame pointer. Use
+    /// [`FrameProc::frame_register`] to find `frame_reg`.
+    pub fn resolve_frame_pointer(self, frame_reg: ArchReg) -> Self {
+        match self {
+            Self::FramePointerRelative { offset } => Self::RegisterRelative {
+                reg: frame_reg,
+                offset,
+            },
+            Self::Pieces(pieces) => Self::Pieces(
+                pieces
+                    .into_iter()
+                    .map(|p| VarPiece {
+                        offset_in_parent: p.offset_in_parent,
+                        location: p.location.resolve_frame_pointer(frame_reg),
+                    })
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+}
+
+/// Describes the location of part of a variable. See [`VarLocation::Pieces`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VarPiece {
+    /// The byte offset of this piece within the variable.
+    pub offset_in_parent: u32,
+    /// The location of this piece
+    pub location: VarLocation,
+}
+
+/// An `S_LOCAL` record and the `S_DEFRANGE_*` records that describe where it lives.
+#[derive(Clone, Debug)]
+pub struct LocalVar<'a> {
+    /// The `S_LOCAL` record
+    pub local: Local<'a>,
+    /// The `S_DEFRANGE_*` records that immediately follow the `S_LOCAL` record
+    pub def_ranges: Vec<SymData<'a>>,
+}
+
+impl<'a> LocalVar<'a> {
+    /// Parses an `S_LOCAL` record and collects the def-range records that follow it.
+    ///
+    /// `following` should be positioned on the record immediately after `local`. Records are
+    /// read from it until a record is found that is not a def-range record.
+    pub fn new(local: &Sym<'a>, following: SymIter<'a>) -> Result<Self, ParserError> {
+        if local.kind != SymKind::S_LOCAL {
+            return Err(ParserError::new());
+        }
+        let local: Local<'a> = local.parse_as()?;
+        let mut def_ranges = Vec::new();
+        for sym in following {
+            if !is_def_range_kind(sym.kind) {
+                break;
+            }
+            def_ranges.push(sym.parse()?);
+        }
+        Ok(Self { local, def_ranges })
+    }
+
+    /// Returns `true` if this variable is a parameter (`fIsParam`).
+    pub fn is_param(&self) -> bool {
+        self.local.fixed.flags.get() & 1 != 0
+    }
+
+    /// Finds the location of the variable at the code address `addr`, and resolves
+    /// frame-relative locations using the `S_FRAMEPROC` record of the enclosing procedure.
+    ///
+    /// If the frame register cannot be decoded, frame-relative locations are left unresolved.
+    pub fn location_in_frame(
+        &self,
+        arch: Arch,
+        frame_proc: &FrameProc,
+        addr: OffsetSegment,
+    ) -> Option<VarLocation> {
+        let location = self.location_at(arch, addr)?;
+        Some(match frame_proc.frame_register(arch, self.is_param()) {
+            Some(frame_reg) => location.resolve_frame_pointer(frame_reg),
+            None => location,
+        })
+    }
+
+    /// Finds the location of the variable at the code address `addr`.
+    ///
+    /// Each def-range record gives a location and the address range (minus any gaps) where that
+    /// location is valid. Records that apply to the whole variable take precedence over
+    /// sub-field records. `S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE` is used only if no other
+    /// record covers `addr`.
+    ///
+    /// Returns `None` if the variable is not live at `addr`.
+    pub fn location_at(&self, arch: Arch, addr: OffsetSegment) -> Option<VarLocation> {
+        let mut pieces: Vec<VarPiece> = Vec::new();
+        let mut full_scope: Option<VarLocation> = None;
+
+        for def_range in self.def_ranges.iter() {
+            match def_range {
+                SymData::DefRange(r) if r.fixed.range.is_live(r.gaps, addr) => {
+                    return Some(VarLocation::Program(r.fixed.program.get()));
+                }
+
+                SymData::DefRangeRegister(r)
+                    if r.fixed.range.is_live(gaps_from_bytes(r.gaps), addr) =>
+                {
+                    return Some(VarLocation::Register(ArchReg::new(arch, r.fixed.reg.get())));
+                }
+
+                SymData::DefRangeFramePointerRel(r) if r.fixed.range.is_live(r.gaps, addr) => {
+                    return Some(VarLocation::FramePointerRelative {
+                        offset: r.fixed.offset_to_frame_pointer.get() as i32,
+                    });
+                }
+
+                SymData::DefRangeRegisterRel(r)
+                    if r.fixed.range.is_live(gaps_from_bytes(r.gaps), addr) =>
+                {
+                    let location = VarLocation::RegisterRelative {
+                        reg: ArchReg::new(arch, r.fixed.base_reg.get()),
+                        offset: r.fixed.base_pointer_offset.get(),
+                    };
+                    let flags = r.fixed.flags.get();
+                    if flags & 1 != 0 {
+                        // spilledUdtMember: this is one field of the variable.
+                        pieces.push(VarPiece {
+                            offset_in_parent: (flags >> 4) as u32,
+                            location,
+                        });
+                    } else {
+                        return Some(location);
+                    }
+                }
+
+                SymData::DefRangeSubFieldRegister(r)
+                    if r.fixed.range.is_live(gaps_from_bytes(r.gaps), addr) =>
+                {
+                    pieces.push(VarPiece {
+                        offset_in_parent: r.fixed.flags.get() & OFFSET_PARENT_MASK,
+                        location: VarLocation::Register(ArchReg::new(arch, r.fixed.reg.get())),
+                    });
+                }
+
+                SymData::DefRangeSubField(r) if r.fixed.range.is_live(r.gaps, addr) => {
+                    pieces.push(VarPiece {
+                        offset_in_parent: r.fixed.offset_parent.get() & OFFSET_PARENT_MASK,
+                        location: VarLocation::Program(r.fixed.program.get()),
+                    });
+                }
+
+                SymData::DefRangeFramePointerRelFullScope(r) => {
+                    full_scope = Some(VarLocation::FramePointerRelative {
+                        offset: r.frame_pointer_offset.get(),
+                    });
+                }
+
+                _ => {}
+            }
+        }
+
+        if !pieces.is_empty() {
+            pieces.sort_by_key(|p| p.offset_in_parent);
+            return Some(VarLocation::Pieces(pieces));
+        }
+
+        full_scope
+    }
+}
+
+/// `CV_OFFSET_PARENT_LENGTH_LIMIT` is 12 bits.
+const OFFSET_PARENT_MASK: u32 = (1 << 12) - 1;
+
+/// Returns `true` if `kind` is one of the `S_DEFRANGE_*` kinds.
+pub fn is_def_range_kind(kind: SymKind) -> bool {
+    matches!(
+        kind,
+        SymKind::S_DEFRANGE
+            | SymKind::S_DEFRANGE_SUBFIELD
+            | SymKind::S_DEFRANGE_REGISTER
+            | SymKind::S_DEFRANGE_FRAMEPOINTER_REL
+            | SymKind::S_DEFRANGE_SUBFIELD_REGISTER
+            | SymKind::S_DEFRANGE_FRAMEPOINTER_REL_FULL_SCOPE
+            | SymKind::S_DEFRANGE_REGISTER_REL
+            | SymKind::S_DEFRANGE_HLSL
+            | SymKind::S_DEFRANGE_DPC_PTR_TAG
+    )
+}
+
+/// Several def-range records store their gaps as unparsed bytes.
+fn gaps_from_bytes(bytes: &[u8]) -> &[LVarAddrGap] {
+    <[LVarAddrGap]>::ref_from_prefix_with_elems(bytes, bytes.len() / size_of::<LVarAddrGap>())
+        .map(|(gaps, _)| gaps)
+        .unwrap_or_default()
+}
+
+impl LVarAddrRange {
+    /// Returns `true` if `addr` is within this range.
+    pub fn contains(&self, addr: OffsetSegment) -> bool {
+        addr.segment() == self.start.segment()
+            && addr.offset().wrapping_sub(self.start.offset()) < self.range_size.get() as u32
+    }
+
+    /// Returns `true` if `addr` is within this range and is not within any of `gaps`.
+    pub fn is_live(&self, gaps: &[LVarAddrGap], addr: OffsetSegment) -> bool {
+        if !self.contains(addr) {
+            return false;
+        }
+        let rel = addr.offset() - self.start.offset();
+        !gaps.iter().any(|gap| {
+            rel.wrapping_sub(gap.gap_start_offset.get() as u32) < gap.range_size.get() as u32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arch::amd64::Amd64Reg;
+    use crate::syms::builder::SymBuilder;
+    use crate::types::TypeIndex;
+    use zerocopy::FromZeros;
+
+    fn range(offset: u32, size: u16) -> LVarAddrRange {
+        LVarAddrRange {
+            start: OffsetSegment::new(offset, 1),
+            range_size: U16::new(size),
+        }
+    }
+
+    fn gap(start: u16, size: u16) -> LVarAddrGap {
+        LVarAddrGap {
+            gap_start_offset: U16::new(start),
+            range_size: U16::new(size),
+        }
+    }
+
+    fn local_var(stream: &[u8]) -> LocalVar<'_> {
+        let mut iter = SymIter::new(stream);
+        let local = iter.next().unwrap();
+        LocalVar::new(&local, iter).unwrap()
+    }
+
+    fn at(offset: u32) -> OffsetSegment {
+        OffsetSegment::new(offset, 1)
+    }
+
+    #[test]
+    fn register_and_stack() {
+        let mut b = SymBuilder::new();
+        b.local(TypeIndex(0x74), 0, "x".into());
+        let mut reg = DefRangeRegisterFixed::new_zeroed();
+        reg.reg = U16::new(Amd64Reg::RCX.0);
+        reg.range = range(0x1000, 0x20);
+        b.def_range_register(&reg, &[gap(0x8, 4)]);
+        let mut rel = DefRangeRegisterRelFixed::new_zeroed();
+        rel.base_reg = U16::new(Amd64Reg::RSP.0);
+        rel.base_pointer_offset = I32::new(0x28);
+        rel.range = range(0x1020, 0x40);
+        b.def_range_register_rel(&rel, &[]);
+        b.udt(TypeIndex(0x1000), "T".into());
+        let stream = b.finish();
+
+        let var = local_var(&stream);
+        assert_eq!(var.local.name, "x");
+        assert_eq!(var.def_ranges.len(), 2);
+
+        let rcx = ArchReg::new(Arch::AMD64, Amd64Reg::RCX.0);
+        let rsp = ArchReg::new(Arch::AMD64, Amd64Reg::RSP.0);
+        assert_eq!(var.location_at(Arch::AMD64, at(0xfff)), None);
+        assert_eq!(
+            var.location_at(Arch::AMD64, at(0x1000)),
+            Some(VarLocation::Register(rcx))
+        );
+        assert_eq!(var.location_at(Arch::AMD64, at(0x1009)), None);
+        assert_eq!(
+            var.location_at(Arch::AMD64, at(0x100c)),
+            Some(VarLocation::Register(rcx))
+        );
+        assert_eq!(
+            var.location_at(Arch::AMD64, at(0x1020)),
+            Some(VarLocation::RegisterRelative {
+                reg: rsp,
+                offset: 0x28
+            })
+        );
+        assert_eq!(var.location_at(Arch::AMD64, at(0x1060)), None);
+        assert_eq!(
+            var.location_at(Arch::AMD64, OffsetSegment::new(0x1000, 2)),
+            None
+        );
+    }
+
+    #[test]
+    fn subfields_and_full_scope() {
+        let mut b = SymBuilder::new();
+        b.local(TypeIndex(0x1000), 0, "s".into());
+        for (reg, offset_parent) in [(Amd64Reg::RDX, 8), (Amd64Reg::RAX, 0)] {
+            let mut f = DefRangeSubFieldRegisterFixed::new_zeroed();
+            f.reg = U16::new(reg.0);
+            f.flags = U32::new(offset_parent);
+            f.range = range(0x1000, 0x10);
+            b.def_range_subfield_register(&f, &[]);
+        }
+        b.def_range_frame_pointer_rel_full_scope(-0x18);
+        let stream = b.finish();
+        let var = local_var(&stream);
+
+        assert_eq!(
+            var.location_at(Arch::AMD64, at(0x1004)),
+            Some(VarLocation::Pieces(vec![
+                VarPiece {
+                    offset_in_parent: 0,
+                    location: VarLocation::Register(ArchReg::new(Arch::AMD64, Amd64Reg::RAX.0)),
+                },
+                VarPiece {
+                    offset_in_parent: 8,
+                    location: VarLocation::Register(ArchReg::new(Arch::AMD64, Amd64Reg::RDX.0)),
+                },
+            ]))
+        );
+        assert_eq!(
+            var.location_at(Arch::AMD64, at(0x2000)),
+            Some(VarLocation::FramePointerRelative { offset: -0x18 })
+        );
+
+        let mut frame = FrameProc::new_zeroed();
+        frame.flags = U32::new((2 << 14) | (1 << 16)); // locals: RBP, params: RSP
+        assert_eq!(
+            var.location_in_frame(Arch::AMD64, &frame, at(0x2000)),
+            Some(VarLocation::RegisterRelative {
+                reg: ArchReg::new(Arch::AMD64, Amd64Reg::RBP.0),
+                offset: -0x18
+            })
+        );
+    }
+}
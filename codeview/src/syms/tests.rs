@@ -0,0 +1,735 @@
+use super::builder::SymBuilder;
+use super::*;
+use crate::encoder::Encoder;
+
+/// Encodes a single record, then decodes it.
+fn round_trip(kind: SymKind, f: impl FnOnce(&mut Encoder)) -> Vec<u8> {
+    let mut b = SymBuilder::new();
+    f(&mut b.record(kind).enc);
+    let stream = b.finish();
+    assert_eq!(stream.len() % 4, 0);
+
+    let mut iter = SymIter::new(&stream);
+    let sym = iter.next().unwrap();
+    assert_eq!(sym.kind, kind);
+    assert!(iter.next().is_none());
+    stream
+}
+
+fn parse_one(stream: &[u8]) -> SymData<'_> {
+    SymIter::one(stream).unwrap().parse().unwrap()
+}
+
+#[test]
+fn compile2() {
+    let s = round_trip(SymKind::S_COMPILE2, |e| {
+        e.u32(0x0000_4001);
+        e.u16(0xd0);
+        for v in [19, 29, 30133, 19, 29, 30133] {
+            e.u16(v);
+        }
+        e.strz("Microsoft (R) Optimizing Compiler".into());
+        e.strz("foo".into());
+        e.strz("bar".into());
+        e.u8(0);
+    });
+    let SymData::Compile2(c) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(c.fixed.flags.get(), 0x4001);
+    assert_eq!(c.fixed.machine.get(), 0xd0);
+    assert_eq!(c.fixed.ver_build.get(), 30133);
+    assert_eq!(c.name, "Microsoft (R) Optimizing Compiler");
+    let extra: Vec<&BStr> = c.iter_extra_strings().collect();
+    assert_eq!(extra, ["foo", "bar"]);
+}
+
+#[test]
+fn env_block() {
+    let s = round_trip(SymKind::S_ENVBLOCK, |e| {
+        e.u8(0);
+        for x in ["cwd", "d:\\src", "exe", "cl.exe"] {
+            e.strz(x.into());
+        }
+        e.u8(0);
+    });
+    let SymData::EnvBlock(env) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(env.flags, 0);
+    let strings: Vec<&BStr> = env.iter_strings().collect();
+    assert_eq!(strings, ["cwd", "d:\\src", "exe", "cl.exe"]);
+}
+
+#[test]
+fn register_bprel_slot() {
+    let s = round_trip(SymKind::S_REGISTER, |e| {
+        e.u32(0x1234);
+        e.u16(17);
+        e.strz("x".into());
+    });
+    let d = parse_one(&s);
+    let SymData::Register(r) = &d else { panic!() };
+    assert_eq!(r.fixed.ty.get(), TypeIndex(0x1234));
+    assert_eq!(r.fixed.register.get(), 17);
+    assert_eq!(d.name().unwrap(), "x");
+
+    let s = round_trip(SymKind::S_BPREL32, |e| {
+        e.u32((-8i32) as u32);
+        e.u32(0x74);
+        e.strz("y".into());
+    });
+    let d = parse_one(&s);
+    let SymData::BpRel(b) = &d else { panic!() };
+    assert_eq!(b.fixed.offset.get(), -8);
+    assert_eq!(b.fixed.ty.get(), TypeIndex(0x74));
+    assert_eq!(d.name().unwrap(), "y");
+
+    for kind in [SymKind::S_LOCALSLOT, SymKind::S_PARAMSLOT] {
+        let s = round_trip(kind, |e| {
+            e.u32(3);
+            e.u32(0x1001);
+            e.strz("slot".into());
+        });
+        let d = parse_one(&s);
+        let SymData::Slot(slot) = &d else { panic!() };
+        assert_eq!(slot.fixed.slot.get(), 3);
+        assert_eq!(slot.fixed.ty.get(), TypeIndex(0x1001));
+        assert_eq!(d.name().unwrap(), "slot");
+    }
+}
+
+#[test]
+fn sepcode() {
+    let s = round_trip(SymKind::S_SEPCODE, |e| {
+        for v in [0x10, 0x80, 0x20, 1, 0x400, 0x100] {
+            e.u32(v);
+        }
+        e.u16(1);
+        e.u16(2);
+    });
+    let SymData::SepCode(sep) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(sep.block.p_parent.get(), 0x10);
+    assert_eq!(sep.block.p_end.get(), 0x80);
+    assert_eq!(sep.length.get(), 0x20);
+    assert_eq!(sep.flags.get(), 1);
+    assert_eq!(sep.offset_segment(), OffsetSegment::new(0x400, 1));
+    assert_eq!(sep.parent_offset_segment(), OffsetSegment::new(0x100, 2));
+    assert_eq!(sep.as_bytes(), &s[4..4 + size_of::<SepCode>()]);
+}
+
+#[test]
+fn export() {
+    let s = round_trip(SymKind::S_EXPORT, |e| {
+        e.u16(7);
+        e.u16(0x12);
+        e.strz("DllMain".into());
+    });
+    let d = parse_one(&s);
+    let SymData::Export(ex) = &d else { panic!() };
+    assert_eq!(ex.fixed.ordinal.get(), 7);
+    assert_eq!(ex.flags(), ExportFlags::DATA | ExportFlags::ORDINAL);
+    assert_eq!(d.name().unwrap(), "DllMain");
+}
+
+#[test]
+fn file_static() {
+    let s = round_trip(SymKind::S_FILESTATIC, |e| {
+        e.u32(0x1003);
+        e.u32(0x44);
+        e.u16(0x100);
+        e.strz("g_counter".into());
+    });
+    let d = parse_one(&s);
+    let SymData::FileStatic(f) = &d else { panic!() };
+    assert_eq!(f.fixed.ty.get(), TypeIndex(0x1003));
+    assert_eq!(f.fixed.module_filename.get(), 0x44);
+    assert_eq!(f.fixed.flags.get(), 0x100);
+    assert_eq!(d.name().unwrap(), "g_counter");
+}
+
+#[test]
+fn many_reg() {
+    let s = round_trip(SymKind::S_MANYREG, |e| {
+        e.u32(0x13);
+        e.u8(2);
+        e.bytes(&[17, 18]);
+        e.strz("ll".into());
+    });
+    let d = parse_one(&s);
+    let SymData::ManyReg(r) = &d else { panic!() };
+    assert_eq!(r.ty, TypeIndex(0x13));
+    assert_eq!(r.regs, &[17, 18]);
+    assert_eq!(d.name().unwrap(), "ll");
+
+    let s = round_trip(SymKind::S_MANYREG2, |e| {
+        e.u32(0x13);
+        e.u16(2);
+        e.u16(328);
+        e.u16(329);
+        e.strz("ll2".into());
+    });
+    let d = parse_one(&s);
+    let SymData::ManyReg2(r) = &d else { panic!() };
+    assert_eq!(r.ty, TypeIndex(0x13));
+    let regs: Vec<u16> = r.regs.iter().map(|r| r.get()).collect();
+    assert_eq!(regs, [328, 329]);
+    assert_eq!(d.name().unwrap(), "ll2");
+}
+
+#[test]
+fn defrange_subfield() {
+    let s = round_trip(SymKind::S_DEFRANGE_SUBFIELD, |e| {
+        e.u32(5); // program
+        e.u32(8); // offset_parent
+        e.u32(0x1000); // range.start.offset
+        e.u16(1); // range.start.segment
+        e.u16(0x40); // range.range_size
+        e.u16(0x10); // gap start
+        e.u16(4); // gap size
+    });
+    let SymData::DefRangeSubField(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.fixed.program.get(), 5);
+    assert_eq!(r.fixed.offset_parent.get(), 8);
+    assert_eq!(r.fixed.range.start, OffsetSegment::new(0x1000, 1));
+    assert_eq!(r.fixed.range.range_size.get(), 0x40);
+    assert_eq!(r.gaps.len(), 1);
+    assert_eq!(r.gaps[0].gap_start_offset.get(), 0x10);
+    assert_eq!(r.gaps[0].range_size.get(), 4);
+}
+
+/// The layout of `DEFRANGESYMREGISTER` in `cvinfo.h`: the register and its attributes are
+/// followed by an address range, and then by the gaps. The address range used to be decoded as
+/// part of the gaps.
+#[test]
+fn defrange_register() {
+    let s = round_trip(SymKind::S_DEFRANGE_REGISTER, |e| {
+        e.u16(329); // reg (CV_AMD64_RBX)
+        e.u16(1); // attr (maybe)
+        e.u32(0x2000); // range.start.offset
+        e.u16(1); // range.start.segment
+        e.u16(0x30); // range.range_size
+        e.u16(0x8); // gap start
+        e.u16(2); // gap size
+    });
+    let SymData::DefRangeRegister(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.fixed.reg.get(), 329);
+    assert_eq!(r.fixed.attr.value.get(), 1);
+    assert_eq!(r.fixed.range.start, OffsetSegment::new(0x2000, 1));
+    assert_eq!(r.fixed.range.range_size.get(), 0x30);
+    assert_eq!(r.gaps, [0x08, 0x00, 0x02, 0x00]);
+}
+
+#[test]
+fn pogo_data() {
+    let s = round_trip(SymKind::S_POGODATA, |e| {
+        e.u32(100);
+        e.bytes(&5000i64.to_le_bytes());
+        e.u32(30);
+        e.u32(25);
+    });
+    let SymData::PogoData(p) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(p.invocations.get(), 100);
+    assert_eq!(p.dynamic_count.get(), 5000);
+    assert_eq!(p.num_instrs.get(), 30);
+    assert_eq!(p.num_live_instrs.get(), 25);
+}
+
+#[test]
+fn mod_typeref() {
+    let s = round_trip(SymKind::S_MOD_TYPEREF, |e| {
+        e.u32(0x10);
+        e.u16(12);
+        e.u16(0);
+    });
+    assert_eq!(s.len(), 4 + 8);
+    let SymData::ModTypeRef(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.flags(), ModTypeRefFlags::OWN_TM);
+    assert_eq!(r.word0.get(), 12);
+    assert_eq!(r.word1.get(), 0);
+}
+
+#[test]
+fn ref_minipdb_and_fastlink() {
+    let s = round_trip(SymKind::S_REF_MINIPDB, |e| {
+        e.u32(0x1005);
+        e.u16(3);
+        e.u16(0x4);
+        e.strz("Foo".into());
+    });
+    let d = parse_one(&s);
+    let SymData::RefMiniPdb(r) = &d else { panic!() };
+    assert_eq!(r.fixed.coff_section_or_type.get(), 0x1005);
+    assert_eq!(r.fixed.module.get(), 3);
+    assert_eq!(r.flags(), RefMiniPdbFlags::UDT);
+    assert_eq!(d.name().unwrap(), "Foo");
+
+    let s = round_trip(SymKind::S_FASTLINK, |e| {
+        e.u32(0x1006);
+        e.u16(1);
+        e.strz("Bar".into());
+    });
+    let d = parse_one(&s);
+    let SymData::FastLink(f) = &d else { panic!() };
+    assert_eq!(f.fixed.ty.get(), TypeIndex(0x1006));
+    assert_eq!(f.fixed.flags.get(), 1);
+    assert_eq!(d.name().unwrap(), "Bar");
+}
+
+#[test]
+fn pdbmap() {
+    let s = round_trip(SymKind::S_PDBMAP, |e| {
+        e.strz("a.pdb".into());
+        e.strz("b.pdb".into());
+    });
+    let SymData::PdbMap(m) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(m.from, "a.pdb");
+    assert_eq!(m.to, "b.pdb");
+}
+
+#[test]
+fn discarded() {
+    let mut inner = SymBuilder::new();
+    inner.udt(TypeIndex(0x1000), "T".into());
+    let inner = inner.finish();
+
+    let s = round_trip(SymKind::S_DISCARDED, |e| {
+        e.u32(DISCARDED_NOT_SELECTED as u32);
+        e.u32(0x18);
+        e.u32(42);
+        e.bytes(&inner);
+    });
+    let SymData::Discarded(d) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(d.discarded_type(), DISCARDED_NOT_SELECTED);
+    assert_eq!(d.fixed.file_id.get(), 0x18);
+    assert_eq!(d.fixed.line.get(), 42);
+    let original = SymIter::one(d.data).unwrap();
+    assert_eq!(original.kind, SymKind::S_UDT);
+}
+
+#[test]
+fn vftable32() {
+    let s = round_trip(SymKind::S_VFTABLE32, |e| {
+        e.u32(0x1000);
+        e.u32(0x1001);
+        e.u32(0x2000);
+        e.u16(2);
+    });
+    let SymData::VFTable32(v) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(v.root.get(), TypeIndex(0x1000));
+    assert_eq!(v.path.get(), TypeIndex(0x1001));
+    assert_eq!(v.offset_segment, OffsetSegment::new(0x2000, 2));
+}
+
+#[test]
+fn hlsl() {
+    for kind in [SymKind::S_GDATA_HLSL, SymKind::S_LDATA_HLSL] {
+        let s = round_trip(kind, |e| {
+            e.u32(0x1000);
+            for v in [1, 2, 3, 4, 5, 6] {
+                e.u16(v);
+            }
+            e.strz("cb".into());
+        });
+        let d = parse_one(&s);
+        let SymData::DataHlsl(h) = &d else { panic!() };
+        assert_eq!(h.fixed.reg_type.get(), 1);
+        assert_eq!(h.fixed.uav_slot.get(), 6);
+        assert_eq!(d.name().unwrap(), "cb");
+    }
+
+    for kind in [SymKind::S_GDATA_HLSL32, SymKind::S_LDATA_HLSL32] {
+        let s = round_trip(kind, |e| {
+            e.u32(0x1000);
+            for v in [1, 2, 3, 4, 5] {
+                e.u32(v);
+            }
+            e.u16(6);
+            e.strz("cb32".into());
+        });
+        let d = parse_one(&s);
+        let SymData::DataHlsl32(h) = &d else { panic!() };
+        assert_eq!(h.fixed.data_slot.get(), 1);
+        assert_eq!(h.fixed.reg_type.get(), 6);
+        assert_eq!(d.name().unwrap(), "cb32");
+    }
+
+    for kind in [SymKind::S_GDATA_HLSL32_EX, SymKind::S_LDATA_HLSL32_EX] {
+        let s = round_trip(kind, |e| {
+            e.u32(0x1000);
+            for v in [1, 2, 3, 4] {
+                e.u32(v);
+            }
+            e.u16(5);
+            e.strz("cbex".into());
+        });
+        let d = parse_one(&s);
+        let SymData::DataHlsl32Ex(h) = &d else {
+            panic!()
+        };
+        assert_eq!(h.fixed.reg_id.get(), 1);
+        assert_eq!(h.fixed.bind_slot.get(), 4);
+        assert_eq!(d.name().unwrap(), "cbex");
+    }
+
+    let s = round_trip(SymKind::S_DEFRANGE_HLSL, |e| {
+        e.u16(3); // reg_type
+        e.u16(2); // flags: 2 register indices
+        e.u16(0); // offset_parent
+        e.u16(16); // size_in_parent
+        e.u32(0x100); // range.start.offset
+        e.u16(1); // range.start.segment
+        e.u16(0x20); // range.range_size
+        e.u16(4); // gap start
+        e.u16(2); // gap size
+        e.u32(7); // reg index 0
+        e.u32(9); // reg index 1
+    });
+    let SymData::DefRangeHlsl(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.fixed.reg_type.get(), 3);
+    assert_eq!(r.num_reg_indices(), 2);
+    assert_eq!(r.gaps().len(), 1);
+    assert_eq!(r.gaps()[0].gap_start_offset.get(), 4);
+    let regs: Vec<u32> = r.reg_indices().iter().map(|r| r.get()).collect();
+    assert_eq!(regs, [7, 9]);
+}
+
+#[test]
+fn remap_type_indexes() {
+    use crate::types::visitor::{
+        IndexVisitor, IndexVisitorMut, visit_type_indexes_in_sym_slice,
+        visit_type_indexes_in_sym_slice_mut,
+    };
+    use crate::types::{ItemIdLe, TypeIndex, TypeIndexLe};
+    use zerocopy::FromZeros;
+
+    let mut b = SymBuilder::new();
+    let mut proc = ProcFixed::new_zeroed();
+    proc.proc_type = TypeIndexLe(U32::new(0x1001));
+    b.begin_proc(SymKind::S_GPROC32_ID, &proc, "f".into());
+    b.local(TypeIndex(0x1002), 0, "x".into());
+    b.begin_inline_site(0x2001, &[]).unwrap();
+    b.end_scope();
+    b.function_list(SymKind::S_INLINEES, &[0x2002, 0x2003], &[]);
+    let mut udt_ref = RefMiniPdbFixed::new_zeroed();
+    udt_ref.coff_section_or_type = U32::new(0x1003);
+    udt_ref.flags = U16::new(RefMiniPdbFlags::UDT.bits());
+    b.ref_mini_pdb(&udt_ref, "T".into());
+    let mut data_ref = RefMiniPdbFixed::new_zeroed();
+    data_ref.coff_section_or_type = U32::new(3);
+    b.ref_mini_pdb(&data_ref, "g".into());
+    b.end_scope();
+    b.build_info(0x2004);
+
+    // The type indexes of a discarded record are visited, too.
+    let mut discarded = SymBuilder::new();
+    discarded.local(TypeIndex(0x1004), 0, "y".into());
+    b.discarded(&DiscardedFixed::new_zeroed(), &discarded.finish());
+
+    // S_MANFRAMEREL contains a metadata token, which is not visited. S_ATTR_FRAMEREL contains a
+    // type index.
+    let mut frame_rel = AttrFrameRelFixed::new_zeroed();
+    frame_rel.ty = TypeIndexLe(U32::new(0x0600_0001));
+    b.attr_frame_rel(SymKind::S_MANFRAMEREL, &frame_rel, "m".into());
+    frame_rel.ty = TypeIndexLe(U32::new(0x1005));
+    b.attr_frame_rel(SymKind::S_ATTR_FRAMEREL, &frame_rel, "a".into());
+    let mut stream = b.finish();
+
+    struct Remap;
+    impl IndexVisitorMut for Remap {
+        fn type_index(&mut self, _: usize, value: &mut TypeIndexLe) -> Result<(), ParserError> {
+            *value = TypeIndexLe(U32::new(value.get().0 + 0x100));
+            Ok(())
+        }
+        fn item_id(&mut self, _: usize, value: &mut ItemIdLe) -> Result<(), ParserError> {
+            *value = U32::new(value.get() + 0x100);
+            Ok(())
+        }
+    }
+
+    for sym in SymIterMut::new(&mut stream) {
+        visit_type_indexes_in_sym_slice_mut(sym.kind, sym.data, Remap).unwrap();
+    }
+
+    #[derive(Default)]
+    struct Collect {
+        types: Vec<u32>,
+        items: Vec<u32>,
+    }
+    impl IndexVisitor for &mut Collect {
+        fn type_index(&mut self, _: usize, value: TypeIndex) -> Result<(), ParserError> {
+            self.types.push(value.0);
+            Ok(())
+        }
+        fn item_id(&mut self, _: usize, value: u32) -> Result<(), ParserError> {
+            self.items.push(value);
+            Ok(())
+        }
+    }
+
+    let mut c = Collect::default();
+    for sym in SymIter::new(&stream) {
+        visit_type_indexes_in_sym_slice(sym.kind, sym.data, &mut c).unwrap();
+    }
+    assert_eq!(c.types, [0x1102, 0x1103, 0x1104, 0x1105]);
+    assert_eq!(c.items, [0x1101, 0x2101, 0x2102, 0x2103, 0x2104]);
+}
+
+#[test]
+fn frame_proc_registers() {
+    use crate::arch::{amd64::Amd64Reg, arm64::Arm64Reg, x86::X86Reg};
+    use zerocopy::FromZeros;
+
+    let mut frame = FrameProc::new_zeroed();
+    // local base pointer = 2 (frame pointer), param base pointer = 1 (stack pointer)
+    frame.flags = U32::new((2 << 14) | (1 << 16) | 1 << 8 | 1 << 20);
+    assert_eq!(
+        frame.flags(),
+        FrameProcFlags::SECURITY_CHECKS | FrameProcFlags::OPT_SPEED
+    );
+    assert_eq!(frame.local_base_pointer(), 2);
+    assert_eq!(frame.param_base_pointer(), 1);
+
+    let reg = |arch, is_param| frame.frame_register(arch, is_param).map(|r| r.reg);
+    assert_eq!(reg(Arch::AMD64, false), Some(Amd64Reg::RBP.0));
+    assert_eq!(reg(Arch::AMD64, true), Some(Amd64Reg::RSP.0));
+    assert_eq!(reg(Arch::X86, false), Some(X86Reg::EBP.0));
+    assert_eq!(reg(Arch::X86, true), Some(X86Reg::VFRAME.0));
+    assert_eq!(reg(Arch::ARM64, false), Some(Arm64Reg::FP.0));
+    assert_eq!(reg(Arch::ARM64, true), Some(Arm64Reg::SP.0));
+
+    for (arch, base) in [
+        (Arch::AMD64, Amd64Reg::R13.0),
+        (Arch::X86, X86Reg::EBX.0),
+        (Arch::ARM64, Arm64Reg::X19.0),
+    ] {
+        assert_eq!(arch.frame_pointer_reg(3), Some(ArchReg::new(arch, base)));
+        assert_eq!(arch.frame_pointer_reg(0), None);
+    }
+}
+
+#[test]
+fn compile3_flags_and_toolchain() {
+    use zerocopy::FromZeros;
+
+    let mut fixed = Compile3Fixed::new_zeroed();
+    fixed.flags = U32::new(
+        SourceLanguage::CXX.0 as u32
+            | (CompileFlags::SECURITY_CHECKS | CompileFlags::LTCG | CompileFlags::SDL).bits(),
+    );
+    fixed.frontend_major = U16::new(19);
+    fixed.frontend_minor = U16::new(29);
+    fixed.frontend_build = U16::new(30133);
+
+    let mut b = SymBuilder::new();
+    b.compile3(&fixed, "Microsoft (R) Optimizing Compiler".into());
+    let s = b.finish();
+    let SymData::Compile3(c) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(c.fixed.language(), SourceLanguage::CXX);
+    assert!(
+        c.fixed
+            .compile_flags()
+            .contains(CompileFlags::SECURITY_CHECKS)
+    );
+    assert!(!c.fixed.compile_flags().contains(CompileFlags::HOT_PATCH));
+    assert_eq!(c.toolchain(), Toolchain::Msvc);
+    assert_eq!(
+        c.fixed.frontend_version(),
+        ToolVersion::new(19, 29, 30133, 0)
+    );
+    assert!(c.fixed.frontend_version() < ToolVersion::new(19, 30, 0, 0));
+    assert!(c.fixed.frontend_version() > ToolVersion::new(19, 29, 30038, 1));
+
+    let classify = |lang: SourceLanguage, name: &str| {
+        let mut fixed = Compile3Fixed::new_zeroed();
+        fixed.flags = U32::new(lang.0 as u32);
+        let mut b = SymBuilder::new();
+        b.compile3(&fixed, name.into());
+        let s = b.finish();
+        let SymData::Compile3(c) = parse_one(&s) else {
+            panic!()
+        };
+        c.toolchain()
+    };
+    assert_eq!(
+        classify(SourceLanguage::CXX, "clang version 17.0.1"),
+        Toolchain::Clang
+    );
+    assert_eq!(
+        classify(SourceLanguage::RUST, "rustc version 1.80.0"),
+        Toolchain::Rustc
+    );
+    assert_eq!(
+        classify(SourceLanguage::MASM, "Microsoft (R) Macro Assembler"),
+        Toolchain::Masm
+    );
+    assert_eq!(
+        classify(SourceLanguage::LINK, "Microsoft (R) LINK"),
+        Toolchain::Linker
+    );
+    assert_eq!(classify(SourceLanguage::C, "tcc"), Toolchain::Other);
+    assert_eq!(format!("{}", SourceLanguage(0x99)), "??(0x99)");
+}
+
+#[test]
+fn managed_attr_syms() {
+    let attr = |e: &mut Encoder| {
+        e.u32(0x40); // offset
+        e.u16(1); // segment
+        e.u16(2); // flags
+    };
+
+    let s = round_trip(SymKind::S_MANSLOT, |e| {
+        e.u32(5);
+        e.u32(0x1002);
+        attr(e);
+        e.strz("s".into());
+    });
+    let d = parse_one(&s);
+    let SymData::AttrSlot(slot) = &d else {
+        panic!()
+    };
+    assert_eq!(slot.fixed.slot.get(), 5);
+    assert_eq!(slot.fixed.ty.get(), TypeIndex(0x1002));
+    assert_eq!(slot.fixed.attr.offset_segment, OffsetSegment::new(0x40, 1));
+    assert_eq!(slot.fixed.attr.flags.get(), 2);
+    assert_eq!(d.name().unwrap(), "s");
+
+    let s = round_trip(SymKind::S_MANREGISTER, |e| {
+        e.u32(0x74);
+        attr(e);
+        e.u16(18);
+        e.strz("r".into());
+    });
+    let SymData::AttrRegister(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.fixed.register.get(), 18);
+    assert_eq!(r.name, "r");
+
+    let s = round_trip(SymKind::S_MANFRAMEREL, |e| {
+        e.u32((-16i32) as u32);
+        e.u32(0x74);
+        attr(e);
+        e.strz("f".into());
+    });
+    let SymData::AttrFrameRel(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.fixed.offset.get(), -16);
+    assert_eq!(r.name, "f");
+
+    let s = round_trip(SymKind::S_ATTR_REGREL, |e| {
+        e.u32(0x20);
+        e.u32(0x74);
+        e.u16(335);
+        attr(e);
+        e.strz("rr".into());
+    });
+    let SymData::AttrRegRel(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.fixed.offset.get(), 0x20);
+    assert_eq!(r.fixed.register.get(), 335);
+    assert_eq!(r.name, "rr");
+
+    let s = round_trip(SymKind::S_MANMANYREG, |e| {
+        e.u32(0x13);
+        attr(e);
+        e.u8(2);
+        e.bytes(&[17, 18]);
+        e.strz("m".into());
+    });
+    let SymData::AttrManyReg(r) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(r.regs, &[17, 18]);
+    assert_eq!(r.name, "m");
+
+    let s = round_trip(SymKind::S_OEM, |e| {
+        e.bytes(&[0xaa; 16]);
+        e.u32(0x1000);
+        e.bytes(&[1, 2, 3, 4]);
+    });
+    let SymData::Oem(oem) = parse_one(&s) else {
+        panic!()
+    };
+    assert_eq!(oem.fixed.oem_id, [0xaa; 16]);
+    assert_eq!(oem.fixed.ty.get(), TypeIndex(0x1000));
+    assert_eq!(oem.data, &[1, 2, 3, 4]);
+
+    let s = round_trip(SymKind::S_TOKENREF, |e| {
+        e.u32(0);
+        e.u32(0x40);
+        e.u16(3);
+        e.strz("Main".into());
+    });
+    assert!(matches!(parse_one(&s), SymData::RefSym2(_)));
+
+    let token = MetadataToken(0x0600_0012);
+    assert_eq!(token.table(), MetadataToken::TABLE_METHOD_DEF);
+    assert_eq!(token.rid(), 0x12);
+}
+
+#[test]
+fn debug_info_format() {
+    use crate::types::{Leaf, builder::TypeBuilder};
+
+    assert_eq!(
+        DebugInfoFormat::new(CompileFlags::SECURITY_CHECKS, false),
+        DebugInfoFormat::Z7
+    );
+    assert_eq!(
+        DebugInfoFormat::new(CompileFlags::empty(), true),
+        DebugInfoFormat::Zi
+    );
+    assert_eq!(
+        DebugInfoFormat::new(CompileFlags::EDIT_AND_CONTINUE, true),
+        DebugInfoFormat::ZiEditAndContinue
+    );
+    assert_eq!(
+        DebugInfoFormat::new(CompileFlags::NO_DEBUG_INFO, false),
+        DebugInfoFormat::None
+    );
+    assert_eq!(DebugInfoFormat::Zi.option(), Some("/Zi"));
+
+    // A .debug$T section of an object file compiled with /Zi contains only LF_TYPESERVER2.
+    let mut b = TypeBuilder::new();
+    b.record(Leaf::LF_TYPESERVER2, |e| {
+        e.bytes(&[0; 16]);
+        e.u32(1);
+        e.strz("c:\\obj\\vc140.pdb".into());
+    });
+    let mut debug_t = 4u32.to_le_bytes().to_vec();
+    debug_t.extend_from_slice(&b.finish());
+    assert!(types_use_type_server(&debug_t));
+    assert!(types_use_type_server(&debug_t[4..]));
+
+    // /Z7 stores the type records themselves.
+    let mut b = TypeBuilder::new();
+    b.record(Leaf::LF_ARGLIST, |e| e.u32(0));
+    let mut debug_t = 4u32.to_le_bytes().to_vec();
+    debug_t.extend_from_slice(&b.finish());
+    assert!(!types_use_type_server(&debug_t));
+    assert!(!types_use_type_server(&[]));
+}
@@ -0,0 +1,484 @@
+//! Builds the scope hierarchy (procedure → block → inline site) of a symbol stream.
+
+use super::*;
+use crate::IteratorWithRangesExt;
+
+/// One symbol record within a [`SymTree`].
+#[derive(Clone, Debug)]
+pub struct SymNode<'a> {
+    /// The byte offset of the record within the symbol stream.
+    pub offset: u32,
+    /// The record.
+    pub sym: Sym<'a>,
+    /// The index of the node of the scope that contains this record, or `None` for top-level
+    /// records.
+    pub parent: Option<usize>,
+    /// For records that start a scope, this is the index of the node of the record that ends the
+    /// scope (or the index of the last node in the tree, if the scope is never closed). For all
+    /// other records, this is the index of this node.
+    pub end: usize,
+}
+
+/// Describes a place where the `p_parent` or `p_end` fields of a scope record do not agree with
+/// the nesting of `S_*` start and end records in the stream.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScopeMismatch {
+    /// The stored `p_parent` field is not the offset of the enclosing scope record.
+    WrongParent {
+        /// Offset of the record that contains the bad `p_parent` field
+        record_offset: u32,
+        /// The value of `p_parent`
+        stored: u32,
+        /// The offset of the enclosing scope record, or 0 if there is none.
+        actual: u32,
+    },
+    /// The stored `p_end` field is not the offset of the record that ends the scope.
+    WrongEnd {
+        /// Offset of the record that contains the bad `p_end` field
+        record_offset: u32,
+        /// The value of `p_end`
+        stored: u32,
+        /// The offset of the record that ends the scope.
+        actual: u32,
+    },
+    /// The scope was never closed.
+    Unterminated {
+        /// Offset of the record that starts the scope
+        record_offset: u32,
+    },
+    /// A record that ends a scope was found, but no scope was open.
+    UnmatchedEnd {
+        /// Offset of the record that ends a scope
+        record_offset: u32,
+    },
+}
+
+/// The scope hierarchy of a symbol stream.
+///
+/// Nodes are stored in stream order, so the index of a node is also its position in the stream.
+/// Nesting is determined by [`SymKind::starts_scope`] and [`SymKind::ends_scope`], not by the
+/// stored `p_parent` and `p_end` fields. Disagreements between the two are reported by
+/// [`SymTree::mismatches`].
+pub struct SymTree<'a> {
+    nodes: Vec<SymNode<'a>>,
+    mismatches: Vec<ScopeMismatch>,
+}
+
+impl<'a> SymTree<'a> {
+    /// Builds the tree for a sequence of symbol records. `base_offset` is the stream offset of
+    /// `data[0]`.
+    pub fn new(data: &'a [u8], base_offset: u32) -> Self {
+        let mut nodes: Vec<SymNode<'a>> = Vec::new();
+        let mut mismatches = Vec::new();
+        let mut stack: Vec<usize> = Vec::new();
+
+        for (range, sym) in SymIter::new(data).with_ranges() {
+            let index = nodes.len();
+            let offset = base_offset + range.start as u32;
+            let kind = sym.kind;
+
+            if kind.ends_scope() {
+                if let Some(start) = stack.pop() {
+                    nodes[start].end = index;
+                    nodes.push(SymNode {
+                        offset,
+                        sym,
+                        parent: Some(start),
+                        end: index,
+                    });
+                } else {
+                    mismatches.push(ScopeMismatch::UnmatchedEnd {
+                        record_offset: offset,
+                    });
+                    nodes.push(SymNode {
+                        offset,
+                        sym,
+                        parent: None,
+                        end: index,
+                    });
+                }
+                continue;
+            }
+
+            let parent = stack.last().copied();
+            nodes.push(SymNode {
+                offset,
+                sym,
+                parent,
+                end: index,
+            });
+            if kind.starts_scope() {
+                stack.push(index);
+            }
+        }
+
+        let last = nodes.len().saturating_sub(1);
+        for &start in stack.iter() {
+            nodes[start].end = last;
+            mismatches.push(ScopeMismatch::Unterminated {
+                record_offset: nodes[start].offset,
+            });
+        }
+
+        // Now compare the stored scope pointers against the actual nesting.
+        for (index, node) in nodes.iter().enumerate() {
+            if !node.sym.kind.starts_scope() {
+                continue;
+            }
+            let Ok((header, _)) = BlockHeader::read_from_prefix(node.sym.data) else {
+                continue;
+            };
+
+            let actual_parent = node.parent.map(|p| nodes[p].offset).unwrap_or(0);
+            if header.p_parent.get() != actual_parent {
+                mismatches.push(ScopeMismatch::WrongParent {
+                    record_offset: node.offset,
+                    stored: header.p_parent.get(),
+                    actual: actual_parent,
+                });
+            }
+
+            if node.end != index && nodes[node.end].sym.kind.ends_scope() {
+                let actual_end = nodes[node.end].offset;
+                if header.p_end.get() != actual_end {
+                    mismatches.push(ScopeMismatch::WrongEnd {
+                        record_offset: node.offset,
+                        stored: header.p_end.get(),
+                        actual: actual_end,
+                    });
+                }
+            }
+        }
+
+        mismatches.sort_by_key(|m| match *m {
+            ScopeMismatch::WrongParent { record_offset, .. }
+            | ScopeMismatch::WrongEnd { record_offset, .. }
+            | ScopeMismatch::Unterminated { record_offset }
+            | ScopeMismatch::UnmatchedEnd { record_offset } => record_offset,
+        });
+
+        Self { nodes, mismatches }
+    }
+
+    /// Builds the tree for the symbols in a module symbol stream. This skips the 4-byte
+    /// signature at the start of the stream, and uses stream offsets that include it.
+    pub fn for_module_syms(data: &'a [u8]) -> Self {
+        if data.len() < 4 {
+            return Self::new(&[], 4);
+        }
+        Self::new(&data[4..], 4)
+    }
+
+    /// All nodes, in stream order.
+    pub fn nodes(&self) -> &[SymNode<'a>] {
+        &self.nodes
+    }
+
+    /// Gets a node by index.
+    pub fn node(&self, index: usize) -> &SymNode<'a> {
+        &self.nodes[index]
+    }
+
+    /// The problems that were found while building the tree. These are sorted by record offset.
+    pub fn mismatches(&self) -> &[ScopeMismatch] {
+        &self.mismatches
+    }
+
+    /// Finds the node for the record that starts at `offset`.
+    pub fn find(&self, offset: u32) -> Option<usize> {
+        self.nodes.binary_search_by_key(&offset, |n| n.offset).ok()
+    }
+
+    /// The index of the scope that contains node `index`.
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.nodes[index].parent
+    }
+
+    /// Iterates the scopes that contain node `index`, starting with the innermost.
+    pub fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.nodes[index].parent, |&i| self.nodes[i].parent)
+    }
+
+    /// Iterates the top-level records.
+    pub fn roots(&self) -> SymChildren<'_, 'a> {
+        SymChildren {
+            nodes: &self.nodes,
+            next: 0,
+            end: self.nodes.len(),
+        }
+    }
+
+    /// Iterates the records that are directly contained within the scope started by node
+    /// `index`. The record that ends the scope is not included.
+    pub fn children(&self, index: usize) -> SymChildren<'_, 'a> {
+        let node = &self.nodes[index];
+        let end = if node.sym.kind.starts_scope() && !self.nodes[node.end].sym.kind.ends_scope() {
+            // Unterminated scope; the last node is part of the scope.
+            node.end + 1
+        } else {
+            node.end
+        };
+        SymChildren {
+            nodes: &self.nodes,
+            next: index + 1,
+            end,
+        }
+    }
+
+    /// Finds the scopes within procedure `proc` that contain `code_offset`, which is relative to
+    /// the start of the procedure. The result begins with `proc` and ends with the innermost scope.
+    ///
+    /// Blocks and inline sites are included only if they cover `code_offset`. Scopes whose address
+    /// range is not known are treated as covering the entire procedure.
+    pub fn scopes_at(&self, proc: usize, code_offset: u32) -> Vec<usize> {
+        let mut scopes = vec![proc];
+        let Some(proc_start) = self.scope_start(proc) else {
+            return scopes;
+        };
+
+        let mut current = proc;
+        'outer: loop {
+            for child in self.children(current) {
+                let node = &self.nodes[child];
+                if !node.sym.kind.starts_scope() {
+                    continue;
+                }
+                if self.scope_contains(child, proc_start, code_offset) {
+                    scopes.push(child);
+                    current = child;
+                    continue 'outer;
+                }
+            }
+            break;
+        }
+
+        scopes
+    }
+
+    /// Finds the variable records (`S_LOCAL`, `S_REGREL32`, `S_BPREL32`, etc.) that are visible
+    /// at `code_offset` within procedure `proc`. `code_offset` is relative to the start of the
+    /// procedure.
+    ///
+    /// Records are returned from the outermost scope to the innermost scope. Live ranges of
+    /// `S_LOCAL` records (`S_DEFRANGE_*`) are not checked.
+    pub fn variables_at(&self, proc: usize, code_offset: u32) -> Vec<usize> {
+        let mut vars = Vec::new();
+        for scope in self.scopes_at(proc, code_offset) {
+            for child in self.children(scope) {
+                if is_variable_kind(self.nodes[child].sym.kind) {
+                    vars.push(child);
+                }
+            }
+        }
+        vars
+    }
+
+    /// Gets the code offset of the start of a procedure, block, or thunk. Returns `None` for
+    /// other records.
+    fn scope_start(&self, index: usize) -> Option<u32> {
+        let sym = &self.nodes[index].sym;
+        if sym.kind.is_proc() {
+            // S_GPROC32_ID and friends use the same layout as S_GPROC32.
+            let p: Proc = sym.parse_as().ok()?;
+            return Some(p.fixed.offset_segment.offset());
+        }
+        match sym.parse().ok()? {
+            SymData::ManagedProc(p) => Some(p.fixed.offset_segment.offset()),
+            SymData::Block(b) => Some(b.fixed.offset_segment.offset()),
+            SymData::Thunk(t) => Some(t.fixed.offset_segment.offset()),
+            SymData::SepCode(s) => Some(s.offset.get()),
+            _ => None,
+        }
+    }
+
+    fn scope_contains(&self, index: usize, proc_start: u32, code_offset: u32) -> bool {
+        let sym = &self.nodes[index].sym;
+        let Ok(data) = sym.parse() else {
+            return true;
+        };
+        match data {
+            SymData::Block(b) => {
+                let start = b.fixed.offset_segment.offset().wrapping_sub(proc_start);
+                code_offset.wrapping_sub(start) < b.fixed.length.get()
+            }
+            SymData::InlineSite(site) => site
+                .iter_lines(0, 0)
+                .any(|row| row.code_offset_range.contains(&code_offset)),
+            SymData::InlineSite2(site) => site
+                .iter_lines(0, 0)
+                .any(|row| row.code_offset_range.contains(&code_offset)),
+            _ => true,
+        }
+    }
+}
+
+/// Returns `true` for records that describe a variable or a named value within a scope.
+fn is_variable_kind(kind: SymKind) -> bool {
+    matches!(
+        kind,
+        SymKind::S_LOCAL
+            | SymKind::S_REGREL32
+            | SymKind::S_BPREL32
+            | SymKind::S_REGISTER
+            | SymKind::S_MANYREG
+            | SymKind::S_MANYREG2
+            | SymKind::S_LDATA32
+            | SymKind::S_LTHREAD32
+            | SymKind::S_CONSTANT
+            | SymKind::S_FILESTATIC
+            | SymKind::S_LOCALSLOT
+            | SymKind::S_PARAMSLOT
+    )
+}
+
+/// Iterates the children of a scope. See [`SymTree::children`].
+#[derive(Clone)]
+pub struct SymChildren<'t, 'a> {
+    nodes: &'t [SymNode<'a>],
+    next: usize,
+    end: usize,
+}
+
+impl<'t, 'a> Iterator for SymChildren<'t, 'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.next >= self.end {
+            return None;
+        }
+        let index = self.next;
+        let node = &self.nodes[index];
+        if node.sym.kind.ends_scope() && node.parent.is_none() {
+            // An unmatched end record at the top level.
+            self.next = index + 1;
+            return Some(index);
+        }
+        self.next = node.end.max(index) + 1;
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syms::builder::SymBuilder;
+    use crate::types::TypeIndex;
+    use zerocopy::FromZeros;
+
+    fn build() -> Vec<u8> {
+        let mut b = SymBuilder::with_base_offset(4);
+        let mut proc = ProcFixed::new_zeroed();
+        proc.proc_len = U32::new(0x100);
+        proc.offset_segment = OffsetSegment::new(0x1000, 1);
+        b.begin_proc(SymKind::S_GPROC32, &proc, "f".into());
+        b.local(TypeIndex(0x74), 1, "a".into());
+        b.begin_block(0x20, OffsetSegment::new(0x1010, 1), "".into());
+        b.local(TypeIndex(0x74), 0, "b".into());
+        b.begin_inline_site(
+            0x1000,
+            &[
+                BinaryAnnotation::ChangeCodeOffset(0x18),
+                BinaryAnnotation::ChangeCodeLength(4),
+            ],
+        )
+        .unwrap();
+        b.local(TypeIndex(0x74), 0, "c".into());
+        b.end_scope();
+        b.end_scope();
+        b.local(TypeIndex(0x74), 0, "d".into());
+        b.end_scope();
+        b.udt(TypeIndex(0x1000), "T".into());
+        b.finish()
+    }
+
+    fn names(tree: &SymTree, ids: &[usize]) -> Vec<String> {
+        ids.iter()
+            .map(|&i| {
+                tree.node(i)
+                    .sym
+                    .parse()
+                    .unwrap()
+                    .name()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tree_navigation() {
+        let stream = build();
+        let tree = SymTree::new(&stream, 4);
+        assert!(tree.mismatches().is_empty(), "{:?}", tree.mismatches());
+
+        let roots: Vec<usize> = tree.roots().collect();
+        assert_eq!(roots.len(), 2);
+        let proc = roots[0];
+        assert_eq!(tree.node(proc).sym.kind, SymKind::S_GPROC32);
+        assert_eq!(tree.node(roots[1]).sym.kind, SymKind::S_UDT);
+
+        let children: Vec<SymKind> = tree.children(proc).map(|i| tree.node(i).sym.kind).collect();
+        assert_eq!(
+            children,
+            [SymKind::S_LOCAL, SymKind::S_BLOCK32, SymKind::S_LOCAL]
+        );
+
+        let block = tree.children(proc).nth(1).unwrap();
+        let site = tree.children(block).nth(1).unwrap();
+        assert_eq!(tree.node(site).sym.kind, SymKind::S_INLINESITE);
+        assert_eq!(tree.parent(site), Some(block));
+        assert_eq!(tree.ancestors(site).collect::<Vec<_>>(), [block, proc]);
+        assert_eq!(tree.find(tree.node(site).offset), Some(site));
+        assert_eq!(tree.find(tree.node(site).offset + 1), None);
+    }
+
+    #[test]
+    fn tree_visible_variables() {
+        let stream = build();
+        let tree = SymTree::new(&stream, 4);
+        let proc = 0;
+
+        assert_eq!(names(&tree, &tree.variables_at(proc, 0)), ["a", "d"]);
+        assert_eq!(
+            names(&tree, &tree.variables_at(proc, 0x12)),
+            ["a", "d", "b"]
+        );
+        assert_eq!(
+            names(&tree, &tree.variables_at(proc, 0x19)),
+            ["a", "d", "b", "c"]
+        );
+        assert_eq!(tree.scopes_at(proc, 0x40), [proc]);
+    }
+
+    #[test]
+    fn tree_mismatches() {
+        let mut stream = build();
+        // Corrupt the p_end of the block. The block is the third record.
+        let tree = SymTree::new(&stream, 4);
+        let block = tree.nodes()[2].offset;
+        let p_end_pos = (block - 4) as usize + 8;
+        stream[p_end_pos] ^= 0x40;
+
+        // Append an unmatched S_END.
+        stream.extend_from_slice(&[2, 0, 6, 0]);
+
+        let tree = SymTree::new(&stream, 4);
+        let m = tree.mismatches();
+        assert_eq!(m.len(), 2, "{m:?}");
+        assert!(
+            matches!(m[0], ScopeMismatch::WrongEnd { record_offset, .. } if record_offset == block)
+        );
+        assert!(matches!(m[1], ScopeMismatch::UnmatchedEnd { .. }));
+
+        // Drop the trailing S_END and the UDT, and the final S_END of the proc.
+        let tree = SymTree::new(&stream, 4);
+        let end_of_proc = tree.node(0).end;
+        let cut = (tree.node(end_of_proc).offset - 4) as usize;
+        let tree = SymTree::new(&stream[..cut], 4);
+        assert!(
+            tree.mismatches()
+                .contains(&ScopeMismatch::Unterminated { record_offset: 4 })
+        );
+        assert_eq!(tree.children(0).count(), 3);
+    }
+}
@@ -11,6 +11,7 @@ mod kind;
 #[doc(inline)]
 pub use kind::*;
 
+pub mod builder;
 pub mod fields;
 pub mod number;
 pub mod primitive;
@@ -87,7 +88,9 @@ impl TypeIndexLe {
 pub enum TypeData<'a> {
     Array(Array<'a>),
     Struct(Struct<'a>),
+    Struct2(Struct2<'a>),
     Union(Union<'a>),
+    Union2(Union2<'a>),
     Enum(Enum<'a>),
     Proc(&'a Proc),
     MemberFunc(&'a MemberFunc),
@@ -107,6 +110,13 @@ pub enum TypeData<'a> {
     SubStrList(SubStrList<'a>),
     BuildInfo(BuildInfo<'a>),
     VFTable(&'a VFTable),
+    VFTPath(VFTPath<'a>),
+    Label(&'a Label),
+    DimArray(DimArray<'a>),
+    Precomp(Precomp<'a>),
+    EndPrecomp(&'a EndPrecomp),
+    TypeServer2(TypeServer2<'a>),
+    Managed(Managed<'a>),
     Unknown,
 }
 
@@ -122,7 +132,11 @@ impl<'a> TypeData<'a> {
         Ok(match kind {
             Leaf::LF_ARRAY => Self::Array(p.parse()?),
             Leaf::LF_CLASS | Leaf::LF_STRUCTURE | Leaf::LF_INTERFACE => Self::Struct(p.parse()?),
+            Leaf::LF_CLASS2 | Leaf::LF_STRUCTURE2 | Leaf::LF_INTERFACE2 => {
+                Self::Struct2(p.parse()?)
+            }
             Leaf::LF_UNION => Self::Union(p.parse()?),
+            Leaf::LF_UNION2 => Self::Union2(p.parse()?),
             Leaf::LF_ENUM => Self::Enum(p.parse()?),
             Leaf::LF_PROCEDURE => Self::Proc(p.get()?),
             Leaf::LF_MEMBER => Self::MemberFunc(p.get()?),
@@ -136,6 +150,13 @@ impl<'a> TypeData<'a> {
             }
 
             Leaf::LF_VFTABLE => Self::VFTable(p.get()?),
+            Leaf::LF_VFTPATH => Self::VFTPath(p.parse()?),
+            Leaf::LF_LABEL => Self::Label(p.get()?),
+            Leaf::LF_DIMARRAY => Self::DimArray(p.parse()?),
+            Leaf::LF_PRECOMP => Self::Precomp(p.parse()?),
+            Leaf::LF_ENDPRECOMP => Self::EndPrecomp(p.get()?),
+            Leaf::LF_TYPESERVER2 => Self::TypeServer2(p.parse()?),
+            Leaf::LF_MANAGED => Self::Managed(p.parse()?),
 
             Leaf::LF_POINTER => {
                 let fixed = p.get()?;
@@ -174,9 +195,17 @@ impl<'a> TypeData<'a> {
         match self {
             // From TPI
             Self::Struct(t) => Some(t.name),
+            Self::Struct2(t) => Some(t.name),
             Self::Union(t) => Some(t.name),
+            Self::Union2(t) => Some(t.name),
             Self::Enum(t) => Some(t.name),
             Self::Alias(t) => Some(t.name),
+            Self::DimArray(t) => Some(t.name),
+            Self::Managed(t) => Some(t.name),
+
+            // From .debug$T in object files
+            Self::Precomp(t) => Some(t.name),
+            Self::TypeServer2(t) => Some(t.name),
 
             // From IPI
             Self::FuncId(t) => Some(t.name),
@@ -190,10 +219,15 @@ impl<'a> TypeData<'a> {
     pub fn udt_name(&self) -> Option<&'a BStr> {
         match self {
             Self::Struct(t) => Some(t.name),
+            Self::Struct2(t) => Some(t.name),
             Self::Union(t) => Some(t.name),
+            Self::Union2(t) => Some(t.name),
             Self::Enum(t) => Some(t.name),
             Self::Alias(t) => Some(t.name),
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,574 @@
+//! Supports building new type records
+
+use super::*;
+use crate::encoder::Encoder;
+use crate::types::fields::VirtualBaseClassFixed;
+
+/// The maximum length of a type record, including its length field. Field lists that are longer
+/// than this are split into several `LF_FIELDLIST` records, linked by `LF_INDEX` fields.
+pub const MAX_TYPE_RECORD_LEN: usize = 0xff00;
+
+/// The size of an `LF_INDEX` field.
+const INDEX_FIELD_LEN: usize = 8;
+
+/// Writes type records into a buffer.
+///
+/// Type indexes are assigned to records in the order that they are written. Each method that
+/// writes a record returns the type index of that record.
+///
+/// All records are padded to a multiple of 4 bytes using `LF_PAD` bytes (`0xf3 0xf2 0xf1`).
+///
+/// # Panics
+///
+/// The methods that write a record panic if the record would be longer than
+/// [`MAX_TYPE_RECORD_LEN`]. Long field lists are split instead; see [`TypeBuilder::field_list`].
+pub struct TypeBuilder {
+    /// Contains the encoded type records
+    pub buffer: Vec<u8>,
+
+    /// The type index that will be assigned to the next record.
+    next_type_index: TypeIndex,
+}
+
+impl Default for TypeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeBuilder {
+    /// Creates a new empty type record builder. The first record will be assigned
+    /// [`TypeIndex::MIN_BEGIN`].
+    pub fn new() -> Self {
+        Self::with_type_index_begin(TypeIndex::MIN_BEGIN)
+    }
+
+    /// Creates a new empty type record builder. The first record will be assigned
+    /// `type_index_begin`. Use this when appending records to an existing type stream.
+    pub fn with_type_index_begin(type_index_begin: TypeIndex) -> Self {
+        Self {
+            buffer: Vec::new(),
+            next_type_index: type_index_begin,
+        }
+    }
+
+    /// Consumes this builder and returns the encoded type records.
+    pub fn finish(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    /// The type index that will be assigned to the next record.
+    pub fn next_type_index(&self) -> TypeIndex {
+        self.next_type_index
+    }
+
+    /// Adds a record. `payload` writes the contents of the record, not including the record
+    /// length or the record kind.
+    pub fn record(&mut self, kind: Leaf, payload: impl FnOnce(&mut Encoder)) -> TypeIndex {
+        let record_start = self.buffer.len();
+        self.buffer.extend_from_slice(&[0, 0]); // placeholder for record length
+        self.buffer.extend_from_slice(&kind.0.to_le_bytes());
+
+        let mut e = Encoder::new(&mut self.buffer);
+        payload(&mut e);
+        pad4(&mut e, record_start);
+
+        let record_len = self.buffer.len() - record_start;
+        assert!(
+            record_len <= MAX_TYPE_RECORD_LEN,
+            "type record is too long ({record_len} bytes)"
+        );
+        self.buffer[record_start..record_start + 2]
+            .copy_from_slice(&((record_len - 2) as u16).to_le_bytes());
+
+        let type_index = self.next_type_index;
+        self.next_type_index.0 += 1;
+        type_index
+    }
+
+    /// Adds a record whose payload is a fixed-size structure.
+    fn fixed<T: IntoBytes + Immutable>(&mut self, kind: Leaf, fixed: &T) -> TypeIndex {
+        self.record(kind, |e| e.t(fixed))
+    }
+
+    /// Adds a record whose payload is a fixed-size structure, a numeric leaf, a name, and
+    /// (optionally) a unique name. This is the layout of `LF_CLASS`, `LF_UNION`, etc.
+    fn fixed_udt<T: IntoBytes + Immutable>(
+        &mut self,
+        kind: Leaf,
+        fixed: &T,
+        length: Option<u64>,
+        name: &BStr,
+        unique_name: Option<&BStr>,
+    ) -> TypeIndex {
+        self.record(kind, |e| {
+            e.t(fixed);
+            if let Some(length) = length {
+                e.number_u64(length);
+            }
+            e.strz(name);
+            if let Some(unique_name) = unique_name {
+                e.strz(unique_name);
+            }
+        })
+    }
+
+    /// Adds an `LF_CLASS`, `LF_STRUCTURE`, or `LF_INTERFACE` record.
+    ///
+    /// The `hasuniquename` property must be set if and only if `unique_name` is `Some`.
+    pub fn struct_(
+        &mut self,
+        kind: Leaf,
+        fixed: &StructFixed,
+        length: u64,
+        name: &BStr,
+        unique_name: Option<&BStr>,
+    ) -> TypeIndex {
+        debug_assert!(matches!(
+            kind,
+            Leaf::LF_CLASS | Leaf::LF_STRUCTURE | Leaf::LF_INTERFACE
+        ));
+        debug_assert_eq!(fixed.property.get().hasuniquename(), unique_name.is_some());
+        self.fixed_udt(kind, fixed, Some(length), name, unique_name)
+    }
+
+    /// Adds an `LF_CLASS2`, `LF_STRUCTURE2`, or `LF_INTERFACE2` record. These use a 32-bit
+    /// property field.
+    ///
+    /// The `hasuniquename` property must be set if and only if `unique_name` is `Some`.
+    pub fn struct2(
+        &mut self,
+        kind: Leaf,
+        fixed: &Struct2Fixed,
+        length: u64,
+        name: &BStr,
+        unique_name: Option<&BStr>,
+    ) -> TypeIndex {
+        debug_assert!(matches!(
+            kind,
+            Leaf::LF_CLASS2 | Leaf::LF_STRUCTURE2 | Leaf::LF_INTERFACE2
+        ));
+        debug_assert_eq!(
+            UdtProperties(fixed.property.get() as u16).hasuniquename(),
+            unique_name.is_some()
+        );
+        self.fixed_udt(kind, fixed, Some(length), name, unique_name)
+    }
+
+    /// Adds an `LF_UNION` record.
+    pub fn union(
+        &mut self,
+        fixed: &UnionFixed,
+        length: u64,
+        name: &BStr,
+        unique_name: Option<&BStr>,
+    ) -> TypeIndex {
+        debug_assert_eq!(fixed.property.get().hasuniquename(), unique_name.is_some());
+        self.fixed_udt(Leaf::LF_UNION, fixed, Some(length), name, unique_name)
+    }
+
+    /// Adds an `LF_UNION2` record, which uses a 32-bit property field.
+    pub fn union2(
+        &mut self,
+        fixed: &Union2Fixed,
+        length: u64,
+        name: &BStr,
+        unique_name: Option<&BStr>,
+    ) -> TypeIndex {
+        debug_assert_eq!(
+            UdtProperties(fixed.property.get() as u16).hasuniquename(),
+            unique_name.is_some()
+        );
+        self.fixed_udt(Leaf::LF_UNION2, fixed, Some(length), name, unique_name)
+    }
+
+    /// Adds an `LF_ENUM` record.
+    pub fn enum_(
+        &mut self,
+        fixed: &EnumFixed,
+        name: &BStr,
+        unique_name: Option<&BStr>,
+    ) -> TypeIndex {
+        debug_assert_eq!(fixed.property.get().hasuniquename(), unique_name.is_some());
+        self.fixed_udt(Leaf::LF_ENUM, fixed, None, name, unique_name)
+    }
+
+    /// Adds an `LF_ARRAY` record. `length` is the size of the array in bytes.
+    pub fn array(&mut self, fixed: &ArrayFixed, length: u64, name: &BStr) -> TypeIndex {
+        self.fixed_udt(Leaf::LF_ARRAY, fixed, Some(length), name, None)
+    }
+
+    /// Adds an `LF_POINTER` record. `variant` contains the data that follows the fixed-size
+    /// part, such as the containing class of a pointer to member.
+    pub fn pointer(&mut self, fixed: &PointerFixed, variant: &[u8]) -> TypeIndex {
+        self.record(Leaf::LF_POINTER, |e| {
+            e.t(fixed);
+            e.bytes(variant);
+        })
+    }
+
+    /// Adds an `LF_POINTER` record for a pointer to a data member or member function.
+    /// `pm_type` is the representation of the pointer to member (`CV_pmtype_e`).
+    pub fn member_pointer(
+        &mut self,
+        fixed: &PointerFixed,
+        containing_class: TypeIndex,
+        pm_type: u16,
+    ) -> TypeIndex {
+        self.record(Leaf::LF_POINTER, |e| {
+            e.t(fixed);
+            e.u32(containing_class.0);
+            e.u16(pm_type);
+        })
+    }
+
+    /// Adds an `LF_MODIFIER` record.
+    pub fn modifier(&mut self, modifier: &TypeModifier) -> TypeIndex {
+        self.fixed(Leaf::LF_MODIFIER, modifier)
+    }
+
+    /// Adds an `LF_BITFIELD` record.
+    pub fn bitfield(&mut self, bitfield: &Bitfield) -> TypeIndex {
+        self.fixed(Leaf::LF_BITFIELD, bitfield)
+    }
+
+    /// Adds an `LF_PROCEDURE` record.
+    pub fn proc(&mut self, proc: &Proc) -> TypeIndex {
+        self.fixed(Leaf::LF_PROCEDURE, proc)
+    }
+
+    /// Adds an `LF_MFUNCTION` record.
+    pub fn member_func(&mut self, member_func: &MemberFunc) -> TypeIndex {
+        self.fixed(Leaf::LF_MFUNCTION, member_func)
+    }
+
+    /// Adds an `LF_ARGLIST` record.
+    pub fn arg_list(&mut self, args: &[TypeIndex]) -> TypeIndex {
+        self.record(Leaf::LF_ARGLIST, |e| {
+            e.u32(args.len() as u32);
+            for arg in args {
+                e.u32(arg.0);
+            }
+        })
+    }
+
+    /// Adds an `LF_METHODLIST` record. The `vtab_offset` of each method must be present if and
+    /// only if its attributes indicate that it introduces a virtual function.
+    pub fn method_list(&mut self, methods: &[MethodListItem]) -> TypeIndex {
+        self.record(Leaf::LF_METHODLIST, |e| {
+            for m in methods {
+                debug_assert_eq!(introduces_virtual(m.attr), m.vtab_offset.is_some());
+                e.u16(m.attr);
+                e.u16(0); // padding
+                e.u32(m.ty.0);
+                if let Some(vtab_offset) = m.vtab_offset {
+                    e.u32(vtab_offset);
+                }
+            }
+        })
+    }
+
+    /// Adds an `LF_VTSHAPE` record. `descriptors` contains `count` 4-bit descriptors, packed two
+    /// per byte.
+    pub fn vtable_shape(&mut self, count: u16, descriptors: &[u8]) -> TypeIndex {
+        debug_assert_eq!(descriptors.len(), (count as usize).div_ceil(2));
+        self.record(Leaf::LF_VTSHAPE, |e| {
+            e.u16(count);
+            e.bytes(descriptors);
+        })
+    }
+
+    /// Adds an `LF_ALIAS` record.
+    pub fn alias(&mut self, utype: TypeIndex, name: &BStr) -> TypeIndex {
+        self.record(Leaf::LF_ALIAS, |e| {
+            e.u32(utype.0);
+            e.strz(name);
+        })
+    }
+
+    /// Adds an `LF_FIELDLIST` record, or a chain of them.
+    ///
+    /// If the fields do not fit within a single record, then they are split into several
+    /// records. Each record (except the last) ends with an `LF_INDEX` field that points to the
+    /// record that contains the next fields. Since records may only point to earlier records,
+    /// the records are written in reverse order. The returned type index is the record that
+    /// contains the first fields.
+    pub fn field_list(&mut self, fields: &FieldListBuilder) -> TypeIndex {
+        // The space available for fields in each record, leaving room for an LF_INDEX field.
+        const MAX_FIELDS_LEN: usize = MAX_TYPE_RECORD_LEN - 4 - INDEX_FIELD_LEN;
+
+        // Find the ranges of fields that are placed in each record.
+        let mut segments: Vec<(usize, usize)> = Vec::new();
+        let mut segment_start = 0;
+        for (i, &field_start) in fields.starts.iter().enumerate() {
+            let field_end = fields
+                .starts
+                .get(i + 1)
+                .copied()
+                .unwrap_or(fields.bytes.len());
+            if field_end - segment_start > MAX_FIELDS_LEN && field_start > segment_start {
+                segments.push((segment_start, field_start));
+                segment_start = field_start;
+            }
+        }
+        segments.push((segment_start, fields.bytes.len()));
+
+        let mut next: Option<TypeIndex> = None;
+        for &(start, end) in segments.iter().rev() {
+            next = Some(self.record(Leaf::LF_FIELDLIST, |e| {
+                e.bytes(&fields.bytes[start..end]);
+                if let Some(next) = next {
+                    e.u16(Leaf::LF_INDEX.0);
+                    e.u16(0); // padding
+                    e.u32(next.0);
+                }
+            }));
+        }
+        next.unwrap()
+    }
+
+    /// Adds an `LF_UDT_SRC_LINE` record (IPI).
+    pub fn udt_src_line(&mut self, fixed: &UdtSrcLine) -> TypeIndex {
+        self.fixed(Leaf::LF_UDT_SRC_LINE, fixed)
+    }
+
+    /// Adds an `LF_UDT_MOD_SRC_LINE` record (IPI).
+    pub fn udt_mod_src_line(&mut self, fixed: &UdtModSrcLine) -> TypeIndex {
+        self.fixed(Leaf::LF_UDT_MOD_SRC_LINE, fixed)
+    }
+
+    /// Adds an `LF_FUNC_ID` record (IPI).
+    pub fn func_id(&mut self, fixed: &FuncIdFixed, name: &BStr) -> TypeIndex {
+        self.record(Leaf::LF_FUNC_ID, |e| {
+            e.t(fixed);
+            e.strz(name);
+        })
+    }
+
+    /// Adds an `LF_MFUNC_ID` record (IPI).
+    pub fn mfunc_id(&mut self, fixed: &MFuncIdFixed, name: &BStr) -> TypeIndex {
+        self.record(Leaf::LF_MFUNC_ID, |e| {
+            e.t(fixed);
+            e.strz(name);
+        })
+    }
+
+    /// Adds an `LF_STRING_ID` record (IPI). `id` is an `LF_SUBSTR_LIST` record, or 0.
+    pub fn string_id(&mut self, id: ItemId, name: &BStr) -> TypeIndex {
+        self.record(Leaf::LF_STRING_ID, |e| {
+            e.u32(id);
+            e.strz(name);
+        })
+    }
+
+    /// Adds an `LF_SUBSTR_LIST` record (IPI).
+    pub fn substr_list(&mut self, ids: &[ItemId]) -> TypeIndex {
+        self.record(Leaf::LF_SUBSTR_LIST, |e| {
+            e.u32(ids.len() as u32);
+            for &id in ids {
+                e.u32(id);
+            }
+        })
+    }
+
+    /// Adds an `LF_BUILDINFO` record (IPI). See [`BuildInfoIndex`] for the meaning of each
+    /// argument.
+    pub fn build_info(&mut self, args: &[ItemId]) -> TypeIndex {
+        self.record(Leaf::LF_BUILDINFO, |e| {
+            e.u16(args.len() as u16);
+            for &arg in args {
+                e.u32(arg);
+            }
+        })
+    }
+}
+
+/// Builds the list of fields of an `LF_FIELDLIST` record. Use [`TypeBuilder::field_list`] to
+/// write the record.
+///
+/// Each field is padded to a multiple of 4 bytes using `LF_PAD` bytes.
+#[derive(Default)]
+pub struct FieldListBuilder {
+    /// The encoded fields
+    bytes: Vec<u8>,
+    /// The offset within `bytes` of each field
+    starts: Vec<usize>,
+}
+
+impl FieldListBuilder {
+    /// Creates an empty field list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of fields that have been added.
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Returns `true` if no fields have been added.
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Adds a field. `payload` writes the contents of the field, not including the field kind.
+    pub fn field(&mut self, kind: Leaf, payload: impl FnOnce(&mut Encoder)) {
+        let field_start = self.bytes.len();
+        self.starts.push(field_start);
+        let mut e = Encoder::new(&mut self.bytes);
+        e.u16(kind.0);
+        payload(&mut e);
+        pad4(&mut e, field_start);
+    }
+
+    /// Adds an `LF_MEMBER` field (a non-static data member).
+    pub fn member(&mut self, attr: u16, ty: TypeIndex, offset: u64, name: &BStr) {
+        self.field(Leaf::LF_MEMBER, |e| {
+            e.u16(attr);
+            e.u32(ty.0);
+            e.number_u64(offset);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_STMEMBER` field (a static data member).
+    pub fn static_member(&mut self, attr: u16, ty: TypeIndex, name: &BStr) {
+        self.field(Leaf::LF_STMEMBER, |e| {
+            e.u16(attr);
+            e.u32(ty.0);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_BCLASS` field (a non-virtual base class).
+    pub fn base_class(&mut self, attr: u16, ty: TypeIndex, offset: u64) {
+        self.field(Leaf::LF_BCLASS, |e| {
+            e.u16(attr);
+            e.u32(ty.0);
+            e.number_u64(offset);
+        });
+    }
+
+    /// Adds an `LF_VBCLASS` or `LF_IVBCLASS` field (a direct or indirect virtual base class).
+    pub fn virtual_base_class(
+        &mut self,
+        kind: Leaf,
+        fixed: &VirtualBaseClassFixed,
+        vbpoff: u64,
+        vboff: u64,
+    ) {
+        debug_assert!(matches!(kind, Leaf::LF_VBCLASS | Leaf::LF_IVBCLASS));
+        self.field(kind, |e| {
+            e.t(fixed);
+            e.number_u64(vbpoff);
+            e.number_u64(vboff);
+        });
+    }
+
+    /// Adds an `LF_ENUMERATE` field (an enumerator).
+    pub fn enumerate(&mut self, attr: u16, value: i64, name: &BStr) {
+        self.field(Leaf::LF_ENUMERATE, |e| {
+            e.u16(attr);
+            e.number_i64(value);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_ENUMERATE` field whose value does not fit in an `i64`.
+    pub fn enumerate_u64(&mut self, attr: u16, value: u64, name: &BStr) {
+        self.field(Leaf::LF_ENUMERATE, |e| {
+            e.u16(attr);
+            e.number_u64(value);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_NESTEDTYPE` field.
+    pub fn nested_type(&mut self, ty: TypeIndex, name: &BStr) {
+        self.field(Leaf::LF_NESTEDTYPE, |e| {
+            e.u16(0); // padding
+            e.u32(ty.0);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_NESTEDTYPEEX` field.
+    pub fn nested_type_ex(&mut self, attr: u16, ty: TypeIndex, name: &BStr) {
+        self.field(Leaf::LF_NESTEDTYPEEX, |e| {
+            e.u16(attr);
+            e.u32(ty.0);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_VFUNCTAB` field (a virtual function table pointer).
+    pub fn vfunc_table(&mut self, ty: TypeIndex) {
+        self.field(Leaf::LF_VFUNCTAB, |e| {
+            e.u16(0); // padding
+            e.u32(ty.0);
+        });
+    }
+
+    /// Adds an `LF_VFUNCOFF` field.
+    pub fn vfunc_offset(&mut self, vtable_ty: TypeIndex, offset: u32) {
+        self.field(Leaf::LF_VFUNCOFF, |e| {
+            e.u16(0); // padding
+            e.u32(vtable_ty.0);
+            e.u32(offset);
+        });
+    }
+
+    /// Adds an `LF_ONEMETHOD` field (a method that is not overloaded). `vbaseoff` must be present
+    /// if and only if `attr` indicates that the method introduces a virtual function.
+    pub fn one_method(&mut self, attr: u16, ty: TypeIndex, vbaseoff: Option<u32>, name: &BStr) {
+        debug_assert_eq!(introduces_virtual(attr), vbaseoff.is_some());
+        self.field(Leaf::LF_ONEMETHOD, |e| {
+            e.u16(attr);
+            e.u32(ty.0);
+            if let Some(vbaseoff) = vbaseoff {
+                e.u32(vbaseoff);
+            }
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_METHOD` field (a group of overloaded methods). `methods` is an
+    /// `LF_METHODLIST` record.
+    pub fn method(&mut self, count: u16, methods: TypeIndex, name: &BStr) {
+        self.field(Leaf::LF_METHOD, |e| {
+            e.u16(count);
+            e.u32(methods.0);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_FRIENDFCN` field.
+    pub fn friend_fn(&mut self, ty: TypeIndex, name: &BStr) {
+        self.field(Leaf::LF_FRIENDFCN, |e| {
+            e.u16(0); // padding
+            e.u32(ty.0);
+            e.strz(name);
+        });
+    }
+
+    /// Adds an `LF_FRIENDCLS` field.
+    pub fn friend_class(&mut self, ty: TypeIndex) {
+        self.field(Leaf::LF_FRIENDCLS, |e| {
+            e.u16(0); // padding
+            e.u32(ty.0);
+        });
+    }
+}
+
+/// Pads the data written since `start` to a multiple of 4 bytes, using `LF_PAD3`, `LF_PAD2`, and
+/// `LF_PAD1`. The value of each padding byte gives the number of bytes that remain until the
+/// alignment boundary.
+fn pad4(e: &mut Encoder, start: usize) {
+    let len = e.buf.len() - start;
+    for i in (1..=(4 - len % 4) % 4).rev() {
+        e.u8(0xf0 | i as u8);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,322 @@
+use super::*;
+use crate::types::fields::Field;
+use zerocopy::FromZeros;
+
+fn records(b: TypeBuilder) -> Vec<(Leaf, Vec<u8>)> {
+    let bytes = b.finish();
+    assert_eq!(bytes.len() % 4, 0);
+    TypesIter::new(&bytes)
+        .map(|r| (r.kind, r.data.to_vec()))
+        .collect()
+}
+
+fn fields(data: &[u8]) -> Vec<Field<'_>> {
+    let TypeData::FieldList(fl) = TypeData::parse_bytes(Leaf::LF_FIELDLIST, data).unwrap() else {
+        panic!()
+    };
+    fl.iter().collect()
+}
+
+#[test]
+fn udt_and_fields() {
+    let mut b = TypeBuilder::new();
+
+    let mut fl = FieldListBuilder::new();
+    fl.base_class(3, TypeIndex(0x1000), 0);
+    fl.member(3, TypeIndex::T_INT4, 0x12345, "count".into());
+    fl.static_member(3, TypeIndex::T_RCHAR, "s".into());
+    fl.one_method(3 | (4 << 2), TypeIndex(0x1001), Some(8), "f".into());
+    fl.nested_type(TypeIndex(0x1002), "Inner".into());
+    fl.vfunc_table(TypeIndex(0x1003));
+    assert_eq!(fl.len(), 6);
+    let fields_ti = b.field_list(&fl);
+    assert_eq!(fields_ti, TypeIndex(0x1000));
+
+    let mut fixed = StructFixed::new_zeroed();
+    fixed.num_elements = U16::new(6);
+    let mut property = UdtProperties(0);
+    property.set_hasuniquename(true);
+    fixed.property = UdtPropertiesLe(U16::new(property.0));
+    fixed.field_list = fields_ti.into();
+    let s = b.struct_(
+        Leaf::LF_CLASS,
+        &fixed,
+        0x20000,
+        "Outer".into(),
+        Some(".?AVOuter@@".into()),
+    );
+    assert_eq!(s, TypeIndex(0x1001));
+    assert_eq!(b.next_type_index(), TypeIndex(0x1002));
+
+    let recs = records(b);
+    assert_eq!(recs.len(), 2);
+
+    let f = fields(&recs[0].1);
+    assert_eq!(f.len(), 6);
+    let Field::Member(m) = &f[1] else { panic!() };
+    assert_eq!(m.name, "count");
+    assert_eq!(u32::try_from(m.offset).unwrap(), 0x12345);
+    let Field::OneMethod(om) = &f[3] else {
+        panic!()
+    };
+    assert_eq!(om.vbaseoff, 8);
+    assert_eq!(om.name, "f");
+    assert!(matches!(f[5], Field::VFuncTable(TypeIndex(0x1003))));
+
+    let TypeData::Struct(t) = TypeData::parse_bytes(recs[1].0, &recs[1].1).unwrap() else {
+        panic!()
+    };
+    assert_eq!(t.fixed.num_elements.get(), 6);
+    assert_eq!(t.fixed.field_list.get(), fields_ti);
+    assert_eq!(u64::try_from(t.length).unwrap(), 0x20000);
+    assert_eq!(t.name, "Outer");
+    assert_eq!(t.unique_name.unwrap(), ".?AVOuter@@");
+}
+
+#[test]
+fn simple_records() {
+    let mut b = TypeBuilder::new();
+    let pint = b.pointer(
+        &PointerFixed {
+            ty: TypeIndex::T_INT4.into(),
+            attr: U32::new(0x1000c),
+        },
+        &[],
+    );
+    b.member_pointer(
+        &PointerFixed {
+            ty: TypeIndex::T_INT4.into(),
+            attr: U32::new(0x1004c),
+        },
+        TypeIndex(0x1234),
+        1,
+    );
+    let cint = b.modifier(&TypeModifier {
+        underlying_type: TypeIndex::T_INT4.into(),
+        attributes: U16::new(1),
+    });
+    b.array(
+        &ArrayFixed {
+            element_type: cint.into(),
+            index_type: TypeIndex::T_UINT8.into(),
+        },
+        40,
+        "".into(),
+    );
+    let args = b.arg_list(&[pint, TypeIndex::T_INT4]);
+    let mut proc = Proc::new_zeroed();
+    proc.return_value = TypeIndex::T_VOID.into();
+    proc.num_params = U16::new(2);
+    proc.arg_list = args.into();
+    b.proc(&proc);
+    b.bitfield(&Bitfield {
+        underlying_type: TypeIndex::T_UINT4.into(),
+        length: 3,
+        position: 5,
+    });
+    b.alias(TypeIndex::T_UINT8, "size_t".into());
+    b.vtable_shape(3, &[0x11, 0x01]);
+
+    let mut fl = FieldListBuilder::new();
+    fl.enumerate(3, -1, "Neg".into());
+    fl.enumerate(3, 0x8000, "Big".into());
+    fl.enumerate_u64(3, u64::MAX, "Max".into());
+    let fields_ti = b.field_list(&fl);
+    let mut e = EnumFixed::new_zeroed();
+    e.count = U16::new(3);
+    e.underlying_type = TypeIndex::T_INT8.into();
+    e.fields = fields_ti.into();
+    b.enum_(&e, "E".into(), None);
+
+    let recs = records(b);
+    let parse = |i: usize| TypeData::parse_bytes(recs[i].0, &recs[i].1).unwrap();
+
+    let TypeData::Pointer(p) = parse(0) else {
+        panic!()
+    };
+    assert_eq!(p.fixed.ty.get(), TypeIndex::T_INT4);
+    assert!(p.variant.iter().all(|&b| b >= 0xf0));
+    let TypeData::Pointer(p) = parse(1) else {
+        panic!()
+    };
+    assert_eq!(p.member_class(), Some(TypeIndex(0x1234)));
+    let TypeData::Modifier(m) = parse(2) else {
+        panic!()
+    };
+    assert!(m.is_const());
+    let TypeData::Array(a) = parse(3) else {
+        panic!()
+    };
+    assert_eq!(a.fixed.element_type.get(), cint);
+    assert_eq!(u32::try_from(a.len).unwrap(), 40);
+    let TypeData::ArgList(l) = parse(4) else {
+        panic!()
+    };
+    assert_eq!(l.args.len(), 2);
+    assert_eq!(l.args[0].get(), pint);
+    let TypeData::Proc(p) = parse(5) else {
+        panic!()
+    };
+    assert_eq!(p.arg_list.get(), args);
+    let TypeData::Bitfield(bf) = parse(6) else {
+        panic!()
+    };
+    assert_eq!((bf.length, bf.position), (3, 5));
+    let TypeData::Alias(al) = parse(7) else {
+        panic!()
+    };
+    assert_eq!(al.name, "size_t");
+    let TypeData::VTableShape(vt) = parse(8) else {
+        panic!()
+    };
+    assert_eq!(vt.count, 3);
+    assert_eq!(&vt.descriptors[..2], &[0x11, 0x01]);
+
+    let f = fields(&recs[9].1);
+    let values: Vec<String> = f
+        .iter()
+        .map(|f| match f {
+            Field::Enumerate(e) => format!("{} = {}", e.name, e.value),
+            _ => panic!(),
+        })
+        .collect();
+    assert_eq!(
+        values,
+        ["Neg = -1", "Big = 32768", "Max = 18446744073709551615"]
+    );
+
+    let TypeData::Enum(en) = parse(10) else {
+        panic!()
+    };
+    assert_eq!(en.fixed.fields.get(), fields_ti);
+    assert_eq!(en.name, "E");
+    assert!(en.unique_name.is_none());
+}
+
+#[test]
+fn class2_and_union2() {
+    let mut b = TypeBuilder::new();
+    let mut fixed = Struct2Fixed::new_zeroed();
+    fixed.property = U32::new(0x1_0000 | (1 << 9));
+    fixed.num_elements = U16::new(2);
+    b.struct2(
+        Leaf::LF_STRUCTURE2,
+        &fixed,
+        24,
+        "S2".into(),
+        Some(".?AUS2@@".into()),
+    );
+    let mut fixed = Union2Fixed::new_zeroed();
+    fixed.property = U32::new(1 << 7);
+    b.union2(&fixed, 0, "U2".into(), None);
+
+    let recs = records(b);
+    assert_eq!(recs[0].0, Leaf::LF_STRUCTURE2);
+    let mut p = Parser::new(&recs[0].1);
+    let f: &Struct2Fixed = p.get().unwrap();
+    assert_eq!(f.property.get(), 0x1_0200);
+    assert_eq!(f.num_elements.get(), 2);
+    assert_eq!(u32::try_from(p.number().unwrap()).unwrap(), 24);
+    assert_eq!(p.strz().unwrap(), "S2");
+    assert_eq!(p.strz().unwrap(), ".?AUS2@@");
+
+    assert_eq!(recs[1].0, Leaf::LF_UNION2);
+    let mut p = Parser::new(&recs[1].1);
+    let f: &Union2Fixed = p.get().unwrap();
+    assert_eq!(f.property.get(), 1 << 7);
+    assert_eq!(u32::try_from(p.number().unwrap()).unwrap(), 0);
+    assert_eq!(p.strz().unwrap(), "U2");
+}
+
+#[test]
+fn long_field_list() {
+    let mut b = TypeBuilder::with_type_index_begin(TypeIndex(0x2000));
+    let mut fl = FieldListBuilder::new();
+    let names: Vec<String> = (0..5000).map(|i| format!("member_{i:05}")).collect();
+    for (i, name) in names.iter().enumerate() {
+        fl.member(3, TypeIndex::T_INT4, i as u64 * 4, name.as_str().into());
+    }
+    let first = b.field_list(&fl);
+
+    let bytes = b.finish();
+    let recs: Vec<TypeRecord> = TypesIter::new(&bytes).collect();
+    assert!(recs.len() > 1);
+    let end = TypeIndex(0x2000 + recs.len() as u32);
+    // The first fields are in the last record.
+    assert_eq!(first.0, end.0 - 1);
+
+    // Follow the chain of LF_INDEX fields.
+    let mut found: Vec<String> = Vec::new();
+    let mut next = Some(first);
+    while let Some(ti) = next.take() {
+        let r = &recs[(ti.0 - 0x2000) as usize];
+        assert!(r.data.len() + 4 <= MAX_TYPE_RECORD_LEN);
+        for f in fields(r.data) {
+            match f {
+                Field::Member(m) => found.push(m.name.to_string()),
+                Field::Index(i) => {
+                    assert!(i < ti);
+                    next = Some(i);
+                }
+                _ => panic!(),
+            }
+        }
+    }
+    assert_eq!(found, names);
+}
+
+#[test]
+fn ipi_records() {
+    let mut b = TypeBuilder::new();
+    let s = b.string_id(0, "C:\\src".into());
+    let list = b.substr_list(&[s.0]);
+    b.func_id(
+        &FuncIdFixed {
+            scope: U32::new(0),
+            func_type: TypeIndex(0x1234).into(),
+        },
+        "main".into(),
+    );
+    b.build_info(&[s.0, s.0]);
+    b.method_list(&[
+        MethodListItem {
+            attr: 3,
+            ty: TypeIndex(0x1000),
+            vtab_offset: None,
+        },
+        MethodListItem {
+            attr: 3 | (4 << 2),
+            ty: TypeIndex(0x1001),
+            vtab_offset: Some(16),
+        },
+    ]);
+
+    let recs = records(b);
+    let parse = |i: usize| TypeData::parse_bytes(recs[i].0, &recs[i].1).unwrap();
+    let TypeData::StringId(sid) = parse(0) else {
+        panic!()
+    };
+    assert_eq!(sid.name, "C:\\src");
+    let TypeData::SubStrList(sl) = parse(1) else {
+        panic!()
+    };
+    assert_eq!(sl.ids[0].get(), s.0);
+    assert_eq!(list, TypeIndex(0x1001));
+    let TypeData::FuncId(f) = parse(2) else {
+        panic!()
+    };
+    assert_eq!(f.name, "main");
+    assert_eq!(f.fixed.func_type.get(), TypeIndex(0x1234));
+    let TypeData::BuildInfo(bi) = parse(3) else {
+        panic!()
+    };
+    assert_eq!(bi.arg(BuildInfoIndex::BuildTool), Some(s.0));
+
+    let mut ml = MethodList::parse(&recs[4].1).unwrap();
+    let m0 = ml.next().unwrap().unwrap();
+    assert_eq!(m0.vtab_offset, None);
+    let m1 = ml.next().unwrap().unwrap();
+    assert_eq!(m1.ty, TypeIndex(0x1001));
+    assert_eq!(m1.vtab_offset, Some(16));
+    assert!(ml.next().unwrap().is_none());
+}
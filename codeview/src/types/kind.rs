@@ -39,6 +39,7 @@ cv_leaf! {
     0x000e, LF_LABEL;
     0x000f, LF_NULL;
     0x0014, LF_ENDPRECOMP;
+    0x0016, LF_TYPESERVER;  // obsolete; replaced by LF_TYPESERVER2
     0x020c, LF_REFSYM;
     0x040b, LF_FRIENDCLS;   // (in field list) friend class
     0x1001, LF_MODIFIER;
@@ -66,6 +67,7 @@ cv_leaf! {
     0x1404, LF_INDEX;       // (in field list) index to another type record
     0x1409, LF_VFUNCTAB;    // (in field list) virtual function table pointer
     0x140c, LF_VFUNCOFF;    // (in field list) virtual function offset
+    0x1501, LF_TYPESERVER_ST;
     0x1502, LF_ENUMERATE;   // (in field list) an enumerator value
     0x1503, LF_ARRAY;
     0x1504, LF_CLASS;
@@ -196,6 +198,10 @@ impl Leaf {
                 | Leaf::LF_ALIAS
                 | Leaf::LF_MANAGED
                 | Leaf::LF_TYPESERVER2
+                | Leaf::LF_CLASS2
+                | Leaf::LF_STRUCTURE2
+                | Leaf::LF_UNION2
+                | Leaf::LF_INTERFACE2
         )
     }
 
@@ -218,6 +224,12 @@ impl Leaf {
         )
     }
 
+    /// Checks whether this `Leaf` is an ID record. ID records are stored in the IPI stream, not the
+    /// TPI stream. Object files store ID records and type records in the same `.debug$T` section.
+    pub fn is_id_record(self) -> bool {
+        (Leaf::LF_FUNC_ID.0..=Leaf::LF_UDT_MOD_SRC_LINE.0).contains(&self.0)
+    }
+
     /// Indicates whether a given type record can contain references to other type records.
     pub fn can_reference_types(self) -> bool {
         matches!(
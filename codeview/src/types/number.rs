@@ -499,3 +499,41 @@ fn display_bogus() {
     };
     println!("bogus_num = {bogus_num}");
 }
+
+#[test]
+fn number_encode_round_trip() {
+    use crate::encoder::Encoder;
+
+    for x in [
+        0i64,
+        0x7fff,
+        0x8000,
+        0xffff,
+        0x1_0000,
+        0xffff_ffff,
+        0x1_0000_0000,
+        i64::MAX,
+        -1,
+        -128,
+        -129,
+        -32768,
+        -32769,
+        i32::MIN as i64,
+        i32::MIN as i64 - 1,
+        i64::MIN,
+    ] {
+        let mut buf = Vec::new();
+        Encoder::new(&mut buf).number_i64(x);
+        let n = parse_number(&buf);
+        assert_eq!(i64::try_from(n).ok(), Some(x), "{x}");
+    }
+
+    let mut buf = Vec::new();
+    Encoder::new(&mut buf).number_u64(u64::MAX);
+    assert_eq!(parse_number(&buf).kind(), Leaf::LF_UQUADWORD);
+    assert_eq!(u64::try_from(parse_number(&buf)).unwrap(), u64::MAX);
+
+    let mut buf = Vec::new();
+    Encoder::new(&mut buf).number_i64(-1);
+    assert_eq!(buf, [0x00, 0x80, 0xff]);
+}
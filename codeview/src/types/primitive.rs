@@ -377,6 +377,38 @@ pub fn dump_primitive_type_index(
     Ok(())
 }
 
+/// Gets the size in bytes of a primitive type. For primitive pointer types (such as `T_64PVOID`),
+/// this is the size of the pointer.
+pub fn primitive_type_size(type_index: TypeIndex) -> Option<u32> {
+    let mode = (type_index.0 >> 8) & 7;
+    match mode {
+        0 => {}
+        1 => return Some(2),
+        2..=4 => return Some(4),
+        5 => return Some(6),
+        6 => return Some(8),
+        _ => return None,
+    }
+
+    Some(match type_index.0 & 0xff {
+        0x08 => 4,                             // T_HRESULT
+        0x10 | 0x20 | 0x30 | 0x68 | 0x69 => 1, // char, uchar, bool, __int8
+        0x70 | 0x7c => 1,                      // T_RCHAR, T_CHAR8
+        0x11 | 0x21 | 0x31 | 0x46 => 2,        // short, ushort, bool16, real16
+        0x71 | 0x72 | 0x73 | 0x7a => 2,        // wchar_t, __int16, char16_t
+        0x12 | 0x22 | 0x32 | 0x40 | 0x45 => 4, // long, ulong, bool32, float
+        0x62 | 0x74 | 0x75 | 0x7b => 4,        // __int32, char32_t
+        0x13 | 0x23 | 0x33 | 0x41 | 0x50 => 8, // long long, bool64, double, complex32
+        0x76 | 0x77 => 8,                      // __int64
+        0x44 => 6,                             // real48
+        0x42 => 10,                            // real80
+        0x14 | 0x24 | 0x43 | 0x51 | 0x78 | 0x79 => 16,
+        0x52 => 20,
+        0x53 => 32,
+        _ => return None,
+    })
+}
+
 #[test]
 fn test_dump() {
     let mut s = String::new();
@@ -386,3 +418,12 @@ fn test_dump() {
     s.clear();
     dump_primitive_type_index(&mut s, TypeIndex(0x067c)).unwrap();
 }
+
+#[test]
+fn test_size() {
+    assert_eq!(primitive_type_size(TypeIndex::T_RCHAR), Some(1));
+    assert_eq!(primitive_type_size(TypeIndex::T_REAL64), Some(8));
+    assert_eq!(primitive_type_size(TypeIndex::T_64PVOID), Some(8));
+    assert_eq!(primitive_type_size(TypeIndex::T_32PVOID), Some(4));
+    assert_eq!(primitive_type_size(TypeIndex::T_VOID), None);
+}
@@ -151,6 +151,110 @@ impl<'a> Parse<'a> for Struct<'a> {
     }
 }
 
+/// The fixed-size part of `LF_CLASS2`, `LF_STRUCTURE2`, and `LF_INTERFACE2`. These are the same
+/// as `LF_CLASS`, etc., except that the property field is 32 bits and the element count follows
+/// the type indexes.
+///
+/// See `lfClass2` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct Struct2Fixed {
+    /// Bit flags. The low 16 bits are the same as [`UdtProperties`].
+    pub property: U32<LE>,
+    pub field_list: TypeIndexLe,
+    pub derivation_list: TypeIndexLe,
+    pub vtable_shape: TypeIndexLe,
+    pub num_elements: U16<LE>,
+    // numeric leaf
+}
+
+impl Struct2Fixed {
+    /// The low 16 bits of the property field, which have the same meaning as the property field
+    /// of `LF_CLASS`, etc.
+    #[inline(always)]
+    pub fn udt_properties(&self) -> UdtProperties {
+        UdtProperties(self.property.get() as u16)
+    }
+}
+
+/// For `LF_CLASS2`, `LF_STRUCTURE2`, and `LF_INTERFACE2`.
+#[derive(Clone, Debug)]
+pub struct Struct2<'a> {
+    pub fixed: &'a Struct2Fixed,
+    pub length: Number<'a>,
+    pub name: &'a BStr,
+    pub unique_name: Option<&'a BStr>,
+}
+
+impl<'a> Parse<'a> for Struct2<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let fixed: &Struct2Fixed = p.get()?;
+        let length = p.number()?;
+        let name = p.strz()?;
+        let unique_name = if fixed.udt_properties().hasuniquename() {
+            Some(p.strz()?)
+        } else {
+            None
+        };
+        Ok(Struct2 {
+            fixed,
+            length,
+            name,
+            unique_name,
+        })
+    }
+}
+
+/// The fixed-size part of `LF_UNION2`.
+///
+/// See `lfUnion2` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct Union2Fixed {
+    /// Bit flags. The low 16 bits are the same as [`UdtProperties`].
+    pub property: U32<LE>,
+    pub fields: TypeIndexLe,
+    pub count: U16<LE>,
+    // numeric leaf
+}
+
+impl Union2Fixed {
+    /// The low 16 bits of the property field, which have the same meaning as the property field
+    /// of `LF_UNION`.
+    #[inline(always)]
+    pub fn udt_properties(&self) -> UdtProperties {
+        UdtProperties(self.property.get() as u16)
+    }
+}
+
+/// For `LF_UNION2`.
+#[derive(Clone, Debug)]
+pub struct Union2<'a> {
+    pub fixed: &'a Union2Fixed,
+    pub length: Number<'a>,
+    pub name: &'a BStr,
+    pub unique_name: Option<&'a BStr>,
+}
+
+impl<'a> Parse<'a> for Union2<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let fixed: &Union2Fixed = p.get()?;
+        let length = p.number()?;
+        let name = p.strz()?;
+        let unique_name = if fixed.udt_properties().hasuniquename() {
+            Some(p.strz()?)
+        } else {
+            None
+        };
+        Ok(Union2 {
+            fixed,
+            length,
+            name,
+            unique_name,
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Union<'a> {
     pub fixed: &'a UnionFixed,
@@ -253,6 +357,14 @@ pub struct Proc {
     pub arg_list: TypeIndexLe,
 }
 
+impl Proc {
+    /// The calling convention of the procedure
+    pub fn calling_convention(&self) -> CallingConvention {
+        CallingConvention(self.call)
+    }
+}
+
+/// `LF_MFUNCTION`
 #[repr(C)]
 #[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
 pub struct MemberFunc {
@@ -266,6 +378,96 @@ pub struct MemberFunc {
     pub this_adjust: U32<LE>,
 }
 
+impl MemberFunc {
+    /// The calling convention of the member function
+    pub fn calling_convention(&self) -> CallingConvention {
+        CallingConvention(self.call)
+    }
+}
+
+/// Identifies the calling convention of a procedure type. This is the `call` field of
+/// `LF_PROCEDURE` and `LF_MFUNCTION`.
+///
+/// See `CV_call_e` in `cvconst.h`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct CallingConvention(pub u8);
+
+macro_rules! calling_conventions {
+    ($($value:expr, $name:ident;)*) => {
+        impl CallingConvention {
+            $(
+                pub const $name: CallingConvention = CallingConvention($value);
+            )*
+
+            /// Gets the name of this calling convention, if it is known.
+            pub fn get_name(self) -> Option<&'static str> {
+                match self {
+                    $(
+                        Self::$name => Some(stringify!($name)),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+calling_conventions! {
+    0x00, NEAR_C;
+    0x01, FAR_C;
+    0x02, NEAR_PASCAL;
+    0x03, FAR_PASCAL;
+    0x04, NEAR_FAST;
+    0x05, FAR_FAST;
+    0x06, SKIPPED;
+    0x07, NEAR_STD;
+    0x08, FAR_STD;
+    0x09, NEAR_SYS;
+    0x0a, FAR_SYS;
+    0x0b, THISCALL;
+    0x0c, MIPSCALL;
+    0x0d, GENERIC;
+    0x0e, ALPHACALL;
+    0x0f, PPCCALL;
+    0x10, SHCALL;
+    0x11, ARMCALL;
+    0x12, AM33CALL;
+    0x13, TRICALL;
+    0x14, SH5CALL;
+    0x15, M32RCALL;
+    0x16, CLRCALL;
+    0x17, INLINE;
+    0x18, NEAR_VECTOR;
+    0x19, SWIFT;
+}
+
+impl CallingConvention {
+    /// Gets the C/C++ keyword for this calling convention, such as `__cdecl`. Returns `None` for
+    /// calling conventions that do not have a keyword.
+    pub fn keyword(self) -> Option<&'static str> {
+        Some(match self {
+            Self::NEAR_C | Self::FAR_C => "__cdecl",
+            Self::NEAR_PASCAL | Self::FAR_PASCAL => "__pascal",
+            Self::NEAR_FAST | Self::FAR_FAST => "__fastcall",
+            Self::NEAR_STD | Self::FAR_STD => "__stdcall",
+            Self::NEAR_SYS | Self::FAR_SYS => "__syscall",
+            Self::THISCALL => "__thiscall",
+            Self::CLRCALL => "__clrcall",
+            Self::NEAR_VECTOR => "__vectorcall",
+            _ => return None,
+        })
+    }
+}
+
+impl Debug for CallingConvention {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.get_name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "??(0x{:02x})", self.0),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned)]
 pub struct VTableShapeFixed {
@@ -354,6 +556,20 @@ impl<'a> Parse<'a> for Pointer<'a> {
     }
 }
 
+impl<'a> Pointer<'a> {
+    /// For pointers to members (`CV_PTR_MODE_PMEM` and `CV_PTR_MODE_PMFUNC`), gets the type of
+    /// the class that contains the member.
+    pub fn member_class(&self) -> Option<TypeIndex> {
+        match self.fixed.attr().mode() {
+            POINTER_MODE_MEMBER_DATA | POINTER_MODE_MEMBER_FUNCTION => {
+                let mut p = Parser::new(self.variant);
+                p.type_index().ok()
+            }
+            _ => None,
+        }
+    }
+}
+
 impl<'a> Debug for Pointer<'a> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         let attr = self.fixed.attr();
@@ -388,6 +604,17 @@ impl PointerFlags {
     }
 }
 
+/// `CV_PTR_MODE_PTR`: an ordinary pointer. This is a value of [`PointerFlags::mode`].
+pub const POINTER_MODE_POINTER: u32 = 0;
+/// `CV_PTR_MODE_LVREF`: an l-value reference (`&`)
+pub const POINTER_MODE_LVALUE_REF: u32 = 1;
+/// `CV_PTR_MODE_PMEM`: a pointer to a data member
+pub const POINTER_MODE_MEMBER_DATA: u32 = 2;
+/// `CV_PTR_MODE_PMFUNC`: a pointer to a member function
+pub const POINTER_MODE_MEMBER_FUNCTION: u32 = 3;
+/// `CV_PTR_MODE_RVREF`: an r-value reference (`&&`)
+pub const POINTER_MODE_RVALUE_REF: u32 = 4;
+
 /// Payload for `LF_METHODLIST`
 #[derive(Clone, Debug)]
 pub struct MethodListData<'a> {
@@ -446,7 +673,7 @@ pub struct UdtSrcLine {
     pub ty: TypeIndexLe,
 
     /// The source file which contains this UDT definition.
-    /// This is a `NameIndex` value in the `/names` stream.
+    /// This is an `ItemId` that points to an `LF_STRING_ID` record.
     pub src: U32<LE>,
 
     /// Line number
@@ -611,3 +838,142 @@ pub struct VFTable {
     /// segment of virtual function table
     pub seg: U16<LE>,
 }
+
+/// `LF_VFTPATH`: The path to a virtual function table, as a list of base classes.
+#[derive(Clone, Debug)]
+pub struct VFTPath<'a> {
+    /// The base classes, starting from the most-derived class.
+    pub bases: &'a [TypeIndexLe],
+}
+
+impl<'a> Parse<'a> for VFTPath<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        let count = p.u32()?;
+        let bases = p.slice(count as usize)?;
+        Ok(Self { bases })
+    }
+}
+
+/// `LF_LABEL`
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct Label {
+    /// Addressing mode of the label. 0 is near, 4 is far.
+    pub mode: U16<LE>,
+}
+
+/// `LF_DIMARRAY`: A multi-dimensional array.
+#[derive(Clone, Debug)]
+pub struct DimArray<'a> {
+    pub fixed: &'a DimArrayFixed,
+    pub name: &'a BStr,
+}
+
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct DimArrayFixed {
+    /// The element type
+    pub element_type: TypeIndexLe,
+    /// The dimension information (an `LF_DIMCONU`, `LF_DIMVARU`, etc. record)
+    pub dim_info: TypeIndexLe,
+}
+
+impl<'a> Parse<'a> for DimArray<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `LF_PRECOMP`: Refers to the type records of a precompiled header. These records are only
+/// found in the `.debug$T` section of object files; the linker replaces them with the type
+/// records of the object file that created the precompiled header.
+///
+/// See `lfPreComp` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+pub struct Precomp<'a> {
+    pub fixed: &'a PrecompFixed,
+    /// The name of the object file that contains the precompiled types.
+    pub name: &'a BStr,
+}
+
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct PrecompFixed {
+    /// The first type index that is defined by the precompiled header.
+    pub start: TypeIndexLe,
+    /// The number of type records that are defined by the precompiled header.
+    pub count: U32<LE>,
+    /// Signature of the precompiled header. This must match [`EndPrecomp::signature`].
+    pub signature: U32<LE>,
+}
+
+impl<'a> Parse<'a> for Precomp<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `LF_ENDPRECOMP`: Marks the end of the type records of a precompiled header, in the object file
+/// that created the precompiled header.
+///
+/// See `lfEndPreComp` in `cvinfo.h`.
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct EndPrecomp {
+    pub signature: U32<LE>,
+}
+
+/// `LF_TYPESERVER2`: Specifies that the type records of an object file are stored in a PDB
+/// (a "type server"), rather than in the object file. This is the only record in the `.debug$T`
+/// section of an object file compiled with `/Zi`.
+///
+/// See `lfTypeServer2` in `cvinfo.h`.
+#[derive(Clone, Debug)]
+pub struct TypeServer2<'a> {
+    pub fixed: &'a TypeServer2Fixed,
+    /// The path to the PDB.
+    pub name: &'a BStr,
+}
+
+#[repr(C)]
+#[derive(IntoBytes, Immutable, KnownLayout, FromBytes, Unaligned, Debug)]
+pub struct TypeServer2Fixed {
+    /// The unique ID of the PDB, in the byte order of a Windows `GUID`.
+    pub guid: [u8; 16],
+    /// The age of the PDB.
+    pub age: U32<LE>,
+}
+
+impl TypeServer2Fixed {
+    /// The unique ID of the PDB.
+    pub fn guid(&self) -> uuid::Uuid {
+        uuid::Uuid::from_bytes_le(self.guid)
+    }
+}
+
+impl<'a> Parse<'a> for TypeServer2<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self {
+            fixed: p.get()?,
+            name: p.strz()?,
+        })
+    }
+}
+
+/// `LF_MANAGED`: A managed (CLR) type, identified by name.
+#[derive(Clone, Debug)]
+pub struct Managed<'a> {
+    pub name: &'a BStr,
+}
+
+impl<'a> Parse<'a> for Managed<'a> {
+    fn from_parser(p: &mut Parser<'a>) -> Result<Self, ParserError> {
+        Ok(Self { name: p.strz()? })
+    }
+}
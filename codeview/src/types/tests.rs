@@ -0,0 +1,177 @@
+use super::builder::TypeBuilder;
+use super::visitor::{IndexVisitor, visit_type_indexes_in_record_slice};
+use super::*;
+use zerocopy::FromZeros;
+
+fn records(b: TypeBuilder) -> Vec<(Leaf, Vec<u8>)> {
+    TypesIter::new(&b.finish())
+        .map(|r| (r.kind, r.data.to_vec()))
+        .collect()
+}
+
+/// Collects the type indexes within a record.
+#[derive(Default)]
+struct TypeIndexes(Vec<TypeIndex>);
+
+impl IndexVisitor for &mut TypeIndexes {
+    fn type_index(&mut self, _offset: usize, value: TypeIndex) -> Result<(), ParserError> {
+        self.0.push(value);
+        Ok(())
+    }
+}
+
+fn type_indexes(kind: Leaf, data: &[u8]) -> Vec<TypeIndex> {
+    let mut v = TypeIndexes::default();
+    visit_type_indexes_in_record_slice(kind, data, &mut v).unwrap();
+    v.0
+}
+
+#[test]
+fn class2_and_union2() {
+    let mut b = TypeBuilder::new();
+    let mut fixed = Struct2Fixed::new_zeroed();
+    fixed.property = U32::new(0x1_0000 | (1 << 8) | (1 << 9));
+    fixed.field_list = TypeIndex(0x1100).into();
+    fixed.vtable_shape = TypeIndex(0x1101).into();
+    fixed.num_elements = U16::new(3);
+    b.struct2(
+        Leaf::LF_CLASS2,
+        &fixed,
+        16,
+        "C".into(),
+        Some(".?AVC@@".into()),
+    );
+    let mut fixed = Union2Fixed::new_zeroed();
+    fixed.fields = TypeIndex(0x1102).into();
+    b.union2(&fixed, 8, "U".into(), None);
+
+    let recs = records(b);
+
+    let t = TypeData::parse_bytes(recs[0].0, &recs[0].1).unwrap();
+    let TypeData::Struct2(s) = &t else {
+        panic!("expected Struct2, got {t:?}");
+    };
+    assert!(s.fixed.udt_properties().scoped());
+    assert_eq!(s.fixed.num_elements.get(), 3);
+    assert_eq!(u32::try_from(s.length).unwrap(), 16);
+    assert_eq!(s.unique_name, Some(".?AVC@@".into()));
+    assert_eq!(t.name(), Some("C".into()));
+    assert_eq!(t.udt_name(), Some("C".into()));
+    assert_eq!(
+        type_indexes(recs[0].0, &recs[0].1),
+        [TypeIndex(0x1100), TypeIndex(0), TypeIndex(0x1101)]
+    );
+
+    let t = TypeData::parse_bytes(recs[1].0, &recs[1].1).unwrap();
+    let TypeData::Union2(u) = &t else {
+        panic!("expected Union2, got {t:?}");
+    };
+    assert_eq!(u.fixed.fields.get(), TypeIndex(0x1102));
+    assert!(u.unique_name.is_none());
+    assert_eq!(t.udt_name(), Some("U".into()));
+    assert_eq!(type_indexes(recs[1].0, &recs[1].1), [TypeIndex(0x1102)]);
+}
+
+#[test]
+fn object_file_records() {
+    let guid = uuid::Uuid::from_u128(0x0011_2233_4455_6677_8899_aabb_ccdd_eeff);
+
+    let mut b = TypeBuilder::new();
+    b.record(Leaf::LF_TYPESERVER2, |e| {
+        e.uuid(&guid);
+        e.u32(3);
+        e.strz("c:\\obj\\vc140.pdb".into());
+    });
+    b.record(Leaf::LF_PRECOMP, |e| {
+        e.u32(0x1000);
+        e.u32(0x20);
+        e.u32(0xcafe);
+        e.strz("c:\\obj\\pch.obj".into());
+    });
+    b.record(Leaf::LF_ENDPRECOMP, |e| e.u32(0xcafe));
+    let recs = records(b);
+
+    let t = TypeData::parse_bytes(recs[0].0, &recs[0].1).unwrap();
+    let TypeData::TypeServer2(ts) = &t else {
+        panic!("expected TypeServer2, got {t:?}");
+    };
+    assert_eq!(ts.fixed.guid(), guid);
+    assert_eq!(ts.fixed.age.get(), 3);
+    assert_eq!(t.name(), Some("c:\\obj\\vc140.pdb".into()));
+    assert_eq!(t.udt_name(), None);
+
+    let t = TypeData::parse_bytes(recs[1].0, &recs[1].1).unwrap();
+    let TypeData::Precomp(pc) = &t else {
+        panic!("expected Precomp, got {t:?}");
+    };
+    assert_eq!(pc.fixed.start.get(), TypeIndex(0x1000));
+    assert_eq!(pc.fixed.count.get(), 0x20);
+    assert_eq!(pc.name, "c:\\obj\\pch.obj");
+
+    let TypeData::EndPrecomp(end) = TypeData::parse_bytes(recs[2].0, &recs[2].1).unwrap() else {
+        panic!("expected EndPrecomp");
+    };
+    assert_eq!(end.signature.get(), pc.fixed.signature.get());
+
+    // None of these records refer to other records.
+    for (kind, data) in recs.iter() {
+        assert!(type_indexes(*kind, data).is_empty(), "{kind:?}");
+    }
+}
+
+#[test]
+fn dim_array_and_vftpath() {
+    let mut b = TypeBuilder::new();
+    b.record(Leaf::LF_DIMVARLU, |e| {
+        e.u32(2); // rank
+        e.u32(TypeIndex::T_INT4.0);
+        for ti in 0x1010..0x1014 {
+            e.u32(ti);
+        }
+    });
+    b.record(Leaf::LF_DIMARRAY, |e| {
+        e.u32(TypeIndex::T_REAL64.0);
+        e.u32(0x1000);
+        e.strz("m".into());
+    });
+    b.record(Leaf::LF_VFTPATH, |e| {
+        e.u32(2);
+        e.u32(0x1020);
+        e.u32(0x1021);
+    });
+    b.record(Leaf::LF_LABEL, |e| e.u16(4));
+    b.record(Leaf::LF_MANAGED, |e| e.strz("System.String".into()));
+    let recs = records(b);
+
+    assert_eq!(type_indexes(recs[0].0, &recs[0].1).len(), 5);
+
+    let t = TypeData::parse_bytes(recs[1].0, &recs[1].1).unwrap();
+    let TypeData::DimArray(a) = &t else {
+        panic!("expected DimArray, got {t:?}");
+    };
+    assert_eq!(a.fixed.element_type.get(), TypeIndex::T_REAL64);
+    assert_eq!(t.name(), Some("m".into()));
+    assert_eq!(
+        type_indexes(recs[1].0, &recs[1].1),
+        [TypeIndex::T_REAL64, TypeIndex(0x1000)]
+    );
+
+    let TypeData::VFTPath(path) = TypeData::parse_bytes(recs[2].0, &recs[2].1).unwrap() else {
+        panic!("expected VFTPath");
+    };
+    assert_eq!(path.bases.len(), 2);
+    assert_eq!(
+        type_indexes(recs[2].0, &recs[2].1),
+        [TypeIndex(0x1020), TypeIndex(0x1021)]
+    );
+
+    let TypeData::Label(label) = TypeData::parse_bytes(recs[3].0, &recs[3].1).unwrap() else {
+        panic!("expected Label");
+    };
+    assert_eq!(label.mode.get(), 4);
+
+    let t = TypeData::parse_bytes(recs[4].0, &recs[4].1).unwrap();
+    assert!(matches!(t, TypeData::Managed(_)));
+    assert_eq!(t.name(), Some("System.String".into()));
+    assert!(type_indexes(recs[4].0, &recs[4].1).is_empty());
+}
@@ -3,6 +3,7 @@
 
 use super::{ItemId, ItemIdLe};
 use crate::parser::{Parser, ParserError, ParserMut};
+use crate::syms::{RefMiniPdbFlags, SymKind};
 use crate::types::{Leaf, TypeIndex, TypeIndexLe};
 use crate::types::{PointerFlags, introduces_virtual};
 use anyhow::Context;
@@ -287,7 +288,7 @@ pub fn visit_type_indexes_in_record<V: RecordVisitor>(
             p.ty()?; // index type
         }
 
-        Leaf::LF_CLASS | Leaf::LF_STRUCTURE => {
+        Leaf::LF_CLASS | Leaf::LF_STRUCTURE | Leaf::LF_INTERFACE => {
             p.u16()?; // count
             p.skip(2)?; // property
             p.ty()?; // field list
@@ -500,8 +501,28 @@ pub fn visit_type_indexes_in_record<V: RecordVisitor>(
             }
         }
 
-        // These types do not contain any pointers to other types.
-        Leaf::LF_VTSHAPE | Leaf::LF_PRECOMP | Leaf::LF_ENDPRECOMP | Leaf::LF_SKIP => {}
+        Leaf::LF_DIMVARLU => {
+            let rank = p.u32()?;
+            p.ty()?; // index type
+            for _ in 0..rank {
+                p.ty()?; // lower bound for this dimension
+                p.ty()?; // upper bound for this dimension
+            }
+        }
+
+        Leaf::LF_DIMARRAY => {
+            p.ty()?; // element type
+            p.ty()?; // dimension info
+        }
+
+        // These types do not contain any pointers to other types. The `start` field of
+        // LF_PRECOMP is the first type index of the precompiled types, not a reference.
+        Leaf::LF_VTSHAPE
+        | Leaf::LF_PRECOMP
+        | Leaf::LF_ENDPRECOMP
+        | Leaf::LF_SKIP
+        | Leaf::LF_TYPESERVER2
+        | Leaf::LF_MANAGED => {}
 
         Leaf::LF_VFTPATH => {
             let count = p.u32()?;
@@ -515,13 +536,18 @@ pub fn visit_type_indexes_in_record<V: RecordVisitor>(
             p.ty()?; // base_vftable
         }
 
-        Leaf::LF_CLASS2 | Leaf::LF_STRUCTURE2 | Leaf::LF_UNION2 | Leaf::LF_INTERFACE2 => {
+        Leaf::LF_CLASS2 | Leaf::LF_STRUCTURE2 | Leaf::LF_INTERFACE2 => {
             p.skip(4)?; // property
             p.ty()?; // field
             p.ty()?; // derived
             p.ty()?; // vshape
         }
 
+        Leaf::LF_UNION2 => {
+            p.skip(4)?; // property
+            p.ty()?; // field
+        }
+
         Leaf::LF_FUNC_ID => {
             p.item()?; // parent scope of the ID, 0 if global
             p.ty()?; // function type
@@ -552,7 +578,7 @@ pub fn visit_type_indexes_in_record<V: RecordVisitor>(
 
         Leaf::LF_UDT_SRC_LINE => {
             p.ty()?;
-            p.name_index()?; // NameIndex of source file name
+            p.item()?; // LF_STRING_ID of source file name
         }
 
         Leaf::LF_UDT_MOD_SRC_LINE => {
@@ -568,3 +594,179 @@ pub fn visit_type_indexes_in_record<V: RecordVisitor>(
 
     Ok(())
 }
+
+/// Scans the type indexes within a symbol record and calls `f` for each type index. This function
+/// can only read data.
+#[inline(never)]
+pub fn visit_type_indexes_in_sym_slice<IV: IndexVisitor>(
+    sym_kind: SymKind,
+    record_data: &[u8],
+    index_visitor: IV,
+) -> Result<(), anyhow::Error> {
+    let record_data_len = record_data.len();
+    let mut v = RefVisitor {
+        original_len: record_data.len(),
+        parser: Parser::new(record_data),
+        index_visitor,
+    };
+    visit_type_indexes_in_sym(sym_kind, &mut v).with_context(|| {
+        let offset = record_data_len - v.parser.len();
+        format!("at byte offset 0x{offset:x} {offset} within symbol record")
+    })
+}
+
+/// Scans the type indexes within a symbol record and calls `f` for each type index. This function
+/// can modify the type indexes within the record.
+#[inline(never)]
+pub fn visit_type_indexes_in_sym_slice_mut<IV>(
+    sym_kind: SymKind,
+    record_data: &mut [u8],
+    index_visitor: IV,
+) -> Result<(), anyhow::Error>
+where
+    IV: IndexVisitorMut,
+{
+    let record_data_len = record_data.len();
+    let mut v = MutVisitor {
+        original_len: record_data.len(),
+        parser: ParserMut::new(record_data),
+        index_visitor,
+    };
+    visit_type_indexes_in_sym(sym_kind, &mut v).with_context(|| {
+        let offset = record_data_len - v.parser.len();
+        format!("at byte offset 0x{offset:x} {offset} within symbol record")
+    })
+}
+
+/// This function examines a symbol record and traverses the `TypeIndex`, `ItemId`, and
+/// `NameIndex` values within it.
+///
+/// `TypeIndex` values point into the TPI and `ItemId` values point into the IPI. Records that do
+/// not contain any of these (including symbol kinds that are not recognized) are ignored. Managed
+/// symbols (`S_GMANPROC`, `S_GMANDATA`, `S_MANSLOT`, etc.) contain metadata tokens, not type
+/// indexes, so they are ignored, too. `cvinfo.h` describes the `typind` field of the `S_MAN*`
+/// variable records as "Type index or Metadata token"; the `S_ATTR_*` records, which use the same
+/// layout for native code, contain type indexes.
+pub fn visit_type_indexes_in_sym<V: RecordVisitor>(
+    sym_kind: SymKind,
+    p: &mut V,
+) -> Result<(), ParserError> {
+    match sym_kind {
+        SymKind::S_GPROC32
+        | SymKind::S_LPROC32
+        | SymKind::S_LPROC32_DPC
+        | SymKind::S_GPROCIA64
+        | SymKind::S_LPROCIA64 => {
+            p.skip(24)?; // p_parent, p_end, p_next, proc_len, debug_start, debug_end
+            p.ty()?;
+        }
+
+        SymKind::S_GPROC32_ID
+        | SymKind::S_LPROC32_ID
+        | SymKind::S_LPROC32_DPC_ID
+        | SymKind::S_GPROCIA64_ID
+        | SymKind::S_LPROCIA64_ID => {
+            p.skip(24)?; // p_parent, p_end, p_next, proc_len, debug_start, debug_end
+            p.item()?; // LF_FUNC_ID or LF_MFUNC_ID
+        }
+
+        SymKind::S_UDT
+        | SymKind::S_COBOLUDT
+        | SymKind::S_CONSTANT
+        | SymKind::S_LDATA32
+        | SymKind::S_GDATA32
+        | SymKind::S_LTHREAD32
+        | SymKind::S_GTHREAD32
+        | SymKind::S_REGISTER
+        | SymKind::S_LOCAL
+        | SymKind::S_MANYREG
+        | SymKind::S_MANYREG2
+        | SymKind::S_ATTR_REGISTER
+        | SymKind::S_ATTR_MANYREG
+        | SymKind::S_FASTLINK
+        | SymKind::S_GDATA_HLSL
+        | SymKind::S_LDATA_HLSL
+        | SymKind::S_GDATA_HLSL32
+        | SymKind::S_LDATA_HLSL32
+        | SymKind::S_GDATA_HLSL32_EX
+        | SymKind::S_LDATA_HLSL32_EX => {
+            p.ty()?;
+        }
+
+        SymKind::S_REGREL32
+        | SymKind::S_BPREL32
+        | SymKind::S_LOCALSLOT
+        | SymKind::S_PARAMSLOT
+        | SymKind::S_ATTR_FRAMEREL
+        | SymKind::S_ATTR_REGREL => {
+            p.skip(4)?; // offset or slot
+            p.ty()?;
+        }
+
+        SymKind::S_FILESTATIC => {
+            p.ty()?;
+            p.name_index()?; // NameIndex of the module filename
+        }
+
+        SymKind::S_CALLSITEINFO | SymKind::S_HEAPALLOCSITE => {
+            p.skip(8)?; // offset, segment, padding or instruction size
+            p.ty()?;
+        }
+
+        SymKind::S_BUILDINFO | SymKind::S_HOTPATCHFUNC => {
+            p.item()?;
+        }
+
+        SymKind::S_INLINESITE | SymKind::S_INLINESITE2 => {
+            p.skip(8)?; // p_parent, p_end
+            p.item()?; // inlinee
+        }
+
+        SymKind::S_CALLEES | SymKind::S_CALLERS | SymKind::S_INLINEES => {
+            let count = p.u32()?;
+            for _ in 0..count {
+                p.item()?;
+            }
+        }
+
+        SymKind::S_OEM => {
+            p.skip(16)?; // OEM id
+            p.ty()?;
+        }
+
+        SymKind::S_VFTABLE32 => {
+            p.ty()?; // root
+            p.ty()?; // path
+        }
+
+        SymKind::S_DISCARDED => {
+            p.skip(12)?; // flags, file_id, line
+
+            // The discarded record follows, including its length and kind. The record may be
+            // missing, and an S_DISCARDED record never contains another S_DISCARDED record.
+            if p.is_empty() {
+                return Ok(());
+            }
+            p.u16()?; // record length
+            let kind = SymKind(p.u16()?);
+            if kind != SymKind::S_DISCARDED {
+                visit_type_indexes_in_sym(kind, p)?;
+            }
+        }
+
+        SymKind::S_REF_MINIPDB => {
+            // The first field is a TypeIndex only if the flags (at offset 6) contain fUDT.
+            let flags = match p.peek_rest().get(6..8) {
+                Some(&[lo, hi]) => u16::from_le_bytes([lo, hi]),
+                _ => return Err(ParserError::new()),
+            };
+            if RefMiniPdbFlags::from_bits_retain(flags).contains(RefMiniPdbFlags::UDT) {
+                p.ty()?;
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use ms_pdb::Pdb;
+use ms_pdb::line_index::{LineIndex, LineIndexOptions};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -8,6 +9,22 @@ use tokio::sync::Mutex;
 pub struct OpenPdb {
     pub pdb: Box<Pdb>,
     pub path: PathBuf,
+    /// The line index of the PDB. This is built the first time it is needed.
+    pub line_index: Option<LineIndex>,
+}
+
+/// Gets the line index of `pdb`, building it and storing it in `cache` if it has not been built
+/// yet.
+///
+/// This takes the fields of [`OpenPdb`] separately so that callers can keep borrowing `pdb`.
+pub fn cached_line_index<'a>(
+    pdb: &Pdb,
+    cache: &'a mut Option<LineIndex>,
+) -> anyhow::Result<&'a LineIndex> {
+    if cache.is_none() {
+        *cache = Some(pdb.build_line_index(&LineIndexOptions::default())?);
+    }
+    Ok(cache.as_ref().unwrap())
 }
 
 /// The PDB MCP server state.
@@ -564,7 +581,7 @@ impl PdbMcpServer {
 
     /// Resolve an address to module, function, source file, and line number.
     #[tool(
-        description = "Resolve a code address to its full symbolic context: module, enclosing function, source file, and line number. Accepts either an RVA or section:offset. Uses section contributions for module lookup, scans module symbols for the enclosing procedure, and reads C13 line data for source mapping. This is the equivalent of a debugger's 'ln' + source line display."
+        description = "Resolve a code address to its full symbolic context: module, enclosing function, source file, and line number. Accepts either an RVA or section:offset. Uses section contributions for module lookup, scans module symbols for the enclosing procedure, and looks up the source line in a line index, which is built on first use and kept while the PDB is open. This is the equivalent of a debugger's 'ln' + source line display."
     )]
     async fn addr_to_line(
         &self,
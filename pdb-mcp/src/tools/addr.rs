@@ -1,7 +1,5 @@
-use crate::server::PdbMcpServer;
+use crate::server::{OpenPdb, PdbMcpServer, cached_line_index};
 use crate::undecorate;
-use ms_pdb::lines::{FileChecksumsSubsection, LineData, LinesSubsection, SubsectionKind};
-use ms_pdb::names::NameIndex;
 use ms_pdb::syms::SymIter;
 use std::fmt::Write;
 
@@ -87,13 +85,14 @@ pub async fn addr_to_line_impl(
     offset: Option<u32>,
     do_undecorate: bool,
 ) -> String {
-    let pdbs = server.pdbs.lock().await;
-    let Some(open_pdb) = pdbs.get(&alias) else {
+    let mut pdbs = server.pdbs.lock().await;
+    let Some(OpenPdb {
+        pdb, line_index, ..
+    }) = pdbs.get_mut(&alias)
+    else {
         return format!("Error: no open PDB with alias '{alias}'.");
     };
 
-    let pdb = &open_pdb.pdb;
-
     // Step 1: Resolve to section:offset
     let sections = match pdb.section_headers() {
         Ok(s) => s,
@@ -179,102 +178,26 @@ pub async fn addr_to_line_impl(
             find_enclosing_proc(&mut out, sym_bytes, sec, off, do_undecorate);
         }
 
-        // Step 4: Read C13 line data and find the matching line
-        let modi = match pdb.read_module_stream(&module_info) {
-            Ok(Some(m)) => m,
-            Ok(None) => {
-                writeln!(out, "  No module stream.").unwrap();
-                return out;
-            }
+        // Step 4: Find the matching line in the line index
+        let index = match cached_line_index(pdb, line_index) {
+            Ok(index) => index,
             Err(e) => {
-                writeln!(out, "  Error reading module stream: {e}").unwrap();
+                writeln!(out, "  Error building line index: {e}").unwrap();
                 return out;
             }
         };
 
-        let c13_bytes = modi.c13_line_data_bytes();
-        if c13_bytes.is_empty() {
-            writeln!(out, "  No C13 line data.").unwrap();
-            return out;
-        }
-
-        let line_data = LineData::new(c13_bytes);
-
-        // Find the FILE_CHECKSUMS subsection for file name resolution
-        let checksums = line_data.find_checksums();
-
-        // Load names stream for resolving NameIndex → file path
-        let names = pdb.names().ok();
-
-        // Scan LINES subsections
-        let mut found_line = false;
-        for subsection in line_data.subsections() {
-            if subsection.kind != SubsectionKind::LINES {
-                continue;
-            }
-
-            let lines_sub = match LinesSubsection::parse(subsection.data) {
-                Ok(l) => l,
-                Err(_) => continue,
-            };
-
-            let contrib_sec = lines_sub.contribution.segment.get();
-            let contrib_off = lines_sub.contribution.offset.get();
-            let contrib_size = lines_sub.contribution.size.get();
-
-            // Check if our address falls within this contribution
-            if contrib_sec != sec {
-                continue;
-            }
-            if off < contrib_off || off >= contrib_off + contrib_size {
-                continue;
-            }
-
-            let relative_off = off - contrib_off;
-
-            for block in lines_sub.blocks() {
-                let lines = block.lines();
-                if lines.is_empty() {
-                    continue;
-                }
-
-                // Find the line record with the largest offset <= relative_off
-                let mut best_line: Option<&ms_pdb::lines::LineRecord> = None;
-                for line_rec in lines {
-                    if line_rec.offset.get() <= relative_off {
-                        best_line = Some(line_rec);
-                    } else {
-                        break; // Lines are sorted by offset
-                    }
-                }
-
-                if let Some(line_rec) = best_line {
-                    // Resolve file name
-                    let file_name =
-                        resolve_file_name(&checksums, &names, block.header.file_index.get());
-
-                    let line_num = line_rec.line_num_start();
-                    let line_off = relative_off - line_rec.offset.get();
-
-                    writeln!(out, "  File:       {file_name}").unwrap();
-                    writeln!(out, "  Line:       {line_num}").unwrap();
-                    if line_off > 0 {
-                        writeln!(out, "  Offset:     +0x{line_off:x} bytes from line start")
-                            .unwrap();
-                    }
-
-                    found_line = true;
-                    break;
+        match index.find(rva_val) {
+            Some(location) => {
+                let row = location.row;
+                let line_off = rva_val - row.rva;
+                writeln!(out, "  File:       {}", index.file_name(row.file)).unwrap();
+                writeln!(out, "  Line:       {}", row.line).unwrap();
+                if line_off > 0 {
+                    writeln!(out, "  Offset:     +0x{line_off:x} bytes from line start").unwrap();
                 }
             }
-
-            if found_line {
-                break;
-            }
-        }
-
-        if !found_line {
-            writeln!(out, "  No line data for this address.").unwrap();
+            None => writeln!(out, "  No line data for this address.").unwrap(),
         }
     }
 
@@ -327,30 +250,3 @@ fn find_enclosing_proc(
     )
     .unwrap();
 }
-
-/// Resolve a file_index (from BlockHeader) → file name string.
-fn resolve_file_name(
-    checksums: &Option<FileChecksumsSubsection<'_>>,
-    names: &Option<&ms_pdb::names::NamesStream<Vec<u8>>>,
-    file_index: u32,
-) -> String {
-    let Some(checksums) = checksums else {
-        return format!("(file_index=0x{file_index:x}, no checksums subsection)");
-    };
-
-    let file = match checksums.get_file(file_index) {
-        Ok(f) => f,
-        Err(_) => return format!("(file_index=0x{file_index:x}, lookup failed)"),
-    };
-
-    let name_index = NameIndex(file.header.name.get());
-
-    let Some(names) = names else {
-        return format!("(name_index={}, no names stream)", name_index.0);
-    };
-
-    match names.get_string(name_index) {
-        Ok(name) => name.to_string(),
-        Err(_) => format!("(name_index={}, lookup failed)", name_index.0),
-    }
-}
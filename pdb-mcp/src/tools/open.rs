@@ -53,6 +53,7 @@ pub async fn open_pdb_impl(server: &PdbMcpServer, path: String, alias: Option<St
         OpenPdb {
             pdb,
             path: path_obj.to_path_buf(),
+            line_index: None,
         },
     );
 
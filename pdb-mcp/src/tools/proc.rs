@@ -2,7 +2,8 @@ use crate::server::PdbMcpServer;
 use crate::undecorate;
 use bstr::BStr;
 use ms_pdb::syms::{SymData, SymIter, SymKind};
-use ms_pdb::types::{TypeData, TypeIndex};
+use ms_pdb::tpi::TypeFormatter;
+use ms_pdb::types::TypeIndex;
 use std::fmt::Write;
 
 pub async fn get_proc_impl(
@@ -292,51 +293,5 @@ fn resolve_proc_ref(pdb: &ms_pdb::Pdb, name: &str) -> anyhow::Result<Option<(u32
 
 /// Brief type description from a TypeIndex, using the TPI stream.
 fn describe_type_brief(tpi: &ms_pdb::tpi::TypeStream<Vec<u8>>, ti: TypeIndex) -> String {
-    if tpi.is_primitive(ti) {
-        // Use the primitive type display
-        use ms_pdb::types::primitive::dump_primitive_type_index;
-        let mut s = String::new();
-        let _ = dump_primitive_type_index(&mut s, ti);
-        return s;
-    }
-
-    let record = match tpi.record(ti) {
-        Ok(r) => r,
-        Err(_) => return format!("0x{:x}", ti.0),
-    };
-
-    match record.parse() {
-        Ok(TypeData::Struct(t)) => t.name.to_string(),
-        Ok(TypeData::Enum(t)) => format!("enum {}", t.name),
-        Ok(TypeData::Union(t)) => format!("union {}", t.name),
-        Ok(TypeData::Alias(t)) => t.name.to_string(),
-        Ok(TypeData::Pointer(p)) => {
-            let referent = describe_type_brief(tpi, p.fixed.ty.get());
-            format!("{referent}*")
-        }
-        Ok(TypeData::Modifier(m)) => {
-            let base = describe_type_brief(tpi, m.underlying_type.get());
-            let attrs = m.attributes.get();
-            let is_const = (attrs & 1) != 0;
-            let is_volatile = (attrs & 2) != 0;
-            let mut prefix = String::new();
-            if is_const {
-                prefix.push_str("const ");
-            }
-            if is_volatile {
-                prefix.push_str("volatile ");
-            }
-            format!("{prefix}{base}")
-        }
-        Ok(TypeData::Proc(p)) => {
-            let ret = describe_type_brief(tpi, p.return_value.get());
-            format!("{ret}(*)({} params)", p.num_params.get())
-        }
-        Ok(TypeData::Array(a)) => {
-            let elem = describe_type_brief(tpi, a.fixed.element_type.get());
-            format!("{elem}[{}]", a.len)
-        }
-        Ok(_) => format!("0x{:x}", ti.0),
-        Err(_) => format!("0x{:x}", ti.0),
-    }
+    TypeFormatter::new(tpi).format(ti)
 }
@@ -1,5 +1,6 @@
 use crate::format;
 use crate::server::PdbMcpServer;
+use ms_pdb::tpi::TypeFormatter;
 use ms_pdb::types::{TypeData, TypeIndex};
 use std::fmt::Write;
 
@@ -107,6 +108,13 @@ pub async fn dump_type_impl(
                     if let Some(name) = type_data.name() {
                         writeln!(out, "  Name: {name}").unwrap();
                     }
+                    if !matches!(
+                        type_data,
+                        TypeData::FieldList(_) | TypeData::ArgList(_) | TypeData::MethodList(_)
+                    ) {
+                        let formatter = TypeFormatter::new(&tpi).calling_conventions(true);
+                        writeln!(out, "  Declaration: {}", formatter.format(current_ti)).unwrap();
+                    }
                     format_type_data_detail(&mut out, &type_data);
                 }
                 Err(e) => {
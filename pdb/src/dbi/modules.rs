@@ -66,6 +66,13 @@ impl ModuleInfoFixed {
     pub fn stream(&self) -> Option<u32> {
         self.stream.get()
     }
+
+    /// The index into the TSM (type server map) list (bits 8-15 of `flags`). This is non-zero if
+    /// the type records of this module came from a type server, i.e. the module was compiled
+    /// with `/Zi` or `/ZI` rather than `/Z7`. See `DebugInfoFormat` in `ms_codeview`.
+    pub fn type_server_index(&self) -> u8 {
+        (self.flags.get() >> 8) as u8
+    }
 }
 
 /// Holds or refers to the data of a substream within a Module Info record.
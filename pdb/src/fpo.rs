@@ -0,0 +1,282 @@
+//! Decodes the frame information used for unwinding the stack of 32-bit x86 code.
+//!
+//! 32-bit x86 code does not have unwind tables in the PE image. Instead, the PDB describes the
+//! stack frame of each function, in one of two forms:
+//!
+//! * `FPO_DATA` records, stored in the `FPO_DATA` Optional Debug Stream. These are the original
+//!   "frame pointer omission" records, which describe the frame using a few fixed-size fields.
+//! * `FRAMEDATA` records, stored in the `NEW_FPO_DATA` Optional Debug Stream and in the
+//!   `FRAMEDATA` subsections of C13 Line Data. Each of these refers to a "program string"
+//!   (stored in the Names Stream) that computes the registers of the caller. See
+//!   [`FrameProgram`].
+//!
+//! # References
+//! * [`FPO_DATA` in `winnt.h`](https://learn.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-fpo_data)
+//! * `FRAMEDATA` in `cvinfo.h`
+
+mod program;
+
+pub use program::*;
+
+use crate::dbi::optional_dbg::OptionalDebugStream;
+use crate::names::NameIndex;
+use crate::{Pdb, ReadAt};
+use anyhow::bail;
+use bitflags::bitflags;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, LE, U16, U32, Unaligned};
+
+/// Describes the stack frame of a range of code. This is stored in the `NEW_FPO_DATA` Optional
+/// Debug Stream and in the `FRAMEDATA` subsections of C13 Line Data.
+///
+/// See `FRAMEDATA` in `cvinfo.h`.
+#[repr(C)]
+#[derive(Clone, Debug, FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned)]
+pub struct FrameData {
+    /// The RVA of the start of the code range.
+    pub rva_start: U32<LE>,
+    /// The size in bytes of the code range.
+    pub code_size: U32<LE>,
+    /// The size in bytes of the local variables.
+    pub locals_size: U32<LE>,
+    /// The size in bytes of the parameters.
+    pub params_size: U32<LE>,
+    /// The maximum number of bytes pushed on the stack by the code.
+    pub max_stack_size: U32<LE>,
+    /// The program string that computes the registers of the caller, as a `NameIndex` in the
+    /// Names Stream. If this is zero, then the frame has no program string.
+    pub frame_func: U32<LE>,
+    /// The size in bytes of the prolog.
+    pub prolog_size: U16<LE>,
+    /// The size in bytes of the callee-saved registers that are saved by the prolog.
+    pub saved_regs_size: U16<LE>,
+    /// Bit flags. See [`FrameDataFlags`].
+    pub flags: U32<LE>,
+}
+
+bitflags! {
+    /// Flags for [`FrameData`].
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct FrameDataFlags: u32 {
+        /// The function has a structured exception handler.
+        const HAS_SEH = 1 << 0;
+        /// The function has a C++ exception handler.
+        const HAS_EH = 1 << 1;
+        /// The code range is the start of a function.
+        const IS_FUNCTION_START = 1 << 2;
+    }
+}
+
+impl FrameData {
+    /// The bit flags
+    pub fn flags(&self) -> FrameDataFlags {
+        FrameDataFlags::from_bits_retain(self.flags.get())
+    }
+
+    /// The program string that computes the registers of the caller, if any.
+    pub fn frame_func(&self) -> Option<NameIndex> {
+        match self.frame_func.get() {
+            0 => None,
+            ni => Some(NameIndex(ni)),
+        }
+    }
+
+    /// Sets the variables that program strings use to describe the frame: `.cbLocals`,
+    /// `.cbParams`, `.cbSavedRegs`, `.raSearch` and `.raSearchStart`.
+    ///
+    /// `.raSearch` and `.raSearchStart` are set to the address where the return address is
+    /// expected to be, which is computed from `$esp` (which must already be set) and the sizes
+    /// of the locals and the saved registers.
+    pub fn set_frame_vars(&self, vars: &mut FrameVars) -> anyhow::Result<()> {
+        let Some(esp) = vars.get("$esp") else {
+            bail!("The $esp register is not set");
+        };
+        let ra_search = esp
+            .wrapping_add(self.locals_size.get())
+            .wrapping_add(self.saved_regs_size.get() as u32);
+        vars.set(".cbLocals", self.locals_size.get());
+        vars.set(".cbParams", self.params_size.get());
+        vars.set(".cbSavedRegs", self.saved_regs_size.get() as u32);
+        vars.set(".raSearch", ra_search);
+        vars.set(".raSearchStart", ra_search);
+        Ok(())
+    }
+}
+
+/// The kind of stack frame described by an [`FpoData`] record.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FpoFrameKind(pub u8);
+
+impl FpoFrameKind {
+    /// `FRAME_FPO`: the function does not use a frame pointer.
+    pub const FPO: Self = Self(0);
+    /// `FRAME_TRAP`: the frame is a kernel trap frame.
+    pub const TRAP: Self = Self(1);
+    /// `FRAME_TSS`: the frame is a task state segment.
+    pub const TSS: Self = Self(2);
+    /// `FRAME_NONFPO`: the function uses a frame pointer.
+    pub const NON_FPO: Self = Self(3);
+}
+
+/// Describes the stack frame of a function. This is stored in the `FPO_DATA` Optional Debug
+/// Stream.
+///
+/// See `FPO_DATA` in `winnt.h`.
+#[repr(C)]
+#[derive(Clone, Debug, FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned)]
+pub struct FpoData {
+    /// The RVA of the start of the function.
+    pub rva_start: U32<LE>,
+    /// The size in bytes of the function.
+    pub proc_size: U32<LE>,
+    /// The number of 4-byte words of local variables.
+    pub locals: U32<LE>,
+    /// The number of 4-byte words of parameters.
+    pub params: U16<LE>,
+    /// Contains the bit fields `cbProlog` (8 bits), `cbRegs` (3 bits), `fHasSEH` (1 bit),
+    /// `fUseBP` (1 bit), `reserved` (1 bit) and `cbFrame` (2 bits).
+    pub attributes: U16<LE>,
+}
+
+impl FpoData {
+    /// The size in bytes of the prolog.
+    pub fn prolog_size(&self) -> u8 {
+        self.attributes.get() as u8
+    }
+
+    /// The number of callee-saved registers that are saved by the prolog.
+    pub fn saved_regs(&self) -> u8 {
+        ((self.attributes.get() >> 8) & 7) as u8
+    }
+
+    /// The function has a structured exception handler.
+    pub fn has_seh(&self) -> bool {
+        self.attributes.get() & (1 << 11) != 0
+    }
+
+    /// The function uses `ebp` as a frame pointer.
+    pub fn uses_bp(&self) -> bool {
+        self.attributes.get() & (1 << 12) != 0
+    }
+
+    /// The kind of frame.
+    pub fn frame_kind(&self) -> FpoFrameKind {
+        FpoFrameKind((self.attributes.get() >> 14) as u8)
+    }
+}
+
+/// A record that describes the stack frame of a range of code.
+pub trait FrameRecord {
+    /// The RVA of the start of the code range.
+    fn rva_start(&self) -> u32;
+    /// The size in bytes of the code range.
+    fn code_size(&self) -> u32;
+
+    /// Checks whether `rva` is within the code range.
+    fn contains(&self, rva: u32) -> bool {
+        rva.wrapping_sub(self.rva_start()) < self.code_size()
+    }
+}
+
+impl FrameRecord for FrameData {
+    fn rva_start(&self) -> u32 {
+        self.rva_start.get()
+    }
+
+    fn code_size(&self) -> u32 {
+        self.code_size.get()
+    }
+}
+
+impl FrameRecord for FpoData {
+    fn rva_start(&self) -> u32 {
+        self.rva_start.get()
+    }
+
+    fn code_size(&self) -> u32 {
+        self.proc_size.get()
+    }
+}
+
+/// A table of frame records, sorted by address.
+#[derive(Clone, Debug)]
+pub struct FrameTable<T> {
+    records: Vec<T>,
+}
+
+impl<T: FrameRecord> FrameTable<T> {
+    /// Builds a table from a list of records, in any order.
+    pub fn new(mut records: Vec<T>) -> Self {
+        records.sort_by_key(|r| r.rva_start());
+        Self { records }
+    }
+
+    /// The records, sorted by `rva_start`.
+    pub fn records(&self) -> &[T] {
+        &self.records
+    }
+
+    /// Finds the record whose code range contains `rva`.
+    ///
+    /// The code ranges of `FRAMEDATA` records may be nested; a function may have one record for
+    /// the whole function and more records for smaller ranges within it. If more than one
+    /// record contains `rva`, then this returns the one that starts last, which is the most
+    /// specific one.
+    pub fn find(&self, rva: u32) -> Option<&T> {
+        let end = self.records.partition_point(|r| r.rva_start() <= rva);
+        self.records[..end].iter().rev().find(|r| r.contains(rva))
+    }
+}
+
+impl<F: ReadAt> Pdb<F> {
+    /// Reads (uncached) the `NEW_FPO_DATA` Optional Debug Stream, which contains `FRAMEDATA`
+    /// records.
+    pub fn read_frame_data(&self) -> anyhow::Result<Option<Vec<FrameData>>> {
+        self.read_optional_debug_records(OptionalDebugStream::NEW_FPO_DATA)
+    }
+
+    /// Reads (uncached) the `FPO_DATA` Optional Debug Stream.
+    pub fn read_fpo_data(&self) -> anyhow::Result<Option<Vec<FpoData>>> {
+        self.read_optional_debug_records(OptionalDebugStream::FPO_DATA)
+    }
+
+    /// Reads the `NEW_FPO_DATA` Optional Debug Stream and builds a table that can be searched
+    /// by address. If the stream is not present, the table is empty.
+    pub fn frame_data_table(&self) -> anyhow::Result<FrameTable<FrameData>> {
+        Ok(FrameTable::new(self.read_frame_data()?.unwrap_or_default()))
+    }
+
+    /// Reads the `FPO_DATA` Optional Debug Stream and builds a table that can be searched by
+    /// address. If the stream is not present, the table is empty.
+    pub fn fpo_data_table(&self) -> anyhow::Result<FrameTable<FpoData>> {
+        Ok(FrameTable::new(self.read_fpo_data()?.unwrap_or_default()))
+    }
+
+    fn read_optional_debug_records<T>(
+        &self,
+        stream: OptionalDebugStream,
+    ) -> anyhow::Result<Option<Vec<T>>>
+    where
+        T: FromBytes + IntoBytes + Immutable,
+    {
+        let Some(stream_index) = self.optional_debug_stream(stream)? else {
+            return Ok(None);
+        };
+
+        let sr = self.get_stream_reader(stream_index)?;
+        let stream_size = sr.stream_size() as usize;
+        if !stream_size.is_multiple_of(size_of::<T>()) {
+            bail!(
+                "The {} stream has an invalid size. The size is required to be a multiple of {}. Size: {stream_size}",
+                stream.name().unwrap_or("?"),
+                size_of::<T>()
+            );
+        }
+
+        let mut records: Vec<T> = T::new_vec_zeroed(stream_size / size_of::<T>()).unwrap();
+        sr.read_exact_at(records.as_mut_bytes(), 0)?;
+        Ok(Some(records))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,187 @@
+//! Evaluates the program strings of `FRAMEDATA` records.
+//!
+//! A program string is a sequence of assignments, written in postfix notation. For example,
+//! `$T0 $ebp = $eip $T0 4 + ^ = $ebp $T0 ^ = $esp $T0 8 + =` assigns the value of `ebp` to the
+//! temporary `$T0`, then computes the `eip`, `ebp` and `esp` registers of the caller from it.
+//!
+//! Tokens are separated by whitespace. The operators are:
+//!
+//! * `+`, `-`, `*`, `/`, `%`: arithmetic on 32-bit values.
+//! * `@`: aligns the first operand down to a multiple of the second operand.
+//! * `^`: reads the 32-bit value at the address given by the operand.
+//! * `=`: assigns the second operand to the variable named by the first operand.
+//!
+//! Any other token is either a decimal number or the name of a variable, such as a register
+//! (`$eip`), a temporary (`$T0`), or a value that describes the frame (`.raSearch`).
+
+use super::*;
+use bstr::{BStr, ByteSlice};
+use std::collections::BTreeMap;
+
+/// The variables used by a program string, such as registers and temporaries.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FrameVars {
+    vars: BTreeMap<String, u32>,
+}
+
+impl FrameVars {
+    /// Creates an empty set of variables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the value of a variable.
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.vars.get(name).copied()
+    }
+
+    /// Sets the value of a variable.
+    pub fn set(&mut self, name: &str, value: u32) {
+        self.vars.insert(name.to_string(), value);
+    }
+
+    /// Iterates the variables, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        self.vars
+            .iter()
+            .map(|(name, &value)| (name.as_str(), value))
+    }
+}
+
+/// An operand on the evaluation stack.
+enum Operand<'a> {
+    Name(&'a str),
+    Value(u32),
+}
+
+/// A program string of a `FRAMEDATA` record.
+#[derive(Copy, Clone, Debug)]
+pub struct FrameProgram<'a> {
+    /// The text of the program.
+    pub text: &'a BStr,
+}
+
+impl<'a> FrameProgram<'a> {
+    /// Wraps the text of a program string.
+    pub fn new(text: &'a BStr) -> Self {
+        Self { text }
+    }
+
+    /// Runs the program. The program reads and assigns variables in `vars`, and reads memory
+    /// by calling `read_memory`, which returns the 32-bit value at an address, or `None` if the
+    /// address cannot be read.
+    pub fn evaluate<M>(&self, vars: &mut FrameVars, mut read_memory: M) -> anyhow::Result<()>
+    where
+        M: FnMut(u32) -> Option<u32>,
+    {
+        let mut stack: Vec<Operand<'a>> = Vec::new();
+
+        let pop_value = |stack: &mut Vec<Operand<'a>>, vars: &FrameVars| -> anyhow::Result<u32> {
+            match stack.pop() {
+                Some(Operand::Value(value)) => Ok(value),
+                Some(Operand::Name(name)) => match vars.get(name) {
+                    Some(value) => Ok(value),
+                    None => bail!("Variable {name} is not defined"),
+                },
+                None => bail!("Stack underflow"),
+            }
+        };
+
+        for token in self.text.fields() {
+            let Ok(token) = token.to_str() else {
+                bail!(
+                    "Program string contains invalid UTF-8: {:?}",
+                    token.as_bstr()
+                );
+            };
+
+            match token {
+                "+" | "-" | "*" | "/" | "%" | "@" => {
+                    let b = pop_value(&mut stack, vars)?;
+                    let a = pop_value(&mut stack, vars)?;
+                    let value = match token {
+                        "+" => a.wrapping_add(b),
+                        "-" => a.wrapping_sub(b),
+                        "*" => a.wrapping_mul(b),
+                        "/" if b != 0 => a / b,
+                        "%" if b != 0 => a % b,
+                        "@" if b != 0 => a & !(b - 1),
+                        _ => bail!("Division by zero in operator {token}"),
+                    };
+                    stack.push(Operand::Value(value));
+                }
+
+                "^" => {
+                    let address = pop_value(&mut stack, vars)?;
+                    let Some(value) = read_memory(address) else {
+                        bail!("Failed to read memory at 0x{address:08x}");
+                    };
+                    stack.push(Operand::Value(value));
+                }
+
+                "=" => {
+                    let value = pop_value(&mut stack, vars)?;
+                    match stack.pop() {
+                        Some(Operand::Name(name)) => vars.set(name, value),
+                        Some(Operand::Value(_)) => bail!("Cannot assign to a number"),
+                        None => bail!("Stack underflow"),
+                    }
+                }
+
+                _ => {
+                    let digits = token.strip_prefix('-').unwrap_or(token);
+                    if digits.starts_with(|c: char| c.is_ascii_digit()) {
+                        let Ok(value) = token.parse::<i64>() else {
+                            bail!("Invalid number: {token}");
+                        };
+                        stack.push(Operand::Value(value as u32));
+                    } else {
+                        stack.push(Operand::Name(token));
+                    }
+                }
+            }
+        }
+
+        if !stack.is_empty() {
+            bail!(
+                "Program string is incomplete; {} operands were not used",
+                stack.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl FrameData {
+    /// Computes the registers of the caller of a frame described by this record.
+    ///
+    /// `regs` contains the registers of the current frame; it must contain at least `$eip` and
+    /// `$esp`. This sets the variables that describe the frame (see [`Self::set_frame_vars`]),
+    /// evaluates `program`, and returns the resulting variables. The caller's registers are the
+    /// `$eip`, `$esp`, `$ebp`, etc. variables of the result.
+    pub fn unwind<M>(
+        &self,
+        program: &FrameProgram<'_>,
+        regs: &FrameVars,
+        read_memory: M,
+    ) -> anyhow::Result<FrameVars>
+    where
+        M: FnMut(u32) -> Option<u32>,
+    {
+        let mut vars = regs.clone();
+        self.set_frame_vars(&mut vars)?;
+        program.evaluate(&mut vars, read_memory)?;
+        Ok(vars)
+    }
+}
+
+impl<F: ReadAt> Pdb<F> {
+    /// Gets the program string of a `FRAMEDATA` record, from the Names Stream.
+    pub fn frame_program(&self, frame: &FrameData) -> anyhow::Result<Option<FrameProgram<'_>>> {
+        let Some(ni) = frame.frame_func() else {
+            return Ok(None);
+        };
+        Ok(Some(FrameProgram::new(self.get_name(ni)?)))
+    }
+}
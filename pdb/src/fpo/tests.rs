@@ -0,0 +1,130 @@
+use super::*;
+use std::collections::HashMap;
+use zerocopy::FromZeros;
+
+fn frame(rva_start: u32, code_size: u32) -> FrameData {
+    let mut f = FrameData::new_zeroed();
+    f.rva_start = U32::new(rva_start);
+    f.code_size = U32::new(code_size);
+    f
+}
+
+#[test]
+fn find_frame() {
+    let table = FrameTable::new(vec![
+        frame(0x2000, 0x100),
+        frame(0x1000, 0x80),
+        // A smaller range, nested within the function at 0x1000.
+        frame(0x1010, 0x20),
+    ]);
+
+    let starts: Vec<u32> = table.records().iter().map(|f| f.rva_start()).collect();
+    assert_eq!(starts, [0x1000, 0x1010, 0x2000]);
+
+    assert_eq!(table.find(0x1000).unwrap().rva_start(), 0x1000);
+    assert_eq!(table.find(0x1018).unwrap().rva_start(), 0x1010);
+    assert_eq!(table.find(0x1030).unwrap().rva_start(), 0x1000);
+    assert_eq!(table.find(0x20ff).unwrap().rva_start(), 0x2000);
+    assert!(table.find(0x0fff).is_none());
+    assert!(table.find(0x1080).is_none());
+    assert!(table.find(0x2100).is_none());
+}
+
+#[test]
+fn fpo_data_fields() {
+    let mut fpo = FpoData::new_zeroed();
+    // cbProlog = 5, cbRegs = 3, fHasSEH = 1, fUseBP = 1, cbFrame = FRAME_NONFPO
+    fpo.attributes = U16::new(5 | (3 << 8) | (1 << 11) | (1 << 12) | (3 << 14));
+    assert_eq!(fpo.prolog_size(), 5);
+    assert_eq!(fpo.saved_regs(), 3);
+    assert!(fpo.has_seh());
+    assert!(fpo.uses_bp());
+    assert_eq!(fpo.frame_kind(), FpoFrameKind::NON_FPO);
+
+    fpo.proc_size = U32::new(0x40);
+    fpo.rva_start = U32::new(0x3000);
+    assert!(fpo.contains(0x303f));
+    assert!(!fpo.contains(0x3040));
+}
+
+#[test]
+fn evaluate_program() {
+    let mut vars = FrameVars::new();
+    vars.set("$x", 10);
+    FrameProgram::new("$y $x 3 * 4 - = $z $y 8 @ = $w -1 =".into())
+        .evaluate(&mut vars, |_| None)
+        .unwrap();
+    assert_eq!(vars.get("$y"), Some(26));
+    assert_eq!(vars.get("$z"), Some(24));
+    assert_eq!(vars.get("$w"), Some(u32::MAX));
+}
+
+#[test]
+fn evaluate_errors() {
+    let run = |program: &str| {
+        let mut vars = FrameVars::new();
+        FrameProgram::new(program.into()).evaluate(&mut vars, |_| None)
+    };
+    assert!(run("$x $y =").is_err()); // undefined variable
+    assert!(run("$x +").is_err()); // stack underflow
+    assert!(run("1 2 =").is_err()); // assignment to a number
+    assert!(run("$x 1 0 / =").is_err()); // division by zero
+    assert!(run("$x 4 ^ =").is_err()); // unreadable memory
+    assert!(run("$x 1 = 2").is_err()); // unused operand
+    assert!(run("").is_ok());
+}
+
+#[test]
+fn unwind_ebp_frame() {
+    // The program emitted for a function that uses ebp as a frame pointer.
+    let program = FrameProgram::new(
+        "$T0 $ebp = $eip $T0 4 + ^ = $ebp $T0 ^ = $esp $T0 8 + = \
+         $L $T0 .cbSavedRegs - = $P $T0 8 + .cbParams + ="
+            .into(),
+    );
+
+    let mut f = frame(0x1000, 0x80);
+    f.params_size = U32::new(8);
+    f.locals_size = U32::new(0x10);
+
+    let memory: HashMap<u32, u32> = [(0x8000, 0x9000), (0x8004, 0x1234)].into();
+
+    let mut regs = FrameVars::new();
+    regs.set("$eip", 0x1020);
+    regs.set("$esp", 0x7ff0);
+    regs.set("$ebp", 0x8000);
+
+    let caller = f
+        .unwind(&program, &regs, |address| memory.get(&address).copied())
+        .unwrap();
+    assert_eq!(caller.get("$eip"), Some(0x1234));
+    assert_eq!(caller.get("$ebp"), Some(0x9000));
+    assert_eq!(caller.get("$esp"), Some(0x8008));
+    assert_eq!(caller.get("$P"), Some(0x8010));
+    assert_eq!(caller.get(".raSearch"), Some(0x8000));
+}
+
+#[test]
+fn unwind_fpo_frame() {
+    // The program emitted for a function that does not use a frame pointer.
+    let program = FrameProgram::new("$T0 .raSearch = $eip $T0 ^ = $esp $T0 4 + =".into());
+
+    let mut f = frame(0x1000, 0x80);
+    f.locals_size = U32::new(0xc);
+    f.saved_regs_size = U16::new(4);
+
+    let mut regs = FrameVars::new();
+    regs.set("$eip", 0x1020);
+    regs.set("$esp", 0x7ff0);
+
+    let caller = f
+        .unwind(&program, &regs, |address| {
+            (address == 0x8000).then_some(0x4444)
+        })
+        .unwrap();
+    assert_eq!(caller.get("$eip"), Some(0x4444));
+    assert_eq!(caller.get("$esp"), Some(0x8004));
+
+    // $esp is required.
+    assert!(f.unwind(&program, &FrameVars::new(), |_| None).is_err());
+}
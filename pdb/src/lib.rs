@@ -15,9 +15,11 @@
 
 pub mod container;
 pub mod dbi;
+pub mod fpo;
 pub mod globals;
 pub mod guid;
 pub mod hash;
+pub mod line_index;
 pub mod lines;
 pub mod modi;
 pub mod taster;
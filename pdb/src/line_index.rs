@@ -0,0 +1,375 @@
+//! An index that maps addresses to source lines, for an entire PDB.
+//!
+//! Resolving an address using the raw data structures requires finding the module (using the
+//! section contributions), then scanning the symbols and the C13 Line Data of that module.
+//! [`LineIndex`] does that work once, for all modules, and then answers queries using binary
+//! search. It can also be saved to a cache file, using [`LineIndex::to_bytes`] and
+//! [`LineIndex::from_bytes`].
+//!
+//! All addresses in the index are RVAs. Addresses in the PDB (`segment:offset`) are converted to
+//! RVAs using the section headers of the PDB.
+
+mod build;
+mod cache;
+mod source_lines;
+
+pub use build::ModuleLines;
+pub use source_lines::{LineAddressRange, SourceLineMatch};
+
+use crate::types::ItemId;
+use crate::utils::path::source_path_matches;
+use crate::{BindingKey, Pdb, ReadAt};
+use anyhow::Context;
+use bstr::{BStr, BString};
+use std::ops::Range;
+
+/// Options for [`Pdb::build_line_index`].
+#[derive(Clone, Debug, Default)]
+pub struct LineIndexOptions {
+    /// If `true`, the index also contains the line information of inlined functions, which comes
+    /// from the binary annotations of `S_INLINESITE` symbols.
+    pub include_inlinees: bool,
+
+    /// The number of threads to use for building the index. If this is zero, then this uses the
+    /// available parallelism of the machine.
+    pub num_threads: usize,
+}
+
+/// Indicates that a [`LineRow`] is not within any procedure.
+pub const NO_PROC: u32 = u32::MAX;
+
+/// One row of line information, which covers a range of code.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineRow {
+    /// The RVA of the start of the range.
+    pub rva: u32,
+    /// The size in bytes of the range.
+    pub len: u32,
+    /// The index of the module (in the DBI Modules Substream) that contains the code.
+    pub module: u32,
+    /// The index of the procedure in [`LineIndex::procs`], or [`NO_PROC`].
+    pub proc: u32,
+    /// The index of the source file in [`LineIndex::files`].
+    pub file: u32,
+    /// The line number. This value is 1-based.
+    pub line: u32,
+    /// The column number, or 0 if the PDB does not contain column information.
+    pub column: u32,
+    /// True if this row describes a statement, false if it describes an expression.
+    pub is_statement: bool,
+}
+
+impl LineRow {
+    /// The range of RVAs covered by this row.
+    pub fn rva_range(&self) -> Range<u32> {
+        self.rva..self.rva.wrapping_add(self.len)
+    }
+
+    fn contains(&self, rva: u32) -> bool {
+        rva.wrapping_sub(self.rva) < self.len
+    }
+}
+
+/// One row of line information for the code of an inlined function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InlineRow {
+    /// The line information. `proc` is the procedure that the code was inlined into.
+    pub row: LineRow,
+    /// The `ItemId` (in the IPI) of the inlinee.
+    pub inlinee: ItemId,
+    /// The number of inline sites that contain this inline site. This is 0 for functions that
+    /// were inlined directly into a procedure.
+    pub depth: u32,
+}
+
+/// A procedure, from an `S_GPROC32` (or similar) symbol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProcEntry {
+    /// The name of the procedure.
+    pub name: BString,
+    /// The index of the module (in the DBI Modules Substream) that contains the procedure.
+    pub module: u32,
+    /// The RVA of the start of the procedure.
+    pub rva: u32,
+    /// The size in bytes of the procedure.
+    pub len: u32,
+}
+
+impl ProcEntry {
+    /// The range of RVAs covered by this procedure.
+    pub fn rva_range(&self) -> Range<u32> {
+        self.rva..self.rva.wrapping_add(self.len)
+    }
+}
+
+/// The result of looking up an address in a [`LineIndex`].
+#[derive(Clone, Debug)]
+pub struct LineLocation<'a> {
+    /// The line row that contains the address.
+    pub row: &'a LineRow,
+    /// The procedure that contains the address, if known.
+    pub proc: Option<&'a ProcEntry>,
+    /// The inlined functions that contain the address, innermost first. This is empty unless
+    /// the index was built with [`LineIndexOptions::include_inlinees`].
+    pub inline_frames: Vec<&'a InlineRow>,
+}
+
+/// Maps RVAs to source lines, for an entire PDB.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LineIndex {
+    /// Identifies the PDB that the index was built from.
+    pub binding_key: Option<BindingKey>,
+    /// The names of the source files.
+    pub files: Vec<BString>,
+    /// The procedures, sorted by RVA.
+    pub procs: Vec<ProcEntry>,
+    /// The line rows of all modules, sorted by RVA.
+    pub rows: Vec<LineRow>,
+    /// The line rows of inlined functions, sorted by RVA and then by depth.
+    pub inline_rows: Vec<InlineRow>,
+}
+
+impl LineIndex {
+    /// Builds an index by merging the line information of modules, which were produced by
+    /// [`ModuleLines::build`].
+    ///
+    /// `get_name` looks up a `NameIndex` in the Names Stream; this is used for file names.
+    pub fn from_modules<N>(modules: Vec<ModuleLines>, mut get_name: N) -> anyhow::Result<Self>
+    where
+        N: FnMut(crate::names::NameIndex) -> anyhow::Result<BString>,
+    {
+        let mut files = Vec::new();
+        let mut file_map = std::collections::HashMap::new();
+        let mut map_file = |ni: u32| -> anyhow::Result<u32> {
+            if let Some(&f) = file_map.get(&ni) {
+                return Ok(f);
+            }
+            let name = get_name(crate::names::NameIndex(ni))?;
+            let f = files.len() as u32;
+            files.push(name);
+            file_map.insert(ni, f);
+            Ok(f)
+        };
+
+        let mut procs = Vec::new();
+        let mut rows = Vec::new();
+        let mut inline_rows = Vec::new();
+        for module in modules {
+            procs.extend(module.procs);
+            for mut row in module.rows {
+                row.file = map_file(row.file)?;
+                rows.push(row);
+            }
+            for mut inline_row in module.inline_rows {
+                inline_row.row.file = map_file(inline_row.row.file)?;
+                inline_rows.push(inline_row);
+            }
+        }
+
+        procs.sort_by_key(|p| p.rva);
+        rows.sort_by_key(|r| r.rva);
+        inline_rows.sort_by_key(|r| (r.row.rva, r.depth));
+
+        let mut index = Self {
+            binding_key: None,
+            files,
+            procs,
+            rows,
+            inline_rows,
+        };
+
+        for i in 0..index.rows.len() {
+            index.rows[i].proc = index.find_proc_index(index.rows[i].rva);
+        }
+        for i in 0..index.inline_rows.len() {
+            index.inline_rows[i].row.proc = index.find_proc_index(index.inline_rows[i].row.rva);
+        }
+
+        Ok(index)
+    }
+
+    fn find_proc_index(&self, rva: u32) -> u32 {
+        let end = self.procs.partition_point(|p| p.rva <= rva);
+        match end.checked_sub(1) {
+            Some(i) if rva.wrapping_sub(self.procs[i].rva) < self.procs[i].len => i as u32,
+            _ => NO_PROC,
+        }
+    }
+
+    /// Finds the procedure that contains `rva`.
+    pub fn find_proc(&self, rva: u32) -> Option<&ProcEntry> {
+        self.procs.get(self.find_proc_index(rva) as usize)
+    }
+
+    /// Gets the name of a source file, given the `file` field of a [`LineRow`].
+    pub fn file_name(&self, file: u32) -> &BStr {
+        self.files
+            .get(file as usize)
+            .map(|f| f.as_ref())
+            .unwrap_or_default()
+    }
+
+    /// Gets the procedure of a row.
+    pub fn proc_of(&self, row: &LineRow) -> Option<&ProcEntry> {
+        self.procs.get(row.proc as usize)
+    }
+
+    /// Finds the line information for `rva`.
+    pub fn find(&self, rva: u32) -> Option<LineLocation<'_>> {
+        let end = self.rows.partition_point(|r| r.rva <= rva);
+        let row = &self.rows[..end].last().filter(|r| r.contains(rva))?;
+        let proc = self.proc_of(row);
+
+        // Inline rows can overlap, so we scan the rows that start within the procedure.
+        let mut inline_frames = Vec::new();
+        if let Some(proc) = proc {
+            let end = self.inline_rows.partition_point(|r| r.row.rva <= rva);
+            let start = self.inline_rows[..end].partition_point(|r| r.row.rva < proc.rva);
+            inline_frames.extend(
+                self.inline_rows[start..end]
+                    .iter()
+                    .filter(|r| r.row.contains(rva)),
+            );
+            inline_frames.sort_by_key(|r| std::cmp::Reverse(r.depth));
+        }
+
+        Some(LineLocation {
+            row,
+            proc,
+            inline_frames,
+        })
+    }
+
+    /// Gets the line rows for a procedure, sorted by RVA. This does not include the rows of
+    /// inlined functions.
+    pub fn lines_for_proc(&self, proc: &ProcEntry) -> &[LineRow] {
+        let range = proc.rva_range();
+        let start = self.rows.partition_point(|r| r.rva < range.start);
+        let end = self.rows.partition_point(|r| r.rva < range.end);
+        &self.rows[start..end]
+    }
+
+    /// Gets the line rows of inlined functions within a procedure, sorted by RVA.
+    pub fn inline_lines_for_proc(&self, proc: &ProcEntry) -> &[InlineRow] {
+        let range = proc.rva_range();
+        let start = self
+            .inline_rows
+            .partition_point(|r| r.row.rva < range.start);
+        let end = self.inline_rows.partition_point(|r| r.row.rva < range.end);
+        &self.inline_rows[start..end]
+    }
+
+    /// Finds the procedures with the given name.
+    pub fn find_procs_by_name<'a>(
+        &'a self,
+        name: &'a BStr,
+    ) -> impl Iterator<Item = &'a ProcEntry> + 'a {
+        self.procs.iter().filter(move |p| p.name == name)
+    }
+
+    /// Finds the address ranges of the code for a given source line. This includes the code
+    /// of inlined functions, if the index contains it.
+    ///
+    /// File names are compared using [`source_path_matches`], so `file_name` may be a full path
+    /// or the last components of a path. See also [`Self::find_source_line`], which finds the
+    /// nearest line that has code.
+    pub fn addresses_for_line<'a>(
+        &'a self,
+        file_name: &BStr,
+        line: u32,
+    ) -> impl Iterator<Item = Range<u32>> + 'a {
+        let mut ranges: Vec<Range<u32>> = self
+            .rows_for_file(file_name)
+            .filter(|(r, _)| r.line == line)
+            .map(|(r, _)| r.rva_range())
+            .collect();
+        ranges.sort_by_key(|r| r.start);
+        ranges.into_iter()
+    }
+
+    /// Iterates the rows (including the rows of inlined code) whose file name matches
+    /// `file_name`, using [`source_path_matches`]. For rows of inlined code, this also returns
+    /// the inlinee.
+    fn rows_for_file<'a>(
+        &'a self,
+        file_name: &BStr,
+    ) -> impl Iterator<Item = (&'a LineRow, Option<ItemId>)> + 'a {
+        let files: Vec<bool> = self
+            .files
+            .iter()
+            .map(|f| source_path_matches(f, file_name))
+            .collect();
+        let rows = self.rows.iter().map(|r| (r, None));
+        let inline_rows = self.inline_rows.iter().map(|r| (&r.row, Some(r.inlinee)));
+        rows.chain(inline_rows)
+            .filter(move |(r, _)| files.get(r.file as usize).copied().unwrap_or(false))
+    }
+}
+
+impl<F: ReadAt> Pdb<F> {
+    /// Builds a [`LineIndex`] for all modules of this PDB.
+    ///
+    /// The module streams are read on the calling thread, and then decoded in parallel.
+    pub fn build_line_index(&self, options: &LineIndexOptions) -> anyhow::Result<LineIndex> {
+        let sections = self.section_headers()?;
+
+        let mut module_streams = Vec::new();
+        for (module_index, module) in self.modules()?.iter().enumerate() {
+            if let Some(module_stream) = self.read_module_stream(&module)? {
+                module_streams.push((module_index as u32, module_stream));
+            }
+        }
+
+        let num_threads = match options.num_threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }
+        .min(module_streams.len())
+        .max(1);
+
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let results: Vec<anyhow::Result<Vec<ModuleLines>>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..num_threads)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut out = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                            let Some((module_index, module_stream)) = module_streams.get(i) else {
+                                break;
+                            };
+                            let lines = ModuleLines::build(
+                                *module_index,
+                                module_stream.sym_data()?,
+                                &module_stream.c13_line_data(),
+                                sections,
+                                options,
+                            )
+                            .with_context(|| format!("in module {module_index}"))?;
+                            out.push(lines);
+                        }
+                        Ok(out)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|w| w.join().expect("line index worker panicked"))
+                .collect()
+        });
+
+        let mut modules = Vec::new();
+        for result in results {
+            modules.extend(result?);
+        }
+
+        let names = self.names()?;
+        let mut index =
+            LineIndex::from_modules(modules, |ni| Ok(names.get_string(ni)?.to_owned()))?;
+        index.binding_key = Some(self.binding_key());
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,173 @@
+//! Builds the line information of a single module.
+
+use super::*;
+use crate::IMAGE_SECTION_HEADER;
+use crate::lines::{FileChecksumsSubsection, InlineeLineMap, LineData, SubsectionKind};
+use crate::lines::{LinesSubsection, is_jmc_line, missing_checksums};
+use crate::syms::{Proc, SymIter};
+use tracing::warn;
+
+/// The line information of a single module, which has not yet been merged into a
+/// [`LineIndex`].
+///
+/// The `file` fields of the rows in this type are `NameIndex` values, not indexes into
+/// [`LineIndex::files`], and the `proc` fields are not yet assigned.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleLines {
+    /// The procedures of the module.
+    pub procs: Vec<ProcEntry>,
+    /// The line rows of the module.
+    pub rows: Vec<LineRow>,
+    /// The line rows of inlined functions.
+    pub inline_rows: Vec<InlineRow>,
+}
+
+/// Converts a `segment:offset` address to an RVA.
+fn section_rva(sections: &[IMAGE_SECTION_HEADER], segment: u16, offset: u32) -> Option<u32> {
+    let section = sections.get((segment as usize).checked_sub(1)?)?;
+    Some(section.virtual_address.wrapping_add(offset))
+}
+
+/// Gets the `NameIndex` of the file name of a file checksum record.
+fn file_name_index(checksums: &FileChecksumsSubsection<'_>, file_id: u32) -> anyhow::Result<u32> {
+    Ok(checksums.get_file(file_id)?.header.name.get())
+}
+
+impl ModuleLines {
+    /// Decodes the procedures and the line information of one module.
+    ///
+    /// `sym_data` contains the symbol records of the module (without the CodeView signature).
+    /// This function does not access the PDB, so it can be called on any thread.
+    pub fn build(
+        module_index: u32,
+        sym_data: &[u8],
+        line_data: &LineData<'_>,
+        sections: &[IMAGE_SECTION_HEADER],
+        options: &LineIndexOptions,
+    ) -> anyhow::Result<Self> {
+        let mut out = Self::default();
+
+        for sym in SymIter::new(sym_data) {
+            if !sym.kind.is_proc() {
+                continue;
+            }
+            // The _ST variants use a different encoding for the name, and are not supported.
+            let Ok(proc) = sym.parse_as::<Proc>() else {
+                continue;
+            };
+            let os = &proc.fixed.offset_segment;
+            let Some(rva) = section_rva(sections, os.segment(), os.offset()) else {
+                warn!(name = %proc.name, "procedure has an invalid section");
+                continue;
+            };
+            out.procs.push(ProcEntry {
+                name: proc.name.to_owned(),
+                module: module_index,
+                rva,
+                len: proc.fixed.proc_len.get(),
+            });
+        }
+
+        let checksums = line_data.find_checksums();
+
+        for subsection in line_data.subsections() {
+            if subsection.kind != SubsectionKind::LINES {
+                continue;
+            }
+            let Some(checksums) = &checksums else {
+                return Err(missing_checksums(SubsectionKind::LINES));
+            };
+
+            let lines = LinesSubsection::parse(subsection.data)?;
+            let contribution = lines.contribution;
+            let Some(contribution_rva) = section_rva(
+                sections,
+                contribution.segment.get(),
+                contribution.offset.get(),
+            ) else {
+                warn!("LINES subsection has an invalid section");
+                continue;
+            };
+
+            // The line records of all blocks, as (offset, row). The end of each row is the start
+            // of the next row, even if the next row is in a different block.
+            let mut records: Vec<(u32, LineRow)> = Vec::new();
+            for block in lines.blocks() {
+                let file = file_name_index(checksums, block.header.file_index.get())?;
+                let columns = block.columns();
+                for (i, line) in block.lines().iter().enumerate() {
+                    let column = columns
+                        .and_then(|c| c.get(i))
+                        .map_or(0, |c| c.start_offset.get() as u32);
+                    records.push((
+                        line.offset.get(),
+                        LineRow {
+                            rva: contribution_rva.wrapping_add(line.offset.get()),
+                            len: 0,
+                            module: module_index,
+                            proc: NO_PROC,
+                            file,
+                            line: line.line_num_start(),
+                            column,
+                            is_statement: line.statement(),
+                        },
+                    ));
+                }
+            }
+            records.sort_by_key(|(offset, _)| *offset);
+
+            for i in 0..records.len() {
+                let offset = records[i].0;
+                let end = match records.get(i + 1) {
+                    Some(&(next, _)) => next,
+                    None => contribution.size.get(),
+                };
+                let mut row = records[i].1.clone();
+                if is_jmc_line(row.line) {
+                    continue;
+                }
+                row.len = end.saturating_sub(offset);
+                out.rows.push(row);
+            }
+        }
+
+        if options.include_inlinees {
+            let map = InlineeLineMap::new(line_data)?;
+            if !map.is_empty() {
+                let Some(checksums) = &checksums else {
+                    return Err(missing_checksums(SubsectionKind::INLINEELINES));
+                };
+
+                for frame in map.inline_frames(SymIter::new(sym_data))? {
+                    for r in frame.rows {
+                        if r.code_offset_range.is_empty() {
+                            continue;
+                        }
+                        let Some(rva) =
+                            section_rva(sections, frame.segment, r.code_offset_range.start)
+                        else {
+                            warn!("inline site has an invalid section");
+                            break;
+                        };
+                        out.inline_rows.push(InlineRow {
+                            row: LineRow {
+                                rva,
+                                len: r.code_offset_range.len() as u32,
+                                module: module_index,
+                                proc: NO_PROC,
+                                file: file_name_index(checksums, r.file_id)?,
+                                line: r.line,
+                                column: r.column,
+                                is_statement: r.is_statement,
+                            },
+                            inlinee: frame.inlinee,
+                            depth: frame.depth,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
@@ -0,0 +1,190 @@
+//! Saves and loads a [`LineIndex`], so that it can be stored in a cache file.
+//!
+//! The format is a header, followed by the tables of the index. All integers are little-endian
+//! `u32` values. Strings are stored as a length, followed by the bytes of the string.
+
+use super::*;
+use anyhow::bail;
+use ms_codeview::parser::Parser;
+
+const MAGIC: [u8; 8] = *b"PDBLNIDX";
+const VERSION: u32 = 1;
+
+struct Writer {
+    out: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u32(len as u32);
+    }
+
+    fn str(&mut self, s: &BStr) {
+        self.len(s.len());
+        self.out.extend_from_slice(s);
+    }
+
+    fn row(&mut self, row: &LineRow) {
+        self.u32(row.rva);
+        self.u32(row.len);
+        self.u32(row.module);
+        self.u32(row.proc);
+        self.u32(row.file);
+        self.u32(row.line);
+        self.u32(row.column);
+        self.u32(row.is_statement as u32);
+    }
+}
+
+fn read_str(p: &mut Parser<'_>) -> anyhow::Result<BString> {
+    let len = p.u32()? as usize;
+    Ok(BString::from(p.bytes(len)?))
+}
+
+fn read_row(p: &mut Parser<'_>) -> anyhow::Result<LineRow> {
+    Ok(LineRow {
+        rva: p.u32()?,
+        len: p.u32()?,
+        module: p.u32()?,
+        proc: p.u32()?,
+        file: p.u32()?,
+        line: p.u32()?,
+        column: p.u32()?,
+        is_statement: p.u32()? != 0,
+    })
+}
+
+impl LineIndex {
+    /// Encodes the index, so that it can be saved to a cache file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer { out: Vec::new() };
+        w.out.extend_from_slice(&MAGIC);
+        w.u32(VERSION);
+
+        match &self.binding_key {
+            Some(key) => {
+                w.u32(1);
+                w.out.extend_from_slice(key.guid.as_bytes());
+                w.u32(key.age);
+            }
+            None => {
+                w.u32(0);
+                w.out.extend_from_slice(&[0; 16]);
+                w.u32(0);
+            }
+        }
+
+        w.len(self.files.len());
+        for file in self.files.iter() {
+            w.str(file.as_ref());
+        }
+
+        w.len(self.procs.len());
+        for proc in self.procs.iter() {
+            w.str(proc.name.as_ref());
+            w.u32(proc.module);
+            w.u32(proc.rva);
+            w.u32(proc.len);
+        }
+
+        w.len(self.rows.len());
+        for row in self.rows.iter() {
+            w.row(row);
+        }
+
+        w.len(self.inline_rows.len());
+        for inline_row in self.inline_rows.iter() {
+            w.row(&inline_row.row);
+            w.u32(inline_row.inlinee);
+            w.u32(inline_row.depth);
+        }
+
+        w.out
+    }
+
+    /// Decodes an index that was encoded by [`Self::to_bytes`].
+    ///
+    /// The caller should check that [`Self::binding_key`] matches the binding key of the PDB
+    /// before using the index.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut p = Parser::new(bytes);
+        if p.array::<8>()? != MAGIC {
+            bail!("The data is not a line index (wrong signature)");
+        }
+        let version = p.u32()?;
+        if version != VERSION {
+            bail!("The line index has an unsupported version: {version}");
+        }
+
+        let has_binding_key = p.u32()? != 0;
+        let guid = uuid::Uuid::from_bytes(p.array::<16>()?);
+        let age = p.u32()?;
+        let binding_key = has_binding_key.then_some(BindingKey { guid, age });
+
+        let mut files = Vec::new();
+        for _ in 0..p.u32()? {
+            files.push(read_str(&mut p)?);
+        }
+
+        let mut procs = Vec::new();
+        for _ in 0..p.u32()? {
+            procs.push(ProcEntry {
+                name: read_str(&mut p)?,
+                module: p.u32()?,
+                rva: p.u32()?,
+                len: p.u32()?,
+            });
+        }
+
+        let check_row = |row: &LineRow| -> anyhow::Result<()> {
+            if row.file as usize >= files.len() {
+                bail!("Line row has an invalid file index: {}", row.file);
+            }
+            if row.proc != NO_PROC && row.proc as usize >= procs.len() {
+                bail!("Line row has an invalid procedure index: {}", row.proc);
+            }
+            Ok(())
+        };
+
+        let mut rows = Vec::new();
+        for _ in 0..p.u32()? {
+            let row = read_row(&mut p)?;
+            check_row(&row)?;
+            rows.push(row);
+        }
+
+        let mut inline_rows = Vec::new();
+        for _ in 0..p.u32()? {
+            let row = read_row(&mut p)?;
+            check_row(&row)?;
+            inline_rows.push(InlineRow {
+                row,
+                inlinee: p.u32()?,
+                depth: p.u32()?,
+            });
+        }
+
+        if !p.is_empty() {
+            bail!("The line index has unexpected data at the end");
+        }
+
+        let is_sorted = procs.is_sorted_by_key(|p| p.rva)
+            && rows.is_sorted_by_key(|r| r.rva)
+            && inline_rows.is_sorted_by_key(|r| (r.row.rva, r.depth));
+        if !is_sorted {
+            bail!("The line index is not sorted");
+        }
+
+        Ok(Self {
+            binding_key,
+            files,
+            procs,
+            rows,
+            inline_rows,
+        })
+    }
+}
@@ -0,0 +1,191 @@
+//! Finds the code addresses of a source line. This is the reverse of [`LineIndex::find`], and is
+//! used for setting breakpoints and for mapping code coverage to source lines.
+
+use super::*;
+use crate::names::NameIndex;
+use std::collections::HashMap;
+
+/// One range of code that was generated for a source line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineAddressRange {
+    /// The index of the module (in the DBI Modules Substream) that contains the code.
+    pub module: u32,
+    /// The name of the source file, as it is stored in the PDB.
+    pub file: BString,
+    /// The column number, or 0 if the PDB does not contain column information.
+    pub column: u32,
+    /// The RVA of the start of the range.
+    pub rva: u32,
+    /// The size in bytes of the range.
+    pub len: u32,
+    /// True if this range describes a statement, false if it describes an expression.
+    pub is_statement: bool,
+    /// If the code is an inlined copy of a function, then this is the `ItemId` (in the IPI) of
+    /// the inlinee.
+    pub inlinee: Option<ItemId>,
+}
+
+impl LineAddressRange {
+    /// The range of RVAs covered by this range.
+    pub fn rva_range(&self) -> Range<u32> {
+        self.rva..self.rva.wrapping_add(self.len)
+    }
+}
+
+/// The result of finding the code addresses of a source line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourceLineMatch {
+    /// The line that was found. This is the nearest line that is greater than or equal to the
+    /// requested line, and that has code.
+    pub line: u32,
+    /// The ranges of code for `line`, sorted by RVA. This includes every copy of the line, such
+    /// as copies in different modules and inlined copies.
+    pub ranges: Vec<LineAddressRange>,
+}
+
+/// Collects the ranges of the nearest line that is greater than or equal to `requested`.
+struct NearestLine {
+    requested: u32,
+    found: Option<SourceLineMatch>,
+}
+
+impl NearestLine {
+    fn new(requested: u32) -> Self {
+        Self {
+            requested,
+            found: None,
+        }
+    }
+
+    fn add(&mut self, row: &LineRow, file: &BStr, inlinee: Option<ItemId>) {
+        if row.line < self.requested || row.len == 0 {
+            return;
+        }
+
+        let found = match &mut self.found {
+            Some(found) if found.line < row.line => return,
+            Some(found) if found.line == row.line => found,
+            _ => self.found.insert(SourceLineMatch {
+                line: row.line,
+                ranges: Vec::new(),
+            }),
+        };
+
+        found.ranges.push(LineAddressRange {
+            module: row.module,
+            file: file.to_owned(),
+            column: row.column,
+            rva: row.rva,
+            len: row.len,
+            is_statement: row.is_statement,
+            inlinee,
+        });
+    }
+
+    fn finish(self) -> Option<SourceLineMatch> {
+        let mut found = self.found?;
+        found
+            .ranges
+            .sort_by_key(|r| (r.rva, r.len, r.inlinee.is_some()));
+        found.ranges.dedup();
+        Some(found)
+    }
+}
+
+impl LineIndex {
+    /// Finds the code for a source line. If `line` does not have any code, then this finds the
+    /// nearest line after `line` that does. This includes inlined copies of the line, if the
+    /// index contains them.
+    ///
+    /// File names are compared using [`source_path_matches`], so `file_name` may be a full path
+    /// or the last components of a path.
+    pub fn find_source_line(&self, file_name: &BStr, line: u32) -> Option<SourceLineMatch> {
+        let mut nearest = NearestLine::new(line);
+        for (row, inlinee) in self.rows_for_file(file_name) {
+            nearest.add(row, self.file_name(row.file), inlinee);
+        }
+        nearest.finish()
+    }
+}
+
+impl<F: ReadAt> Pdb<F> {
+    /// Finds the code for a source line, without building a [`LineIndex`]. If `line` does not
+    /// have any code, then this finds the nearest line after `line` that does.
+    ///
+    /// This only decodes the modules whose `FILE_CHECKSUMS` subsection refers to the file. If
+    /// `include_inlinees` is true, then this also finds inlined copies of the line. File names
+    /// are compared using [`source_path_matches`].
+    pub fn find_source_line(
+        &self,
+        file_name: &BStr,
+        line: u32,
+        include_inlinees: bool,
+    ) -> anyhow::Result<Option<SourceLineMatch>> {
+        let sections = self.section_headers()?;
+        let names = self.names()?;
+        let options = LineIndexOptions {
+            include_inlinees,
+            ..Default::default()
+        };
+
+        // Maps NameIndex values to whether the name matches file_name. Names are shared by all
+        // modules, so this avoids comparing the same name many times.
+        let mut name_matches: HashMap<u32, bool> = HashMap::new();
+        let mut nearest = NearestLine::new(line);
+
+        for (module_index, module) in self.modules()?.iter().enumerate() {
+            let Some(module_stream) = self.read_module_stream(&module)? else {
+                continue;
+            };
+            let line_data = module_stream.c13_line_data();
+            let Some(checksums) = line_data.find_checksums() else {
+                continue;
+            };
+
+            let mut files: Vec<u32> = Vec::new();
+            for checksum in checksums.iter() {
+                let ni = checksum.name().0;
+                let is_match = match name_matches.get(&ni) {
+                    Some(&is_match) => is_match,
+                    None => {
+                        let is_match =
+                            source_path_matches(names.get_string(NameIndex(ni))?, file_name);
+                        name_matches.insert(ni, is_match);
+                        is_match
+                    }
+                };
+                if is_match {
+                    files.push(ni);
+                }
+            }
+            if files.is_empty() {
+                continue;
+            }
+
+            let module_lines = ModuleLines::build(
+                module_index as u32,
+                module_stream.sym_data()?,
+                &line_data,
+                sections,
+                &options,
+            )
+            .with_context(|| format!("in module {module_index}"))?;
+
+            // The file fields of ModuleLines are NameIndex values.
+            for row in module_lines.rows.iter() {
+                if files.contains(&row.file) {
+                    nearest.add(row, names.get_string(NameIndex(row.file))?, None);
+                }
+            }
+            for inline_row in module_lines.inline_rows.iter() {
+                let row = &inline_row.row;
+                if files.contains(&row.file) {
+                    let file = names.get_string(NameIndex(row.file))?;
+                    nearest.add(row, file, Some(inline_row.inlinee));
+                }
+            }
+        }
+
+        Ok(nearest.finish())
+    }
+}
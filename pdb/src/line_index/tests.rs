@@ -0,0 +1,445 @@
+use super::*;
+use crate::dbi::{DBI_STREAM_VERSION_V110, DbiStreamHeader, ModuleInfoFixed};
+use crate::lines::testing::{checksums, subsection, u32s};
+use crate::lines::{
+    CV_INLINEE_SOURCE_LINE_SIGNATURE, CV_LINES_HAVE_COLUMNS, LineData, SubsectionKind,
+};
+use crate::msf::Msf;
+use crate::names::NameIndex;
+use crate::pdbi::{PDBI_VERSION_VC140, PdbiStream};
+use crate::syms::builder::SymBuilder;
+use crate::syms::{BinaryAnnotation, ProcFixed, SymKind};
+use crate::{IMAGE_SECTION_HEADER, Stream};
+use std::sync::Mutex;
+use sync_file::WriteAt;
+use zerocopy::{FromBytes, FromZeros, I32, IntoBytes, U16, U32};
+
+const STATEMENT: u32 = 1 << 31;
+
+/// A block of line records, as `(file_id, [(offset, line, column)])`.
+type Block<'a> = (u32, &'a [(u32, u32, u16)]);
+
+/// Builds a `LINES` subsection.
+fn lines(out: &mut Vec<u8>, offset: u32, size: u32, have_columns: bool, blocks: &[Block<'_>]) {
+    let flags = if have_columns {
+        CV_LINES_HAVE_COLUMNS
+    } else {
+        0
+    };
+    let mut data = u32s(&[offset]);
+    data.extend_from_slice(&1u16.to_le_bytes()); // segment
+    data.extend_from_slice(&flags.to_le_bytes());
+    data.extend_from_slice(&size.to_le_bytes());
+    for &(file_id, records) in blocks {
+        let record_size = if have_columns { 12 } else { 8 };
+        let block_size = 12 + records.len() as u32 * record_size;
+        data.extend(u32s(&[file_id, records.len() as u32, block_size]));
+        for &(offset, line, _) in records {
+            data.extend(u32s(&[offset, line | STATEMENT]));
+        }
+        if have_columns {
+            for &(_, _, column) in records {
+                data.extend_from_slice(&column.to_le_bytes());
+                data.extend_from_slice(&(column + 1).to_le_bytes());
+            }
+        }
+    }
+    subsection(out, SubsectionKind::LINES, &data);
+}
+
+fn proc(b: &mut SymBuilder, offset: u32, len: u32, name: &str) {
+    let mut fixed = ProcFixed::new_zeroed();
+    fixed.offset_segment.offset = U32::new(offset);
+    fixed.offset_segment.segment = U16::new(1);
+    fixed.proc_len = U32::new(len);
+    b.begin_proc(SymKind::S_GPROC32_ID, &fixed, name.into());
+}
+
+fn sections() -> [IMAGE_SECTION_HEADER; 1] {
+    [IMAGE_SECTION_HEADER {
+        virtual_address: 0x1000,
+        physical_address_or_virtual_size: 0x1000,
+        ..Default::default()
+    }]
+}
+
+/// Builds the symbols and C13 Line Data of two modules:
+///
+/// * Module 0 contains `f` at RVA 0x1100, which has four lines and an inlined function.
+/// * Module 1 contains `g` at RVA 0x1200, which has one line with a column.
+///
+/// The modules refer to `d:\src\a.cpp` as name 0x10 and `d:\src\inl.h` as name 0x20.
+fn module_data() -> [(Vec<u8>, Vec<u8>); 2] {
+    let mut b = SymBuilder::new();
+    proc(&mut b, 0x100, 0x40, "f");
+    b.begin_inline_site(
+        0x1000,
+        &[
+            BinaryAnnotation::CodeOffset(0x10),
+            BinaryAnnotation::ChangeCodeLength(0x18),
+        ],
+    )
+    .unwrap();
+    b.end_scope();
+    b.end_scope();
+    let syms0 = b.finish();
+
+    let mut line_data0 = Vec::new();
+    checksums(&mut line_data0, &[0x10, 0x20]);
+    #[rustfmt::skip]
+    lines(&mut line_data0, 0x100, 0x40, false, &[
+        (0, &[(0, 10, 0), (0x10, 11, 0), (0x30, 12, 0)]),
+        (8, &[(0x20, 5, 0)]),
+    ]);
+    subsection(
+        &mut line_data0,
+        SubsectionKind::INLINEELINES,
+        &u32s(&[CV_INLINEE_SOURCE_LINE_SIGNATURE, 0x1000, 8, 20]),
+    );
+
+    let mut b = SymBuilder::new();
+    proc(&mut b, 0x200, 0x10, "g");
+    b.end_scope();
+    let syms1 = b.finish();
+
+    let mut line_data1 = Vec::new();
+    checksums(&mut line_data1, &[0x10, 0x20]);
+    lines(&mut line_data1, 0x200, 0x10, true, &[(0, &[(0, 30, 7)])]);
+
+    [(syms0, line_data0), (syms1, line_data1)]
+}
+
+/// Builds a line index for the modules of [`module_data`].
+fn build(options: &LineIndexOptions) -> LineIndex {
+    let sections = sections();
+    let modules = module_data()
+        .iter()
+        .enumerate()
+        .map(|(i, (syms, line_data))| {
+            ModuleLines::build(
+                i as u32,
+                syms,
+                &LineData::new(line_data),
+                &sections,
+                options,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    LineIndex::from_modules(modules, |ni| match ni {
+        NameIndex(0x10) => Ok(BString::from("d:\\src\\a.cpp")),
+        NameIndex(0x20) => Ok(BString::from("d:\\src\\inl.h")),
+        _ => panic!("unexpected name {ni:?}"),
+    })
+    .unwrap()
+}
+
+fn with_inlinees() -> LineIndexOptions {
+    LineIndexOptions {
+        include_inlinees: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn find() {
+    let index = build(&with_inlinees());
+    assert_eq!(index.procs.len(), 2);
+    assert_eq!(index.rows.len(), 5);
+
+    let loc = index.find(0x1115).unwrap();
+    assert_eq!(loc.row.rva_range(), 0x1110..0x1120);
+    assert_eq!(loc.row.line, 11);
+    assert_eq!(loc.row.module, 0);
+    assert!(loc.row.is_statement);
+    assert_eq!(index.file_name(loc.row.file), "d:\\src\\a.cpp");
+    assert_eq!(loc.proc.unwrap().name, "f");
+
+    assert_eq!(loc.inline_frames.len(), 1);
+    let frame = loc.inline_frames[0];
+    assert_eq!(frame.inlinee, 0x1000);
+    assert_eq!(frame.row.rva_range(), 0x1110..0x1128);
+    assert_eq!(frame.row.line, 20);
+    assert_eq!(index.file_name(frame.row.file), "d:\\src\\inl.h");
+    assert_eq!(index.proc_of(&frame.row).unwrap().name, "f");
+
+    // The row in the second block ends where the next row (in the first block) begins.
+    let loc = index.find(0x1125).unwrap();
+    assert_eq!(loc.row.rva_range(), 0x1120..0x1130);
+    assert_eq!(loc.row.line, 5);
+    assert_eq!(index.file_name(loc.row.file), "d:\\src\\inl.h");
+
+    // The last row ends at the end of the contribution.
+    assert_eq!(index.find(0x113f).unwrap().row.line, 12);
+    assert!(index.find(0x1130).unwrap().inline_frames.is_empty());
+    assert!(index.find(0x1140).is_none());
+    assert!(index.find(0x10ff).is_none());
+
+    let loc = index.find(0x1208).unwrap();
+    assert_eq!(loc.row.line, 30);
+    assert_eq!(loc.row.column, 7);
+    assert_eq!(loc.row.module, 1);
+    assert_eq!(loc.proc.unwrap().name, "g");
+}
+
+#[test]
+fn ranges() {
+    let index = build(&with_inlinees());
+
+    let f = index.find_procs_by_name("f".into()).next().unwrap();
+    let lines: Vec<u32> = index.lines_for_proc(f).iter().map(|r| r.line).collect();
+    assert_eq!(lines, [10, 11, 5, 12]);
+    assert_eq!(index.inline_lines_for_proc(f).len(), 1);
+
+    let ranges = |file: &str, line: u32| -> Vec<Range<u32>> {
+        index.addresses_for_line(file.into(), line).collect()
+    };
+    assert_eq!(ranges("a.cpp", 11), vec![0x1110..0x1120]);
+    assert_eq!(ranges("D:\\SRC\\A.CPP", 10), vec![0x1100..0x1110]);
+    assert_eq!(ranges("inl.h", 20), vec![0x1110..0x1128]);
+    assert!(ranges("x\\a.cpp", 10).is_empty());
+    assert!(ranges("a.cpp", 99).is_empty());
+}
+
+#[test]
+fn without_inlinees() {
+    let index = build(&LineIndexOptions::default());
+    assert!(index.inline_rows.is_empty());
+    assert!(index.find(0x1115).unwrap().inline_frames.is_empty());
+}
+
+#[test]
+fn cache() {
+    let mut index = build(&with_inlinees());
+    index.binding_key = Some(BindingKey {
+        guid: uuid::Uuid::from_bytes([7; 16]),
+        age: 3,
+    });
+
+    let bytes = index.to_bytes();
+    let loaded = LineIndex::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded, index);
+
+    assert!(LineIndex::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(LineIndex::from_bytes(b"not a line index").is_err());
+
+    let mut extra = bytes.clone();
+    extra.push(0);
+    assert!(LineIndex::from_bytes(&extra).is_err());
+}
+
+#[test]
+fn find_source_line() {
+    let index = build(&with_inlinees());
+
+    let found = index.find_source_line("src/a.cpp".into(), 11).unwrap();
+    assert_eq!(found.line, 11);
+    assert_eq!(found.ranges.len(), 1);
+    assert_eq!(found.ranges[0].rva_range(), 0x1110..0x1120);
+    assert_eq!(found.ranges[0].file, "d:\\src\\a.cpp");
+    assert_eq!(found.ranges[0].inlinee, None);
+
+    // Line 31 has no code, and there is no later line.
+    assert!(index.find_source_line("a.cpp".into(), 31).is_none());
+
+    // Line 25 has no code, so this finds line 30, which is in a different module.
+    let found = index.find_source_line("D:/SRC/A.CPP".into(), 25).unwrap();
+    assert_eq!(found.line, 30);
+    assert_eq!(found.ranges[0].module, 1);
+    assert_eq!(found.ranges[0].column, 7);
+
+    // The nearest line may be in an inlined copy.
+    let found = index.find_source_line("inl.h".into(), 6).unwrap();
+    assert_eq!(found.line, 20);
+    assert_eq!(found.ranges.len(), 1);
+    assert_eq!(found.ranges[0].rva_range(), 0x1110..0x1128);
+    assert_eq!(found.ranges[0].inlinee, Some(0x1000));
+
+    assert_eq!(index.find_source_line("inl.h".into(), 1).unwrap().line, 5);
+    assert!(index.find_source_line("rc/a.cpp".into(), 1).is_none());
+}
+
+#[test]
+fn invalid_file_index() {
+    let mut index = build(&with_inlinees());
+    let mut row = index.rows[0].clone();
+    row.file = 1000;
+    index.rows.push(row);
+
+    assert_eq!(
+        index
+            .addresses_for_line("a.cpp".into(), 10)
+            .collect::<Vec<_>>(),
+        vec![0x1100..0x1110]
+    );
+    assert_eq!(
+        index
+            .find_source_line("a.cpp".into(), 10)
+            .unwrap()
+            .ranges
+            .len(),
+        1
+    );
+}
+
+/// A file in memory, for building a PDB with [`Msf`].
+#[derive(Default)]
+struct MemFile(Mutex<Vec<u8>>);
+
+impl ReadAt for MemFile {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.0.lock().unwrap().read_at(buf, offset)
+    }
+}
+
+impl WriteAt for MemFile {
+    fn write_at(&self, buf: &[u8], offset: u64) -> std::io::Result<usize> {
+        let mut data = self.0.lock().unwrap();
+        let start = offset as usize;
+        if data.len() < start + buf.len() {
+            data.resize(start + buf.len(), 0);
+        }
+        data[start..start + buf.len()].copy_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+/// Builds a PDB that contains the modules of [`module_data`], the section headers, and a
+/// `/names` stream. Module 2 has no module stream.
+fn build_pdb() -> Box<Pdb<Vec<u8>>> {
+    let mut msf = Msf::create_for(MemFile::default(), Default::default()).unwrap();
+
+    // Place the file names at the name indexes that module_data uses.
+    let mut strings = vec![0u8; 0x10];
+    strings.extend_from_slice(b"d:\\src\\a.cpp\0\0\0\0");
+    strings.extend_from_slice(b"d:\\src\\inl.h\0");
+    let mut names = u32s(&[
+        crate::names::NAMES_STREAM_SIGNATURE,
+        1,
+        strings.len() as u32,
+    ]);
+    names.extend_from_slice(&strings);
+    names.extend(u32s(&[0, 2])); // num_hashes, num_strings
+    let names_stream = msf.new_stream_data(&names).unwrap();
+
+    let mut pdbi = PdbiStream {
+        signature: 0,
+        version: PDBI_VERSION_VC140,
+        age: 1,
+        unique_id: Some(uuid::Uuid::nil()),
+        named_streams: Default::default(),
+        features: Vec::new(),
+    };
+    pdbi.named_streams
+        .insert(crate::names::NAMES_STREAM_NAME, names_stream);
+    msf.write_stream(Stream::PDB.into())
+        .unwrap()
+        .set_contents(&pdbi.to_bytes().unwrap())
+        .unwrap();
+
+    let mut modules = Vec::new();
+    for (i, (syms, line_data)) in module_data().iter().enumerate() {
+        let mut module_stream = u32s(&[4]); // CV_SIGNATURE_C13
+        module_stream.extend_from_slice(syms);
+        module_stream.extend_from_slice(line_data);
+        module_stream.extend(u32s(&[0])); // global_refs_size
+
+        let mut fixed = ModuleInfoFixed::new_zeroed();
+        fixed.stream = msf
+            .new_stream_data(&module_stream)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        fixed.sym_byte_size = U32::new(4 + syms.len() as u32);
+        fixed.c13_byte_size = U32::new(line_data.len() as u32);
+        modules.extend_from_slice(fixed.as_bytes());
+        modules.extend_from_slice(format!("m{i}\0m{i}.obj\0\0\0").as_bytes());
+    }
+    let mut fixed = ModuleInfoFixed::new_zeroed();
+    fixed.stream = crate::StreamIndexU16::NIL;
+    modules.extend_from_slice(fixed.as_bytes());
+    modules.extend_from_slice(b"m2\0m2.obj\0\0\0");
+
+    // The Section Header Data stream is entry 5 of the Optional Debug Header.
+    let section_headers = msf.new_stream_data(sections().as_bytes()).unwrap();
+    let mut optional_dbg: Vec<u16> = vec![0xffff; 5];
+    optional_dbg.push(section_headers as u16);
+
+    let mut header =
+        DbiStreamHeader::read_from_bytes(&crate::dbi::EMPTY_DBI_STREAM_HEADER).unwrap();
+    header.version = U32::new(DBI_STREAM_VERSION_V110);
+    header.mod_info_size = I32::new(modules.len() as i32);
+    header.optional_dbg_header_size = I32::new(optional_dbg.as_bytes().len() as i32);
+    let mut dbi = header.as_bytes().to_vec();
+    dbi.extend_from_slice(&modules);
+    dbi.extend_from_slice(optional_dbg.as_bytes());
+    msf.write_stream(Stream::DBI.into())
+        .unwrap()
+        .set_contents(&dbi)
+        .unwrap();
+
+    msf.commit().unwrap();
+    let file = msf.into_file().0.into_inner().unwrap();
+    Pdb::open_from_random_file(file).unwrap()
+}
+
+#[test]
+fn pdb_find_source_line() {
+    let pdb = build_pdb();
+
+    let found = pdb
+        .find_source_line("src/a.cpp".into(), 11, false)
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.line, 11);
+    assert_eq!(found.ranges.len(), 1);
+    assert_eq!(found.ranges[0].rva_range(), 0x1110..0x1120);
+    assert_eq!(found.ranges[0].file, "d:\\src\\a.cpp");
+    assert_eq!(found.ranges[0].module, 0);
+
+    // Line 25 has no code, so this finds line 30, which is in a different module.
+    let found = pdb
+        .find_source_line("D:/SRC/A.CPP".into(), 25, false)
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.line, 30);
+    assert_eq!(found.ranges[0].module, 1);
+    assert_eq!(found.ranges[0].column, 7);
+    assert!(
+        pdb.find_source_line("a.cpp".into(), 31, false)
+            .unwrap()
+            .is_none()
+    );
+
+    // Inlined copies are only found if they are requested.
+    assert!(
+        pdb.find_source_line("inl.h".into(), 6, false)
+            .unwrap()
+            .is_none()
+    );
+    let found = pdb
+        .find_source_line("inl.h".into(), 6, true)
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.line, 20);
+    assert_eq!(found.ranges[0].rva_range(), 0x1110..0x1128);
+    assert_eq!(found.ranges[0].inlinee, Some(0x1000));
+
+    // The results match those of a line index.
+    let index = pdb.build_line_index(&with_inlinees()).unwrap();
+    for file in ["a.cpp", "inl.h"] {
+        for line in [1, 5, 11, 12, 20, 25] {
+            assert_eq!(
+                pdb.find_source_line(file.into(), line, true).unwrap(),
+                index.find_source_line(file.into(), line),
+            );
+        }
+    }
+
+    assert!(
+        pdb.find_source_line("b.cpp".into(), 1, true)
+            .unwrap()
+            .is_none()
+    );
+}
@@ -4,9 +4,17 @@
 //! * [/ZH (Hash algorithm for calculation of file checksum in debug info)](https://learn.microsoft.com/en-us/cpp/build/reference/zh?view=msvc-170)
 
 mod checksum;
+mod cross_scope;
+mod frame_data;
+mod inlinee_lines;
 mod subsection;
+#[cfg(test)]
+pub(crate) mod testing;
 
 pub use checksum::*;
+pub use cross_scope::*;
+pub use frame_data::*;
+pub use inlinee_lines::*;
 pub use subsection::*;
 
 use crate::codeview::syms::OffsetSegment;
@@ -62,7 +70,11 @@ subsections! {
     CROSSSCOPEEXPORTS = 0xF8;
 
     IL_LINES = 0xF9;
+    /// Maps functions to CLR metadata tokens. cvinfo.h does not document the layout of this
+    /// subsection, so it is not decoded. Its raw contents can be read with
+    /// [`LineData::subsections`].
     FUNC_MDTOKEN_MAP = 0xFA;
+    /// Maps types to CLR metadata tokens. Like `FUNC_MDTOKEN_MAP`, this is not decoded.
     TYPE_MDTOKEN_MAP = 0xFB;
     MERGED_ASSEMBLYINPUT = 0xFC;
 
@@ -103,6 +115,49 @@ impl<'a> LineData<'a> {
         Some(FileChecksumsSubsection::new(subsection_bytes))
     }
 
+    /// Iterates the `IL_LINES` subsections. These have the same format as `LINES` subsections,
+    /// but the offsets in their line records are offsets within the IL (MSIL) of a managed
+    /// function, rather than offsets within native code.
+    pub fn il_lines(&self) -> impl Iterator<Item = LinesSubsection<'a>> + 'a {
+        self.subsections()
+            .filter(|s| s.kind == SubsectionKind::IL_LINES)
+            .filter_map(|s| LinesSubsection::parse(s.data).ok())
+    }
+
+    /// Finds the `CROSSSCOPEIMPORTS` subsection, which lists the IDs that this module uses that
+    /// are defined by other modules.
+    pub fn find_cross_scope_imports(&self) -> Option<CrossScopeImportsSubsection<'a>> {
+        let subsection = self
+            .subsections()
+            .find(|s| s.kind == SubsectionKind::CROSSSCOPEIMPORTS)?;
+        CrossScopeImportsSubsection::parse(subsection.data).ok()
+    }
+
+    /// Finds the `CROSSSCOPEEXPORTS` subsection, which maps the IDs that this module defines
+    /// to IDs in the IPI.
+    pub fn find_cross_scope_exports(&self) -> Option<CrossScopeExportsSubsection<'a>> {
+        let subsection = self
+            .subsections()
+            .find(|s| s.kind == SubsectionKind::CROSSSCOPEEXPORTS)?;
+        CrossScopeExportsSubsection::parse(subsection.data).ok()
+    }
+
+    /// Finds the `FRAMEDATA` subsection, which describes the stack frames of 32-bit x86 code.
+    pub fn find_frame_data(&self) -> Option<FrameDataSubsection<'a>> {
+        let subsection = self
+            .subsections()
+            .find(|s| s.kind == SubsectionKind::FRAMEDATA)?;
+        FrameDataSubsection::parse(subsection.data).ok()
+    }
+
+    /// Iterates the `INLINEELINES` subsections, which give the starting source line of each
+    /// inlinee. Subsections that cannot be decoded are skipped.
+    pub fn inlinee_lines(&self) -> impl Iterator<Item = InlineeLinesSubsection<'a>> + 'a {
+        self.subsections()
+            .filter(|s| s.kind == SubsectionKind::INLINEELINES)
+            .filter_map(|s| InlineeLinesSubsection::parse(s.data).ok())
+    }
+
     /// Iterates the `NameIndex` values that appear in this Line Data section.
     ///
     /// This may iterate the same `NameIndex` value more than once.
@@ -113,7 +168,7 @@ impl<'a> LineData<'a> {
         if let Some(checksums) = self.find_checksums() {
             for subsection in self.subsections() {
                 match subsection.kind {
-                    SubsectionKind::LINES => {
+                    SubsectionKind::LINES | SubsectionKind::IL_LINES => {
                         let lines_subsection = LinesSubsection::parse(subsection.data)?;
                         for block in lines_subsection.blocks() {
                             let file = checksums.get_file(block.header.file_index.get())?;
@@ -127,10 +182,8 @@ impl<'a> LineData<'a> {
         } else {
             for subsection in self.subsections() {
                 match subsection.kind {
-                    SubsectionKind::LINES => {
-                        bail!(
-                            "This C13 Line Data substream contains LINES subsections, but does not contain a FILE_CHECKSUMS subsection."
-                        );
+                    SubsectionKind::LINES | SubsectionKind::IL_LINES => {
+                        return Err(missing_checksums(subsection.kind));
                     }
                     _ => {}
                 }
@@ -141,6 +194,14 @@ impl<'a> LineData<'a> {
     }
 }
 
+/// The error for C13 Line Data that contains `kind` subsections, which refer to file checksums,
+/// but no `FILE_CHECKSUMS` subsection.
+pub(crate) fn missing_checksums(kind: SubsectionKind) -> anyhow::Error {
+    anyhow::anyhow!(
+        "This C13 Line Data substream contains {kind:?} subsections, but does not contain a FILE_CHECKSUMS subsection."
+    )
+}
+
 /// Enables decoding of the line data stored in a Module Stream. This decodes the "C13 line data"
 /// substream.
 pub struct LineDataMut<'a> {
@@ -615,7 +676,7 @@ pub fn fixup_c13_line_data(
 
     for subsection in c13_line_data.subsections_mut() {
         match subsection.kind {
-            SubsectionKind::LINES => {
+            SubsectionKind::LINES | SubsectionKind::IL_LINES => {
                 // We need to rewrite the file_index values within each line block.
                 let mut lines = LinesSubsectionMut::parse(subsection.data)?;
                 for block in lines.blocks_mut() {
@@ -0,0 +1,320 @@
+//! Decodes the `CROSSSCOPEIMPORTS` and `CROSSSCOPEEXPORTS` subsections.
+//!
+//! These subsections are used when the ID records of a module are not merged into the IPI, such
+//! as when linking with `/DEBUG:FASTLINK`. A module that refers to an ID record that is defined
+//! by another module uses a _cross-scope ID_ (see [`CrossScopeId`]). The cross-scope ID selects
+//! an entry in the `CROSSSCOPEIMPORTS` subsection of the importing module, which names the
+//! exporting module and gives the local ID within that module. The `CROSSSCOPEEXPORTS` subsection
+//! of the exporting module maps its local IDs to global IDs, which are `ItemId` values in the IPI.
+
+use super::*;
+use crate::types::ItemId;
+use crate::{Pdb, ReadAt, get_or_init_err};
+use bstr::{BStr, BString};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+
+/// A cross-scope ID, which refers to an ID record in another module.
+///
+/// * bit 31 is set for cross-scope IDs.
+/// * bits 20-30 are the index of the entry in the `CROSSSCOPEIMPORTS` subsection.
+/// * bits 0-19 are the index of the local ID within that entry.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CrossScopeId(pub u32);
+
+impl CrossScopeId {
+    const FLAG: u32 = 1 << 31;
+    const IMPORT_INDEX_BITS: u32 = 11;
+    const ID_INDEX_BITS: u32 = 20;
+
+    /// The largest value of [`Self::import_index`].
+    pub const MAX_IMPORT_INDEX: u32 = (1 << Self::IMPORT_INDEX_BITS) - 1;
+
+    /// The largest value of [`Self::id_index`].
+    pub const MAX_ID_INDEX: u32 = (1 << Self::ID_INDEX_BITS) - 1;
+
+    /// Checks whether `id` is a cross-scope ID. If so, returns it as a `CrossScopeId`.
+    pub fn from_item_id(id: ItemId) -> Option<Self> {
+        if id & Self::FLAG != 0 {
+            Some(Self(id))
+        } else {
+            None
+        }
+    }
+
+    /// Creates a cross-scope ID from its parts.
+    ///
+    /// Panics if `import_index` is greater than [`Self::MAX_IMPORT_INDEX`] or `id_index` is
+    /// greater than [`Self::MAX_ID_INDEX`], because they would not fit in their bit fields.
+    pub fn new(import_index: u32, id_index: u32) -> Self {
+        assert!(
+            import_index <= Self::MAX_IMPORT_INDEX,
+            "import_index 0x{import_index:x} does not fit in a cross-scope ID"
+        );
+        assert!(
+            id_index <= Self::MAX_ID_INDEX,
+            "id_index 0x{id_index:x} does not fit in a cross-scope ID"
+        );
+        Self(Self::FLAG | (import_index << Self::ID_INDEX_BITS) | id_index)
+    }
+
+    /// The index of the entry in the `CROSSSCOPEIMPORTS` subsection.
+    pub fn import_index(self) -> u32 {
+        (self.0 & !Self::FLAG) >> Self::ID_INDEX_BITS
+    }
+
+    /// The index of the local ID within the entry in the `CROSSSCOPEIMPORTS` subsection.
+    pub fn id_index(self) -> u32 {
+        self.0 & Self::MAX_ID_INDEX
+    }
+}
+
+/// The fixed-size header of one entry in a `CROSSSCOPEIMPORTS` subsection.
+#[derive(IntoBytes, FromBytes, KnownLayout, Immutable, Unaligned, Clone, Debug)]
+#[repr(C)]
+pub struct CrossScopeImportHeader {
+    /// The name of the exporting module, as a `NameIndex` in the Names Stream.
+    pub module_name: U32<LE>,
+    /// The number of local IDs that follow.
+    pub count: U32<LE>,
+}
+
+/// One entry in a `CROSSSCOPEIMPORTS` subsection. Lists the IDs that are imported from one
+/// module.
+#[derive(Clone)]
+pub struct CrossScopeImport<'a> {
+    /// The fixed-size header.
+    pub header: &'a CrossScopeImportHeader,
+    /// The local IDs within the exporting module.
+    pub local_ids: &'a [U32<LE>],
+}
+
+impl<'a> CrossScopeImport<'a> {
+    /// The name of the exporting module.
+    pub fn module_name(&self) -> NameIndex {
+        NameIndex(self.header.module_name.get())
+    }
+}
+
+/// Decodes a `CROSSSCOPEIMPORTS` subsection.
+///
+/// `parse` validates the entire subsection, so iterating the entries cannot fail.
+#[derive(Clone)]
+pub struct CrossScopeImportsSubsection<'a> {
+    /// The encoded entries.
+    pub bytes: &'a [u8],
+}
+
+impl<'a> CrossScopeImportsSubsection<'a> {
+    /// Parses the subsection and checks that the entries are well-formed.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ParserError> {
+        let mut p = Parser::new(bytes);
+        while !p.is_empty() {
+            read_import(&mut p)?;
+        }
+        Ok(Self { bytes })
+    }
+
+    /// Iterates the entries.
+    pub fn iter(&self) -> IterCrossScopeImports<'a> {
+        IterCrossScopeImports {
+            p: Parser::new(self.bytes),
+        }
+    }
+
+    /// Gets the entry at `index`.
+    pub fn get(&self, index: u32) -> Option<CrossScopeImport<'a>> {
+        self.iter().nth(index as usize)
+    }
+
+    /// Finds the name of the exporting module and the local ID within that module for a
+    /// cross-scope ID.
+    pub fn lookup(&self, id: CrossScopeId) -> Option<(NameIndex, ItemId)> {
+        let import = self.get(id.import_index())?;
+        let local_id = import.local_ids.get(id.id_index() as usize)?;
+        Some((import.module_name(), local_id.get()))
+    }
+}
+
+fn read_import<'a>(p: &mut Parser<'a>) -> Result<CrossScopeImport<'a>, ParserError> {
+    let header: &CrossScopeImportHeader = p.get()?;
+    let local_ids = p.slice(header.count.get() as usize)?;
+    Ok(CrossScopeImport { header, local_ids })
+}
+
+/// Iterates the entries of a [`CrossScopeImportsSubsection`].
+pub struct IterCrossScopeImports<'a> {
+    p: Parser<'a>,
+}
+
+impl<'a> Iterator for IterCrossScopeImports<'a> {
+    type Item = CrossScopeImport<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.p.is_empty() {
+            return None;
+        }
+        match read_import(&mut self.p) {
+            Ok(import) => Some(import),
+            Err(_) => {
+                warn!("failed to decode CROSSSCOPEIMPORTS entry");
+                self.p = Parser::new(&[]);
+                None
+            }
+        }
+    }
+}
+
+/// One entry in a `CROSSSCOPEEXPORTS` subsection.
+#[derive(IntoBytes, FromBytes, KnownLayout, Immutable, Unaligned, Clone, Debug)]
+#[repr(C)]
+pub struct CrossScopeExport {
+    /// The ID within the exporting module.
+    pub local_id: U32<LE>,
+    /// The ID within the IPI.
+    pub global_id: U32<LE>,
+}
+
+/// Decodes a `CROSSSCOPEEXPORTS` subsection.
+pub struct CrossScopeExportsSubsection<'a> {
+    /// The entries of the subsection.
+    pub entries: &'a [CrossScopeExport],
+}
+
+impl<'a> CrossScopeExportsSubsection<'a> {
+    /// Parses the subsection. The size of `bytes` must be a multiple of the size of
+    /// `CrossScopeExport`.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ParserError> {
+        let Ok(entries) = <[CrossScopeExport]>::ref_from_bytes(bytes) else {
+            return Err(ParserError::new());
+        };
+        Ok(Self { entries })
+    }
+
+    /// Finds the global ID for `local_id`.
+    pub fn find(&self, local_id: ItemId) -> Option<ItemId> {
+        self.entries
+            .iter()
+            .find(|e| e.local_id.get() == local_id)
+            .map(|e| e.global_id.get())
+    }
+}
+
+/// The result of resolving a cross-scope ID.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CrossScopeTarget {
+    /// The index of the exporting module in the DBI Modules Substream.
+    pub module_index: usize,
+    /// The ID within the exporting module.
+    pub local_id: ItemId,
+    /// The ID within the IPI.
+    pub global_id: ItemId,
+}
+
+/// Resolves cross-scope IDs to IDs in the IPI, by finding the exporting module and reading its
+/// `CROSSSCOPEEXPORTS` subsection.
+///
+/// The exports of each module are read on demand and cached.
+pub struct CrossScopeResolver<'p, F> {
+    pdb: &'p Pdb<F>,
+    /// Maps module names and object file names, converted to ASCII lowercase, to module indexes.
+    module_names: OnceCell<HashMap<BString, usize>>,
+    exports: HashMap<usize, Vec<CrossScopeExport>>,
+}
+
+impl<'p, F: ReadAt> CrossScopeResolver<'p, F> {
+    /// Creates a new resolver.
+    pub fn new(pdb: &'p Pdb<F>) -> Self {
+        Self {
+            pdb,
+            module_names: OnceCell::new(),
+            exports: HashMap::new(),
+        }
+    }
+
+    /// Finds the module with the given name. Module names are compared without regard to
+    /// (ASCII) case, and may match either the module name or the object file name.
+    ///
+    /// If more than one module matches, this returns the first one.
+    pub fn find_module(&self, name: &BStr) -> anyhow::Result<Option<usize>> {
+        let module_names = get_or_init_err(&self.module_names, || {
+            let mut module_names = HashMap::new();
+            for (i, module) in self.pdb.modules()?.iter().enumerate() {
+                for name in [module.module_name(), module.obj_file()] {
+                    module_names
+                        .entry(name.to_ascii_lowercase().into())
+                        .or_insert(i);
+                }
+            }
+            anyhow::Ok(module_names)
+        })?;
+        Ok(module_names.get(&name.to_ascii_lowercase()[..]).copied())
+    }
+
+    /// Resolves `id`, which was found in the module whose `CROSSSCOPEIMPORTS` subsection is
+    /// `imports`.
+    pub fn resolve(
+        &mut self,
+        imports: &CrossScopeImportsSubsection<'_>,
+        id: CrossScopeId,
+    ) -> anyhow::Result<CrossScopeTarget> {
+        let Some((module_name, local_id)) = imports.lookup(id) else {
+            bail!(
+                "Cross-scope ID 0x{:08x} is out of range of the CROSSSCOPEIMPORTS subsection",
+                id.0
+            );
+        };
+
+        let module_name = self.pdb.get_name(module_name)?;
+        let Some(module_index) = self.find_module(module_name)? else {
+            bail!(
+                "Cross-scope ID 0x{:08x} refers to module {module_name}, which was not found",
+                id.0
+            );
+        };
+
+        let exports = CrossScopeExportsSubsection {
+            entries: self.module_exports(module_index)?,
+        };
+        let Some(global_id) = exports.find(local_id) else {
+            bail!(
+                "Module {module_name} does not export local ID 0x{local_id:x} (cross-scope ID 0x{:08x})",
+                id.0
+            );
+        };
+
+        Ok(CrossScopeTarget {
+            module_index,
+            local_id,
+            global_id,
+        })
+    }
+
+    /// Gets the contents of the `CROSSSCOPEEXPORTS` subsection of a module. If the module has
+    /// no such subsection, this returns an empty slice.
+    pub fn module_exports(&mut self, module_index: usize) -> anyhow::Result<&[CrossScopeExport]> {
+        if !self.exports.contains_key(&module_index) {
+            let Some(module) = self.pdb.modules()?.iter().nth(module_index) else {
+                bail!("Module index {module_index} is out of range");
+            };
+
+            let mut exports = Vec::new();
+            if let Some(module_stream) = self.pdb.read_module_stream(&module)? {
+                let line_data = module_stream.c13_line_data();
+                for subsection in line_data.subsections() {
+                    if subsection.kind == SubsectionKind::CROSSSCOPEEXPORTS {
+                        let subsection = CrossScopeExportsSubsection::parse(subsection.data)
+                            .context("failed to decode CROSSSCOPEEXPORTS subsection")?;
+                        exports.extend(subsection.entries.iter().cloned());
+                    }
+                }
+            }
+            self.exports.insert(module_index, exports);
+        }
+
+        Ok(&self.exports[&module_index])
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,77 @@
+use super::*;
+use crate::lines::testing::u32s;
+
+#[test]
+fn cross_scope_id() {
+    let id = CrossScopeId::new(3, 0x12345);
+    assert_eq!(id.0, 0x8031_2345);
+    assert_eq!(CrossScopeId::from_item_id(id.0), Some(id));
+    assert_eq!(id.import_index(), 3);
+    assert_eq!(id.id_index(), 0x12345);
+    assert_eq!(CrossScopeId::from_item_id(0x1000), None);
+}
+
+#[test]
+fn cross_scope_id_round_trip() {
+    let max_import = CrossScopeId::MAX_IMPORT_INDEX;
+    let max_id = CrossScopeId::MAX_ID_INDEX;
+    for (import_index, id_index) in [(0, 0), (max_import, 0), (0, max_id), (max_import, max_id)] {
+        let id = CrossScopeId::new(import_index, id_index);
+        let id = CrossScopeId::from_item_id(id.0).unwrap();
+        assert_eq!(id.import_index(), import_index);
+        assert_eq!(id.id_index(), id_index);
+    }
+    assert_eq!(CrossScopeId::new(max_import, max_id).0, 0xffff_ffff);
+}
+
+#[test]
+#[should_panic]
+fn cross_scope_id_import_index_too_large() {
+    CrossScopeId::new(CrossScopeId::MAX_IMPORT_INDEX + 1, 0);
+}
+
+#[test]
+#[should_panic]
+fn cross_scope_id_id_index_too_large() {
+    CrossScopeId::new(0, CrossScopeId::MAX_ID_INDEX + 1);
+}
+
+#[test]
+fn parse_imports() {
+    #[rustfmt::skip]
+    let bytes = u32s(&[
+        0x10, 2, 0x1000, 0x1004, // first entry
+        0x20, 0, // second entry
+        0x30, 1, 0x1008, // third entry
+    ]);
+    let imports = CrossScopeImportsSubsection::parse(&bytes).unwrap();
+    assert_eq!(imports.iter().count(), 3);
+    assert_eq!(imports.get(1).unwrap().module_name(), NameIndex(0x20));
+    assert!(imports.get(1).unwrap().local_ids.is_empty());
+
+    assert_eq!(
+        imports.lookup(CrossScopeId::new(0, 1)),
+        Some((NameIndex(0x10), 0x1004))
+    );
+    assert_eq!(
+        imports.lookup(CrossScopeId::new(2, 0)),
+        Some((NameIndex(0x30), 0x1008))
+    );
+    assert_eq!(imports.lookup(CrossScopeId::new(1, 0)), None);
+    assert_eq!(imports.lookup(CrossScopeId::new(3, 0)), None);
+
+    // Count is larger than the data
+    assert!(CrossScopeImportsSubsection::parse(&u32s(&[0x10, 3, 0x1000])).is_err());
+    assert!(CrossScopeImportsSubsection::parse(&u32s(&[0x10])).is_err());
+}
+
+#[test]
+fn parse_exports() {
+    let bytes = u32s(&[0x1000, 0x1500, 0x1004, 0x1501]);
+    let exports = CrossScopeExportsSubsection::parse(&bytes).unwrap();
+    assert_eq!(exports.entries.len(), 2);
+    assert_eq!(exports.find(0x1004), Some(0x1501));
+    assert_eq!(exports.find(0x1008), None);
+
+    assert!(CrossScopeExportsSubsection::parse(&bytes[..12]).is_err());
+}
@@ -0,0 +1,57 @@
+//! Decodes the `FRAMEDATA` subsection, which describes the stack frames of the 32-bit x86
+//! functions in a module.
+
+use super::*;
+use crate::fpo::FrameData;
+
+/// Decodes a `FRAMEDATA` subsection.
+///
+/// The subsection may begin with a 32-bit value that the linker uses for relocating the RVAs in
+/// the records. This is followed by an array of [`FrameData`] records.
+pub struct FrameDataSubsection<'a> {
+    /// The relocation value, if present.
+    pub reloc_ptr: Option<u32>,
+    /// The frame records.
+    pub frames: &'a [FrameData],
+}
+
+impl<'a> FrameDataSubsection<'a> {
+    /// Parses the subsection. The relocation value is present if the size of `bytes` is not a
+    /// multiple of the size of `FrameData`.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ParserError> {
+        let mut p = Parser::new(bytes);
+        let reloc_ptr = if !bytes.len().is_multiple_of(size_of::<FrameData>()) {
+            Some(p.u32()?)
+        } else {
+            None
+        };
+        let Ok(frames) = <[FrameData]>::ref_from_bytes(p.into_rest()) else {
+            return Err(ParserError::new());
+        };
+        Ok(Self { reloc_ptr, frames })
+    }
+}
+
+#[test]
+fn parse_frame_data() {
+    use zerocopy::FromZeros;
+
+    let mut frame = FrameData::new_zeroed();
+    frame.rva_start = U32::new(0x1000);
+    frame.code_size = U32::new(0x20);
+
+    let mut bytes = 0x400u32.to_le_bytes().to_vec();
+    bytes.extend_from_slice(frame.as_bytes());
+    bytes.extend_from_slice(frame.as_bytes());
+
+    let subsection = FrameDataSubsection::parse(&bytes).unwrap();
+    assert_eq!(subsection.reloc_ptr, Some(0x400));
+    assert_eq!(subsection.frames.len(), 2);
+    assert_eq!(subsection.frames[1].rva_start.get(), 0x1000);
+
+    let subsection = FrameDataSubsection::parse(&bytes[4..]).unwrap();
+    assert_eq!(subsection.reloc_ptr, None);
+    assert_eq!(subsection.frames.len(), 2);
+
+    assert!(FrameDataSubsection::parse(&bytes[..10]).is_err());
+}
@@ -0,0 +1,357 @@
+//! Decodes the `INLINEELINES` subsection, which gives the source location of each function that
+//! has been inlined into the code of a module.
+//!
+//! Line numbers in the binary annotations of `S_INLINESITE` symbols are relative to the starting
+//! line of the inlinee. [`InlineeLineMap`] combines this subsection with those annotations to
+//! produce absolute source lines for inlined frames.
+
+use super::*;
+use crate::syms::{
+    InlineLineRow, InlineLinesIter, InlineSite, InlineSite2, ProcFixed, SymIter, SymKind,
+};
+use ms_codeview::types::ItemId;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// Signature of an `INLINEELINES` subsection whose entries have no extra files.
+pub const CV_INLINEE_SOURCE_LINE_SIGNATURE: u32 = 0;
+/// Signature of an `INLINEELINES` subsection whose entries have a list of extra files.
+pub const CV_INLINEE_SOURCE_LINE_SIGNATURE_EX: u32 = 1;
+
+/// The fixed-size part of one entry in an `INLINEELINES` subsection.
+///
+/// See `cvinfo.h`, `CodeViewInfo::InlineeSourceLine`.
+#[derive(IntoBytes, FromBytes, KnownLayout, Immutable, Unaligned, Clone, Debug)]
+#[repr(C)]
+pub struct InlineeSourceLine {
+    /// The `ItemId` (in the IPI) of the `LF_FUNC_ID` or `LF_MFUNC_ID` record of the inlinee.
+    pub inlinee: U32<LE>,
+    /// The byte offset of the file's record within the `FILE_CHECKSUMS` subsection.
+    pub file_id: U32<LE>,
+    /// The line number of the start of the inlinee.
+    pub source_line_num: U32<LE>,
+}
+
+/// One entry in an `INLINEELINES` subsection.
+#[derive(Clone)]
+pub struct InlineeLine<'a> {
+    /// The fixed-size header.
+    pub header: &'a InlineeSourceLine,
+    /// Additional files that contribute code to the inlinee, as byte offsets within the
+    /// `FILE_CHECKSUMS` subsection. This is empty unless the subsection uses the
+    /// `CV_INLINEE_SOURCE_LINE_SIGNATURE_EX` signature.
+    pub extra_files: &'a [U32<LE>],
+}
+
+impl<'a> InlineeLine<'a> {
+    /// The `ItemId` of the inlinee.
+    pub fn inlinee(&self) -> ItemId {
+        self.header.inlinee.get()
+    }
+
+    /// The byte offset of the file's record within the `FILE_CHECKSUMS` subsection.
+    pub fn file_id(&self) -> u32 {
+        self.header.file_id.get()
+    }
+
+    /// The line number of the start of the inlinee.
+    pub fn source_line(&self) -> u32 {
+        self.header.source_line_num.get()
+    }
+
+    /// Starts evaluating the binary annotations of an inline site of this inlinee. The rows
+    /// contain absolute line numbers.
+    pub fn iter_lines<'b>(&self, binary_annotations: &'b [u8]) -> InlineLinesIter<'b> {
+        InlineLinesIter::new(binary_annotations, self.file_id(), self.source_line())
+    }
+}
+
+/// Decodes an `INLINEELINES` subsection.
+///
+/// `parse` validates the entire subsection, so iterating the entries cannot fail.
+#[derive(Clone)]
+pub struct InlineeLinesSubsection<'a> {
+    /// Either `CV_INLINEE_SOURCE_LINE_SIGNATURE` or `CV_INLINEE_SOURCE_LINE_SIGNATURE_EX`.
+    pub signature: u32,
+    /// The encoded entries, following the signature.
+    pub entries_data: &'a [u8],
+}
+
+impl<'a> InlineeLinesSubsection<'a> {
+    /// Parses the signature and checks that the entries are well-formed.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ParserError> {
+        let mut p = Parser::new(bytes);
+        let signature = p.u32()?;
+        let ex = match signature {
+            CV_INLINEE_SOURCE_LINE_SIGNATURE => false,
+            CV_INLINEE_SOURCE_LINE_SIGNATURE_EX => true,
+            _ => return Err(ParserError::new()),
+        };
+        let entries_data = p.into_rest();
+
+        let mut p = Parser::new(entries_data);
+        while !p.is_empty() {
+            read_entry(&mut p, ex)?;
+        }
+
+        Ok(Self {
+            signature,
+            entries_data,
+        })
+    }
+
+    /// Indicates whether entries in this subsection have a list of extra files.
+    pub fn has_extra_files(&self) -> bool {
+        self.signature == CV_INLINEE_SOURCE_LINE_SIGNATURE_EX
+    }
+
+    /// Iterates the entries.
+    pub fn iter(&self) -> IterInlineeLines<'a> {
+        IterInlineeLines {
+            p: Parser::new(self.entries_data),
+            ex: self.has_extra_files(),
+        }
+    }
+
+    /// Finds the entry for `inlinee`.
+    pub fn find(&self, inlinee: ItemId) -> Option<InlineeLine<'a>> {
+        self.iter().find(|e| e.inlinee() == inlinee)
+    }
+}
+
+fn read_entry<'a>(p: &mut Parser<'a>, ex: bool) -> Result<InlineeLine<'a>, ParserError> {
+    let header: &InlineeSourceLine = p.get()?;
+    let extra_files: &[U32<LE>] = if ex {
+        let num_extra_files = p.u32()?;
+        p.slice(num_extra_files as usize)?
+    } else {
+        &[]
+    };
+    Ok(InlineeLine {
+        header,
+        extra_files,
+    })
+}
+
+/// Iterates the entries of an [`InlineeLinesSubsection`].
+pub struct IterInlineeLines<'a> {
+    p: Parser<'a>,
+    ex: bool,
+}
+
+impl<'a> Iterator for IterInlineeLines<'a> {
+    type Item = InlineeLine<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.p.is_empty() {
+            return None;
+        }
+        match read_entry(&mut self.p, self.ex) {
+            Ok(entry) => Some(entry),
+            Err(_) => {
+                warn!("failed to decode INLINEELINES entry");
+                self.p = Parser::new(&[]);
+                None
+            }
+        }
+    }
+}
+
+/// Decodes an `INLINEELINES` subsection, with mutable access.
+pub struct InlineeLinesSubsectionMut<'a> {
+    signature: u32,
+    entries_data: &'a mut [u8],
+}
+
+impl<'a> InlineeLinesSubsectionMut<'a> {
+    /// Parses the signature and checks that the entries are well-formed.
+    pub fn parse(bytes: &'a mut [u8]) -> Result<Self, ParserError> {
+        let signature = InlineeLinesSubsection::parse(bytes)?.signature;
+        Ok(Self {
+            signature,
+            entries_data: &mut bytes[4..],
+        })
+    }
+
+    /// Iterates the fixed-size headers of the entries, with mutable access.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut InlineeSourceLine> + '_ {
+        let ex = self.signature == CV_INLINEE_SOURCE_LINE_SIGNATURE_EX;
+        let mut p = ParserMut::new(self.entries_data);
+        std::iter::from_fn(move || {
+            if p.is_empty() {
+                return None;
+            }
+            // The contents were validated by parse(), so this cannot fail.
+            let header: &mut InlineeSourceLine = p.get_mut().ok()?;
+            if ex {
+                let num_extra_files = p.u32().ok()?;
+                p.skip(num_extra_files as usize * 4).ok()?;
+            }
+            Some(header)
+        })
+    }
+}
+
+/// Maps inlinees to their entries in the `INLINEELINES` subsections of a module.
+pub struct InlineeLineMap<'a> {
+    entries: HashMap<ItemId, InlineeLine<'a>>,
+    checksums: Option<FileChecksumsSubsection<'a>>,
+}
+
+impl<'a> InlineeLineMap<'a> {
+    /// Builds the map from all of the `INLINEELINES` subsections in `line_data`. If more than one
+    /// entry has the same inlinee, then the first one is used.
+    pub fn new(line_data: &LineData<'a>) -> anyhow::Result<Self> {
+        let mut entries = HashMap::new();
+        for subsection in line_data.subsections() {
+            if subsection.kind != SubsectionKind::INLINEELINES {
+                continue;
+            }
+            let inlinee_lines = InlineeLinesSubsection::parse(subsection.data)
+                .context("failed to decode INLINEELINES subsection")?;
+            for entry in inlinee_lines.iter() {
+                if let Entry::Vacant(v) = entries.entry(entry.inlinee()) {
+                    v.insert(entry);
+                }
+            }
+        }
+
+        Ok(Self {
+            entries,
+            checksums: line_data.find_checksums(),
+        })
+    }
+
+    /// The number of inlinees in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Indicates whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the entry for `inlinee`.
+    pub fn get(&self, inlinee: ItemId) -> Option<&InlineeLine<'a>> {
+        self.entries.get(&inlinee)
+    }
+
+    /// Gets the file checksum record of the file that contains the start of `inlinee`.
+    pub fn file(&self, inlinee: ItemId) -> anyhow::Result<FileChecksum<'a>> {
+        let Some(entry) = self.get(inlinee) else {
+            bail!("inlinee 0x{inlinee:x} is not in the INLINEELINES subsections");
+        };
+        let Some(checksums) = &self.checksums else {
+            return Err(super::missing_checksums(SubsectionKind::INLINEELINES));
+        };
+        checksums.get_file(entry.file_id())
+    }
+
+    /// Starts evaluating the binary annotations of an inline site of `inlinee`. The rows contain
+    /// absolute line numbers. Returns `None` if `inlinee` is not in the map.
+    pub fn iter_site_lines<'b>(
+        &self,
+        inlinee: ItemId,
+        binary_annotations: &'b [u8],
+    ) -> Option<InlineLinesIter<'b>> {
+        Some(self.get(inlinee)?.iter_lines(binary_annotations))
+    }
+
+    /// Finds the inline sites (`S_INLINESITE` and `S_INLINESITE2`) in the symbols of a module and
+    /// decodes their line information.
+    ///
+    /// The code ranges of the rows are converted from offsets within the parent procedure to
+    /// offsets within the procedure's section. Inline sites whose inlinee is not in the map, or
+    /// which are not within a procedure, are skipped.
+    pub fn inline_frames(&self, syms: SymIter<'_>) -> anyhow::Result<Vec<InlineFrame>> {
+        struct Scope {
+            is_inline_site: bool,
+            /// Segment and offset of the procedure that contains this scope.
+            proc: Option<(u16, u32)>,
+        }
+
+        let mut scopes: Vec<Scope> = Vec::new();
+        let mut frames = Vec::new();
+
+        for sym in syms {
+            if sym.kind.ends_scope() {
+                if scopes.pop().is_none() {
+                    warn!("found unbalanced end of scope symbol");
+                }
+                continue;
+            }
+
+            if !sym.kind.starts_scope() {
+                continue;
+            }
+
+            let enclosing_proc = scopes.last().and_then(|s| s.proc);
+
+            let site = match sym.kind {
+                SymKind::S_INLINESITE => {
+                    let site: InlineSite = sym.parse_as()?;
+                    Some((site.fixed.inlinee.get(), site.binary_annotations))
+                }
+                SymKind::S_INLINESITE2 => {
+                    let site: InlineSite2 = sym.parse_as()?;
+                    Some((site.fixed.inlinee.get(), site.binary_annotations))
+                }
+                _ => None,
+            };
+
+            let proc = if sym.kind.is_proc() {
+                let fixed: &ProcFixed = Parser::new(sym.data).get()?;
+                Some((
+                    fixed.offset_segment.segment.get(),
+                    fixed.offset_segment.offset.get(),
+                ))
+            } else {
+                enclosing_proc
+            };
+
+            if let (Some((inlinee, annotations)), Some((segment, proc_offset))) = (site, proc) {
+                if let Some(lines) = self.iter_site_lines(inlinee, annotations) {
+                    let rows = lines
+                        .map(|mut row| {
+                            row.code_offset_range = row.code_offset_range.start + proc_offset
+                                ..row.code_offset_range.end + proc_offset;
+                            row
+                        })
+                        .collect();
+                    frames.push(InlineFrame {
+                        inlinee,
+                        depth: scopes.iter().filter(|s| s.is_inline_site).count() as u32,
+                        segment,
+                        rows,
+                    });
+                }
+            }
+
+            scopes.push(Scope {
+                is_inline_site: site.is_some(),
+                proc,
+            });
+        }
+
+        Ok(frames)
+    }
+}
+
+/// The line information of one inline site, produced by [`InlineeLineMap::inline_frames`].
+#[derive(Clone, Debug)]
+pub struct InlineFrame {
+    /// The `ItemId` of the inlinee.
+    pub inlinee: ItemId,
+    /// The number of inline sites that contain this inline site. This is 0 for functions that
+    /// were inlined directly into a procedure.
+    pub depth: u32,
+    /// The section that contains the code of the parent procedure.
+    pub segment: u16,
+    /// The line rows of the inline site. The code ranges are offsets within `segment` and the
+    /// line numbers are absolute.
+    pub rows: Vec<InlineLineRow>,
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,160 @@
+use super::*;
+use crate::lines::testing::{subsection, u32s};
+use crate::syms::BinaryAnnotation;
+use crate::syms::builder::SymBuilder;
+use zerocopy::FromZeros;
+
+#[test]
+fn parse_plain() {
+    #[rustfmt::skip]
+    let bytes = u32s(&[
+        CV_INLINEE_SOURCE_LINE_SIGNATURE,
+        0x1000, 0x18, 10, // first entry
+        0x1001, 0x30, 20, // second entry
+    ]);
+    let inlinee_lines = InlineeLinesSubsection::parse(&bytes).unwrap();
+    assert!(!inlinee_lines.has_extra_files());
+
+    let entries: Vec<(ItemId, u32, u32)> = inlinee_lines
+        .iter()
+        .map(|e| (e.inlinee(), e.file_id(), e.source_line()))
+        .collect();
+    assert_eq!(entries, [(0x1000, 0x18, 10), (0x1001, 0x30, 20)]);
+
+    let e = inlinee_lines.find(0x1001).unwrap();
+    assert_eq!(e.source_line(), 20);
+    assert!(e.extra_files.is_empty());
+    assert!(inlinee_lines.find(0x1002).is_none());
+}
+
+#[test]
+fn parse_extra_files() {
+    #[rustfmt::skip]
+    let bytes = u32s(&[
+        CV_INLINEE_SOURCE_LINE_SIGNATURE_EX,
+        0x1000, 0x18, 10, 2, 0x30, 0x48, // two extra files
+        0x1001, 0x30, 20, 0, // no extra files
+    ]);
+    let inlinee_lines = InlineeLinesSubsection::parse(&bytes).unwrap();
+    assert!(inlinee_lines.has_extra_files());
+
+    let entries: Vec<InlineeLine> = inlinee_lines.iter().collect();
+    assert_eq!(entries.len(), 2);
+    let extra: Vec<u32> = entries[0].extra_files.iter().map(|f| f.get()).collect();
+    assert_eq!(extra, [0x30, 0x48]);
+    assert_eq!(entries[1].inlinee(), 0x1001);
+    assert!(entries[1].extra_files.is_empty());
+}
+
+#[test]
+fn parse_malformed() {
+    // Unrecognized signature
+    assert!(InlineeLinesSubsection::parse(&u32s(&[2, 0x1000, 0, 10])).is_err());
+    // Truncated entry
+    assert!(InlineeLinesSubsection::parse(&u32s(&[0, 0x1000, 0])).is_err());
+    // Too many extra files
+    assert!(InlineeLinesSubsection::parse(&u32s(&[1, 0x1000, 0, 10, 3, 0])).is_err());
+    // Empty subsection is fine
+    assert_eq!(
+        InlineeLinesSubsection::parse(&u32s(&[1]))
+            .unwrap()
+            .iter()
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn remap_inlinees() {
+    #[rustfmt::skip]
+    let mut bytes = u32s(&[
+        CV_INLINEE_SOURCE_LINE_SIGNATURE_EX,
+        0x1000, 0x18, 10, 1, 0x30, //
+        0x1001, 0x30, 20, 0,
+    ]);
+    let mut inlinee_lines = InlineeLinesSubsectionMut::parse(&mut bytes).unwrap();
+    for e in inlinee_lines.iter_mut() {
+        e.inlinee = U32::new(e.inlinee.get() + 0x100);
+    }
+
+    let inlinee_lines = InlineeLinesSubsection::parse(&bytes).unwrap();
+    let ids: Vec<ItemId> = inlinee_lines.iter().map(|e| e.inlinee()).collect();
+    assert_eq!(ids, [0x1100, 0x1101]);
+}
+
+#[test]
+fn inline_frames() {
+    let mut line_data = Vec::new();
+    subsection(
+        &mut line_data,
+        SubsectionKind::INLINEELINES,
+        &u32s(&[
+            CV_INLINEE_SOURCE_LINE_SIGNATURE,
+            0x1000,
+            0,
+            100,
+            0x1001,
+            0,
+            200,
+        ]),
+    );
+    subsection(
+        &mut line_data,
+        SubsectionKind::INLINEELINES,
+        &u32s(&[CV_INLINEE_SOURCE_LINE_SIGNATURE_EX, 0x1002, 0x18, 300, 0]),
+    );
+    let line_data = LineData::new(&line_data);
+    assert_eq!(line_data.inlinee_lines().count(), 2);
+
+    let map = InlineeLineMap::new(&line_data).unwrap();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.get(0x1002).unwrap().file_id(), 0x18);
+
+    let mut b = SymBuilder::new();
+    let mut proc = ProcFixed::new_zeroed();
+    proc.offset_segment.offset = U32::new(0x400);
+    proc.offset_segment.segment = U16::new(1);
+    b.begin_proc(SymKind::S_GPROC32_ID, &proc, "f".into());
+    b.begin_inline_site(
+        0x1000,
+        &[
+            BinaryAnnotation::ChangeLineOffset(2),
+            BinaryAnnotation::CodeOffset(0x10),
+            BinaryAnnotation::ChangeCodeLength(8),
+        ],
+    )
+    .unwrap();
+    b.begin_inline_site(
+        0x1002,
+        &[
+            BinaryAnnotation::ChangeCodeOffset(0x12),
+            BinaryAnnotation::ChangeCodeLength(4),
+        ],
+    )
+    .unwrap();
+    b.end_scope();
+    b.end_scope();
+    // This inlinee is not in the map.
+    b.begin_inline_site(0x2000, &[BinaryAnnotation::ChangeCodeOffset(0x20)])
+        .unwrap();
+    b.end_scope();
+    b.end_scope();
+    let syms = b.finish();
+
+    let frames = map.inline_frames(SymIter::new(&syms)).unwrap();
+    assert_eq!(frames.len(), 2);
+
+    assert_eq!(frames[0].inlinee, 0x1000);
+    assert_eq!(frames[0].depth, 0);
+    assert_eq!(frames[0].segment, 1);
+    assert_eq!(frames[0].rows.len(), 1);
+    assert_eq!(frames[0].rows[0].code_offset_range, 0x410..0x418);
+    assert_eq!(frames[0].rows[0].line, 102);
+    assert_eq!(frames[0].rows[0].file_id, 0);
+
+    assert_eq!(frames[1].inlinee, 0x1002);
+    assert_eq!(frames[1].depth, 1);
+    assert_eq!(frames[1].rows[0].code_offset_range, 0x412..0x416);
+    assert_eq!(frames[1].rows[0].line, 300);
+    assert_eq!(frames[1].rows[0].file_id, 0x18);
+}
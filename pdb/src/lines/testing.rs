@@ -0,0 +1,26 @@
+//! Helpers for building C13 Line Data in tests.
+
+use super::SubsectionKind;
+
+/// Encodes `values` as little-endian `u32` values.
+pub(crate) fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Wraps `data` in a subsection header and appends it to `out`.
+pub(crate) fn subsection(out: &mut Vec<u8>, kind: SubsectionKind, data: &[u8]) {
+    out.extend_from_slice(&kind.0.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Appends a `FILE_CHECKSUMS` subsection that contains one entry (without a checksum) for each
+/// of `names`. The file ID of the entry for `names[i]` is `i * 8`.
+pub(crate) fn checksums(out: &mut Vec<u8>, names: &[u32]) {
+    let mut data = Vec::new();
+    for name in names {
+        data.extend_from_slice(&name.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0]); // checksum_size, checksum_kind, padding
+    }
+    subsection(out, SubsectionKind::FILE_CHECKSUMS, &data);
+}
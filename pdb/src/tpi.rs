@@ -36,7 +36,26 @@
 //!   The offset and size of the Type Index Offset BUffer is specified in the `TypeStreamHeader`,
 //!   in the `index_offset_buffer_offset` and `index_offset_buffer_length` fields, respectively.
 
+mod builder;
+pub mod compact;
+pub mod diff;
+mod format;
 pub mod hash;
+mod header;
+pub mod layout;
+pub mod merge;
+#[cfg(test)]
+mod testing;
+mod udt_index;
+
+pub use builder::{DEFAULT_NUM_HASH_BUCKETS, TypeStreamBuilder};
+pub use diff::TypeDiff;
+pub use format::TypeFormatter;
+pub use hash::TypeHashStream;
+pub use header::HeaderGenerator;
+pub use layout::TypeLayout;
+pub use merge::{TypeIndexRemap, TypeMerger};
+pub use udt_index::UdtIndex;
 
 use super::*;
 use crate::types::fields::{Field, IterFields};
@@ -481,6 +500,19 @@ impl<F: ReadAt> crate::Pdb<F> {
         let stream_data = self.read_stream_to_vec(stream_index.into())?;
         TypeStream::parse(stream_index, stream_data)
     }
+
+    /// Reads the Type Hash Stream that is associated with `type_stream`, if it has one.
+    pub fn read_type_hash_stream<StreamData: AsRef<[u8]>>(
+        &self,
+        type_stream: &TypeStream<StreamData>,
+    ) -> anyhow::Result<Option<TypeHashStream<Vec<u8>>>> {
+        let (Some(header), Some(hash_stream)) = (type_stream.header(), type_stream.hash_stream())
+        else {
+            return Ok(None);
+        };
+        let stream_data = self.read_stream_to_vec(hash_stream)?;
+        Ok(Some(TypeHashStream::parse(header, stream_data)?))
+    }
 }
 
 /// Maps `TypeIndex` values to the byte range of records within a type stream.
@@ -0,0 +1,256 @@
+//! Builds new Type Streams (TPI or IPI) and their Type Hash Streams.
+
+use super::hash::{HashAdjuster, hash_type_record};
+use super::{TYPE_STREAM_VERSION_2004, TypeStreamHeader};
+use crate::StreamIndexU16;
+use crate::names::NameIndex;
+use crate::pdbi::HashEntry;
+use crate::types::{Leaf, TypeIndex};
+use anyhow::bail;
+use bitvec::prelude::{BitSlice, Lsb0};
+use ms_codeview::encoder::Encoder;
+use zerocopy::{I32, IntoBytes, U32};
+
+/// The number of hash buckets that MSVC uses for the TPI and IPI streams.
+pub const DEFAULT_NUM_HASH_BUCKETS: u32 = 0x3ffff;
+
+/// The Type Index Offset Buffer contains an entry for (roughly) every 8 KiB of type records.
+const INDEX_OFFSET_CHUNK_SIZE: u32 = 8 << 10;
+
+/// Builds a new Type Stream (TPI or IPI) and its Type Hash Stream from a list of type records.
+///
+/// Type indexes are assigned to records in the order that they are added, starting at
+/// [`TypeIndex::MIN_BEGIN`]. The hash value of each record is computed when it is added.
+///
+/// ```ignore
+/// let mut builder = TypeStreamBuilder::new();
+/// let ti = builder.add_record(Leaf::LF_POINTER, &payload)?;
+/// let hash_stream = msf.new_stream_data(&builder.encode_hash_stream())?;
+/// let type_stream = builder.encode_type_stream(Some(hash_stream))?;
+/// ```
+pub struct TypeStreamBuilder {
+    num_hash_buckets: u32,
+
+    /// The encoded type records, including their length prefix.
+    records: Vec<u8>,
+    num_types: u32,
+
+    /// The hash value of each type record, already reduced modulo `num_hash_buckets`.
+    hash_values: Vec<u32>,
+    index_offsets: Vec<(TypeIndex, u32)>,
+    hash_adjusters: Vec<HashAdjuster>,
+}
+
+impl Default for TypeStreamBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeStreamBuilder {
+    /// Starts building a new Type Stream, using the default number of hash buckets.
+    pub fn new() -> Self {
+        Self::with_num_hash_buckets(DEFAULT_NUM_HASH_BUCKETS)
+    }
+
+    /// Starts building a new Type Stream, using a specific number of hash buckets.
+    pub fn with_num_hash_buckets(num_hash_buckets: u32) -> Self {
+        assert!(num_hash_buckets > 0);
+        Self {
+            num_hash_buckets,
+            records: Vec::new(),
+            num_types: 0,
+            hash_values: Vec::new(),
+            index_offsets: Vec::new(),
+            hash_adjusters: Vec::new(),
+        }
+    }
+
+    /// The number of hash buckets.
+    pub fn num_hash_buckets(&self) -> u32 {
+        self.num_hash_buckets
+    }
+
+    /// The number of type records that have been added.
+    pub fn num_types(&self) -> u32 {
+        self.num_types
+    }
+
+    /// The type index that will be assigned to the next record.
+    pub fn next_type_index(&self) -> TypeIndex {
+        TypeIndex(TypeIndex::MIN_BEGIN.0 + self.num_types)
+    }
+
+    /// The encoded type records that have been added.
+    pub fn type_records_bytes(&self) -> &[u8] {
+        &self.records
+    }
+
+    /// Adds a type record, given its kind and its payload. The payload does not include the
+    /// record length or the record kind. If the payload is not a multiple of 4 bytes in length,
+    /// then padding bytes (`LF_PAD1`, etc.) are added.
+    pub fn add_record(&mut self, kind: Leaf, payload: &[u8]) -> anyhow::Result<TypeIndex> {
+        let padding = (4 - (payload.len() + 4) % 4) % 4;
+        let record_len = 2 + payload.len() + padding;
+        if record_len > 0xffff {
+            bail!("The type record is too large ({record_len} bytes)");
+        }
+
+        let record_start = self.records.len();
+        self.records
+            .extend_from_slice(&(record_len as u16).to_le_bytes());
+        self.records.extend_from_slice(&kind.0.to_le_bytes());
+        self.records.extend_from_slice(payload);
+        for i in (1..=padding).rev() {
+            self.records.push(0xf0 | i as u8);
+        }
+
+        self.push_record(record_start)
+    }
+
+    /// Adds a type record that has already been encoded. `record` contains the record length,
+    /// the record kind, and the payload. The length of `record` must be a multiple of 4.
+    pub fn add_record_bytes(&mut self, record: &[u8]) -> anyhow::Result<TypeIndex> {
+        if record.len() < 4 || !record.len().is_multiple_of(4) {
+            bail!(
+                "The type record has an invalid length ({} bytes)",
+                record.len()
+            );
+        }
+        let record_len = u16::from_le_bytes([record[0], record[1]]) as usize;
+        if record_len + 2 != record.len() {
+            bail!("The type record length field does not match the length of the record");
+        }
+
+        let record_start = self.records.len();
+        self.records.extend_from_slice(record);
+        self.push_record(record_start)
+    }
+
+    /// Adds the hash value and (if needed) an index offset for the record that was just appended
+    /// to `records`.
+    fn push_record(&mut self, record_start: usize) -> anyhow::Result<TypeIndex> {
+        let record_bytes = &self.records[record_start..];
+        let kind = Leaf(u16::from_le_bytes([record_bytes[2], record_bytes[3]]));
+        let hash = match hash_type_record(kind, record_bytes, &record_bytes[4..]) {
+            Ok(hash) => hash,
+            Err(e) => {
+                self.records.truncate(record_start);
+                bail!("Failed to decode type record (kind {kind:?}): {e:?}");
+            }
+        };
+
+        // Add an index offset whenever the type records cross an 8 KiB boundary.
+        let type_index = self.next_type_index();
+        let start = record_start as u32;
+        let end = self.records.len() as u32;
+        if self.num_types == 0 || end / INDEX_OFFSET_CHUNK_SIZE > start / INDEX_OFFSET_CHUNK_SIZE {
+            self.index_offsets.push((type_index, start));
+        }
+
+        self.hash_values.push(hash % self.num_hash_buckets);
+        self.num_types += 1;
+        Ok(type_index)
+    }
+
+    /// Adds an entry to the Hash Adjusters table. This selects `type_index` as the record that
+    /// is found by a lookup of the name `name`, when more than one record has that name.
+    pub fn add_hash_adjuster(&mut self, name: NameIndex, type_index: TypeIndex) {
+        self.hash_adjusters.push(HashAdjuster { name, type_index });
+    }
+
+    /// Builds the `TypeStreamHeader` for the new stream.
+    fn header(&self, hash_stream: Option<u32>) -> anyhow::Result<TypeStreamHeader> {
+        let hash_values_len = self.hash_values.len() * 4;
+        let index_offsets_len = self.index_offsets.len() * 8;
+
+        let mut header = TypeStreamHeader::empty();
+        header.version = U32::new(TYPE_STREAM_VERSION_2004);
+        header.type_index_end = self.next_type_index().into();
+        header.type_record_bytes = U32::new(self.records.len() as u32);
+        header.hash_stream_index = match hash_stream {
+            Some(s) => StreamIndexU16::try_from(s)?,
+            None => StreamIndexU16::NIL,
+        };
+        header.hash_key_size = U32::new(4);
+        header.num_hash_buckets = U32::new(self.num_hash_buckets);
+        header.hash_value_buffer_offset = I32::new(0);
+        header.hash_value_buffer_length = U32::new(hash_values_len as u32);
+        header.index_offset_buffer_offset = I32::new(hash_values_len as i32);
+        header.index_offset_buffer_length = U32::new(index_offsets_len as u32);
+        header.hash_adj_buffer_offset = I32::new((hash_values_len + index_offsets_len) as i32);
+        header.hash_adj_buffer_length = U32::new(self.encode_hash_adjusters().len() as u32);
+        Ok(header)
+    }
+
+    /// Encodes the Type Stream. `hash_stream` is the stream index of the Type Hash Stream, which
+    /// is stored in the header of the Type Stream.
+    pub fn encode_type_stream(&self, hash_stream: Option<u32>) -> anyhow::Result<Vec<u8>> {
+        let header = self.header(hash_stream)?;
+        let mut stream = Vec::with_capacity(header.as_bytes().len() + self.records.len());
+        stream.extend_from_slice(header.as_bytes());
+        stream.extend_from_slice(&self.records);
+        Ok(stream)
+    }
+
+    /// Encodes the Type Hash Stream. This contains the Hash Value Buffer, the Type Index Offset
+    /// Buffer, and the Hash Adjusters table, in that order.
+    pub fn encode_hash_stream(&self) -> Vec<u8> {
+        let mut stream = Vec::new();
+        let mut e = Encoder::new(&mut stream);
+        for &h in self.hash_values.iter() {
+            e.u32(h);
+        }
+        for &(type_index, offset) in self.index_offsets.iter() {
+            e.u32(type_index.0);
+            e.u32(offset);
+        }
+        e.bytes(&self.encode_hash_adjusters());
+        stream
+    }
+
+    /// Encodes the Hash Adjusters table, using the serialized hash table format of PDBI. This
+    /// table uses linear probing and the key (a `NameIndex`) is its own hash.
+    fn encode_hash_adjusters(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        if self.hash_adjusters.is_empty() {
+            return out;
+        }
+
+        let hash_size = self.hash_adjusters.len() * 2;
+        let mut hash_slots: Vec<Option<HashAdjuster>> = vec![None; hash_size];
+        for adj in self.hash_adjusters.iter() {
+            let mut slot = adj.name.0 as usize % hash_size;
+            while hash_slots[slot].is_some() {
+                slot = (slot + 1) % hash_size;
+            }
+            hash_slots[slot] = Some(*adj);
+        }
+
+        let bitmap_size_u32s = hash_size.div_ceil(32);
+        let mut present_bitmap_bytes: Vec<u8> = vec![0; bitmap_size_u32s * 4];
+        let present_bitmap: &mut BitSlice<u8, Lsb0> =
+            BitSlice::from_slice_mut(present_bitmap_bytes.as_mut_slice());
+        for (i, slot) in hash_slots.iter().enumerate() {
+            present_bitmap.set(i, slot.is_some());
+        }
+
+        let mut e = Encoder::new(&mut out);
+        e.u32(self.hash_adjusters.len() as u32);
+        e.u32(hash_size as u32);
+        e.u32(bitmap_size_u32s as u32);
+        e.bytes(&present_bitmap_bytes);
+        // The "deleted" bitmap is empty.
+        e.u32(0);
+        for adj in hash_slots.iter().flatten() {
+            e.t(&HashEntry {
+                key: U32::new(adj.name.0),
+                value: U32::new(adj.type_index.0),
+            });
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests;
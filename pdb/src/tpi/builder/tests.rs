@@ -0,0 +1,119 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::hash::TypeHashStream;
+use crate::tpi::testing::*;
+use crate::tpi::{TypeStream, UdtIndex};
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+
+#[test]
+fn round_trip() {
+    let mut t = TypeBuilder::new();
+    let fwd = udt(&mut t, Leaf::LF_STRUCTURE, "S", TypeIndex(0), 0, 1 << 7);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "x".into());
+    let fields = t.field_list(&fields);
+    let s = udt(&mut t, Leaf::LF_STRUCTURE, "S", fields, 4, 0);
+    pointer(&mut t, fwd, PTR64);
+    let original = type_stream(Stream::TPI, &t.finish());
+
+    let mut builder = TypeStreamBuilder::with_num_hash_buckets(0x101);
+    for record in original.iter_type_records() {
+        builder.add_record(record.kind, record.data).unwrap();
+    }
+    // Adding a record with an odd-sized payload adds padding.
+    let modifier = builder
+        .add_record(Leaf::LF_MODIFIER, &[0x74, 0, 0, 0, 1, 0])
+        .unwrap();
+    assert_eq!(modifier, TypeIndex(0x1004));
+    builder.add_hash_adjuster(NameIndex(1), s);
+
+    let type_stream = builder.encode_type_stream(Some(9)).unwrap();
+    let tpi = TypeStream::parse(Stream::TPI, type_stream).unwrap();
+    let header = tpi.header().unwrap();
+    assert_eq!(header.version.get(), TYPE_STREAM_VERSION_2004);
+    assert_eq!(tpi.hash_stream(), Some(9));
+    assert_eq!(tpi.num_types(), 5);
+    assert_eq!(
+        &tpi.type_records_bytes()[..original.type_records_bytes().len()],
+        original.type_records_bytes()
+    );
+    let last = tpi.record(modifier).unwrap();
+    assert_eq!(last.kind, Leaf::LF_MODIFIER);
+    assert_eq!(last.data, &[0x74, 0, 0, 0, 1, 0, 0xf2, 0xf1]);
+
+    let hashes = TypeHashStream::parse(header, builder.encode_hash_stream()).unwrap();
+    assert_eq!(hashes.num_hash_buckets(), 0x101);
+    assert_eq!(hashes.hash_values().len(), 5);
+    assert!(hashes.hash_values().iter().all(|h| h.get() < 0x101));
+    // The definition of a UDT is hashed by its name.
+    assert_eq!(
+        hashes.hash_values()[(s.0 - 0x1000) as usize].get(),
+        crate::hash::hash_mod_u32(b"S", 0x101)
+    );
+    assert_eq!(hashes.index_offsets().len(), 1);
+    assert_eq!(
+        hashes.index_offsets()[0].type_index.get(),
+        TypeIndex(0x1000)
+    );
+    assert_eq!(hashes.index_offsets()[0].offset.get(), 0);
+    assert_eq!(
+        hashes.hash_adjusters(),
+        &[HashAdjuster {
+            name: NameIndex(1),
+            type_index: s
+        }]
+    );
+
+    let index = UdtIndex::with_hash_stream(&tpi, &hashes);
+    assert!(index.uses_hash_stream());
+    assert_eq!(index.resolve_forward_ref(fwd), Some(s));
+}
+
+#[test]
+fn index_offsets() {
+    let mut builder = TypeStreamBuilder::new();
+    // Each record is 1000 bytes (including the length field).
+    let payload = [0u8; 994];
+    for _ in 0..20 {
+        builder.add_record(Leaf::LF_VTSHAPE, &payload).unwrap();
+    }
+    assert_eq!(builder.type_records_bytes().len(), 20_000);
+
+    let hash_stream = builder.encode_hash_stream();
+    let header = builder.header(None).unwrap();
+    assert_eq!(header.hash_stream_index.get(), None);
+    let hashes = TypeHashStream::parse(&header, hash_stream).unwrap();
+    let offsets: Vec<(u32, u32)> = hashes
+        .index_offsets()
+        .iter()
+        .map(|p| (p.type_index.get().0, p.offset.get()))
+        .collect();
+    // Records 8 (offset 8000..9000) and 16 (offset 16000..17000) cross 8 KiB boundaries.
+    assert_eq!(offsets, [(0x1000, 0), (0x1008, 8000), (0x1010, 16000)]);
+}
+
+#[test]
+fn add_record_bytes() {
+    let mut builder = TypeStreamBuilder::new();
+    let record = [10, 0, 0x01, 0x10, 0x74, 0, 0, 0, 1, 0, 0xf2, 0xf1];
+    assert_eq!(
+        builder.add_record_bytes(&record).unwrap(),
+        TypeIndex::MIN_BEGIN
+    );
+    assert_eq!(builder.type_records_bytes(), &record);
+
+    // Bad length fields are rejected.
+    assert!(
+        builder
+            .add_record_bytes(&[8, 0, 0x01, 0x10, 0x74, 0, 0, 0])
+            .is_err()
+    );
+    assert!(builder.add_record_bytes(&[2, 0, 0x01]).is_err());
+    // Records that cannot be decoded are rejected.
+    assert!(
+        builder
+            .add_record_bytes(&[6, 0, 0x01, 0x10, 0x74, 0, 0, 0])
+            .is_err()
+    );
+    assert_eq!(builder.num_types(), 1);
+}
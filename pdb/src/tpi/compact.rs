@@ -0,0 +1,707 @@
+//! Removes unused records from the TPI and IPI streams ("dead type elimination").
+//!
+//! Linkers copy every type record from every object file into the PDB, so large PDBs contain
+//! many type records that no symbol refers to. Compaction works in two phases:
+//!
+//! 1. [`LiveTypes`] marks the records that are reachable from a set of roots: the symbols in the
+//!    module streams and the Global Symbol Stream, the `INLINEELINES` subsections of the C13 Line
+//!    Data, ID records, and (optionally) UDTs selected by name.
+//!
+//! 2. [`TypeCompaction`] renumbers the live records, builds new TPI and IPI streams, and rewrites
+//!    the `TypeIndex` and `ItemId` values in symbol records and line data.
+//!
+//! Live records keep their relative order, so records still only refer to records that precede
+//! them.
+//!
+//! [`Pdb::plan_type_compaction`](crate::Pdb::plan_type_compaction) and
+//! [`Pdb::apply_type_compaction`](crate::Pdb::apply_type_compaction) run both phases on a PDB.
+
+use super::hash::TypeHashStream;
+use super::{DEFAULT_NUM_HASH_BUCKETS, TypeStream, TypeStreamBuilder, UdtIndex};
+use crate::dbi::ModuleInfoFixed;
+use crate::lines::{
+    InlineeLinesSubsection, InlineeLinesSubsectionMut, LineData, LineDataMut, SubsectionKind,
+};
+use crate::modi::ModiStreamData;
+use crate::syms::{SymIter, SymIterMut};
+use crate::types::visitor::{
+    IndexVisitor, IndexVisitorMut, visit_type_indexes_in_record_slice,
+    visit_type_indexes_in_record_slice_mut, visit_type_indexes_in_sym_slice,
+    visit_type_indexes_in_sym_slice_mut,
+};
+use crate::types::{ItemId, ItemIdLe, Leaf, TypeIndex, TypeIndexLe};
+use crate::{ReadAt, Stream, WriteAt};
+use anyhow::{Context, bail};
+use bstr::BString;
+use ms_codeview::parser::ParserError;
+use tracing::warn;
+use zerocopy::U32;
+
+/// Options for [`Pdb::plan_type_compaction`](crate::Pdb::plan_type_compaction).
+#[derive(Clone, Debug, Default)]
+pub struct CompactTypesOptions {
+    /// Names of UDTs that are kept even if nothing refers to them.
+    pub root_names: Vec<BString>,
+
+    /// If `false` (the default), every record in the IPI is a root, so the IPI is not compacted
+    /// (but its references to the TPI are still rewritten). If `true`, ID records are removed
+    /// unless a symbol or line data refers to them. `LF_UDT_SRC_LINE` and `LF_UDT_MOD_SRC_LINE`
+    /// records are kept if their UDT is live, together with the `LF_STRING_ID` records of their
+    /// source files.
+    pub strip_ids: bool,
+}
+
+/// Tracks which records of a TPI or IPI stream are live.
+struct LiveSet {
+    type_index_begin: TypeIndex,
+    live: Vec<bool>,
+    /// Records that have been marked, but whose references have not been marked.
+    pending: Vec<TypeIndex>,
+}
+
+impl LiveSet {
+    fn new<StreamData: AsRef<[u8]>>(stream: &TypeStream<StreamData>) -> Self {
+        Self {
+            type_index_begin: stream.type_index_begin(),
+            live: vec![false; stream.num_types() as usize],
+            pending: Vec::new(),
+        }
+    }
+
+    /// Marks a record as live. Primitive types and the nil item ID are ignored.
+    fn mark(&mut self, ti: TypeIndex) -> anyhow::Result<()> {
+        if ti < self.type_index_begin {
+            return Ok(());
+        }
+        let Some(live) = self.live.get_mut((ti.0 - self.type_index_begin.0) as usize) else {
+            bail!("Reference to record 0x{:x}, which is out of range", ti.0);
+        };
+        if !*live {
+            *live = true;
+            self.pending.push(ti);
+        }
+        Ok(())
+    }
+
+    fn is_live(&self, ti: TypeIndex) -> bool {
+        if ti < self.type_index_begin {
+            return true;
+        }
+        self.live
+            .get((ti.0 - self.type_index_begin.0) as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    fn count(&self) -> u32 {
+        self.live.iter().filter(|&&live| live).count() as u32
+    }
+}
+
+/// Collects the `TypeIndex` and `ItemId` values within a record.
+#[derive(Default)]
+struct References {
+    types: Vec<TypeIndex>,
+    items: Vec<ItemId>,
+}
+
+impl References {
+    fn clear(&mut self) {
+        self.types.clear();
+        self.items.clear();
+    }
+}
+
+impl IndexVisitor for &mut References {
+    fn type_index(&mut self, _offset: usize, value: TypeIndex) -> Result<(), ParserError> {
+        self.types.push(value);
+        Ok(())
+    }
+
+    fn item_id(&mut self, _offset: usize, value: ItemId) -> Result<(), ParserError> {
+        self.items.push(value);
+        Ok(())
+    }
+}
+
+/// Finds the records in the TPI and IPI that are reachable from a set of roots.
+///
+/// ```ignore
+/// let mut live = LiveTypes::new(&tpi, &ipi);
+/// live.mark_symbols(module.iter_syms())?;
+/// live.mark_line_data(&module.c13_line_data())?;
+/// live.propagate(&tpi, &ipi, &UdtIndex::new(&tpi))?;
+/// let compaction = live.compact(&tpi, &ipi, None, None)?;
+/// ```
+pub struct LiveTypes {
+    types: LiveSet,
+    items: LiveSet,
+    refs: References,
+}
+
+impl LiveTypes {
+    /// Creates a `LiveTypes` in which no records are live.
+    pub fn new<StreamData: AsRef<[u8]>>(
+        tpi: &TypeStream<StreamData>,
+        ipi: &TypeStream<StreamData>,
+    ) -> Self {
+        Self {
+            types: LiveSet::new(tpi),
+            items: LiveSet::new(ipi),
+            refs: References::default(),
+        }
+    }
+
+    /// Marks a type record (in the TPI) as live.
+    pub fn mark_type(&mut self, ti: TypeIndex) -> anyhow::Result<()> {
+        self.types.mark(ti)
+    }
+
+    /// Marks an ID record (in the IPI) as live.
+    pub fn mark_item(&mut self, id: ItemId) -> anyhow::Result<()> {
+        self.items.mark(TypeIndex(id))
+    }
+
+    /// Marks every ID record as live.
+    pub fn mark_all_items(&mut self) {
+        for i in 0..self.items.live.len() {
+            if !self.items.live[i] {
+                self.items.live[i] = true;
+                self.items
+                    .pending
+                    .push(TypeIndex(self.items.type_index_begin.0 + i as u32));
+            }
+        }
+    }
+
+    /// Marks the records that symbols refer to.
+    pub fn mark_symbols(&mut self, syms: SymIter<'_>) -> anyhow::Result<()> {
+        for sym in syms {
+            self.refs.clear();
+            visit_type_indexes_in_sym_slice(sym.kind, sym.data, &mut self.refs)
+                .with_context(|| format!("in symbol {:?}", sym.kind))?;
+            self.mark_refs()?;
+        }
+        Ok(())
+    }
+
+    /// Marks the ID records that the `INLINEELINES` subsections of C13 Line Data refer to.
+    pub fn mark_line_data(&mut self, line_data: &LineData<'_>) -> anyhow::Result<()> {
+        for subsection in line_data.subsections() {
+            if subsection.kind == SubsectionKind::INLINEELINES {
+                let inlinee_lines = InlineeLinesSubsection::parse(subsection.data)
+                    .context("failed to decode INLINEELINES subsection")?;
+                for entry in inlinee_lines.iter() {
+                    self.mark_item(entry.inlinee())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn mark_refs(&mut self) -> anyhow::Result<()> {
+        for &ti in self.refs.types.iter() {
+            self.types.mark(ti)?;
+        }
+        for &id in self.refs.items.iter() {
+            self.items.mark(TypeIndex(id))?;
+        }
+        Ok(())
+    }
+
+    /// Marks every record that is reachable from the records that have already been marked.
+    ///
+    /// When a forward reference to a UDT is live, the definition of the UDT (found using `udts`)
+    /// is also live. After this, `LF_UDT_SRC_LINE` and `LF_UDT_MOD_SRC_LINE` records are marked
+    /// if their UDT is live.
+    pub fn propagate<StreamData: AsRef<[u8]>>(
+        &mut self,
+        tpi: &TypeStream<StreamData>,
+        ipi: &TypeStream<StreamData>,
+        udts: &UdtIndex<'_, StreamData>,
+    ) -> anyhow::Result<()> {
+        // ID records refer to type records, but type records never refer to ID records, so
+        // all of the IDs are processed first.
+        self.propagate_items(ipi)?;
+
+        while let Some(ti) = self.types.pending.pop() {
+            let record = tpi.record(ti)?;
+            self.refs.clear();
+            visit_type_indexes_in_record_slice(record.kind, record.data, &mut self.refs)
+                .with_context(|| format!("in type record {ti:?} ({:?})", record.kind))?;
+            for &r in self.refs.types.iter() {
+                self.types.mark(r)?;
+            }
+            if let Some(definition) = udts.resolve_forward_ref(ti) {
+                self.types.mark(definition)?;
+            }
+        }
+
+        // Nothing refers to LF_UDT_SRC_LINE and LF_UDT_MOD_SRC_LINE records, so they are marked
+        // last. Both refer to a UDT, which is already live. LF_UDT_SRC_LINE also refers to the
+        // LF_STRING_ID of its source file, and LF_UDT_MOD_SRC_LINE refers to the Names Stream.
+        let mut id = self.items.type_index_begin;
+        for record in ipi.iter_type_records() {
+            if matches!(
+                record.kind,
+                Leaf::LF_UDT_SRC_LINE | Leaf::LF_UDT_MOD_SRC_LINE
+            ) && record.data.len() >= 4
+            {
+                let udt = TypeIndex(u32::from_le_bytes(record.data[..4].try_into().unwrap()));
+                if self.types.is_live(udt) {
+                    self.items.mark(id)?;
+                }
+            }
+            id.0 += 1;
+        }
+
+        // Mark the LF_STRING_ID records (and their LF_SUBSTR_LIST records) of the source files.
+        // These do not refer to type records.
+        self.propagate_items(ipi)?;
+        debug_assert!(self.types.pending.is_empty());
+
+        Ok(())
+    }
+
+    /// Marks the records that the pending ID records refer to, until no ID records are pending.
+    fn propagate_items<StreamData: AsRef<[u8]>>(
+        &mut self,
+        ipi: &TypeStream<StreamData>,
+    ) -> anyhow::Result<()> {
+        while let Some(id) = self.items.pending.pop() {
+            let record = ipi.record(id)?;
+            self.refs.clear();
+            visit_type_indexes_in_record_slice(record.kind, record.data, &mut self.refs)
+                .with_context(|| format!("in ID record 0x{:x} ({:?})", id.0, record.kind))?;
+            self.mark_refs()?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the type record `ti` is live. Primitive types are always live.
+    pub fn is_type_live(&self, ti: TypeIndex) -> bool {
+        self.types.is_live(ti)
+    }
+
+    /// Returns `true` if the ID record `id` is live.
+    pub fn is_item_live(&self, id: ItemId) -> bool {
+        self.items.is_live(TypeIndex(id))
+    }
+
+    /// The number of live type records.
+    pub fn num_live_types(&self) -> u32 {
+        self.types.count()
+    }
+
+    /// The number of live ID records.
+    pub fn num_live_items(&self) -> u32 {
+        self.items.count()
+    }
+
+    /// Builds the compacted TPI and IPI streams. This should be called after [`Self::propagate`].
+    ///
+    /// If the hash streams are provided, then the entries in their Hash Adjusters tables that
+    /// point to live records are kept.
+    pub fn compact<StreamData: AsRef<[u8]>, HashData: AsRef<[u8]>>(
+        &self,
+        tpi: &TypeStream<StreamData>,
+        ipi: &TypeStream<StreamData>,
+        tpi_hash: Option<&TypeHashStream<HashData>>,
+        ipi_hash: Option<&TypeHashStream<HashData>>,
+    ) -> anyhow::Result<TypeCompaction> {
+        let types = Renumbering::new(&self.types);
+        let items = Renumbering::new(&self.items);
+
+        let new_tpi = compact_stream(tpi, tpi_hash, &types, &types, &items)
+            .context("Failed to compact the TPI Stream")?;
+        let new_ipi = compact_stream(ipi, ipi_hash, &items, &types, &items)
+            .context("Failed to compact the IPI Stream")?;
+
+        Ok(TypeCompaction {
+            tpi_before: StreamSizes::before(tpi),
+            ipi_before: StreamSizes::before(ipi),
+            tpi_hash_stream: tpi.hash_stream(),
+            ipi_hash_stream: ipi.hash_stream(),
+            types,
+            items,
+            tpi: new_tpi,
+            ipi: new_ipi,
+        })
+    }
+}
+
+/// Builds a new stream that contains the live records of `stream`.
+fn compact_stream<StreamData: AsRef<[u8]>, HashData: AsRef<[u8]>>(
+    stream: &TypeStream<StreamData>,
+    hash_stream: Option<&TypeHashStream<HashData>>,
+    renumbering: &Renumbering,
+    types: &Renumbering,
+    items: &Renumbering,
+) -> anyhow::Result<TypeStreamBuilder> {
+    if stream.type_index_begin() != TypeIndex::MIN_BEGIN {
+        bail!(
+            "Streams that begin at type index {:?} are not supported",
+            stream.type_index_begin()
+        );
+    }
+
+    let num_hash_buckets = match stream.header() {
+        Some(header) if header.num_hash_buckets.get() != 0 => header.num_hash_buckets.get(),
+        _ => DEFAULT_NUM_HASH_BUCKETS,
+    };
+    let mut builder = TypeStreamBuilder::with_num_hash_buckets(num_hash_buckets);
+
+    for (i, record) in stream.iter_type_records().enumerate() {
+        let Some(new) = renumbering.new_indexes[i] else {
+            continue;
+        };
+        let mut data = record.data.to_vec();
+        let mut remapper = Remapper::new(types, items);
+        remapper
+            .remap(|r| visit_type_indexes_in_record_slice_mut(record.kind, &mut data, r))
+            .with_context(|| format!("in record 0x{:x} ({:?})", new.0, record.kind))?;
+        let ti = builder.add_record(record.kind, &data)?;
+        debug_assert_eq!(ti, new);
+    }
+
+    if let Some(hash_stream) = hash_stream {
+        for adj in hash_stream.hash_adjusters() {
+            if let Some(ti) = renumbering.map(adj.type_index) {
+                builder.add_hash_adjuster(adj.name, ti);
+            }
+        }
+    }
+
+    Ok(builder)
+}
+
+/// Maps the index of each live record to its new index.
+struct Renumbering {
+    type_index_begin: TypeIndex,
+    new_indexes: Vec<Option<TypeIndex>>,
+}
+
+impl Renumbering {
+    fn new(live: &LiveSet) -> Self {
+        let mut next = TypeIndex::MIN_BEGIN;
+        let new_indexes = live
+            .live
+            .iter()
+            .map(|&live| {
+                live.then(|| {
+                    let ti = next;
+                    next.0 += 1;
+                    ti
+                })
+            })
+            .collect();
+        Self {
+            type_index_begin: live.type_index_begin,
+            new_indexes,
+        }
+    }
+
+    fn map(&self, old: TypeIndex) -> Option<TypeIndex> {
+        if old < self.type_index_begin {
+            return Some(old);
+        }
+        self.new_indexes
+            .get((old.0 - self.type_index_begin.0) as usize)
+            .copied()
+            .flatten()
+    }
+}
+
+/// Rewrites the `TypeIndex` and `ItemId` values within a record.
+struct Remapper<'a> {
+    types: &'a Renumbering,
+    items: &'a Renumbering,
+    /// The first value that could not be mapped, and whether it is an `ItemId`.
+    bad: Option<(u32, bool)>,
+}
+
+impl<'a> Remapper<'a> {
+    fn new(types: &'a Renumbering, items: &'a Renumbering) -> Self {
+        Self {
+            types,
+            items,
+            bad: None,
+        }
+    }
+
+    fn remap(&mut self, visit: impl FnOnce(&mut Self) -> anyhow::Result<()>) -> anyhow::Result<()> {
+        let result = visit(self);
+        match self.bad {
+            Some((value, false)) => bail!("Reference to type 0x{value:x}, which was removed"),
+            Some((value, true)) => bail!("Reference to item 0x{value:x}, which was removed"),
+            None => result,
+        }
+    }
+}
+
+impl IndexVisitorMut for &mut Remapper<'_> {
+    fn type_index(&mut self, _offset: usize, value: &mut TypeIndexLe) -> Result<(), ParserError> {
+        let Some(new) = self.types.map(value.get()) else {
+            self.bad = Some((value.get().0, false));
+            return Err(ParserError::new());
+        };
+        *value = new.into();
+        Ok(())
+    }
+
+    fn item_id(&mut self, _offset: usize, value: &mut ItemIdLe) -> Result<(), ParserError> {
+        let Some(new) = self.items.map(TypeIndex(value.get())) else {
+            self.bad = Some((value.get(), true));
+            return Err(ParserError::new());
+        };
+        *value = U32::new(new.0);
+        Ok(())
+    }
+}
+
+/// The number of records and the size of the records in a stream, before and after compaction.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct StreamSizes {
+    /// The number of records before compaction.
+    pub records_before: u32,
+    /// The number of records after compaction.
+    pub records_after: u32,
+    /// The size in bytes of the type records before compaction.
+    pub bytes_before: u64,
+    /// The size in bytes of the type records after compaction.
+    pub bytes_after: u64,
+}
+
+impl StreamSizes {
+    fn before<StreamData: AsRef<[u8]>>(stream: &TypeStream<StreamData>) -> Self {
+        Self {
+            records_before: stream.num_types(),
+            bytes_before: stream.type_records_bytes().len() as u64,
+            ..Self::default()
+        }
+    }
+
+    fn after(self, builder: &TypeStreamBuilder) -> Self {
+        Self {
+            records_after: builder.num_types(),
+            bytes_after: builder.type_records_bytes().len() as u64,
+            ..self
+        }
+    }
+}
+
+/// Describes the effect of compaction on the TPI and IPI streams.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompactionReport {
+    /// The TPI Stream.
+    pub tpi: StreamSizes,
+    /// The IPI Stream.
+    pub ipi: StreamSizes,
+}
+
+/// The compacted TPI and IPI streams, and the mapping from old indexes to new indexes.
+pub struct TypeCompaction {
+    types: Renumbering,
+    items: Renumbering,
+    tpi: TypeStreamBuilder,
+    ipi: TypeStreamBuilder,
+    tpi_before: StreamSizes,
+    ipi_before: StreamSizes,
+    tpi_hash_stream: Option<u32>,
+    ipi_hash_stream: Option<u32>,
+}
+
+impl TypeCompaction {
+    /// Maps an old type index to its new type index. Returns `None` if the record was removed.
+    pub fn map_type(&self, ti: TypeIndex) -> Option<TypeIndex> {
+        self.types.map(ti)
+    }
+
+    /// Maps an old item ID to its new item ID. Returns `None` if the record was removed.
+    pub fn map_item(&self, id: ItemId) -> Option<ItemId> {
+        self.items.map(TypeIndex(id)).map(|ti| ti.0)
+    }
+
+    /// The compacted TPI Stream.
+    pub fn tpi(&self) -> &TypeStreamBuilder {
+        &self.tpi
+    }
+
+    /// The compacted IPI Stream.
+    pub fn ipi(&self) -> &TypeStreamBuilder {
+        &self.ipi
+    }
+
+    /// Describes the sizes of the streams, before and after compaction.
+    pub fn report(&self) -> CompactionReport {
+        CompactionReport {
+            tpi: self.tpi_before.after(&self.tpi),
+            ipi: self.ipi_before.after(&self.ipi),
+        }
+    }
+
+    /// Rewrites the `TypeIndex` and `ItemId` values within symbol records.
+    pub fn remap_symbols(&self, syms: SymIterMut<'_>) -> anyhow::Result<()> {
+        for sym in syms {
+            let kind = sym.kind;
+            Remapper::new(&self.types, &self.items)
+                .remap(|r| visit_type_indexes_in_sym_slice_mut(kind, sym.data, r))
+                .with_context(|| format!("in symbol {kind:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the `ItemId` values within the `INLINEELINES` subsections of C13 Line Data.
+    pub fn remap_line_data(&self, line_data: &mut LineDataMut<'_>) -> anyhow::Result<()> {
+        for subsection in line_data.subsections_mut() {
+            if subsection.kind != SubsectionKind::INLINEELINES {
+                continue;
+            }
+            let mut inlinee_lines = InlineeLinesSubsectionMut::parse(subsection.data)
+                .context("failed to decode INLINEELINES subsection")?;
+            for entry in inlinee_lines.iter_mut() {
+                let old = entry.inlinee.get();
+                let Some(new) = self.map_item(old) else {
+                    bail!("INLINEELINES refers to item 0x{old:x}, which was removed");
+                };
+                entry.inlinee = U32::new(new);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F: ReadAt> crate::Pdb<F> {
+    /// Finds the type records and ID records that are live, and builds compacted TPI and IPI
+    /// streams. This does not modify the PDB; use [`Self::apply_type_compaction`] for that.
+    ///
+    /// The roots are the symbols in all module streams and in the Global Symbol Stream, the
+    /// `INLINEELINES` subsections of all modules, and the UDTs named in `options`.
+    pub fn plan_type_compaction(
+        &self,
+        options: &CompactTypesOptions,
+    ) -> anyhow::Result<TypeCompaction> {
+        let tpi = self.read_type_stream()?;
+        let ipi = self.read_ipi_stream()?;
+        let tpi_hash = self.read_type_hash_stream(&tpi)?;
+        let ipi_hash = self.read_type_hash_stream(&ipi)?;
+        let udts = match &tpi_hash {
+            Some(hash_stream) => UdtIndex::with_hash_stream(&tpi, hash_stream),
+            None => UdtIndex::new(&tpi),
+        };
+
+        let mut live = LiveTypes::new(&tpi, &ipi);
+
+        for module in self.modules()?.iter() {
+            let Some(modi) = self.read_module_stream(&module)? else {
+                continue;
+            };
+            let line_data = modi.c13_line_data();
+            if options.strip_ids
+                && line_data
+                    .subsections()
+                    .any(|s| s.kind == SubsectionKind::CROSSSCOPEEXPORTS)
+            {
+                bail!(
+                    "Module {} exports IDs to other modules (CROSSSCOPEEXPORTS), so ID records cannot be removed",
+                    module.module_name()
+                );
+            }
+            live.mark_symbols(modi.iter_syms())
+                .and_then(|()| live.mark_line_data(&line_data))
+                .with_context(|| format!("in module {}", module.module_name()))?;
+        }
+
+        live.mark_symbols(self.gss()?.iter_syms())
+            .context("in the Global Symbol Stream")?;
+
+        for name in options.root_names.iter() {
+            match udts.find_udt_by_name(name.as_ref()) {
+                Some(ti) => live.mark_type(ti)?,
+                None => warn!("Did not find a UDT named {name}"),
+            }
+        }
+
+        if !options.strip_ids {
+            live.mark_all_items();
+        }
+
+        live.propagate(&tpi, &ipi, &udts)?;
+        live.compact(&tpi, &ipi, tpi_hash.as_ref(), ipi_hash.as_ref())
+    }
+}
+
+impl<F: ReadAt + WriteAt> crate::Pdb<F> {
+    /// Replaces the TPI and IPI streams (and their hash streams) with the compacted streams and
+    /// rewrites the symbols and line data of every module and the Global Symbol Stream.
+    ///
+    /// `compaction` must have been created by [`Self::plan_type_compaction`] for this PDB. The
+    /// changes are not committed; the caller must call `commit()`.
+    pub fn apply_type_compaction(&mut self, compaction: &TypeCompaction) -> anyhow::Result<()> {
+        let modules: Vec<ModuleInfoFixed> = self
+            .modules()?
+            .iter()
+            .map(|module| module.header().clone())
+            .collect();
+
+        for module in modules.iter() {
+            let Some(stream) = module.stream() else {
+                continue;
+            };
+            let original = self.read_stream_to_vec(stream)?;
+            let mut modi = ModiStreamData::new(original.clone(), module)?;
+            compaction.remap_symbols(SymIterMut::new(modi.sym_data_mut()?))?;
+            compaction.remap_line_data(&mut modi.c13_line_data_mut())?;
+            if modi.stream_data != original {
+                self.msf_mut_err()?
+                    .write_stream(stream)?
+                    .set_contents(&modi.stream_data)?;
+            }
+        }
+
+        if let Some(gss_stream) = self.dbi_header.global_symbol_stream.get() {
+            let mut gss = self.read_stream_to_vec(gss_stream)?;
+            compaction
+                .remap_symbols(SymIterMut::new(&mut gss))
+                .context("in the Global Symbol Stream")?;
+            self.msf_mut_err()?
+                .write_stream(gss_stream)?
+                .set_contents(&gss)?;
+            self.gss_drop();
+        }
+
+        self.write_type_stream(Stream::TPI, &compaction.tpi, compaction.tpi_hash_stream)?;
+        self.write_type_stream(Stream::IPI, &compaction.ipi, compaction.ipi_hash_stream)?;
+        self.cached.tpi_header = Default::default();
+        self.cached.ipi_header = Default::default();
+
+        Ok(())
+    }
+
+    /// Replaces a TPI or IPI stream and its hash stream. If the stream does not have a hash
+    /// stream, then a new one is created.
+    fn write_type_stream(
+        &mut self,
+        stream: Stream,
+        builder: &TypeStreamBuilder,
+        hash_stream: Option<u32>,
+    ) -> anyhow::Result<()> {
+        let hash_stream_data = builder.encode_hash_stream();
+        let msf = self.msf_mut_err()?;
+        let hash_stream = match hash_stream {
+            Some(hash_stream) => {
+                msf.write_stream(hash_stream)?
+                    .set_contents(&hash_stream_data)?;
+                hash_stream
+            }
+            None => msf.new_stream_data(&hash_stream_data)?,
+        };
+        let type_stream_data = builder.encode_type_stream(Some(hash_stream))?;
+        msf.write_stream(stream.into())?
+            .set_contents(&type_stream_data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,213 @@
+use super::*;
+use crate::lines::CV_INLINEE_SOURCE_LINE_SIGNATURE;
+use crate::tpi::testing::*;
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+use crate::types::{FuncIdFixed, UdtSrcLine};
+use bstr::ByteSlice;
+use ms_codeview::syms::SymKind;
+use ms_codeview::syms::builder::SymBuilder;
+
+const FWDREF: u16 = 1 << 7;
+
+/// Builds a TPI:
+///
+/// ```text
+/// 0x1000  int*
+/// 0x1001  LF_FIELDLIST { int* p }
+/// 0x1002  struct S (definition)
+/// 0x1003  struct U (not used)
+/// 0x1004  struct S (forward reference)
+/// 0x1005  S*
+/// 0x1006  LF_ARGLIST (int)
+/// 0x1007  void f(int)
+/// ```
+fn build_tpi() -> TypeStream<Vec<u8>> {
+    let mut t = TypeBuilder::new();
+    let int_ptr = pointer(&mut t, TypeIndex::T_INT4, PTR64);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, int_ptr, 0, "p".into());
+    let fields = t.field_list(&fields);
+    udt(&mut t, Leaf::LF_STRUCTURE, "S", fields, 8, 0);
+    udt(&mut t, Leaf::LF_STRUCTURE, "U", TypeIndex(0), 4, 0);
+    let fwd = udt(&mut t, Leaf::LF_STRUCTURE, "S", TypeIndex(0), 0, FWDREF);
+    pointer(&mut t, fwd, PTR64);
+    proc(&mut t, TypeIndex::T_VOID, &[TypeIndex::T_INT4]);
+    type_stream(Stream::TPI, &t.finish())
+}
+
+/// Builds an IPI:
+///
+/// ```text
+/// 0x1000  LF_STRING_ID (not used)
+/// 0x1001  LF_STRING_ID "s.h" (only used by the LF_UDT_SRC_LINE records)
+/// 0x1002  LF_UDT_SRC_LINE S
+/// 0x1003  LF_UDT_SRC_LINE U
+/// 0x1004  LF_FUNC_ID f
+/// ```
+fn build_ipi() -> TypeStream<Vec<u8>> {
+    let mut ids = TypeBuilder::new();
+    ids.string_id(0, b"unused".as_bstr());
+    let src = ids.string_id(0, b"s.h".as_bstr());
+    for udt in [0x1002, 0x1003] {
+        ids.udt_src_line(&UdtSrcLine {
+            ty: TypeIndex(udt).into(),
+            src: U32::new(src.0),
+            line: U32::new(1),
+        });
+    }
+    ids.func_id(
+        &FuncIdFixed {
+            scope: U32::new(0),
+            func_type: TypeIndex(0x1007).into(),
+        },
+        b"f".as_bstr(),
+    );
+    type_stream(Stream::IPI, &ids.finish())
+}
+
+/// Builds C13 Line Data containing an `INLINEELINES` subsection with one entry.
+fn inlinee_lines(inlinee: ItemId) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&SubsectionKind::INLINEELINES.0.to_le_bytes());
+    data.extend_from_slice(&16u32.to_le_bytes());
+    data.extend_from_slice(&CV_INLINEE_SOURCE_LINE_SIGNATURE.to_le_bytes());
+    data.extend_from_slice(&inlinee.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // file_id
+    data.extend_from_slice(&10u32.to_le_bytes()); // source_line_num
+    data
+}
+
+#[test]
+fn compact_types() {
+    let tpi = build_tpi();
+    let ipi = build_ipi();
+
+    let mut syms = SymBuilder::new();
+    syms.udt(TypeIndex(0x1005), b"PS".as_bstr());
+    let mut syms = syms.finish();
+
+    let mut live = LiveTypes::new(&tpi, &ipi);
+    live.mark_symbols(SymIter::new(&syms)).unwrap();
+    live.propagate(&tpi, &ipi, &UdtIndex::new(&tpi)).unwrap();
+
+    // The forward reference keeps the definition of S alive.
+    for ti in [0x1000, 0x1001, 0x1002, 0x1004, 0x1005] {
+        assert!(live.is_type_live(TypeIndex(ti)), "0x{ti:x}");
+    }
+    assert!(!live.is_type_live(TypeIndex(0x1003)));
+    assert!(!live.is_type_live(TypeIndex(0x1006)));
+    assert!(live.is_type_live(TypeIndex::T_INT4));
+    assert_eq!(live.num_live_types(), 5);
+
+    // The source line record of S is kept, because S is live, and so is its source file name.
+    assert!(live.is_item_live(0x1001));
+    assert!(live.is_item_live(0x1002));
+    assert_eq!(live.num_live_items(), 2);
+
+    let compaction = live.compact::<_, Vec<u8>>(&tpi, &ipi, None, None).unwrap();
+    assert_eq!(compaction.map_type(TypeIndex(0x1003)), None);
+    assert_eq!(
+        compaction.map_type(TypeIndex(0x1004)),
+        Some(TypeIndex(0x1003))
+    );
+    assert_eq!(
+        compaction.map_type(TypeIndex(0x1005)),
+        Some(TypeIndex(0x1004))
+    );
+    assert_eq!(compaction.map_item(0x1000), None);
+    assert_eq!(compaction.map_item(0x1001), Some(0x1000));
+    assert_eq!(compaction.map_item(0x1002), Some(0x1001));
+    assert_eq!(compaction.map_item(0x1003), None);
+
+    let report = compaction.report();
+    assert_eq!(report.tpi.records_before, 8);
+    assert_eq!(report.tpi.records_after, 5);
+    assert!(report.tpi.bytes_after < report.tpi.bytes_before);
+    assert_eq!(report.ipi.records_after, 2);
+
+    // The source line record refers to the new index of its source file name.
+    let new_ipi = type_stream(Stream::IPI, compaction.ipi().type_records_bytes());
+    let src_line = new_ipi.record(TypeIndex(0x1001)).unwrap();
+    assert_eq!(src_line.kind, Leaf::LF_UDT_SRC_LINE);
+    assert_eq!(&src_line.data[4..8], &0x1000u32.to_le_bytes());
+
+    // The pointer to the forward reference now points to its new index.
+    let new_tpi = type_stream(Stream::TPI, compaction.tpi().type_records_bytes());
+    let ptr = new_tpi.record(TypeIndex(0x1004)).unwrap();
+    assert_eq!(ptr.kind, Leaf::LF_POINTER);
+    assert_eq!(&ptr.data[..4], &0x1003u32.to_le_bytes());
+
+    compaction
+        .remap_symbols(SymIterMut::new(&mut syms))
+        .unwrap();
+    let sym = SymIter::new(&syms).next().unwrap();
+    assert_eq!(sym.kind, SymKind::S_UDT);
+    assert_eq!(&sym.data[..4], &0x1004u32.to_le_bytes());
+}
+
+#[test]
+fn compact_ids() {
+    let tpi = build_tpi();
+    let ipi = build_ipi();
+    let mut line_data = inlinee_lines(0x1004);
+
+    let mut live = LiveTypes::new(&tpi, &ipi);
+    live.mark_line_data(&LineData::new(&line_data)).unwrap();
+    live.propagate(&tpi, &ipi, &UdtIndex::new(&tpi)).unwrap();
+
+    // The function ID keeps its function type (and its argument list) alive.
+    assert!(live.is_item_live(0x1004));
+    assert!(!live.is_item_live(0x1000));
+    assert!(!live.is_item_live(0x1001));
+    assert!(live.is_type_live(TypeIndex(0x1006)));
+    assert!(live.is_type_live(TypeIndex(0x1007)));
+    assert_eq!(live.num_live_types(), 2);
+    assert_eq!(live.num_live_items(), 1);
+
+    let compaction = live.compact::<_, Vec<u8>>(&tpi, &ipi, None, None).unwrap();
+    assert_eq!(compaction.map_item(0x1004), Some(0x1000));
+    let new_ipi = type_stream(Stream::IPI, compaction.ipi().type_records_bytes());
+    let func_id = new_ipi.record(TypeIndex(0x1000)).unwrap();
+    assert_eq!(func_id.kind, Leaf::LF_FUNC_ID);
+    assert_eq!(&func_id.data[4..8], &0x1001u32.to_le_bytes());
+
+    compaction
+        .remap_line_data(&mut LineDataMut::new(&mut line_data))
+        .unwrap();
+    assert_eq!(&line_data[12..16], &0x1000u32.to_le_bytes());
+}
+
+#[test]
+fn keep_all_ids() {
+    let tpi = build_tpi();
+    let ipi = build_ipi();
+
+    let mut live = LiveTypes::new(&tpi, &ipi);
+    live.mark_all_items();
+    live.propagate(&tpi, &ipi, &UdtIndex::new(&tpi)).unwrap();
+
+    // Every type that an ID record refers to is live, including U.
+    assert_eq!(live.num_live_items(), 5);
+    assert!(live.is_type_live(TypeIndex(0x1003)));
+    assert!(live.is_type_live(TypeIndex(0x1006)));
+    assert!(!live.is_type_live(TypeIndex(0x1004)));
+    assert!(!live.is_type_live(TypeIndex(0x1005)));
+}
+
+#[test]
+fn removed_reference_is_error() {
+    let tpi = build_tpi();
+    let ipi = build_ipi();
+
+    let live = LiveTypes::new(&tpi, &ipi);
+    let compaction = live.compact::<_, Vec<u8>>(&tpi, &ipi, None, None).unwrap();
+    assert_eq!(compaction.tpi().num_types(), 0);
+
+    let mut syms = SymBuilder::new();
+    syms.udt(TypeIndex(0x1002), b"S".as_bstr());
+    let mut syms = syms.finish();
+    let err = compaction
+        .remap_symbols(SymIterMut::new(&mut syms))
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("which was removed"));
+}
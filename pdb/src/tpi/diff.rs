@@ -0,0 +1,489 @@
+//! Compares the UDTs of two type streams structurally.
+//!
+//! Type indexes are assigned in the order in which the compiler and linker encounter types, so
+//! the same UDT usually has a different type index (and refers to different type indexes) in two
+//! builds. [`TypeDiff`] compares UDTs by name instead. Each UDT definition is reduced to a
+//! [`UdtShape`], in which the types of members and base classes are replaced by their C/C++ type
+//! names, and then the shapes are compared.
+//!
+//! A change to a UDT is reported for that UDT only. If a member has type `S` and the layout of
+//! `S` changes, then the change is reported for `S`, not for the UDTs that contain an `S`.
+
+use super::hash::is_udt_anon_name;
+use super::layout::BitfieldLayout;
+use super::{TypeFormatter, TypeStream};
+use crate::types::fields::Field;
+use crate::types::{Leaf, MethodList, TypeData, TypeIndex, UdtProperties, introduces_virtual};
+use anyhow::bail;
+use bstr::BStr;
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Compares the UDTs (structs, classes, and unions) of two type streams.
+///
+/// ```ignore
+/// let old_tpi = old_pdb.read_type_stream()?;
+/// let new_tpi = new_pdb.read_type_stream()?;
+/// let report = TypeDiff::new(&old_tpi, &new_tpi).diff_all()?;
+/// for udt in report.changed.iter() {
+///     println!("{}: {} changes", udt.name, udt.changes.len());
+/// }
+/// ```
+pub struct TypeDiff<'a, OldData, NewData>
+where
+    OldData: AsRef<[u8]>,
+    NewData: AsRef<[u8]>,
+{
+    old: Side<'a, OldData>,
+    new: Side<'a, NewData>,
+}
+
+/// One of the type streams that is being compared.
+struct Side<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    type_stream: &'a TypeStream<StreamData>,
+    formatter: TypeFormatter<'a, StreamData>,
+
+    /// The UDT definitions, by name. If more than one UDT has the same name, this contains the
+    /// first one.
+    udts: BTreeMap<&'a BStr, TypeIndex>,
+}
+
+/// The parts of a UDT definition that are compared. Type indexes have been replaced with type
+/// names, so shapes from different type streams can be compared.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UdtShape {
+    /// `LF_STRUCTURE`, `LF_CLASS`, `LF_UNION`, etc.
+    pub kind: Leaf,
+    /// The name of the UDT.
+    pub name: String,
+    /// The size of the UDT in bytes.
+    pub size: u64,
+    /// The direct base classes of the UDT, in declaration order.
+    pub bases: Vec<BaseShape>,
+    /// The non-static data members of the UDT, in declaration order. If the UDT has a virtual
+    /// function table pointer, then it is included as a member named `__vfptr`. Inherited
+    /// members are not included.
+    pub members: Vec<MemberShape>,
+    /// The virtual methods that are introduced by this UDT (not overrides of virtual methods of
+    /// base classes), sorted by their offset in the virtual function table.
+    pub virtual_methods: Vec<VirtualMethod>,
+}
+
+/// A direct base class of a UDT.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BaseShape {
+    /// The name of the base class.
+    pub ty: String,
+    /// The byte offset of the base class. This is `None` for virtual base classes, whose offset
+    /// is not fixed.
+    pub offset: Option<u64>,
+    /// True if this is a virtual base class.
+    pub is_virtual: bool,
+}
+
+/// A non-static data member of a UDT.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemberShape {
+    /// The name of the member.
+    pub name: String,
+    /// The name of the type of the member. For bitfields, this includes the width, e.g.
+    /// `unsigned long : 3`.
+    pub ty: String,
+    /// The byte offset of the member.
+    pub offset: u64,
+    /// The location of a bitfield within its storage unit.
+    pub bitfield: Option<BitfieldLayout>,
+}
+
+/// A virtual method that occupies a slot in the virtual function table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VirtualMethod {
+    /// The name of the method.
+    pub name: String,
+    /// The name of the type of the method.
+    pub ty: String,
+    /// The byte offset of the slot within the virtual function table.
+    pub vtable_offset: u32,
+}
+
+/// Describes one difference between two definitions of a UDT.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub enum UdtChange {
+    /// The UDT changed from a struct to a class, a class to a union, etc.
+    KindChanged { old: Leaf, new: Leaf },
+    /// The size of the UDT changed.
+    SizeChanged { old: u64, new: u64 },
+    /// The list of direct base classes changed. This includes changes to the order and offsets
+    /// of base classes.
+    BasesChanged {
+        old: Vec<BaseShape>,
+        new: Vec<BaseShape>,
+    },
+    /// A data member was added.
+    MemberAdded(MemberShape),
+    /// A data member was removed.
+    MemberRemoved(MemberShape),
+    /// The type of a data member changed.
+    MemberRetyped { old: MemberShape, new: MemberShape },
+    /// The offset of a data member (or the position of a bitfield) changed.
+    MemberMoved { old: MemberShape, new: MemberShape },
+    /// The virtual methods that are introduced by the UDT, or their slots, changed.
+    VTableChanged {
+        old: Vec<VirtualMethod>,
+        new: Vec<VirtualMethod>,
+    },
+}
+
+/// The differences between two definitions of a UDT.
+#[derive(Clone, Debug)]
+pub struct UdtDiff {
+    /// The name of the UDT.
+    pub name: String,
+    /// The definition of the UDT in the old type stream.
+    pub old_type_index: TypeIndex,
+    /// The definition of the UDT in the new type stream.
+    pub new_type_index: TypeIndex,
+    /// The differences. This is empty if the definitions are equivalent.
+    pub changes: Vec<UdtChange>,
+}
+
+impl UdtDiff {
+    /// Returns `true` if the two definitions are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// The result of comparing all of the UDTs of two type streams.
+#[derive(Clone, Debug, Default)]
+pub struct TypeDiffReport {
+    /// The names of the UDTs that are only defined in the new type stream, sorted by name.
+    pub added: Vec<String>,
+    /// The names of the UDTs that are only defined in the old type stream, sorted by name.
+    pub removed: Vec<String>,
+    /// The UDTs that are defined in both type streams, but differ. Sorted by name.
+    pub changed: Vec<UdtDiff>,
+    /// The names of the UDTs that are defined in both type streams, but whose definitions (or
+    /// the records that they refer to, such as `LF_METHODLIST`) could not be decoded, sorted by
+    /// name. These UDTs are not compared.
+    pub unparseable: Vec<String>,
+}
+
+impl TypeDiffReport {
+    /// Returns `true` if no differences were found and all UDTs could be compared.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.unparseable.is_empty()
+    }
+}
+
+impl<'a, OldData, NewData> TypeDiff<'a, OldData, NewData>
+where
+    OldData: AsRef<[u8]>,
+    NewData: AsRef<[u8]>,
+{
+    /// Prepares to compare the UDTs of `old` and `new`. This finds the UDT definitions in both
+    /// type streams.
+    ///
+    /// Anonymous UDTs and local UDTs (those that are defined within a function) cannot be
+    /// matched by name, so they are not compared directly. Changes to them are usually visible
+    /// as changes to the UDTs that contain them.
+    pub fn new(old: &'a TypeStream<OldData>, new: &'a TypeStream<NewData>) -> Self {
+        Self {
+            old: Side::new(old),
+            new: Side::new(new),
+        }
+    }
+
+    /// Compares the definitions of the UDT named `name`. Fails if the UDT is not defined in both
+    /// type streams.
+    pub fn diff_udt(&self, name: &BStr) -> anyhow::Result<UdtDiff> {
+        let Some(&old_ti) = self.old.udts.get(name) else {
+            bail!("UDT {name} is not defined in the old type stream");
+        };
+        let Some(&new_ti) = self.new.udts.get(name) else {
+            bail!("UDT {name} is not defined in the new type stream");
+        };
+        self.diff_definitions(old_ti, new_ti)
+    }
+
+    /// Compares all of the UDTs of the two type streams. A UDT whose definition cannot be decoded
+    /// is reported in [`TypeDiffReport::unparseable`], and does not stop the comparison.
+    pub fn diff_all(&self) -> anyhow::Result<TypeDiffReport> {
+        let mut report = TypeDiffReport::default();
+
+        for (&name, &old_ti) in self.old.udts.iter() {
+            match self.new.udts.get(name) {
+                Some(&new_ti) => match self.diff_definitions(old_ti, new_ti) {
+                    Ok(diff) => {
+                        if !diff.is_empty() {
+                            report.changed.push(diff);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to compare UDT {name}: {e:#}");
+                        report.unparseable.push(name.to_string());
+                    }
+                },
+                None => report.removed.push(name.to_string()),
+            }
+        }
+
+        for &name in self.new.udts.keys() {
+            if !self.old.udts.contains_key(name) {
+                report.added.push(name.to_string());
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn diff_definitions(&self, old_ti: TypeIndex, new_ti: TypeIndex) -> anyhow::Result<UdtDiff> {
+        let old = self.old.shape(old_ti)?;
+        let new = self.new.shape(new_ti)?;
+        Ok(UdtDiff {
+            changes: diff_shapes(&old, &new),
+            name: new.name,
+            old_type_index: old_ti,
+            new_type_index: new_ti,
+        })
+    }
+}
+
+/// The fixed parts of a struct, class, or union record.
+struct UdtDefinition<'a> {
+    property: UdtProperties,
+    name: &'a BStr,
+    fields: TypeIndex,
+    size: u64,
+}
+
+fn udt_definition<'a>(data: &TypeData<'a>) -> Option<UdtDefinition<'a>> {
+    let (property, name, fields, length) = match data {
+        TypeData::Struct(t) => (t.fixed.property.get(), t.name, t.fixed.field_list, t.length),
+        TypeData::Struct2(t) => (
+            t.fixed.udt_properties(),
+            t.name,
+            t.fixed.field_list,
+            t.length,
+        ),
+        TypeData::Union(t) => (t.fixed.property.get(), t.name, t.fixed.fields, t.length),
+        TypeData::Union2(t) => (t.fixed.udt_properties(), t.name, t.fixed.fields, t.length),
+        _ => return None,
+    };
+    Some(UdtDefinition {
+        property,
+        name,
+        fields: fields.get(),
+        size: u64::try_from(length).ok()?,
+    })
+}
+
+impl<'a, StreamData> Side<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    fn new(type_stream: &'a TypeStream<StreamData>) -> Self {
+        let mut udts = BTreeMap::new();
+        let mut ti = type_stream.type_index_begin();
+        for record in type_stream.iter_type_records() {
+            if let Ok(data) = record.parse()
+                && let Some(udt) = udt_definition(&data)
+                && !udt.property.fwdref()
+                && !udt.property.scoped()
+                && !is_udt_anon_name(udt.name)
+            {
+                udts.entry(udt.name).or_insert(ti);
+            }
+            ti.0 += 1;
+        }
+
+        Self {
+            type_stream,
+            formatter: TypeFormatter::new(type_stream),
+            udts,
+        }
+    }
+
+    /// Reduces a UDT definition to its shape.
+    fn shape(&self, ti: TypeIndex) -> anyhow::Result<UdtShape> {
+        let record = self.type_stream.record(ti)?;
+        let data = record.parse()?;
+        let Some(udt) = udt_definition(&data) else {
+            bail!("Type {ti:?} is not a struct, class, or union");
+        };
+
+        let mut shape = UdtShape {
+            kind: record.kind,
+            name: udt.name.to_string(),
+            size: udt.size,
+            bases: Vec::new(),
+            members: Vec::new(),
+            virtual_methods: Vec::new(),
+        };
+
+        for field in self.type_stream.iter_fields(udt.fields) {
+            match field {
+                Field::BaseClass(b) => shape.bases.push(BaseShape {
+                    ty: self.formatter.format(b.ty),
+                    offset: u64::try_from(b.offset).ok(),
+                    is_virtual: false,
+                }),
+
+                Field::DirectVirtualBaseClass(b) => shape.bases.push(BaseShape {
+                    ty: self.formatter.format(b.fixed.btype.get()),
+                    offset: None,
+                    is_virtual: true,
+                }),
+
+                Field::VFuncTable(ty) => shape.members.push(MemberShape {
+                    name: "__vfptr".to_string(),
+                    ty: self.formatter.format(ty),
+                    offset: 0,
+                    bitfield: None,
+                }),
+
+                Field::Member(m) => {
+                    let Ok(offset) = u64::try_from(m.offset) else {
+                        continue;
+                    };
+                    shape.members.push(MemberShape {
+                        name: m.name.to_string(),
+                        ty: self.formatter.format(m.ty),
+                        offset,
+                        bitfield: self.bitfield(m.ty),
+                    });
+                }
+
+                Field::OneMethod(m) if introduces_virtual(m.attr) => {
+                    shape.virtual_methods.push(VirtualMethod {
+                        name: m.name.to_string(),
+                        ty: self.formatter.format(m.ty),
+                        vtable_offset: m.vbaseoff,
+                    });
+                }
+
+                Field::Method(m) => {
+                    let Ok(TypeData::MethodList(list)) =
+                        self.type_stream.record(m.methods)?.parse()
+                    else {
+                        bail!(
+                            "Method {} refers to {:?}, which is not an LF_METHODLIST",
+                            m.name,
+                            m.methods
+                        );
+                    };
+                    let mut list = MethodList::parse(list.bytes)?;
+                    while let Some(item) = list.next()? {
+                        if let Some(vtable_offset) = item.vtab_offset {
+                            shape.virtual_methods.push(VirtualMethod {
+                                name: m.name.to_string(),
+                                ty: self.formatter.format(item.ty),
+                                vtable_offset,
+                            });
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        shape.virtual_methods.sort_by_key(|m| m.vtable_offset);
+        Ok(shape)
+    }
+
+    fn bitfield(&self, ty: TypeIndex) -> Option<BitfieldLayout> {
+        if self.type_stream.is_primitive(ty) {
+            return None;
+        }
+        match self.type_stream.record(ty).ok()?.parse().ok()? {
+            TypeData::Bitfield(b) => Some(BitfieldLayout {
+                position: b.position,
+                length: b.length,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Compares two shapes of a UDT.
+///
+/// Members are matched by name. If a UDT has more than one member with the same name, then they
+/// are matched in declaration order.
+pub fn diff_shapes(old: &UdtShape, new: &UdtShape) -> Vec<UdtChange> {
+    let mut changes = Vec::new();
+
+    if old.kind != new.kind {
+        changes.push(UdtChange::KindChanged {
+            old: old.kind,
+            new: new.kind,
+        });
+    }
+
+    if old.size != new.size {
+        changes.push(UdtChange::SizeChanged {
+            old: old.size,
+            new: new.size,
+        });
+    }
+
+    if old.bases != new.bases {
+        changes.push(UdtChange::BasesChanged {
+            old: old.bases.clone(),
+            new: new.bases.clone(),
+        });
+    }
+
+    let mut matched = vec![false; new.members.len()];
+    for old_member in old.members.iter() {
+        let found = new
+            .members
+            .iter()
+            .enumerate()
+            .position(|(i, m)| !matched[i] && m.name == old_member.name);
+        let Some(i) = found else {
+            changes.push(UdtChange::MemberRemoved(old_member.clone()));
+            continue;
+        };
+        matched[i] = true;
+
+        let new_member = &new.members[i];
+        if old_member.ty != new_member.ty {
+            changes.push(UdtChange::MemberRetyped {
+                old: old_member.clone(),
+                new: new_member.clone(),
+            });
+        }
+        if old_member.offset != new_member.offset || old_member.bitfield != new_member.bitfield {
+            changes.push(UdtChange::MemberMoved {
+                old: old_member.clone(),
+                new: new_member.clone(),
+            });
+        }
+    }
+
+    for (new_member, matched) in new.members.iter().zip(matched) {
+        if !matched {
+            changes.push(UdtChange::MemberAdded(new_member.clone()));
+        }
+    }
+
+    if old.virtual_methods != new.virtual_methods {
+        changes.push(UdtChange::VTableChanged {
+            old: old.virtual_methods.clone(),
+            new: new.virtual_methods.clone(),
+        });
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,235 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::testing::*;
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+use crate::types::{
+    CallingConvention, PointerFixed, Proc, StructFixed, TypeModifier, UdtPropertiesLe,
+};
+use zerocopy::{FromZeros, U16, U32};
+
+const FWDREF: u16 = 1 << 7;
+
+/// Describes one version of a class `C`, for building a type stream.
+struct Version {
+    /// Extra records at the start of the stream, so that the type indexes of the two versions
+    /// differ.
+    padding: usize,
+    base_offset: u16,
+    members: &'static [(&'static str, TypeIndex, u16)],
+    virtual_methods: &'static [(&'static str, u32)],
+    size: u16,
+}
+
+/// `attr` of a public method that introduces a virtual function slot.
+const INTRO_VIRTUAL: u16 = PUBLIC | (4 << 2);
+
+fn class(t: &mut TypeBuilder, name: &str, fields: TypeIndex, size: u16, property: u16) {
+    let mut fixed = StructFixed::new_zeroed();
+    fixed.property = UdtPropertiesLe(U16::new(property));
+    fixed.field_list = fields.into();
+    t.struct_(Leaf::LF_CLASS, &fixed, size.into(), name.into(), None);
+}
+
+/// Adds the records that the versions of `C` use: `Base`, `Base*`, and `void ()`.
+fn common_types(t: &mut TypeBuilder) -> (TypeIndex, TypeIndex, TypeIndex) {
+    let base = StructFixed::new_zeroed();
+    let base = t.struct_(Leaf::LF_STRUCTURE, &base, 4, "Base".into(), None);
+    let base_ptr = t.pointer(
+        &PointerFixed {
+            ty: base.into(),
+            attr: U32::new(PTR64),
+        },
+        &[],
+    );
+    let args = t.arg_list(&[]);
+    let proc = t.proc(&Proc {
+        return_value: TypeIndex::T_VOID.into(),
+        call: CallingConvention::NEAR_C.0,
+        reserved: 0,
+        num_params: U16::new(0),
+        arg_list: args.into(),
+    });
+    (base, base_ptr, proc)
+}
+
+fn build(v: &Version) -> TypeStream<Vec<u8>> {
+    let mut t = TypeBuilder::new();
+    for i in 0..v.padding {
+        t.modifier(&TypeModifier {
+            underlying_type: TypeIndex::T_INT4.into(),
+            attributes: U16::new(i as u16),
+        });
+    }
+    let (base, base_ptr, proc) = common_types(&mut t);
+
+    let mut fields = FieldListBuilder::new();
+    fields.base_class(PUBLIC, base, v.base_offset.into());
+    for &(name, ty, offset) in v.members {
+        // Use a pointer to Base for members whose type is given as T_NOTYPE.
+        let ty = if ty == TypeIndex::T_NOTYPE {
+            base_ptr
+        } else {
+            ty
+        };
+        fields.member(PUBLIC, ty, offset.into(), name.into());
+    }
+    for &(name, vtable_offset) in v.virtual_methods {
+        fields.one_method(INTRO_VIRTUAL, proc, Some(vtable_offset), name.into());
+    }
+    let fields = t.field_list(&fields);
+    class(&mut t, "C", TypeIndex(0), 0, FWDREF);
+    class(&mut t, "C", fields, v.size, 0);
+    type_stream(Stream::TPI, &t.finish())
+}
+
+const OLD: Version = Version {
+    padding: 0,
+    base_offset: 0,
+    members: &[
+        ("a", TypeIndex::T_INT4, 8),
+        ("b", TypeIndex::T_NOTYPE, 16),
+        ("c", TypeIndex::T_INT4, 24),
+    ],
+    virtual_methods: &[("f", 0), ("g", 8)],
+    size: 32,
+};
+
+#[test]
+fn identical_udts() {
+    let old = build(&OLD);
+    let new = build(&Version { padding: 3, ..OLD });
+
+    let diff = TypeDiff::new(&old, &new);
+    let c = diff.diff_udt("C".into()).unwrap();
+    assert!(c.is_empty(), "{:?}", c.changes);
+    assert_ne!(c.old_type_index, c.new_type_index);
+
+    let report = diff.diff_all().unwrap();
+    assert!(report.is_empty(), "{report:?}");
+}
+
+#[test]
+fn changed_members() {
+    let old = build(&OLD);
+    let new = build(&Version {
+        padding: 1,
+        members: &[
+            ("a", TypeIndex::T_INT8, 8),
+            ("c", TypeIndex::T_INT4, 16),
+            ("d", TypeIndex::T_INT4, 20),
+        ],
+        size: 24,
+        ..OLD
+    });
+
+    let c = TypeDiff::new(&old, &new).diff_udt("C".into()).unwrap();
+    let member = |name: &str, ty: &str, offset| MemberShape {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        offset,
+        bitfield: None,
+    };
+    assert_eq!(
+        c.changes,
+        [
+            UdtChange::SizeChanged { old: 32, new: 24 },
+            UdtChange::MemberRetyped {
+                old: member("a", "__int32", 8),
+                new: member("a", "__int64", 8),
+            },
+            UdtChange::MemberRemoved(member("b", "Base*", 16)),
+            UdtChange::MemberMoved {
+                old: member("c", "__int32", 24),
+                new: member("c", "__int32", 16),
+            },
+            UdtChange::MemberAdded(member("d", "__int32", 20)),
+        ]
+    );
+}
+
+#[test]
+fn changed_bases_and_vtable() {
+    let old = build(&OLD);
+    let new = build(&Version {
+        base_offset: 8,
+        virtual_methods: &[("g", 0), ("f", 8)],
+        ..OLD
+    });
+
+    let c = TypeDiff::new(&old, &new).diff_udt("C".into()).unwrap();
+    assert_eq!(c.changes.len(), 2);
+    let UdtChange::BasesChanged { old, new } = &c.changes[0] else {
+        panic!("{:?}", c.changes[0]);
+    };
+    assert_eq!(old[0].ty, "Base");
+    assert_eq!((old[0].offset, new[0].offset), (Some(0), Some(8)));
+
+    let UdtChange::VTableChanged { old, new } = &c.changes[1] else {
+        panic!("{:?}", c.changes[1]);
+    };
+    let slots = |methods: &[VirtualMethod]| -> Vec<(String, u32)> {
+        methods
+            .iter()
+            .map(|m| (m.name.clone(), m.vtable_offset))
+            .collect()
+    };
+    assert_eq!(slots(old), [("f".to_string(), 0), ("g".to_string(), 8)]);
+    assert_eq!(slots(new), [("g".to_string(), 0), ("f".to_string(), 8)]);
+}
+
+#[test]
+fn added_and_removed_udts() {
+    let old = build(&OLD);
+    let mut t = TypeBuilder::new();
+    udt(&mut t, Leaf::LF_STRUCTURE, "Base", TypeIndex(0), 4, 0);
+    udt(&mut t, Leaf::LF_STRUCTURE, "New", TypeIndex(0), 8, 0);
+    udt(
+        &mut t,
+        Leaf::LF_STRUCTURE,
+        "<unnamed-tag>",
+        TypeIndex(0),
+        8,
+        0,
+    );
+    let new = type_stream(Stream::TPI, &t.finish());
+
+    let diff = TypeDiff::new(&old, &new);
+    let report = diff.diff_all().unwrap();
+    assert_eq!(report.added, ["New"]);
+    assert_eq!(report.removed, ["C"]);
+    assert!(report.changed.is_empty());
+
+    let err = diff.diff_udt("C".into()).unwrap_err();
+    assert!(format!("{err:#}").contains("not defined in the new type stream"));
+}
+
+#[test]
+fn malformed_method_list() {
+    let mut t = TypeBuilder::new();
+    let (_, _, proc) = common_types(&mut t);
+
+    // This method introduces a virtual function, but its vtable offset is missing.
+    let methods = t.record(Leaf::LF_METHODLIST, |e| {
+        e.u16(INTRO_VIRTUAL);
+        e.u16(0);
+        e.u32(proc.0);
+    });
+    let mut fields = FieldListBuilder::new();
+    fields.method(1, methods, "f".into());
+    let fields = t.field_list(&fields);
+    class(&mut t, "C", fields, 8, 0);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "n".into());
+    let fields = t.field_list(&fields);
+    class(&mut t, "D", fields, 4, 0);
+    let old = type_stream(Stream::TPI, &t.finish());
+
+    let diff = TypeDiff::new(&old, &old);
+    assert!(diff.diff_udt("C".into()).is_err());
+    assert!(diff.diff_udt("D".into()).unwrap().is_empty());
+
+    let report = diff.diff_all().unwrap();
+    assert_eq!(report.unparseable, ["C"]);
+    assert!(report.changed.is_empty());
+    assert!(!report.is_empty());
+}
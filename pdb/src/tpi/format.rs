@@ -0,0 +1,456 @@
+//! Renders C/C++ type names and declarations from type records.
+//!
+//! Type records describe a type "from the outside in": a pointer to an array of functions is an
+//! `LF_POINTER` that points to an `LF_ARRAY` whose element type is an `LF_PROCEDURE`. C/C++
+//! declarators are written "from the inside out", so the formatter builds the declarator in the
+//! same order that it walks the records. Each step wraps the declarator built so far, and the
+//! innermost type (a primitive or a UDT) finally becomes the type specifier on the left.
+
+use super::TypeStream;
+use crate::types::primitive::{PRIMITIVES, primitive_type_size};
+use crate::types::{
+    CallingConvention, POINTER_MODE_LVALUE_REF, POINTER_MODE_MEMBER_DATA,
+    POINTER_MODE_MEMBER_FUNCTION, POINTER_MODE_RVALUE_REF, TypeData, TypeIndex,
+};
+use bstr::BStr;
+
+/// The maximum depth of type records that the formatter will follow. This prevents malformed
+/// (cyclic) type streams from causing unbounded recursion.
+const MAX_DEPTH: u32 = 64;
+
+/// Renders C/C++ type names and declarations from the records of a [`TypeStream`].
+///
+/// ```ignore
+/// let tpi = pdb.read_type_stream()?;
+/// let f = TypeFormatter::new(&tpi).calling_conventions(true);
+/// println!("{}", f.format_decl(ty, "callback"));  // void (__cdecl *callback)(const char*, double)
+/// ```
+pub struct TypeFormatter<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    type_stream: &'a TypeStream<StreamData>,
+    calling_conventions: bool,
+    udt_names: Option<&'a UdtNamesFn<'a>>,
+}
+
+/// A function that renders the name of a UDT. See [`TypeFormatter::udt_names`].
+type UdtNamesFn<'a> = dyn Fn(TypeIndex, &BStr) -> String + 'a;
+
+/// The declarator that has been built so far, while walking from the outermost type record to
+/// the innermost one.
+struct Declarator {
+    text: String,
+    /// True if `text` starts with a pointer or reference operator. If a suffix (`[]` or `()`) is
+    /// applied to such a declarator, then it needs to be wrapped in parentheses.
+    is_pointer: bool,
+    /// True if the declarator contains a name. This only affects spacing.
+    named: bool,
+}
+
+impl Declarator {
+    fn new(name: &str) -> Self {
+        Self {
+            text: name.to_string(),
+            is_pointer: false,
+            named: !name.is_empty(),
+        }
+    }
+
+    /// Creates a declarator that wraps `inner`.
+    fn wrap(inner: &Declarator, text: String, is_pointer: bool) -> Self {
+        Self {
+            text,
+            is_pointer,
+            named: inner.named,
+        }
+    }
+
+    /// Joins a type specifier (or a return type) with this declarator. This produces `int*` for
+    /// a type name, but `int *p` for a declaration.
+    fn apply(&self, specifier: &str) -> String {
+        match self.text.as_bytes().first() {
+            None => specifier.to_string(),
+            Some(b'[') => format!("{specifier}{}", self.text),
+            Some(b'*' | b'&') if !self.named => format!("{specifier}{}", self.text),
+            Some(_) => format!("{specifier} {}", self.text),
+        }
+    }
+
+    /// Wraps the declarator in parentheses if it is a pointer or reference, so that a suffix
+    /// can be appended to it.
+    fn parenthesize(self) -> String {
+        if self.is_pointer {
+            format!("({})", self.text)
+        } else {
+            self.text
+        }
+    }
+}
+
+impl<'a, StreamData> TypeFormatter<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// Creates a formatter that reads type records from `type_stream`.
+    pub fn new(type_stream: &'a TypeStream<StreamData>) -> Self {
+        Self {
+            type_stream,
+            calling_conventions: false,
+            udt_names: None,
+        }
+    }
+
+    /// Specifies whether to show the calling conventions (such as `__cdecl`) of function types.
+    /// The default is `false`.
+    pub fn calling_conventions(mut self, value: bool) -> Self {
+        self.calling_conventions = value;
+        self
+    }
+
+    /// Specifies a function that renders the names of UDTs (structs, classes, unions, and enums).
+    /// The function receives the type index of the UDT record and the name stored in it. By
+    /// default, the stored name is used as-is.
+    pub fn udt_names(mut self, f: &'a UdtNamesFn<'a>) -> Self {
+        self.udt_names = Some(f);
+        self
+    }
+
+    /// Renders the name of a type, such as `const char*` or `int (*)[4]`.
+    pub fn format(&self, type_index: TypeIndex) -> String {
+        self.format_decl(type_index, "")
+    }
+
+    /// Renders a declaration of a variable (or function, or field) named `name`, whose type is
+    /// `type_index`, such as `const char* name` or `int (*name)[4]`. If `type_index` is an
+    /// `LF_BITFIELD`, then the width of the bitfield is appended, e.g. `unsigned long flags : 3`.
+    ///
+    /// If `name` is empty, this renders the type name.
+    pub fn format_decl(&self, type_index: TypeIndex, name: &str) -> String {
+        self.decl(type_index, Declarator::new(name), 0)
+    }
+
+    /// Gets the size in bytes of a type, if it can be determined without searching the type
+    /// stream. The size of a forward reference to a UDT is not known.
+    pub fn type_size(&self, type_index: TypeIndex) -> Option<u64> {
+        self.type_size_at_depth(type_index, 0)
+    }
+
+    fn type_size_at_depth(&self, type_index: TypeIndex, depth: u32) -> Option<u64> {
+        if self.type_stream.is_primitive(type_index) {
+            return primitive_type_size(type_index).map(u64::from);
+        }
+        if depth >= MAX_DEPTH {
+            return None;
+        }
+
+        let record = self.type_stream.record(type_index).ok()?;
+        match record.parse().ok()? {
+            TypeData::Struct(t) if !t.fixed.property.get().fwdref() => u64::try_from(t.length).ok(),
+            TypeData::Struct2(t) if !t.fixed.udt_properties().fwdref() => {
+                u64::try_from(t.length).ok()
+            }
+            TypeData::Union(t) if !t.fixed.property.get().fwdref() => u64::try_from(t.length).ok(),
+            TypeData::Union2(t) if !t.fixed.udt_properties().fwdref() => {
+                u64::try_from(t.length).ok()
+            }
+            TypeData::Enum(t) => self.type_size_at_depth(t.fixed.underlying_type.get(), depth + 1),
+            TypeData::Array(t) => u64::try_from(t.len).ok(),
+            TypeData::Pointer(t) => Some(t.fixed.attr().size() as u64),
+            TypeData::Modifier(t) => self.type_size_at_depth(t.underlying_type.get(), depth + 1),
+            TypeData::Alias(t) => self.type_size_at_depth(t.utype, depth + 1),
+            _ => None,
+        }
+    }
+
+    fn decl(&self, type_index: TypeIndex, inner: Declarator, depth: u32) -> String {
+        if self.type_stream.is_primitive(type_index) {
+            return self.primitive_decl(type_index, inner);
+        }
+
+        if depth >= MAX_DEPTH {
+            return inner.apply("...");
+        }
+
+        let Ok(record) = self.type_stream.record(type_index) else {
+            return inner.apply(&format!("{type_index:?}"));
+        };
+        let Ok(data) = record.parse() else {
+            return inner.apply(&format!("{type_index:?}"));
+        };
+
+        match data {
+            TypeData::Struct(t) => inner.apply(&self.udt_name(type_index, t.name)),
+            TypeData::Struct2(t) => inner.apply(&self.udt_name(type_index, t.name)),
+            TypeData::Union(t) => inner.apply(&self.udt_name(type_index, t.name)),
+            TypeData::Union2(t) => inner.apply(&self.udt_name(type_index, t.name)),
+            TypeData::Enum(t) => inner.apply(&self.udt_name(type_index, t.name)),
+            TypeData::Alias(t) => inner.apply(&t.name.to_string()),
+
+            TypeData::Modifier(t) => {
+                let underlying = t.underlying_type.get();
+                let cv = cv_string(t.is_const(), t.is_volatile(), t.is_unaligned());
+
+                // A modifier of a pointer qualifies the pointer itself, so the qualifier goes
+                // between the pointer operator and the inner declarator.
+                if !self.type_stream.is_primitive(underlying)
+                    && let Ok(r) = self.type_stream.record(underlying)
+                    && let Ok(TypeData::Pointer(_)) = r.parse()
+                {
+                    return self.pointer_decl(underlying, &cv, inner, depth + 1);
+                }
+
+                let decl = self.decl(underlying, inner, depth + 1);
+                if cv.is_empty() {
+                    decl
+                } else {
+                    format!("{cv} {decl}")
+                }
+            }
+
+            TypeData::Pointer(_) => self.pointer_decl(type_index, "", inner, depth),
+
+            TypeData::Array(t) => {
+                let element_type = t.fixed.element_type.get();
+                let byte_len = u64::try_from(t.len).ok();
+                let count = match (byte_len, self.type_size_at_depth(element_type, depth + 1)) {
+                    (Some(0), _) => String::new(),
+                    (Some(len), Some(size)) if size != 0 => (len / size).to_string(),
+                    _ => "?".to_string(),
+                };
+                let named = inner.named;
+                let text = format!("{}[{count}]", inner.parenthesize());
+                let outer = Declarator {
+                    text,
+                    is_pointer: false,
+                    named,
+                };
+                self.decl(element_type, outer, depth + 1)
+            }
+
+            TypeData::Proc(t) => {
+                let cc = self.calling_convention_keyword(t.calling_convention());
+                let args = self.arg_list(t.arg_list.get(), depth + 1);
+                let outer = function_declarator(cc, inner, &args, "");
+                self.decl(t.return_value.get(), outer, depth + 1)
+            }
+
+            TypeData::MemberFunc(t) => {
+                let cc = self.calling_convention_keyword(t.calling_convention());
+                let args = self.arg_list(t.arg_list.get(), depth + 1);
+                let qualifiers = self.this_qualifiers(t.this.get());
+                let outer = function_declarator(cc, inner, &args, &qualifiers);
+                self.decl(t.return_value.get(), outer, depth + 1)
+            }
+
+            TypeData::Bitfield(t) => {
+                let decl = self.decl(t.underlying_type.get(), inner, depth + 1);
+                format!("{decl} : {}", t.length)
+            }
+
+            _ => inner.apply(&format!("<{:?} {type_index:?}>", record.kind)),
+        }
+    }
+
+    fn udt_name(&self, type_index: TypeIndex, name: &BStr) -> String {
+        match self.udt_names {
+            Some(f) => f(type_index, name),
+            None => name.to_string(),
+        }
+    }
+
+    fn primitive_decl(&self, type_index: TypeIndex, inner: Declarator) -> String {
+        let mode = (type_index.0 >> 8) & 7;
+        let base = type_index.0 & 0xff;
+
+        let base_name = match PRIMITIVES.binary_search_by_key(&base, |entry| entry.0) {
+            Ok(i) => PRIMITIVES[i].2,
+            Err(_) => return inner.apply(&format!("{type_index:?}")),
+        };
+
+        if mode == 0 {
+            inner.apply(base_name)
+        } else {
+            // Primitive pointer types, such as T_64PVOID
+            let text = if inner.text.is_empty() {
+                "*".to_string()
+            } else {
+                format!("*{}", inner.text)
+            };
+            Declarator::wrap(&inner, text, true).apply(base_name)
+        }
+    }
+
+    /// Renders an `LF_POINTER` record. `extra_cv` contains qualifiers of the pointer that came
+    /// from an `LF_MODIFIER` that points to the pointer.
+    fn pointer_decl(
+        &self,
+        type_index: TypeIndex,
+        extra_cv: &str,
+        inner: Declarator,
+        depth: u32,
+    ) -> String {
+        let Ok(record) = self.type_stream.record(type_index) else {
+            return inner.apply(&format!("{type_index:?}"));
+        };
+        let Ok(TypeData::Pointer(p)) = record.parse() else {
+            return inner.apply(&format!("{type_index:?}"));
+        };
+
+        let attr = p.fixed.attr();
+        let mut text = match attr.mode() {
+            POINTER_MODE_LVALUE_REF => "&".to_string(),
+            POINTER_MODE_RVALUE_REF => "&&".to_string(),
+            POINTER_MODE_MEMBER_DATA | POINTER_MODE_MEMBER_FUNCTION => {
+                let class = match p.member_class() {
+                    Some(class) => self.decl(class, Declarator::new(""), depth + 1),
+                    None => "?".to_string(),
+                };
+                format!("{class}::*")
+            }
+            _ => "*".to_string(),
+        };
+
+        let mut cv = cv_string(attr.r#const(), attr.volatile(), attr.unaligned());
+        if attr.restrict() {
+            push_word(&mut cv, "__restrict");
+        }
+        if !extra_cv.is_empty() {
+            push_word(&mut cv, extra_cv);
+        }
+        if !cv.is_empty() {
+            text.push(' ');
+            text.push_str(&cv);
+            if !inner.text.is_empty() {
+                text.push(' ');
+            }
+        }
+        text.push_str(&inner.text);
+
+        self.decl(
+            p.fixed.ty.get(),
+            Declarator::wrap(&inner, text, true),
+            depth + 1,
+        )
+    }
+
+    fn calling_convention_keyword(&self, cc: CallingConvention) -> Option<&'static str> {
+        if self.calling_conventions {
+            cc.keyword()
+        } else {
+            None
+        }
+    }
+
+    /// Renders the parameter list of an `LF_ARGLIST`.
+    fn arg_list(&self, arg_list: TypeIndex, depth: u32) -> String {
+        if self.type_stream.is_primitive(arg_list) {
+            return String::new();
+        }
+        let Ok(record) = self.type_stream.record(arg_list) else {
+            return "?".to_string();
+        };
+        let Ok(TypeData::ArgList(args)) = record.parse() else {
+            return "?".to_string();
+        };
+
+        if args.args.is_empty() {
+            return "void".to_string();
+        }
+
+        let mut out = String::new();
+        for (i, arg) in args.args.iter().enumerate() {
+            if i != 0 {
+                out.push_str(", ");
+            }
+            let arg = arg.get();
+            // A T_NOTYPE argument at the end of the list indicates a variable argument list.
+            if arg == TypeIndex::T_NOTYPE && i + 1 == args.args.len() {
+                out.push_str("...");
+            } else {
+                out.push_str(&self.decl(arg, Declarator::new(""), depth + 1));
+            }
+        }
+        out
+    }
+
+    /// Gets the qualifiers of a member function (e.g. ` const` or ` &&`) from the type of its
+    /// `this` pointer. Static member functions have no `this` pointer.
+    fn this_qualifiers(&self, this: TypeIndex) -> String {
+        let mut out = String::new();
+        if self.type_stream.is_primitive(this) {
+            return out;
+        }
+        let Ok(record) = self.type_stream.record(this) else {
+            return out;
+        };
+        let Ok(TypeData::Pointer(p)) = record.parse() else {
+            return out;
+        };
+
+        let pointee = p.fixed.ty.get();
+        if !self.type_stream.is_primitive(pointee)
+            && let Ok(r) = self.type_stream.record(pointee)
+            && let Ok(TypeData::Modifier(m)) = r.parse()
+        {
+            let cv = cv_string(m.is_const(), m.is_volatile(), m.is_unaligned());
+            if !cv.is_empty() {
+                out.push(' ');
+                out.push_str(&cv);
+            }
+        }
+
+        let attr = p.fixed.attr();
+        if attr.islref() {
+            out.push_str(" &");
+        } else if attr.isrref() {
+            out.push_str(" &&");
+        }
+        out
+    }
+}
+
+/// Builds the declarator of a function type: the inner declarator, followed by the parameter list
+/// and any qualifiers of `this`.
+fn function_declarator(
+    cc: Option<&str>,
+    inner: Declarator,
+    args: &str,
+    qualifiers: &str,
+) -> Declarator {
+    let named = inner.named;
+    let head = match cc {
+        Some(cc) if inner.is_pointer => format!("({})", inner.apply(cc)),
+        Some(cc) => inner.apply(cc),
+        None => inner.parenthesize(),
+    };
+    Declarator {
+        text: format!("{head}({args}){qualifiers}"),
+        is_pointer: false,
+        named,
+    }
+}
+
+fn push_word(s: &mut String, word: &str) {
+    if !s.is_empty() {
+        s.push(' ');
+    }
+    s.push_str(word);
+}
+
+fn cv_string(is_const: bool, is_volatile: bool, is_unaligned: bool) -> String {
+    let mut s = String::new();
+    if is_const {
+        push_word(&mut s, "const");
+    }
+    if is_volatile {
+        push_word(&mut s, "volatile");
+    }
+    if is_unaligned {
+        push_word(&mut s, "__unaligned");
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests;
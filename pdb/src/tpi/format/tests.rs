@@ -0,0 +1,127 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::testing::*;
+use crate::types::builder::TypeBuilder;
+use crate::types::{Leaf, MemberFunc, PointerFixed};
+use zerocopy::{U16, U32};
+
+#[test]
+fn format_pointers_and_modifiers() {
+    let mut t = TypeBuilder::new();
+    let const_char = modifier(&mut t, TypeIndex::T_RCHAR, 1);
+    let pconst_char = pointer(&mut t, const_char, PTR64);
+    let pint = pointer(&mut t, TypeIndex::T_INT4, PTR64);
+    let const_pint = modifier(&mut t, pint, 1);
+    let const_pint2 = pointer(&mut t, TypeIndex::T_INT4, PTR64 | PTR_CONST);
+    let ppint = pointer(&mut t, pint, PTR64);
+    let foo = udt(&mut t, Leaf::LF_STRUCTURE, "Foo", TypeIndex(0), 8, 0);
+    let const_foo = modifier(&mut t, foo, 1);
+    let lref = pointer(&mut t, const_foo, PTR64 | MODE_LVREF);
+    let rref = pointer(&mut t, foo, PTR64 | MODE_RVREF);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let f = TypeFormatter::new(&tpi);
+    assert_eq!(f.format(pconst_char), "const char*");
+    assert_eq!(f.format_decl(pconst_char, "s"), "const char *s");
+    assert_eq!(f.format(const_pint), "__int32* const");
+    assert_eq!(f.format_decl(const_pint2, "p"), "__int32 * const p");
+    assert_eq!(f.format_decl(ppint, "pp"), "__int32 **pp");
+    assert_eq!(f.format(lref), "const Foo&");
+    assert_eq!(f.format_decl(rref, "r"), "Foo &&r");
+    assert_eq!(f.format(TypeIndex::T_64PVOID), "void*");
+    assert_eq!(f.format_decl(TypeIndex::T_REAL64, "d"), "double d");
+}
+
+#[test]
+fn format_functions_and_arrays() {
+    let mut t = TypeBuilder::new();
+    let const_char = modifier(&mut t, TypeIndex::T_RCHAR, 1);
+    let pconst_char = pointer(&mut t, const_char, PTR64);
+    let func = proc(
+        &mut t,
+        TypeIndex::T_INT4,
+        &[pconst_char, TypeIndex::T_REAL64],
+    );
+    let pfunc = pointer(&mut t, func, PTR64);
+    let varargs = proc(
+        &mut t,
+        TypeIndex::T_VOID,
+        &[TypeIndex::T_INT4, TypeIndex::T_NOTYPE],
+    );
+    let no_args = proc(&mut t, TypeIndex::T_VOID, &[]);
+    let pint = pointer(&mut t, TypeIndex::T_INT4, PTR64);
+    let array_of_pint = array(&mut t, pint, 32);
+    let int_array = array(&mut t, TypeIndex::T_INT4, 16);
+    let p_int_array = pointer(&mut t, int_array, PTR64);
+    let array_of_pfunc = array(&mut t, pfunc, 16);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let f = TypeFormatter::new(&tpi);
+    assert_eq!(f.format(pfunc), "__int32 (*)(const char*, double)");
+    assert_eq!(f.format(varargs), "void (__int32, ...)");
+    assert_eq!(f.format_decl(no_args, "f"), "void f(void)");
+    assert_eq!(f.format_decl(array_of_pint, "a"), "__int32 *a[4]");
+    assert_eq!(f.format(p_int_array), "__int32 (*)[4]");
+    assert_eq!(f.format_decl(p_int_array, "p"), "__int32 (*p)[4]");
+    assert_eq!(
+        f.format_decl(array_of_pfunc, "table"),
+        "__int32 (*table[2])(const char*, double)"
+    );
+
+    let f = TypeFormatter::new(&tpi).calling_conventions(true);
+    assert_eq!(f.format(pfunc), "__int32 (__cdecl*)(const char*, double)");
+    assert_eq!(
+        f.format_decl(pfunc, "cb"),
+        "__int32 (__cdecl *cb)(const char*, double)"
+    );
+    assert_eq!(f.format_decl(no_args, "f"), "void __cdecl f(void)");
+}
+
+#[test]
+fn format_members_and_bitfields() {
+    let mut t = TypeBuilder::new();
+    let foo = udt(&mut t, Leaf::LF_STRUCTURE, "Foo", TypeIndex(0), 8, 0);
+    let const_foo = modifier(&mut t, foo, 1);
+    let this = pointer(&mut t, const_foo, PTR64 | PTR_CONST);
+    let args = t.arg_list(&[]);
+
+    let mfunc = t.member_func(&MemberFunc {
+        return_value: TypeIndex::T_VOID.into(),
+        class: foo.into(),
+        this: this.into(),
+        call: CallingConvention::THISCALL.0,
+        reserved: 0,
+        num_params: U16::new(0),
+        arg_list: args.into(),
+        this_adjust: U32::new(0),
+    });
+
+    let pmfunc = t.member_pointer(
+        &PointerFixed {
+            ty: mfunc.into(),
+            attr: U32::new(PTR64 | MODE_PMFUNC),
+        },
+        foo,
+        0,
+    );
+    let pmem = t.member_pointer(
+        &PointerFixed {
+            ty: TypeIndex::T_INT4.into(),
+            attr: U32::new(0x0c | (4 << 13) | MODE_PMEM),
+        },
+        foo,
+        0,
+    );
+    let bitfield = bitfield(&mut t, TypeIndex::T_ULONG, 3, 5);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let f = TypeFormatter::new(&tpi);
+    assert_eq!(f.format(pmfunc), "void (Foo::*)(void) const");
+    assert_eq!(f.format_decl(pmem, "pm"), "__int32 Foo::*pm");
+    assert_eq!(f.format_decl(bitfield, "flags"), "unsigned long flags : 3");
+    assert_eq!(f.type_size(foo), Some(8));
+    assert_eq!(f.type_size(pmem), Some(4));
+
+    let f = TypeFormatter::new(&tpi).calling_conventions(true);
+    assert_eq!(f.format(pmfunc), "void (__thiscall Foo::*)(void) const");
+}
@@ -4,11 +4,17 @@
 //!
 //! * [`TPI1::hashPrec` in `tpi.cpp`](https://github.com/microsoft/microsoft-pdb/blob/805655a28bd8198004be2ac27e6e0290121a5e89/PDB/dbi/tpi.cpp#L1296)
 
+use super::{HashIndexPair, TypeStreamHeader};
 use crate::hash::hash_u32;
-use crate::types::{Leaf, TypeData, UdtProperties};
+use crate::names::NameIndex;
+use crate::pdbi::HashEntry;
+use crate::types::{Leaf, TypeData, TypeIndex, UdtProperties};
+use anyhow::bail;
 use bstr::BStr;
-use ms_codeview::parser::ParserError;
-use zerocopy::IntoBytes;
+use ms_codeview::parser::{Parser, ParserError};
+use std::mem::size_of;
+use std::ops::Range;
+use zerocopy::{FromBytes, IntoBytes, LE, U32};
 
 /// Hash a type record, using the same rules as `TPI1::hashPrec`.
 pub fn hash_type_record(
@@ -29,6 +35,14 @@ pub fn hash_type_record(
             t.unique_name,
         )),
 
+        // This handles LF_CLASS2, LF_STRUCTURE2, and LF_INTERFACE2.
+        TypeData::Struct2(t) => Ok(hash_udt_name(
+            t.fixed.udt_properties(),
+            record_bytes,
+            t.name,
+            t.unique_name,
+        )),
+
         TypeData::Union(t) => Ok(hash_udt_name(
             t.fixed.property.get(),
             record_bytes,
@@ -36,6 +50,13 @@ pub fn hash_type_record(
             t.unique_name,
         )),
 
+        TypeData::Union2(t) => Ok(hash_udt_name(
+            t.fixed.udt_properties(),
+            record_bytes,
+            t.name,
+            t.unique_name,
+        )),
+
         TypeData::Enum(t) => Ok(hash_udt_name(
             t.fixed.property.get(),
             record_bytes,
@@ -76,3 +97,184 @@ pub fn is_udt_anon_name(name: &BStr) -> bool {
         || name.ends_with(b"::<unnamed-tag>")
         || name.ends_with(b"::__unnamed")
 }
+
+/// The contents of a Type Hash Stream.
+///
+/// The Type Hash Stream contains the hash value of each type record, a list of
+/// [`HashIndexPair`] values that allows finding records by type index, and the Hash Adjusters
+/// table. The locations of these buffers are specified in the [`TypeStreamHeader`].
+///
+/// The hash values in this stream have already been reduced modulo `num_hash_buckets`, so they
+/// identify the bucket that contains each record.
+pub struct TypeHashStream<StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// The stream data of the Type Hash Stream.
+    pub stream_data: StreamData,
+
+    num_hash_buckets: u32,
+    hash_values: Range<usize>,
+    index_offsets: Range<usize>,
+    hash_adjusters: Vec<HashAdjuster>,
+}
+
+/// An entry in the Hash Adjusters table.
+///
+/// When more than one type record has the same name, the Hash Adjusters table specifies which
+/// record should be found by a lookup of that name.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct HashAdjuster {
+    /// The name of the type. This is an offset into the Names Stream (`/names`).
+    pub name: NameIndex,
+    /// The type record that should be found for `name`.
+    pub type_index: TypeIndex,
+}
+
+impl<StreamData> TypeHashStream<StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// Parses a Type Hash Stream. `header` is the header of the Type Stream that owns this
+    /// Type Hash Stream.
+    pub fn parse(header: &TypeStreamHeader, stream_data: StreamData) -> anyhow::Result<Self> {
+        let bytes = stream_data.as_ref();
+
+        let num_types = header
+            .type_index_end
+            .get()
+            .0
+            .saturating_sub(header.type_index_begin.get().0);
+
+        let hash_values = buffer_range(
+            bytes,
+            "hash value",
+            header.hash_value_buffer_offset.get(),
+            header.hash_value_buffer_length.get(),
+        )?;
+        let num_hash_buckets = header.num_hash_buckets.get();
+        if !hash_values.is_empty() {
+            let hash_key_size = header.hash_key_size.get();
+            if hash_key_size != 4 {
+                bail!("The Type Hash Stream uses an unsupported hash key size ({hash_key_size})");
+            }
+            if hash_values.len() != num_types as usize * 4 {
+                bail!(
+                    "The Type Hash Stream contains {} bytes of hash values, but {} type records were expected",
+                    hash_values.len(),
+                    num_types
+                );
+            }
+            if num_hash_buckets == 0 {
+                bail!(
+                    "The Type Hash Stream contains hash values, but the number of hash buckets is zero"
+                );
+            }
+        }
+
+        let index_offsets = buffer_range(
+            bytes,
+            "index offset",
+            header.index_offset_buffer_offset.get(),
+            header.index_offset_buffer_length.get(),
+        )?;
+        if !index_offsets
+            .len()
+            .is_multiple_of(size_of::<HashIndexPair>())
+        {
+            bail!("The Type Hash Stream index offset buffer has an invalid length");
+        }
+
+        let hash_adj = buffer_range(
+            bytes,
+            "hash adjuster",
+            header.hash_adj_buffer_offset.get(),
+            header.hash_adj_buffer_length.get(),
+        )?;
+        let hash_adjusters = if hash_adj.is_empty() {
+            Vec::new()
+        } else {
+            parse_hash_adjusters(&mut Parser::new(&bytes[hash_adj]))?
+        };
+
+        Ok(Self {
+            stream_data,
+            num_hash_buckets,
+            hash_values,
+            index_offsets,
+            hash_adjusters,
+        })
+    }
+
+    /// The number of hash buckets. This is copied from the Type Stream Header.
+    pub fn num_hash_buckets(&self) -> u32 {
+        self.num_hash_buckets
+    }
+
+    /// The hash value of each type record, in type index order. Each hash value is the index of a
+    /// hash bucket. This is empty if the stream does not contain hash values.
+    pub fn hash_values(&self) -> &[U32<LE>] {
+        <[U32<LE>]>::ref_from_bytes(&self.stream_data.as_ref()[self.hash_values.clone()]).unwrap()
+    }
+
+    /// The Type Index Offset Buffer. This lists the byte offset of every Nth type record, in
+    /// increasing type index order.
+    pub fn index_offsets(&self) -> &[HashIndexPair] {
+        <[HashIndexPair]>::ref_from_bytes(&self.stream_data.as_ref()[self.index_offsets.clone()])
+            .unwrap()
+    }
+
+    /// The entries in the Hash Adjusters table.
+    pub fn hash_adjusters(&self) -> &[HashAdjuster] {
+        &self.hash_adjusters
+    }
+}
+
+/// Validates the location of one of the buffers within the Type Hash Stream.
+fn buffer_range(
+    bytes: &[u8],
+    what: &str,
+    offset: i32,
+    length: u32,
+) -> anyhow::Result<Range<usize>> {
+    if length == 0 {
+        return Ok(0..0);
+    }
+    let Ok(start) = usize::try_from(offset) else {
+        bail!("The Type Hash Stream {what} buffer has a negative offset");
+    };
+    let end = start + length as usize;
+    if end > bytes.len() {
+        bail!("The Type Hash Stream {what} buffer is out of range of the stream");
+    }
+    Ok(start..end)
+}
+
+/// Parses the Hash Adjusters table. This uses the same serialized hash table format as the
+/// Named Streams table, but without the string data.
+fn parse_hash_adjusters(p: &mut Parser) -> anyhow::Result<Vec<HashAdjuster>> {
+    let size = p.u32()?;
+    let _capacity = p.u32()?;
+
+    let present_u32_count = p.u32()?;
+    let present_mask = p.bytes(present_u32_count as usize * 4)?;
+    let present_num_items: u32 = present_mask.iter().map(|&b| b.count_ones()).sum();
+
+    let deleted_u32_count = p.u32()?;
+    p.skip(deleted_u32_count as usize * 4)?;
+
+    if present_num_items != size {
+        bail!(
+            "The Hash Adjusters table contains inconsistent values. Size is {size}, but present bitmap count is {present_num_items}."
+        );
+    }
+
+    let items: &[HashEntry] = p.slice(size as usize)?;
+    Ok(items
+        .iter()
+        .map(|item| HashAdjuster {
+            name: NameIndex(item.key.get()),
+            type_index: TypeIndex(item.value.get()),
+        })
+        .collect())
+}
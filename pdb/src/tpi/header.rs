@@ -0,0 +1,1050 @@
+//! Reconstructs a C/C++ header from the UDT definitions in a type stream.
+//!
+//! The generated header contains a definition for each selected struct, class, union, and enum,
+//! and for every UDT that those definitions depend on. Definitions are emitted in dependency
+//! order: a UDT that is used by value (as a member or a base class) is defined before the UDT
+//! that contains it. A UDT that is only used through a pointer or reference is forward-declared
+//! instead, which allows cyclic references between UDTs.
+//!
+//! Type records do not describe everything that a header needs, so some details are
+//! reconstructed from the layout of each UDT:
+//!
+//! * Gaps between members are filled with explicit `unsigned char` padding arrays.
+//! * Members that overlap each other (at the same offset) are placed in anonymous unions. A run
+//!   of consecutive members within such a union is placed in an anonymous struct.
+//! * A UDT that has its own virtual function table pointer gets an explicit `__vfptr` member.
+//!   Methods are not emitted.
+//! * Names that are not valid C++ identifiers (template instances, unnamed types) are rewritten
+//!   to valid identifiers. The namespaces in qualified names become `namespace` blocks, and nested
+//!   types are defined within the UDT that contains them.
+//!
+//! Because of these reconstructions, the generated layout can differ from the layout described
+//! by the PDB. The generator can emit a `static_assert` for the size of each UDT and the offset
+//! of each member, so that compiling the header verifies it against the PDB.
+
+use super::{TypeFormatter, TypeLayout, TypeStream};
+use crate::types::fields::Field;
+use crate::types::{Leaf, TypeData, TypeIndex};
+use bstr::BStr;
+use std::collections::{HashMap, HashSet};
+
+/// The maximum depth of type records that are followed when searching for dependencies.
+const MAX_DEPTH: u32 = 64;
+
+/// Generates a C/C++ header that defines UDTs (structs, classes, unions, and enums) found in a
+/// [`TypeStream`].
+///
+/// ```ignore
+/// let tpi = pdb.read_type_stream()?;
+/// let mut generator = HeaderGenerator::new(&tpi).static_asserts(true);
+/// generator.add_types_where(|name| name.starts_with(b"_IMAGE_"));
+/// print!("{}", generator.generate());
+/// ```
+pub struct HeaderGenerator<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    type_stream: &'a TypeStream<StreamData>,
+    layout: TypeLayout<'a, StreamData>,
+    static_asserts: bool,
+
+    /// The definition of each UDT, in type index order. If more than one record defines a UDT
+    /// with the same name, then only the one found by the layout's `UdtIndex` is listed.
+    definitions: Vec<TypeIndex>,
+    /// Maps a UDT definition to the definitions of the UDTs that are nested within it.
+    nested: HashMap<TypeIndex, Vec<TypeIndex>>,
+
+    /// The UDTs that were selected for the header.
+    roots: Vec<TypeIndex>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum UdtKind {
+    Struct,
+    Class,
+    Union,
+    Enum,
+}
+
+impl UdtKind {
+    fn keyword(self) -> &'static str {
+        match self {
+            Self::Struct => "struct",
+            Self::Class => "class",
+            Self::Union => "union",
+            Self::Enum => "enum",
+        }
+    }
+}
+
+/// The parts of an `LF_STRUCTURE`, `LF_CLASS`, `LF_UNION`, or `LF_ENUM` record (or of an
+/// `LF_STRUCTURE2`, `LF_CLASS2`, or `LF_UNION2` record) that the generator
+/// uses.
+struct Udt<'a> {
+    kind: UdtKind,
+    name: &'a BStr,
+    unique_name: Option<&'a BStr>,
+    fwdref: bool,
+    packed: bool,
+    scoped: bool,
+    fields: TypeIndex,
+    /// The size of the UDT. Enums do not store a size.
+    size: Option<u64>,
+    /// The underlying type of an enum.
+    underlying_type: TypeIndex,
+}
+
+/// A data member (or a virtual function table pointer) of a UDT.
+struct DataMember {
+    offset: u64,
+    size: Option<u64>,
+    name: String,
+    decl: String,
+    bitfield: Option<Bitfield>,
+}
+
+struct Bitfield {
+    position: u8,
+    length: u8,
+    /// The declaration of the underlying type, e.g. `unsigned long`.
+    underlying: String,
+}
+
+impl DataMember {
+    fn end(&self) -> u64 {
+        self.offset + self.size.unwrap_or(0)
+    }
+
+    /// Checks whether this member is a bitfield that continues the bitfield `prev`, within the same
+    /// storage unit.
+    fn continues_bitfield(&self, prev: &DataMember) -> bool {
+        match (&self.bitfield, &prev.bitfield) {
+            (Some(b), Some(p)) => self.offset == prev.offset && b.position > p.position,
+            _ => false,
+        }
+    }
+}
+
+/// Dependencies of a UDT definition on other UDT definitions.
+#[derive(Default)]
+struct Deps {
+    /// UDTs that must be complete, because they are used by value.
+    value: Vec<TypeIndex>,
+    /// UDTs that only need to be declared, because they are used through pointers.
+    decl: Vec<TypeIndex>,
+}
+
+impl<'a, StreamData> HeaderGenerator<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// Creates a generator for the UDTs in `type_stream`. This scans the type stream to find the
+    /// definitions of all UDTs. No UDTs are selected for the header.
+    pub fn new(type_stream: &'a TypeStream<StreamData>) -> Self {
+        let mut generator = Self {
+            type_stream,
+            layout: TypeLayout::new(type_stream),
+            static_asserts: false,
+            definitions: Vec::new(),
+            nested: HashMap::new(),
+            roots: Vec::new(),
+        };
+
+        let mut type_index = type_stream.type_index_begin();
+        for record in type_stream.iter_type_records() {
+            let ti = type_index;
+            type_index.0 += 1;
+
+            let Some(udt) = parse_udt(record.kind, record.data) else {
+                continue;
+            };
+            if udt.fwdref {
+                continue;
+            }
+
+            // Anonymous UDTs are not indexed by name, so each of them is listed.
+            let index = &generator.layout.udt_index;
+            let def = match udt.unique_name {
+                Some(unique_name) if udt.scoped => index.find_udt_by_unique_name(unique_name),
+                _ => index.find_udt_by_name(udt.name),
+            };
+            if def.is_none_or(|def| def == ti) {
+                generator.definitions.push(ti);
+            }
+        }
+
+        for i in 0..generator.definitions.len() {
+            let ti = generator.definitions[i];
+            if let Some(parent) = generator.parent(ti) {
+                generator.nested.entry(parent).or_default().push(ti);
+            }
+        }
+
+        generator
+    }
+
+    /// Specifies whether to emit `static_assert` declarations that check the size of each UDT
+    /// and the offset of each data member against the values stored in the type stream.
+    /// The default is `false`.
+    pub fn static_asserts(mut self, value: bool) -> Self {
+        self.static_asserts = value;
+        self
+    }
+
+    /// Selects a UDT for the header. If `type_index` is a forward reference, then the definition
+    /// of the UDT is selected. If `type_index` is not a UDT, then this does nothing.
+    pub fn add_type(&mut self, type_index: TypeIndex) {
+        if self.udt(type_index).is_some() {
+            let def = self.resolve(type_index);
+            if !self.roots.contains(&def) {
+                self.roots.push(def);
+            }
+        }
+    }
+
+    /// Selects all UDTs whose name matches `predicate`.
+    pub fn add_types_where(&mut self, mut predicate: impl FnMut(&BStr) -> bool) {
+        for i in 0..self.definitions.len() {
+            let ti = self.definitions[i];
+            if let Some(udt) = self.udt(ti)
+                && predicate(udt.name)
+            {
+                self.add_type(ti);
+            }
+        }
+    }
+
+    /// Generates the header for the selected UDTs.
+    pub fn generate(&self) -> String {
+        let udt_names = |ti: TypeIndex, _: &BStr| self.qualified_name(self.resolve(ti));
+        let formatter = TypeFormatter::new(self.type_stream).udt_names(&udt_names);
+
+        let mut emitter = Emitter {
+            generator: self,
+            formatter,
+            state: HashMap::new(),
+            declared: HashSet::new(),
+            items: Vec::new(),
+        };
+        for &root in self.roots.iter() {
+            emitter.emit(root);
+        }
+
+        let mut out = String::new();
+        out.push_str("#pragma once\n");
+        if self.static_asserts {
+            out.push_str("\n#include <cstddef>\n");
+        }
+
+        // Consecutive items in the same namespace share a namespace block.
+        let mut current_namespace: Option<&str> = None;
+        for item in emitter.items.iter() {
+            if current_namespace != Some(item.namespace.as_str()) {
+                if let Some(ns) = current_namespace
+                    && !ns.is_empty()
+                {
+                    out.push_str("\n}\n");
+                }
+                if !item.namespace.is_empty() {
+                    out.push_str(&format!("\nnamespace {} {{\n", item.namespace));
+                }
+                current_namespace = Some(item.namespace.as_str());
+            }
+            out.push('\n');
+            out.push_str(&item.text);
+        }
+        if let Some(ns) = current_namespace
+            && !ns.is_empty()
+        {
+            out.push_str("\n}\n");
+        }
+
+        out
+    }
+
+    fn udt(&self, type_index: TypeIndex) -> Option<Udt<'a>> {
+        if self.type_stream.is_primitive(type_index) {
+            return None;
+        }
+        let record = self.type_stream.record(type_index).ok()?;
+        parse_udt(record.kind, record.data)
+    }
+
+    /// If `type_index` is a forward reference to a UDT, finds the definition of the UDT.
+    /// Otherwise, returns `type_index`.
+    fn resolve(&self, type_index: TypeIndex) -> TypeIndex {
+        self.layout.resolve(type_index)
+    }
+
+    /// Finds the UDT that contains the definition of a nested UDT, using the qualified name of the
+    /// nested UDT.
+    fn parent(&self, type_index: TypeIndex) -> Option<TypeIndex> {
+        let udt = self.udt(type_index)?;
+        let name = udt.name.to_string();
+        let parts = split_qualified_name(&name);
+        if parts.len() < 2 {
+            return None;
+        }
+        let prefix_len = name.len() - parts[parts.len() - 1].len() - 2;
+        let parent = self
+            .layout
+            .udt_index
+            .find_udt_by_name(BStr::new(&name[..prefix_len]))?;
+        match self.udt(parent)?.kind {
+            UdtKind::Enum => None,
+            _ if parent == type_index => None,
+            _ => Some(parent),
+        }
+    }
+
+    /// Finds the UDT that is not nested within any other UDT and which contains `type_index`.
+    fn outermost(&self, type_index: TypeIndex) -> TypeIndex {
+        let mut ti = self.resolve(type_index);
+        for _ in 0..MAX_DEPTH {
+            match self.parent(ti) {
+                Some(parent) => ti = parent,
+                None => break,
+            }
+        }
+        ti
+    }
+
+    /// Gets the namespace that contains a UDT that is not nested within another UDT.
+    fn namespace(&self, type_index: TypeIndex) -> String {
+        let Some(udt) = self.udt(type_index) else {
+            return String::new();
+        };
+        let name = udt.name.to_string();
+        let parts = split_qualified_name(&name);
+        let mut ns = String::new();
+        for part in parts.iter().take(parts.len().saturating_sub(1)) {
+            if !ns.is_empty() {
+                ns.push_str("::");
+            }
+            ns.push_str(&sanitize_identifier(part));
+        }
+        ns
+    }
+
+    /// Gets the name of a UDT definition, as it is written in the header. If `qualified` is
+    /// false, then only the last component of the name is returned.
+    fn udt_name(&self, type_index: TypeIndex, qualified: bool) -> String {
+        let Some(udt) = self.udt(type_index) else {
+            return format!("{type_index:?}");
+        };
+        let name = udt.name.to_string();
+        let parts = split_qualified_name(&name);
+        let last = parts.len() - 1;
+        let mut out = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i != last && !qualified {
+                continue;
+            }
+            if !out.is_empty() {
+                out.push_str("::");
+            }
+            if i == last && is_unnamed(part) {
+                out.push_str(&format!("_unnamed_{:x}", type_index.0));
+            } else {
+                out.push_str(&sanitize_identifier(part));
+            }
+        }
+        out
+    }
+
+    fn qualified_name(&self, type_index: TypeIndex) -> String {
+        self.udt_name(type_index, true)
+    }
+
+    /// Finds the dependencies of a UDT definition and of the UDTs nested within it.
+    fn udt_deps(&self, type_index: TypeIndex, deps: &mut Deps, depth: u32) {
+        let Some(udt) = self.udt(type_index) else {
+            return;
+        };
+        if udt.kind == UdtKind::Enum || depth >= MAX_DEPTH {
+            return;
+        }
+
+        for field in self.type_stream.iter_fields(udt.fields) {
+            match field {
+                Field::BaseClass(b) => deps.value.push(self.resolve(b.ty)),
+                Field::DirectVirtualBaseClass(b) => {
+                    deps.value.push(self.resolve(b.fixed.btype.get()))
+                }
+                Field::IndirectVirtualBaseClass(b) => {
+                    deps.value.push(self.resolve(b.fixed.btype.get()))
+                }
+                Field::Member(m) => self.type_deps(m.ty, true, deps, 0),
+                Field::StaticMember(m) => self.type_deps(m.ty, false, deps, 0),
+                _ => {}
+            }
+        }
+
+        if let Some(nested) = self.nested.get(&type_index) {
+            for &child in nested.iter() {
+                self.udt_deps(child, deps, depth + 1);
+            }
+        }
+    }
+
+    /// Finds the UDTs that are used by a type. `by_value` indicates whether the type is used by
+    /// value, or is only used through a pointer, reference, or function signature.
+    fn type_deps(&self, ti: TypeIndex, by_value: bool, deps: &mut Deps, depth: u32) {
+        if self.type_stream.is_primitive(ti) || depth >= MAX_DEPTH {
+            return;
+        }
+        let Ok(record) = self.type_stream.record(ti) else {
+            return;
+        };
+        let Ok(data) = record.parse() else {
+            return;
+        };
+
+        let depth = depth + 1;
+        match data {
+            TypeData::Struct(_)
+            | TypeData::Struct2(_)
+            | TypeData::Union(_)
+            | TypeData::Union2(_)
+            | TypeData::Enum(_) => {
+                if by_value {
+                    deps.value.push(self.resolve(ti));
+                } else {
+                    deps.decl.push(self.resolve(ti));
+                }
+            }
+            TypeData::Modifier(t) => self.type_deps(t.underlying_type.get(), by_value, deps, depth),
+            TypeData::Array(t) => self.type_deps(t.fixed.element_type.get(), by_value, deps, depth),
+            TypeData::Bitfield(t) => self.type_deps(t.underlying_type.get(), by_value, deps, depth),
+            TypeData::Alias(t) => self.type_deps(t.utype, by_value, deps, depth),
+            TypeData::Pointer(t) => {
+                self.type_deps(t.fixed.ty.get(), false, deps, depth);
+                if let Some(class) = t.member_class() {
+                    self.type_deps(class, false, deps, depth);
+                }
+            }
+            TypeData::Proc(t) => {
+                self.type_deps(t.return_value.get(), false, deps, depth);
+                self.type_deps(t.arg_list.get(), false, deps, depth);
+            }
+            TypeData::MemberFunc(t) => {
+                self.type_deps(t.return_value.get(), false, deps, depth);
+                self.type_deps(t.class.get(), false, deps, depth);
+                self.type_deps(t.arg_list.get(), false, deps, depth);
+            }
+            TypeData::ArgList(t) => {
+                for arg in t.args.iter() {
+                    self.type_deps(arg.get(), false, deps, depth);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum EmitState {
+    InProgress,
+    Done,
+}
+
+/// A forward declaration or a definition, which is placed in a namespace.
+struct Item {
+    namespace: String,
+    text: String,
+}
+
+/// Holds the state of header generation.
+struct Emitter<'g, 'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    generator: &'g HeaderGenerator<'a, StreamData>,
+    formatter: TypeFormatter<'g, StreamData>,
+    state: HashMap<TypeIndex, EmitState>,
+    /// UDTs that have been forward-declared.
+    declared: HashSet<TypeIndex>,
+    items: Vec<Item>,
+}
+
+impl<'g, 'a, StreamData> Emitter<'g, 'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// Emits the definition of a UDT, after emitting its dependencies. If the UDT is nested
+    /// within another UDT, then the outermost UDT is emitted.
+    fn emit(&mut self, type_index: TypeIndex) {
+        let generator = self.generator;
+        let top = generator.outermost(type_index);
+        if self.state.contains_key(&top) {
+            // Either this UDT has already been emitted, or there is a cycle of UDTs that contain
+            // each other by value. A cycle cannot be represented, so ignore it.
+            return;
+        }
+        self.state.insert(top, EmitState::InProgress);
+
+        let mut deps = Deps::default();
+        generator.udt_deps(top, &mut deps, 0);
+
+        for &dep in deps.value.iter() {
+            if generator.outermost(dep) != top {
+                self.emit(dep);
+            }
+        }
+
+        for &dep in deps.decl.iter() {
+            let dep_top = generator.outermost(dep);
+            if dep_top == top {
+                continue;
+            }
+            let Some(udt) = generator.udt(dep) else {
+                continue;
+            };
+
+            // Nested UDTs can only be declared within the UDT that contains them. Enums are
+            // always defined, since they have no dependencies.
+            if dep_top != dep || udt.kind == UdtKind::Enum {
+                self.emit(dep);
+                continue;
+            }
+
+            if self.state.get(&dep) != Some(&EmitState::Done) && self.declared.insert(dep) {
+                self.items.push(Item {
+                    namespace: generator.namespace(dep),
+                    text: format!(
+                        "{} {};\n",
+                        udt.kind.keyword(),
+                        generator.udt_name(dep, false)
+                    ),
+                });
+            }
+        }
+
+        let mut text = String::new();
+        let mut asserts = String::new();
+        self.write_definition(top, 0, &mut text, &mut asserts);
+        self.items.push(Item {
+            namespace: generator.namespace(top),
+            text,
+        });
+        if !asserts.is_empty() {
+            self.items.push(Item {
+                namespace: String::new(),
+                text: asserts,
+            });
+        }
+
+        self.state.insert(top, EmitState::Done);
+    }
+
+    fn write_definition(
+        &self,
+        type_index: TypeIndex,
+        indent: usize,
+        out: &mut String,
+        asserts: &mut String,
+    ) {
+        let generator = self.generator;
+        let Some(udt) = generator.udt(type_index) else {
+            return;
+        };
+        let pad = "    ".repeat(indent);
+        let name = generator.udt_name(type_index, false);
+        let qualified_name = generator.qualified_name(type_index);
+
+        if udt.kind == UdtKind::Enum {
+            let class = if udt.scoped { " class" } else { "" };
+            let underlying = self.formatter.format(udt.underlying_type);
+            out.push_str(&format!("{pad}enum{class} {name} : {underlying} {{\n"));
+            for field in generator.type_stream.iter_fields(udt.fields) {
+                if let Field::Enumerate(e) = field {
+                    out.push_str(&format!("{pad}    {} = {},\n", e.name, e.value));
+                }
+            }
+            out.push_str(&format!("{pad}}};\n"));
+            return;
+        }
+
+        let mut bases: Vec<String> = Vec::new();
+        let mut base_end: u64 = 0;
+        let mut vfptr: Option<TypeIndex> = None;
+        let mut polymorphic_base = false;
+        let mut virtual_bases = false;
+        let mut static_members: Vec<String> = Vec::new();
+        let mut members: Vec<DataMember> = Vec::new();
+
+        for field in generator.type_stream.iter_fields(udt.fields) {
+            match field {
+                Field::BaseClass(b) => {
+                    bases.push(format!(
+                        "{} {}",
+                        access(b.attr),
+                        self.formatter.format(b.ty)
+                    ));
+                    if let (Ok(offset), Some(size)) =
+                        (u64::try_from(b.offset), generator.layout.size_of(b.ty))
+                    {
+                        base_end = base_end.max(offset + size);
+                    }
+                    polymorphic_base |= generator
+                        .layout
+                        .udt_layout(b.ty)
+                        .is_ok_and(|l| l.members.iter().any(|m| m.is_vfptr));
+                }
+                Field::DirectVirtualBaseClass(b) => {
+                    let base = self.formatter.format(b.fixed.btype.get());
+                    bases.push(format!("{} virtual {base}", access(b.fixed.attr.get())));
+                    virtual_bases = true;
+                }
+                Field::IndirectVirtualBaseClass(_) => virtual_bases = true,
+                Field::VFuncTable(ty) => vfptr = Some(ty),
+                Field::StaticMember(m) => {
+                    let decl = self.formatter.format_decl(m.ty, &m.name.to_string());
+                    static_members.push(format!("static {decl};"));
+                }
+                Field::Member(m) => {
+                    let Ok(offset) = u64::try_from(m.offset) else {
+                        continue;
+                    };
+                    let name = m.name.to_string();
+                    let decl = self.formatter.format_decl(m.ty, &name);
+                    let mut bitfield = None;
+                    let mut size = generator.layout.size_of(m.ty);
+                    if !generator.type_stream.is_primitive(m.ty)
+                        && let Ok(r) = generator.type_stream.record(m.ty)
+                        && let Ok(TypeData::Bitfield(b)) = r.parse()
+                    {
+                        let underlying = b.underlying_type.get();
+                        size = generator.layout.size_of(underlying);
+                        bitfield = Some(Bitfield {
+                            position: b.position,
+                            length: b.length,
+                            underlying: self.formatter.format(underlying),
+                        });
+                    }
+                    members.push(DataMember {
+                        offset,
+                        size,
+                        name,
+                        decl,
+                        bitfield,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(vfptr) = vfptr {
+            // MSVC places the vfptr of a class that introduces one at offset 0, before any
+            // non-polymorphic base classes.
+            members.insert(
+                0,
+                DataMember {
+                    offset: if polymorphic_base { base_end } else { 0 },
+                    size: Some(generator.layout.size_of(vfptr).unwrap_or(8)),
+                    name: "__vfptr".to_string(),
+                    decl: "void* __vfptr".to_string(),
+                    bitfield: None,
+                },
+            );
+        }
+
+        if udt.packed {
+            out.push_str(&format!("{pad}#pragma pack(push, 1)\n"));
+        }
+        out.push_str(&format!("{pad}{} {name}", udt.kind.keyword()));
+        if !bases.is_empty() {
+            out.push_str(" : ");
+            out.push_str(&bases.join(", "));
+        }
+        out.push_str(" {\n");
+        if udt.kind == UdtKind::Class {
+            out.push_str(&format!("{pad}public:\n"));
+        }
+
+        if let Some(nested) = generator.nested.get(&type_index) {
+            for &child in self.order_nested(type_index, nested).iter() {
+                self.write_definition(child, indent + 1, out, asserts);
+            }
+        }
+
+        for s in static_members.iter() {
+            out.push_str(&format!("{pad}    {s}\n"));
+        }
+
+        // The compiler places the vbptr of a class with virtual bases, and stores the virtual bases
+        // after the members, so the offsets in the type stream cannot be reproduced with explicit
+        // padding. The members of such a class are declared in order, without padding or asserts.
+        let mut layout = Layout {
+            out,
+            num_padding: 0,
+            explicit_padding: !virtual_bases,
+        };
+        if udt.kind == UdtKind::Union {
+            layout.write_union(&members, 0, udt.size, indent + 1, false);
+        } else {
+            layout.write_struct(&members, base_end, udt.size, indent + 1);
+        }
+
+        out.push_str(&format!("{pad}}};\n"));
+        if udt.packed {
+            out.push_str(&format!("{pad}#pragma pack(pop)\n"));
+        }
+
+        if generator.static_asserts && !virtual_bases {
+            if let Some(size) = udt.size {
+                asserts.push_str(&format!(
+                    "static_assert(sizeof({qualified_name}) == {size}, \"{qualified_name}\");\n"
+                ));
+            }
+            for m in members
+                .iter()
+                .filter(|m| m.bitfield.is_none() && !m.name.is_empty())
+            {
+                asserts.push_str(&format!(
+                    "static_assert(offsetof({qualified_name}, {}) == {}, \"{qualified_name}::{}\");\n",
+                    m.name, m.offset, m.name
+                ));
+            }
+        }
+    }
+
+    /// Orders the UDTs that are nested within `parent` so that each one is defined after the
+    /// nested UDTs that it uses by value.
+    fn order_nested(&self, parent: TypeIndex, nested: &[TypeIndex]) -> Vec<TypeIndex> {
+        fn visit<StreamData: AsRef<[u8]>>(
+            generator: &HeaderGenerator<'_, StreamData>,
+            parent: TypeIndex,
+            nested: &[TypeIndex],
+            ti: TypeIndex,
+            visited: &mut HashSet<TypeIndex>,
+            order: &mut Vec<TypeIndex>,
+        ) {
+            if !visited.insert(ti) {
+                return;
+            }
+            let mut deps = Deps::default();
+            generator.udt_deps(ti, &mut deps, 0);
+            for dep in deps.value {
+                // Find the sibling of `ti` that contains `dep`, if any.
+                let mut sibling = dep;
+                for _ in 0..MAX_DEPTH {
+                    match generator.parent(sibling) {
+                        Some(p) if p == parent => break,
+                        Some(p) => sibling = p,
+                        None => break,
+                    }
+                }
+                if sibling != ti && nested.contains(&sibling) {
+                    visit(generator, parent, nested, sibling, visited, order);
+                }
+            }
+            order.push(ti);
+        }
+
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        for &ti in nested.iter() {
+            visit(self.generator, parent, nested, ti, &mut visited, &mut order);
+        }
+        order
+    }
+}
+
+/// Writes the data members of a UDT, with padding and anonymous unions.
+struct Layout<'o> {
+    out: &'o mut String,
+    num_padding: u32,
+    /// If `false`, gaps between members are left for the compiler to fill.
+    explicit_padding: bool,
+}
+
+impl<'o> Layout<'o> {
+    fn write_padding(&mut self, len: u64, indent: usize) {
+        if !self.explicit_padding {
+            return;
+        }
+        let pad = "    ".repeat(indent);
+        self.out.push_str(&format!(
+            "{pad}unsigned char _padding{}[{len}];\n",
+            self.num_padding
+        ));
+        self.num_padding += 1;
+    }
+
+    fn write_member(&mut self, members: &[DataMember], i: usize, indent: usize) {
+        let pad = "    ".repeat(indent);
+        let m = &members[i];
+        if let Some(b) = &m.bitfield {
+            // Fill any gap between this bitfield and the previous one in the same storage unit.
+            let start = match i.checked_sub(1).map(|p| &members[p]) {
+                Some(prev) if m.continues_bitfield(prev) => match &prev.bitfield {
+                    Some(p) => p.position + p.length,
+                    None => 0,
+                },
+                _ => 0,
+            };
+            if b.position > start {
+                let gap = b.position - start;
+                self.out
+                    .push_str(&format!("{pad}{} : {gap};\n", b.underlying));
+            }
+        }
+        self.out.push_str(&format!("{pad}{};\n", m.decl));
+    }
+
+    /// Checks whether `members[i]` begins a new member at `offset`, rather than continuing a
+    /// bitfield.
+    fn starts_at(members: &[DataMember], i: usize, offset: u64) -> bool {
+        members[i].offset == offset && (i == 0 || !members[i].continues_bitfield(&members[i - 1]))
+    }
+
+    /// Writes members that are laid out sequentially, starting at `cursor`. If `size` is
+    /// specified, then tail padding is added up to `size`.
+    fn write_struct(
+        &mut self,
+        members: &[DataMember],
+        cursor: u64,
+        size: Option<u64>,
+        indent: usize,
+    ) {
+        let mut cursor = cursor;
+        let mut k = 0;
+        while k < members.len() {
+            let m = &members[k];
+            if m.offset > cursor {
+                self.write_padding(m.offset - cursor, indent);
+                cursor = m.offset;
+            }
+
+            // If a later member starts at the same offset as this one, then both are alternatives
+            // of an anonymous union.
+            let mut last_alternative = None;
+            for x in k + 1..members.len() {
+                if members[x].offset < m.offset {
+                    break;
+                }
+                if Self::starts_at(members, x, m.offset) {
+                    last_alternative = Some(x);
+                }
+            }
+
+            let Some(last_alternative) = last_alternative else {
+                self.write_member(members, k, indent);
+                cursor = cursor.max(m.end());
+                k += 1;
+                continue;
+            };
+
+            // The union extends to include the members of the last alternative.
+            let mut union_end = members[k..=last_alternative]
+                .iter()
+                .map(|m| m.end())
+                .max()
+                .unwrap_or(m.offset);
+            let mut r = last_alternative + 1;
+            while r < members.len() && members[r].offset < union_end {
+                union_end = union_end.max(members[r].end());
+                r += 1;
+            }
+
+            let pad = "    ".repeat(indent);
+            self.out.push_str(&format!("{pad}union {{\n"));
+            self.write_union(&members[k..r], m.offset, None, indent + 1, true);
+            self.out.push_str(&format!("{pad}}};\n"));
+            cursor = cursor.max(union_end);
+            k = r;
+        }
+
+        if let Some(size) = size
+            && size > cursor
+        {
+            self.write_padding(size - cursor, indent);
+        }
+    }
+
+    /// Writes members that overlap, starting at `offset`. A run of members that follow an
+    /// alternative, and which do not start at `offset`, is placed in an anonymous struct.
+    fn write_union(
+        &mut self,
+        members: &[DataMember],
+        offset: u64,
+        size: Option<u64>,
+        indent: usize,
+        nested: bool,
+    ) {
+        let pad = "    ".repeat(indent);
+        let mut k = 0;
+        while k < members.len() {
+            let mut end = k + 1;
+            while end < members.len() && !Self::starts_at(members, end, offset) {
+                end += 1;
+            }
+            if end == k + 1 && members[k].offset == offset {
+                self.write_member(members, k, indent);
+            } else {
+                self.out.push_str(&format!("{pad}struct {{\n"));
+                self.write_struct(&members[k..end], offset, None, indent + 1);
+                self.out.push_str(&format!("{pad}}};\n"));
+            }
+            k = end;
+        }
+
+        if !nested
+            && let Some(size) = size
+            && members.iter().map(|m| m.end()).max().unwrap_or(0) < size
+        {
+            self.write_padding(size, indent);
+        }
+    }
+}
+
+fn parse_udt(kind: Leaf, data: &[u8]) -> Option<Udt<'_>> {
+    let udt_kind = match kind {
+        Leaf::LF_STRUCTURE | Leaf::LF_INTERFACE | Leaf::LF_STRUCTURE2 | Leaf::LF_INTERFACE2 => {
+            UdtKind::Struct
+        }
+        Leaf::LF_CLASS | Leaf::LF_CLASS2 => UdtKind::Class,
+        Leaf::LF_UNION | Leaf::LF_UNION2 => UdtKind::Union,
+        Leaf::LF_ENUM => UdtKind::Enum,
+        _ => return None,
+    };
+
+    Some(match TypeData::parse_bytes(kind, data).ok()? {
+        TypeData::Struct(t) => {
+            let property = t.fixed.property.get();
+            Udt {
+                kind: udt_kind,
+                name: t.name,
+                unique_name: t.unique_name,
+                fwdref: property.fwdref(),
+                packed: property.packed(),
+                scoped: property.scoped(),
+                fields: t.fixed.field_list.get(),
+                size: u64::try_from(t.length).ok(),
+                underlying_type: TypeIndex::T_NOTYPE,
+            }
+        }
+        TypeData::Struct2(t) => {
+            let property = t.fixed.udt_properties();
+            Udt {
+                kind: udt_kind,
+                name: t.name,
+                unique_name: t.unique_name,
+                fwdref: property.fwdref(),
+                packed: property.packed(),
+                scoped: property.scoped(),
+                fields: t.fixed.field_list.get(),
+                size: u64::try_from(t.length).ok(),
+                underlying_type: TypeIndex::T_NOTYPE,
+            }
+        }
+        TypeData::Union(t) => {
+            let property = t.fixed.property.get();
+            Udt {
+                kind: udt_kind,
+                name: t.name,
+                unique_name: t.unique_name,
+                fwdref: property.fwdref(),
+                packed: property.packed(),
+                scoped: property.scoped(),
+                fields: t.fixed.fields.get(),
+                size: u64::try_from(t.length).ok(),
+                underlying_type: TypeIndex::T_NOTYPE,
+            }
+        }
+        TypeData::Union2(t) => {
+            let property = t.fixed.udt_properties();
+            Udt {
+                kind: udt_kind,
+                name: t.name,
+                unique_name: t.unique_name,
+                fwdref: property.fwdref(),
+                packed: property.packed(),
+                scoped: property.scoped(),
+                fields: t.fixed.fields.get(),
+                size: u64::try_from(t.length).ok(),
+                underlying_type: TypeIndex::T_NOTYPE,
+            }
+        }
+        TypeData::Enum(t) => {
+            let property = t.fixed.property.get();
+            Udt {
+                kind: udt_kind,
+                name: t.name,
+                unique_name: t.unique_name,
+                fwdref: property.fwdref(),
+                packed: false,
+                scoped: property.scoped(),
+                fields: t.fixed.fields.get(),
+                size: None,
+                underlying_type: t.fixed.underlying_type.get(),
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Gets the access specifier from the `attr` field of a base class.
+fn access(attr: u16) -> &'static str {
+    match attr & 3 {
+        1 => "private",
+        2 => "protected",
+        _ => "public",
+    }
+}
+
+/// Splits a qualified name, such as `ns::Outer<a::b>::Inner`, into its components. Separators
+/// within template arguments, parentheses, and quotes (as in `` `anonymous namespace' ``) are
+/// ignored.
+fn split_qualified_name(name: &str) -> Vec<&str> {
+    let bytes = name.as_bytes();
+    let mut parts = Vec::new();
+    let mut depth: i32 = 0;
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'<' | b'(' | b'[' | b'`' => depth += 1,
+            b'>' | b')' | b']' | b'\'' => depth -= 1,
+            b':' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                parts.push(&name[start..i]);
+                i += 2;
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(&name[start..]);
+    parts
+}
+
+/// Checks whether a name component is one that the compiler invents for unnamed UDTs.
+fn is_unnamed(part: &str) -> bool {
+    part.starts_with("<unnamed-")
+        || part.starts_with("<anonymous-")
+        || part.starts_with("__unnamed")
+}
+
+/// Replaces characters that are not valid in a C/C++ identifier with underscores.
+fn sanitize_identifier(part: &str) -> String {
+    let mut s: String = part
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,305 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::testing::*;
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+use crate::types::fields::VirtualBaseClassFixed;
+use crate::types::{Struct2Fixed, Union2Fixed};
+use zerocopy::{FromZeros, U16};
+
+const FWDREF: u16 = 1 << 7;
+
+#[test]
+fn layout_padding_bitfields_and_unions() {
+    let mut t = TypeBuilder::new();
+    let inner_fwd = udt(&mut t, Leaf::LF_STRUCTURE, "Inner", TypeIndex(0), 0, FWDREF);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "a".into());
+    let inner_fields = t.field_list(&fields);
+    let inner = udt(&mut t, Leaf::LF_STRUCTURE, "Inner", inner_fields, 4, 0);
+    let x = bitfield(&mut t, TypeIndex::T_ULONG, 3, 0);
+    let y = bitfield(&mut t, TypeIndex::T_ULONG, 4, 4);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_RCHAR, 0, "c".into());
+    fields.member(PUBLIC, inner_fwd, 4, "in".into());
+    fields.member(PUBLIC, x, 8, "x".into());
+    fields.member(PUBLIC, y, 8, "y".into());
+    fields.member(PUBLIC, TypeIndex::T_INT8, 16, "u".into());
+    fields.member(PUBLIC, TypeIndex::T_INT4, 16, "v".into());
+    fields.member(PUBLIC, TypeIndex::T_INT4, 20, "w".into());
+    let fields = t.field_list(&fields);
+    let outer = udt(&mut t, Leaf::LF_STRUCTURE, "Outer", fields, 32, 0);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let mut generator = HeaderGenerator::new(&tpi).static_asserts(true);
+    generator.add_type(outer);
+    let header = generator.generate();
+    assert_eq!(
+        header,
+        "\
+#pragma once
+
+#include <cstddef>
+
+struct Inner {
+    __int32 a;
+};
+
+static_assert(sizeof(Inner) == 4, \"Inner\");
+static_assert(offsetof(Inner, a) == 0, \"Inner::a\");
+
+struct Outer {
+    char c;
+    unsigned char _padding0[3];
+    Inner in;
+    unsigned long x : 3;
+    unsigned long : 1;
+    unsigned long y : 4;
+    unsigned char _padding1[4];
+    union {
+        __int64 u;
+        struct {
+            __int32 v;
+            __int32 w;
+        };
+    };
+    unsigned char _padding2[8];
+};
+
+static_assert(sizeof(Outer) == 32, \"Outer\");
+static_assert(offsetof(Outer, c) == 0, \"Outer::c\");
+static_assert(offsetof(Outer, in) == 4, \"Outer::in\");
+static_assert(offsetof(Outer, u) == 16, \"Outer::u\");
+static_assert(offsetof(Outer, v) == 16, \"Outer::v\");
+static_assert(offsetof(Outer, w) == 20, \"Outer::w\");
+"
+    );
+    assert_eq!(inner, generator.resolve(inner_fwd));
+}
+
+#[test]
+fn dependency_order_namespaces_and_nesting() {
+    let mut t = TypeBuilder::new();
+    let a_fwd = udt(&mut t, Leaf::LF_STRUCTURE, "ns::A", TypeIndex(0), 0, FWDREF);
+    let b_fwd = udt(&mut t, Leaf::LF_CLASS, "ns::B", TypeIndex(0), 0, FWDREF);
+    let pa = pointer(&mut t, a_fwd, PTR64);
+
+    let mut fields = FieldListBuilder::new();
+    fields.enumerate(PUBLIC, 0, "Red".into());
+    fields.enumerate(PUBLIC, 1, "Green".into());
+    let color_fields = t.field_list(&fields);
+    let color = enum_(&mut t, "ns::B::Color", TypeIndex::T_INT4, color_fields);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_REAL32, 0, "f".into());
+    let unnamed_fields = t.field_list(&fields);
+    let unnamed = udt(
+        &mut t,
+        Leaf::LF_UNION,
+        "ns::B::<unnamed-tag>",
+        unnamed_fields,
+        8,
+        0,
+    );
+
+    // B points to A, and A contains B, so A must be forward-declared.
+    let mut fields = FieldListBuilder::new();
+    fields.nested_type(color, "Color".into());
+    fields.nested_type(unnamed, "<unnamed-tag>".into());
+    fields.member(PUBLIC, pa, 0, "a".into());
+    fields.member(PUBLIC, color, 8, "color".into());
+    fields.member(PUBLIC, unnamed, 16, "u".into());
+    let b_fields = t.field_list(&fields);
+    udt(&mut t, Leaf::LF_CLASS, "ns::B", b_fields, 24, 0);
+
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, b_fwd, 0, "b".into());
+    let a_fields = t.field_list(&fields);
+    udt(&mut t, Leaf::LF_STRUCTURE, "ns::A", a_fields, 24, 0);
+
+    let vtshape = t.vtable_shape(1, &[0]);
+    let pvtshape = pointer(&mut t, vtshape, PTR64);
+    let mut fields = FieldListBuilder::new();
+    fields.base_class(PUBLIC, a_fwd, 8);
+    fields.vfunc_table(pvtshape);
+    fields.member(PUBLIC, TypeIndex::T_INT4, 32, "n".into());
+    let c_fields = t.field_list(&fields);
+    udt(&mut t, Leaf::LF_STRUCTURE, "C<ns::A>", c_fields, 40, 0);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let mut generator = HeaderGenerator::new(&tpi);
+    generator.add_types_where(|name| name.starts_with(b"C<"));
+    let header = generator.generate();
+    assert_eq!(
+        header,
+        "\
+#pragma once
+
+namespace ns {
+
+struct A;
+
+class B {
+public:
+    enum Color : __int32 {
+        Red = 0,
+        Green = 1,
+    };
+    union _unnamed_1006 {
+        float f;
+        unsigned char _padding0[8];
+    };
+    ns::A *a;
+    ns::B::Color color;
+    unsigned char _padding0[4];
+    ns::B::_unnamed_1006 u;
+};
+
+struct A {
+    ns::B b;
+};
+
+}
+
+struct C_ns__A_ : public ns::A {
+    void* __vfptr;
+    __int32 n;
+    unsigned char _padding0[4];
+};
+"
+    );
+}
+
+#[test]
+fn vfptr_before_non_polymorphic_base() {
+    let mut t = TypeBuilder::new();
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "a".into());
+    let a_fields = t.field_list(&fields);
+    let a = udt(&mut t, Leaf::LF_STRUCTURE, "A", a_fields, 4, 0);
+    let vtshape = t.vtable_shape(1, &[0]);
+    let pvtshape = pointer(&mut t, vtshape, PTR64);
+    let mut fields = FieldListBuilder::new();
+    fields.base_class(PUBLIC, a, 8);
+    fields.vfunc_table(pvtshape);
+    fields.member(PUBLIC, TypeIndex::T_INT4, 12, "n".into());
+    let c_fields = t.field_list(&fields);
+    let c = udt(&mut t, Leaf::LF_STRUCTURE, "C", c_fields, 16, 0);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let mut generator = HeaderGenerator::new(&tpi).static_asserts(true);
+    generator.add_type(c);
+    let header = generator.generate();
+    assert!(
+        header.contains("static_assert(offsetof(C, __vfptr) == 0, \"C::__vfptr\");\n"),
+        "{header}"
+    );
+    assert!(
+        header.contains("static_assert(offsetof(C, n) == 12, \"C::n\");\n"),
+        "{header}"
+    );
+}
+
+#[test]
+fn virtual_base_without_padding_or_asserts() {
+    let mut t = TypeBuilder::new();
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "b".into());
+    let fields = t.field_list(&fields);
+    let base = udt(&mut t, Leaf::LF_STRUCTURE, "Base", fields, 4, 0);
+    let vbptr = pointer(&mut t, TypeIndex::T_INT4, PTR64);
+
+    // The vbptr is at offset 0, `d` follows it, and the virtual base is stored at offset 12.
+    let mut fields = FieldListBuilder::new();
+    fields.virtual_base_class(
+        Leaf::LF_VBCLASS,
+        &VirtualBaseClassFixed {
+            attr: U16::new(PUBLIC),
+            btype: base.into(),
+            vbtype: vbptr.into(),
+        },
+        0,
+        1,
+    );
+    fields.member(PUBLIC, TypeIndex::T_INT4, 8, "d".into());
+    let fields = t.field_list(&fields);
+    let derived = udt(&mut t, Leaf::LF_STRUCTURE, "Derived", fields, 16, 0);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let mut generator = HeaderGenerator::new(&tpi).static_asserts(true);
+    generator.add_type(derived);
+    assert_eq!(
+        generator.generate(),
+        "\
+#pragma once
+
+#include <cstddef>
+
+struct Base {
+    __int32 b;
+};
+
+static_assert(sizeof(Base) == 4, \"Base\");
+static_assert(offsetof(Base, b) == 0, \"Base::b\");
+
+struct Derived : public virtual Base {
+    __int32 d;
+};
+"
+    );
+}
+
+#[test]
+fn structure2_and_union2() {
+    let mut t = TypeBuilder::new();
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "i".into());
+    fields.member(PUBLIC, TypeIndex::T_REAL32, 0, "f".into());
+    let fields = t.field_list(&fields);
+    let mut fixed = Union2Fixed::new_zeroed();
+    fixed.fields = fields.into();
+    let u = t.union2(&fixed, 4, "U2".into(), None);
+
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_RCHAR, 0, "c".into());
+    fields.member(PUBLIC, u, 4, "u".into());
+    let fields = t.field_list(&fields);
+    let mut fixed = Struct2Fixed::new_zeroed();
+    fixed.field_list = fields.into();
+    let s = t.struct2(Leaf::LF_STRUCTURE2, &fixed, 8, "S2".into(), None);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let mut generator = HeaderGenerator::new(&tpi).static_asserts(true);
+    generator.add_type(s);
+    assert_eq!(
+        generator.generate(),
+        "\
+#pragma once
+
+#include <cstddef>
+
+union U2 {
+    __int32 i;
+    float f;
+};
+
+static_assert(sizeof(U2) == 4, \"U2\");
+static_assert(offsetof(U2, i) == 0, \"U2::i\");
+static_assert(offsetof(U2, f) == 0, \"U2::f\");
+
+struct S2 {
+    char c;
+    unsigned char _padding0[3];
+    U2 u;
+};
+
+static_assert(sizeof(S2) == 8, \"S2\");
+static_assert(offsetof(S2, c) == 0, \"S2::c\");
+static_assert(offsetof(S2, u) == 4, \"S2::u\");
+"
+    );
+
+    // The members of the union overlap, but that is expected.
+    let layout = TypeLayout::new(&tpi);
+    assert_eq!(layout.align_of(s), Some(4));
+    assert!(layout.udt_layout(u).unwrap().overlaps.is_empty());
+    assert_eq!(TypeFormatter::new(&tpi).format_decl(u, "x"), "U2 x");
+}
@@ -0,0 +1,476 @@
+//! Computes the sizes, alignments, and member layouts of types.
+//!
+//! Type records store the size of each UDT and the offset of each data member, but not the
+//! alignment of types or the location of padding. [`TypeLayout`] derives these from the records.
+//! It looks through forward references to the definitions of UDTs, so it can be used with the
+//! type indexes found in member lists and symbols, which usually refer to forward references.
+//!
+//! Alignment is computed using the natural alignment of primitive types, as MSVC does. Packing
+//! is only detected if the UDT record has the `packed` property; UDTs that were compiled with a
+//! `#pragma pack` that does not change their layout are not reported as packed.
+
+use super::{TypeStream, UdtIndex};
+use crate::types::fields::Field;
+use crate::types::primitive::primitive_type_size;
+use crate::types::{Leaf, TypeData, TypeIndex};
+use anyhow::bail;
+
+/// The maximum depth of type records (and base classes) that are followed. This prevents
+/// malformed (cyclic) type streams from causing unbounded recursion.
+const MAX_DEPTH: u32 = 64;
+
+/// Computes the sizes, alignments, and member layouts of the types in a [`TypeStream`].
+///
+/// ```ignore
+/// let tpi = pdb.read_type_stream()?;
+/// let layout = TypeLayout::new(&tpi);
+/// let udt = layout.udt_layout(ty)?;
+/// println!("{} has {} bytes of padding", udt.name, udt.padding());
+/// ```
+pub struct TypeLayout<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    type_stream: &'a TypeStream<StreamData>,
+
+    /// Finds the definitions of forward references.
+    pub(super) udt_index: UdtIndex<'a, StreamData>,
+}
+
+/// The layout of a UDT (a struct, class, or union).
+#[derive(Clone, Debug)]
+pub struct UdtLayout {
+    /// The definition of the UDT. This is not a forward reference.
+    pub type_index: TypeIndex,
+    /// `LF_STRUCTURE`, `LF_CLASS`, `LF_INTERFACE`, `LF_UNION`, or one of their `*2` variants
+    pub kind: Leaf,
+    /// The name of the UDT.
+    pub name: String,
+    /// The size of the UDT in bytes, including tail padding.
+    pub size: u64,
+    /// The alignment of the UDT in bytes.
+    pub align: u64,
+    /// True if the UDT record has the `packed` property.
+    pub packed: bool,
+    /// True if the UDT has virtual base classes. The members of virtual base classes are not
+    /// included in `members`, so the space that they (and the virtual base pointer) occupy is
+    /// reported in `holes`.
+    pub has_virtual_bases: bool,
+    /// The data members of the UDT, including the members inherited from non-virtual base
+    /// classes and virtual function table pointers, sorted by offset.
+    pub members: Vec<MemberLayout>,
+    /// Ranges of bytes that are not occupied by any member, sorted by offset. This includes tail
+    /// padding.
+    pub holes: Vec<Hole>,
+    /// Pairs of members that occupy some of the same bits. Members of a union always overlap, so
+    /// this is only computed for structs and classes. Overlaps within a struct are usually caused
+    /// by anonymous unions.
+    pub overlaps: Vec<Overlap>,
+}
+
+/// The location of a data member within a UDT.
+#[derive(Clone, Debug)]
+pub struct MemberLayout {
+    /// The name of the member. For a virtual function table pointer, this is `__vfptr`.
+    pub name: String,
+    /// The type of the member. For bitfields, this is the `LF_BITFIELD` record.
+    pub ty: TypeIndex,
+    /// The byte offset of the member within the outermost UDT.
+    pub offset: u64,
+    /// The size in bytes of the member, if known. For bitfields, this is the size of the
+    /// storage unit that contains the bitfield.
+    pub size: Option<u64>,
+    /// The location of a bitfield within its storage unit.
+    pub bitfield: Option<BitfieldLayout>,
+    /// The UDT that declares this member. For inherited members, this is a base class.
+    pub declared_in: TypeIndex,
+    /// True if this member is a virtual function table pointer.
+    pub is_vfptr: bool,
+}
+
+/// The location of a bitfield within its storage unit.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct BitfieldLayout {
+    /// The position of the first bit, counting from the least-significant bit of the storage unit.
+    pub position: u8,
+    /// The number of bits.
+    pub length: u8,
+}
+
+/// A range of bytes within a UDT that is not occupied by any member.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Hole {
+    /// The byte offset of the hole.
+    pub offset: u64,
+    /// The size of the hole in bytes.
+    pub size: u64,
+}
+
+/// Identifies two members that overlap. The values are indexes into [`UdtLayout::members`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Overlap {
+    #[allow(missing_docs)]
+    pub first: usize,
+    #[allow(missing_docs)]
+    pub second: usize,
+}
+
+impl UdtLayout {
+    /// The total number of bytes in holes, including tail padding.
+    pub fn padding(&self) -> u64 {
+        self.holes.iter().map(|h| h.size).sum()
+    }
+
+    /// The number of bytes of padding at the end of the UDT.
+    pub fn tail_padding(&self) -> u64 {
+        match self.holes.last() {
+            Some(h) if h.offset + h.size == self.size => h.size,
+            _ => 0,
+        }
+    }
+}
+
+impl MemberLayout {
+    /// The range of bits that this member occupies, relative to the start of the UDT.
+    fn bit_range(&self) -> std::ops::Range<u64> {
+        let start = self.offset * 8;
+        match self.bitfield {
+            Some(b) => start + b.position as u64..start + b.position as u64 + b.length as u64,
+            None => start..start + self.size.unwrap_or(0) * 8,
+        }
+    }
+}
+
+impl<'a, StreamData> TypeLayout<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// Creates a `TypeLayout` for the types in `type_stream`.
+    pub fn new(type_stream: &'a TypeStream<StreamData>) -> Self {
+        Self {
+            type_stream,
+            udt_index: UdtIndex::new(type_stream),
+        }
+    }
+
+    /// Uses `udt_index` to find the definitions of forward references. This allows using an
+    /// index that was built from the Type Hash Stream.
+    pub fn udt_index(mut self, udt_index: UdtIndex<'a, StreamData>) -> Self {
+        self.udt_index = udt_index;
+        self
+    }
+
+    /// If `type_index` is a forward reference to a UDT, finds the definition of the UDT. Otherwise
+    /// (or if there is no definition), returns `type_index`.
+    pub fn resolve(&self, type_index: TypeIndex) -> TypeIndex {
+        self.udt_index
+            .resolve_forward_ref(type_index)
+            .unwrap_or(type_index)
+    }
+
+    /// Gets the size of a type in bytes. Returns `None` for types that do not have a size, such
+    /// as `void` and function types, and for forward references that have no definition.
+    pub fn size_of(&self, type_index: TypeIndex) -> Option<u64> {
+        self.size_at_depth(type_index, 0)
+    }
+
+    fn size_at_depth(&self, type_index: TypeIndex, depth: u32) -> Option<u64> {
+        if self.type_stream.is_primitive(type_index) {
+            return primitive_type_size(type_index).map(u64::from);
+        }
+        if depth >= MAX_DEPTH {
+            return None;
+        }
+
+        let ti = self.resolve(type_index);
+        let record = self.type_stream.record(ti).ok()?;
+        match record.parse().ok()? {
+            TypeData::Struct(t) if !t.fixed.property.get().fwdref() => u64::try_from(t.length).ok(),
+            TypeData::Struct2(t) if !t.fixed.udt_properties().fwdref() => {
+                u64::try_from(t.length).ok()
+            }
+            TypeData::Union(t) if !t.fixed.property.get().fwdref() => u64::try_from(t.length).ok(),
+            TypeData::Union2(t) if !t.fixed.udt_properties().fwdref() => {
+                u64::try_from(t.length).ok()
+            }
+            TypeData::Enum(t) => self.size_at_depth(t.fixed.underlying_type.get(), depth + 1),
+            TypeData::Array(t) => u64::try_from(t.len).ok(),
+            TypeData::Pointer(t) => Some(t.fixed.attr().size() as u64),
+            TypeData::Modifier(t) => self.size_at_depth(t.underlying_type.get(), depth + 1),
+            TypeData::Alias(t) => self.size_at_depth(t.utype, depth + 1),
+            TypeData::Bitfield(t) => self.size_at_depth(t.underlying_type.get(), depth + 1),
+            _ => None,
+        }
+    }
+
+    /// Gets the alignment of a type in bytes.
+    pub fn align_of(&self, type_index: TypeIndex) -> Option<u64> {
+        self.align_at_depth(type_index, 0)
+    }
+
+    fn align_at_depth(&self, type_index: TypeIndex, depth: u32) -> Option<u64> {
+        if self.type_stream.is_primitive(type_index) {
+            let size = primitive_type_size(type_index)? as u64;
+            // Use the largest power of two that divides the size, e.g. 2 for an 80-bit real.
+            return Some((1 << size.trailing_zeros()).min(16));
+        }
+        if depth >= MAX_DEPTH {
+            return None;
+        }
+
+        let ti = self.resolve(type_index);
+        let record = self.type_stream.record(ti).ok()?;
+        match record.parse().ok()? {
+            TypeData::Struct(t) if !t.fixed.property.get().fwdref() => {
+                let packed = t.fixed.property.get().packed();
+                self.udt_align(t.fixed.field_list.get(), packed, depth)
+            }
+            TypeData::Struct2(t) if !t.fixed.udt_properties().fwdref() => {
+                let packed = t.fixed.udt_properties().packed();
+                self.udt_align(t.fixed.field_list.get(), packed, depth)
+            }
+            TypeData::Union(t) if !t.fixed.property.get().fwdref() => {
+                let packed = t.fixed.property.get().packed();
+                self.udt_align(t.fixed.fields.get(), packed, depth)
+            }
+            TypeData::Union2(t) if !t.fixed.udt_properties().fwdref() => {
+                let packed = t.fixed.udt_properties().packed();
+                self.udt_align(t.fixed.fields.get(), packed, depth)
+            }
+            TypeData::Enum(t) => self.align_at_depth(t.fixed.underlying_type.get(), depth + 1),
+            TypeData::Array(t) => self.align_at_depth(t.fixed.element_type.get(), depth + 1),
+            TypeData::Pointer(t) => Some(t.fixed.attr().size() as u64),
+            TypeData::Modifier(t) => self.align_at_depth(t.underlying_type.get(), depth + 1),
+            TypeData::Alias(t) => self.align_at_depth(t.utype, depth + 1),
+            TypeData::Bitfield(t) => self.align_at_depth(t.underlying_type.get(), depth + 1),
+            _ => None,
+        }
+    }
+
+    /// Computes the alignment of a UDT from the alignment of its bases and members.
+    fn udt_align(&self, fields: TypeIndex, packed: bool, depth: u32) -> Option<u64> {
+        if packed {
+            return Some(1);
+        }
+        let mut align = 1;
+        for field in self.type_stream.iter_fields(fields) {
+            let a = match field {
+                Field::BaseClass(b) => self.align_at_depth(b.ty, depth + 1),
+                Field::Member(m) => self.align_at_depth(m.ty, depth + 1),
+                Field::VFuncTable(ty) => self.align_at_depth(ty, depth + 1),
+                Field::DirectVirtualBaseClass(b) => {
+                    self.align_at_depth(b.fixed.vbtype.get(), depth + 1)
+                }
+                _ => None,
+            };
+            align = align.max(a.unwrap_or(1));
+        }
+        Some(align)
+    }
+
+    /// Computes the layout of a UDT. If `type_index` is a forward reference, then the layout of
+    /// its definition is computed.
+    pub fn udt_layout(&self, type_index: TypeIndex) -> anyhow::Result<UdtLayout> {
+        let ti = self.resolve(type_index);
+        if self.type_stream.is_primitive(ti) {
+            bail!("Type {ti:?} is a primitive type, not a UDT");
+        }
+        let record = self.type_stream.record(ti)?;
+        let (name, fields, size, fwdref, packed) = match record.parse()? {
+            TypeData::Struct(t) => {
+                let property = t.fixed.property.get();
+                let size = u64::try_from(t.length)?;
+                (
+                    t.name,
+                    t.fixed.field_list.get(),
+                    size,
+                    property.fwdref(),
+                    property.packed(),
+                )
+            }
+            TypeData::Struct2(t) => {
+                let property = t.fixed.udt_properties();
+                let size = u64::try_from(t.length)?;
+                (
+                    t.name,
+                    t.fixed.field_list.get(),
+                    size,
+                    property.fwdref(),
+                    property.packed(),
+                )
+            }
+            TypeData::Union(t) => {
+                let property = t.fixed.property.get();
+                let size = u64::try_from(t.length)?;
+                (
+                    t.name,
+                    t.fixed.fields.get(),
+                    size,
+                    property.fwdref(),
+                    property.packed(),
+                )
+            }
+            TypeData::Union2(t) => {
+                let property = t.fixed.udt_properties();
+                let size = u64::try_from(t.length)?;
+                (
+                    t.name,
+                    t.fixed.fields.get(),
+                    size,
+                    property.fwdref(),
+                    property.packed(),
+                )
+            }
+            _ => bail!("Type {ti:?} is not a struct, class, or union"),
+        };
+        if fwdref {
+            bail!("Type {ti:?} ({name}) is a forward reference, and its definition was not found");
+        }
+
+        let mut layout = UdtLayout {
+            type_index: ti,
+            kind: record.kind,
+            name: name.to_string(),
+            size,
+            align: self.udt_align(fields, packed, 0).unwrap_or(1),
+            packed,
+            has_virtual_bases: false,
+            members: Vec::new(),
+            holes: Vec::new(),
+            overlaps: Vec::new(),
+        };
+
+        self.add_members(&mut layout, ti, fields, 0, 0);
+        layout.members.sort_by_key(|m| m.offset);
+        find_holes(&mut layout);
+        if !matches!(record.kind, Leaf::LF_UNION | Leaf::LF_UNION2) {
+            find_overlaps(&mut layout);
+        }
+
+        Ok(layout)
+    }
+
+    /// Adds the data members of a UDT (and of its non-virtual base classes) to `layout`. `base`
+    /// is the offset of the UDT within the outermost UDT.
+    fn add_members(
+        &self,
+        layout: &mut UdtLayout,
+        declared_in: TypeIndex,
+        fields: TypeIndex,
+        base: u64,
+        depth: u32,
+    ) {
+        if depth >= MAX_DEPTH {
+            return;
+        }
+
+        for field in self.type_stream.iter_fields(fields) {
+            match field {
+                Field::BaseClass(b) => {
+                    let Ok(offset) = u64::try_from(b.offset) else {
+                        continue;
+                    };
+                    let base_ti = self.resolve(b.ty);
+                    if let Some(base_fields) = self.udt_fields(base_ti) {
+                        self.add_members(layout, base_ti, base_fields, base + offset, depth + 1);
+                    }
+                }
+                Field::DirectVirtualBaseClass(_) | Field::IndirectVirtualBaseClass(_) => {
+                    layout.has_virtual_bases = true;
+                }
+                Field::VFuncTable(ty) => layout.members.push(MemberLayout {
+                    name: "__vfptr".to_string(),
+                    ty,
+                    offset: base,
+                    size: self.size_of(ty),
+                    bitfield: None,
+                    declared_in,
+                    is_vfptr: true,
+                }),
+                Field::Member(m) => {
+                    let Ok(offset) = u64::try_from(m.offset) else {
+                        continue;
+                    };
+                    let mut bitfield = None;
+                    if !self.type_stream.is_primitive(m.ty)
+                        && let Ok(r) = self.type_stream.record(m.ty)
+                        && let Ok(TypeData::Bitfield(b)) = r.parse()
+                    {
+                        bitfield = Some(BitfieldLayout {
+                            position: b.position,
+                            length: b.length,
+                        });
+                    }
+                    layout.members.push(MemberLayout {
+                        name: m.name.to_string(),
+                        ty: m.ty,
+                        offset: base + offset,
+                        size: self.size_of(m.ty),
+                        bitfield,
+                        declared_in,
+                        is_vfptr: false,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Gets the field list of a struct, class, or union definition.
+    fn udt_fields(&self, type_index: TypeIndex) -> Option<TypeIndex> {
+        if self.type_stream.is_primitive(type_index) {
+            return None;
+        }
+        let record = self.type_stream.record(type_index).ok()?;
+        match record.parse().ok()? {
+            TypeData::Struct(t) => Some(t.fixed.field_list.get()),
+            TypeData::Struct2(t) => Some(t.fixed.field_list.get()),
+            TypeData::Union(t) => Some(t.fixed.fields.get()),
+            TypeData::Union2(t) => Some(t.fixed.fields.get()),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the bytes that are not occupied by any member. `layout.members` must be sorted by offset.
+fn find_holes(layout: &mut UdtLayout) {
+    let mut cursor = 0;
+    for m in layout.members.iter() {
+        if m.offset > cursor {
+            layout.holes.push(Hole {
+                offset: cursor,
+                size: m.offset - cursor,
+            });
+        }
+        cursor = cursor.max(m.offset + m.size.unwrap_or(0));
+    }
+    if layout.size > cursor {
+        layout.holes.push(Hole {
+            offset: cursor,
+            size: layout.size - cursor,
+        });
+    }
+}
+
+/// Finds pairs of members whose bits overlap. `layout.members` must be sorted by offset.
+fn find_overlaps(layout: &mut UdtLayout) {
+    let members = &layout.members;
+    for i in 0..members.len() {
+        let a = members[i].bit_range();
+        for (j, m) in members.iter().enumerate().skip(i + 1) {
+            if m.offset * 8 >= a.end {
+                break;
+            }
+            let b = m.bit_range();
+            if a.start < b.end && b.start < a.end {
+                layout.overlaps.push(Overlap {
+                    first: i,
+                    second: j,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
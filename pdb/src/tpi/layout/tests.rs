@@ -0,0 +1,122 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::testing::*;
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+
+const FWDREF: u16 = 1 << 7;
+
+#[test]
+fn sizes_and_alignment() {
+    let mut t = TypeBuilder::new();
+    let pint = pointer(&mut t, TypeIndex::T_INT4, PTR64);
+    let array = array(&mut t, TypeIndex::T_INT2, 20);
+    let const_array = modifier(&mut t, array, 1);
+    let s_fwd = udt(&mut t, Leaf::LF_STRUCTURE, "S", TypeIndex(0), 0, FWDREF);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_RCHAR, 0, "c".into());
+    fields.member(PUBLIC, TypeIndex::T_REAL64, 8, "d".into());
+    let s_fields = t.field_list(&fields);
+    let s = udt(&mut t, Leaf::LF_STRUCTURE, "S", s_fields, 16, 0);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let layout = TypeLayout::new(&tpi);
+    assert_eq!(layout.size_of(TypeIndex::T_INT4), Some(4));
+    assert_eq!(layout.size_of(TypeIndex::T_VOID), None);
+    assert_eq!(layout.size_of(pint), Some(8));
+    assert_eq!(layout.size_of(const_array), Some(20));
+    assert_eq!(layout.align_of(const_array), Some(2));
+    assert_eq!(layout.align_of(TypeIndex::T_REAL80), Some(2));
+    assert_eq!(layout.resolve(s_fwd), s);
+    assert_eq!(layout.size_of(s_fwd), Some(16));
+    assert_eq!(layout.align_of(s_fwd), Some(8));
+}
+
+#[test]
+fn holes_inheritance_and_overlaps() {
+    let mut t = TypeBuilder::new();
+    let base_fwd = udt(&mut t, Leaf::LF_CLASS, "Base", TypeIndex(0), 0, FWDREF);
+    let vtshape = t.vtable_shape(1, &[0]);
+    let pvtshape = pointer(&mut t, vtshape, PTR64);
+    let mut fields = FieldListBuilder::new();
+    fields.vfunc_table(pvtshape);
+    fields.member(PUBLIC, TypeIndex::T_RCHAR, 8, "flag".into());
+    let base_fields = t.field_list(&fields);
+    let base = udt(&mut t, Leaf::LF_CLASS, "Base", base_fields, 16, 0);
+
+    let x = bitfield(&mut t, TypeIndex::T_ULONG, 3, 0);
+    let y = bitfield(&mut t, TypeIndex::T_ULONG, 4, 3);
+    let mut fields = FieldListBuilder::new();
+    fields.base_class(PUBLIC, base_fwd, 0);
+    fields.member(PUBLIC, x, 16, "x".into());
+    fields.member(PUBLIC, y, 16, "y".into());
+    fields.member(PUBLIC, TypeIndex::T_INT8, 24, "u".into());
+    fields.member(PUBLIC, TypeIndex::T_INT4, 24, "v".into());
+    fields.member(PUBLIC, TypeIndex::T_INT2, 36, "w".into());
+    let fields = t.field_list(&fields);
+    let derived = udt(&mut t, Leaf::LF_STRUCTURE, "Derived", fields, 40, 0);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    let layout = TypeLayout::new(&tpi);
+    let udt = layout.udt_layout(derived).unwrap();
+    assert_eq!(udt.name, "Derived");
+    assert_eq!(udt.size, 40);
+    assert_eq!(udt.align, 8);
+
+    let members: Vec<(&str, u64, TypeIndex)> = udt
+        .members
+        .iter()
+        .map(|m| (m.name.as_str(), m.offset, m.declared_in))
+        .collect();
+    assert_eq!(
+        members,
+        [
+            ("__vfptr", 0, base),
+            ("flag", 8, base),
+            ("x", 16, derived),
+            ("y", 16, derived),
+            ("u", 24, derived),
+            ("v", 24, derived),
+            ("w", 36, derived),
+        ]
+    );
+    assert!(udt.members[0].is_vfptr);
+    assert_eq!(
+        udt.members[3].bitfield,
+        Some(BitfieldLayout {
+            position: 3,
+            length: 4
+        })
+    );
+
+    assert_eq!(
+        udt.holes,
+        [
+            Hole { offset: 9, size: 7 },
+            Hole {
+                offset: 20,
+                size: 4
+            },
+            Hole {
+                offset: 32,
+                size: 4
+            },
+            Hole {
+                offset: 38,
+                size: 2
+            },
+        ]
+    );
+    assert_eq!(udt.padding(), 17);
+    assert_eq!(udt.tail_padding(), 2);
+
+    // The bitfields x and y share a storage unit, but do not overlap.
+    assert_eq!(
+        udt.overlaps,
+        [Overlap {
+            first: 4,
+            second: 5
+        }]
+    );
+
+    assert!(layout.udt_layout(TypeIndex::T_INT4).is_err());
+}
@@ -0,0 +1,315 @@
+//! Merges type streams, removing duplicate records.
+//!
+//! [`TypeMerger`] combines the type records of several TPI / IPI streams (from PDBs) or
+//! `.debug$T` sections (from object files) into a single TPI stream and a single IPI stream, the
+//! way that the linker does. Each record is copied, the type indexes within it are rewritten to
+//! refer to the merged streams, and then the record is compared with the records that have
+//! already been merged. Two records are the same if their bytes are the same after remapping, so
+//! this deduplicates entire type graphs, not just leaf records.
+//!
+//! This relies on records only referring to records that precede them, which is true for the
+//! type streams produced by MSVC and by LLVM.
+
+use super::{TypeStream, TypeStreamBuilder};
+use crate::names::NameIndex;
+use crate::types::visitor::{IndexVisitorMut, visit_type_indexes_in_record_slice_mut};
+use crate::types::{ItemIdLe, Leaf, TypeIndex, TypeIndexLe, TypesIter};
+use anyhow::{Context, bail};
+use ms_codeview::IteratorWithRangesExt;
+use ms_codeview::parser::ParserError;
+use std::collections::HashMap;
+use zerocopy::{LE, U32};
+
+/// Merges type streams, removing duplicate records.
+///
+/// ```ignore
+/// let mut merger = TypeMerger::new();
+/// for pdb in pdbs.iter() {
+///     let tpi = pdb.read_type_stream()?;
+///     let ipi = pdb.read_ipi_stream()?;
+///     let types = merger.merge_type_stream(&tpi)?;
+///     let ids = merger.merge_id_stream(&ipi, &types, |name| Ok(name))?;
+/// }
+/// let (tpi_builder, ipi_builder) = merger.finish();
+/// ```
+#[derive(Default)]
+pub struct TypeMerger {
+    tpi: MergedStream,
+    ipi: MergedStream,
+}
+
+/// A type stream that is being built by `TypeMerger`.
+#[derive(Default)]
+struct MergedStream {
+    builder: TypeStreamBuilder,
+
+    /// Maps the bytes of each record (including the record length) to its type index.
+    records: HashMap<Vec<u8>, TypeIndex>,
+}
+
+/// Maps the type indexes (or item IDs) of a source stream to the type indexes of a merged stream.
+#[derive(Clone, Debug)]
+pub struct TypeIndexRemap {
+    type_index_begin: TypeIndex,
+    new_indexes: Vec<TypeIndex>,
+}
+
+impl TypeIndexRemap {
+    fn new(type_index_begin: TypeIndex) -> Self {
+        Self {
+            type_index_begin,
+            new_indexes: Vec::new(),
+        }
+    }
+
+    /// Maps a type index in the source stream to a type index in the merged stream. Primitive
+    /// type indexes (and the nil item ID, 0) are not changed. Returns `None` if `old` is out of
+    /// range.
+    pub fn map(&self, old: TypeIndex) -> Option<TypeIndex> {
+        if old < self.type_index_begin {
+            return Some(old);
+        }
+        self.new_indexes
+            .get((old.0 - self.type_index_begin.0) as usize)
+            .copied()
+    }
+
+    /// The number of records in the source stream.
+    pub fn len(&self) -> usize {
+        self.new_indexes.len()
+    }
+
+    /// Returns `true` if the source stream did not contain any records.
+    pub fn is_empty(&self) -> bool {
+        self.new_indexes.is_empty()
+    }
+
+    /// Iterates the `(old, new)` pairs of type indexes.
+    pub fn iter(&self) -> impl Iterator<Item = (TypeIndex, TypeIndex)> + '_ {
+        self.new_indexes
+            .iter()
+            .enumerate()
+            .map(|(i, &new)| (TypeIndex(self.type_index_begin.0 + i as u32), new))
+    }
+}
+
+/// Rewrites the indexes within a record.
+struct Remapper<'a> {
+    /// Maps the type indexes within the record.
+    types: &'a TypeIndexRemap,
+    /// Maps the item IDs within the record.
+    items: &'a TypeIndexRemap,
+    /// Maps the `NameIndex` values within the record.
+    names: NameRemap<'a>,
+    /// The first error that was found. The visitor can only report `ParserError`, which does
+    /// not carry any details.
+    error: Option<anyhow::Error>,
+}
+
+/// Specifies how `NameIndex` values (in `LF_UDT_MOD_SRC_LINE`) are mapped.
+enum NameRemap<'a> {
+    /// The records should not contain any `NameIndex` values.
+    None,
+    /// The values refer to a Names Stream and are mapped by a function.
+    Names(&'a mut dyn FnMut(NameIndex) -> anyhow::Result<NameIndex>),
+}
+
+impl Remapper<'_> {
+    fn fail(&mut self, error: anyhow::Error) -> ParserError {
+        self.error.get_or_insert(error);
+        ParserError::new()
+    }
+}
+
+impl IndexVisitorMut for &mut Remapper<'_> {
+    fn type_index(&mut self, _offset: usize, value: &mut TypeIndexLe) -> Result<(), ParserError> {
+        let old = value.get();
+        match self.types.map(old) {
+            Some(new) => {
+                *value = new.into();
+                Ok(())
+            }
+            None => Err(self.fail(anyhow::anyhow!(
+                "Type record refers to type {old:?}, which is not a preceding record"
+            ))),
+        }
+    }
+
+    fn item_id(&mut self, _offset: usize, value: &mut ItemIdLe) -> Result<(), ParserError> {
+        let old = TypeIndex(value.get());
+        match self.items.map(old) {
+            Some(new) => {
+                *value = U32::new(new.0);
+                Ok(())
+            }
+            None => Err(self.fail(anyhow::anyhow!(
+                "Type record refers to item 0x{:x}, which is not a preceding record",
+                old.0
+            ))),
+        }
+    }
+
+    fn name_index(&mut self, _offset: usize, value: &mut U32<LE>) -> Result<(), ParserError> {
+        let new = match &mut self.names {
+            NameRemap::None => Err(anyhow::anyhow!(
+                "Type record contains a NameIndex, but no Names Stream mapping was provided"
+            )),
+            NameRemap::Names(f) => f(NameIndex(value.get())).map(|ni| ni.0),
+        };
+        match new {
+            Ok(new) => {
+                *value = U32::new(new);
+                Ok(())
+            }
+            Err(e) => Err(self.fail(e)),
+        }
+    }
+}
+
+impl MergedStream {
+    /// Adds a record (whose indexes have already been remapped), unless an identical record is
+    /// already present.
+    fn add(&mut self, record: Vec<u8>) -> anyhow::Result<TypeIndex> {
+        if let Some(&type_index) = self.records.get(&record) {
+            return Ok(type_index);
+        }
+        let type_index = self.builder.add_record_bytes(&record)?;
+        self.records.insert(record, type_index);
+        Ok(type_index)
+    }
+}
+
+/// Copies a record and remaps the indexes within it.
+fn remap_record(kind: Leaf, record: &[u8], remapper: &mut Remapper<'_>) -> anyhow::Result<Vec<u8>> {
+    let mut record = record.to_vec();
+    if let Err(e) = visit_type_indexes_in_record_slice_mut(kind, &mut record[4..], &mut *remapper) {
+        return Err(remapper.error.take().unwrap_or(e));
+    }
+    Ok(record)
+}
+
+/// Iterates the records in `records`, giving the bytes of each record (including the length
+/// field) along with its kind.
+fn iter_records(records: &[u8]) -> impl Iterator<Item = (Leaf, &[u8])> {
+    TypesIter::new(records)
+        .with_ranges()
+        .map(|(range, r)| (r.kind, &records[range]))
+}
+
+impl TypeMerger {
+    /// Creates an empty `TypeMerger`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The merged TPI stream.
+    pub fn tpi(&self) -> &TypeStreamBuilder {
+        &self.tpi.builder
+    }
+
+    /// The merged IPI stream.
+    pub fn ipi(&self) -> &TypeStreamBuilder {
+        &self.ipi.builder
+    }
+
+    /// Consumes the `TypeMerger` and returns the builders for the merged TPI and IPI streams.
+    pub fn finish(self) -> (TypeStreamBuilder, TypeStreamBuilder) {
+        (self.tpi.builder, self.ipi.builder)
+    }
+
+    /// Merges the records of a TPI stream. Returns the mapping from the type indexes of
+    /// `type_stream` to the type indexes of the merged TPI stream.
+    pub fn merge_type_stream<StreamData: AsRef<[u8]>>(
+        &mut self,
+        type_stream: &TypeStream<StreamData>,
+    ) -> anyhow::Result<TypeIndexRemap> {
+        let mut types = TypeIndexRemap::new(type_stream.type_index_begin());
+        let no_items = TypeIndexRemap::new(TypeIndex(0));
+
+        for (kind, record) in iter_records(type_stream.type_records_bytes()) {
+            let old = TypeIndex(types.type_index_begin.0 + types.len() as u32);
+            let mut remapper = Remapper {
+                types: &types,
+                items: &no_items,
+                names: NameRemap::None,
+                error: None,
+            };
+            let new = remap_record(kind, record, &mut remapper)
+                .and_then(|record| self.tpi.add(record))
+                .with_context(|| format!("Failed to merge type record {old:?} ({kind:?})"))?;
+            types.new_indexes.push(new);
+        }
+
+        Ok(types)
+    }
+
+    /// Merges the records of an IPI stream. `types` is the mapping that was returned when the
+    /// TPI stream of the same PDB was merged.
+    ///
+    /// `map_name` maps the `NameIndex` values in `LF_UDT_MOD_SRC_LINE` records from the Names
+    /// Stream of the source PDB to the Names Stream of the output PDB.
+    ///
+    /// Returns the mapping from the item IDs of `id_stream` to the item IDs of the merged IPI
+    /// stream.
+    pub fn merge_id_stream<StreamData: AsRef<[u8]>>(
+        &mut self,
+        id_stream: &TypeStream<StreamData>,
+        types: &TypeIndexRemap,
+        mut map_name: impl FnMut(NameIndex) -> anyhow::Result<NameIndex>,
+    ) -> anyhow::Result<TypeIndexRemap> {
+        let mut items = TypeIndexRemap::new(id_stream.type_index_begin());
+
+        for (kind, record) in iter_records(id_stream.type_records_bytes()) {
+            let old = items.type_index_begin.0 + items.len() as u32;
+            let mut remapper = Remapper {
+                types,
+                items: &items,
+                names: NameRemap::Names(&mut map_name),
+                error: None,
+            };
+            let new = remap_record(kind, record, &mut remapper)
+                .and_then(|record| self.ipi.add(record))
+                .with_context(|| format!("Failed to merge ID record 0x{old:x} ({kind:?})"))?;
+            items.new_indexes.push(new);
+        }
+
+        Ok(items)
+    }
+
+    /// Merges the records of a `.debug$T` section of an object file. `records` contains the type
+    /// records, without the signature at the start of the section.
+    ///
+    /// Object files store type records and ID records in a single stream, so the returned mapping
+    /// maps each type record to the merged TPI stream and each ID record to the merged IPI stream.
+    pub fn merge_object_types(&mut self, records: &[u8]) -> anyhow::Result<TypeIndexRemap> {
+        let mut indexes = TypeIndexRemap::new(TypeIndex::MIN_BEGIN);
+
+        for (kind, record) in iter_records(records) {
+            let old = TypeIndex(indexes.type_index_begin.0 + indexes.len() as u32);
+            if matches!(kind, Leaf::LF_TYPESERVER2 | Leaf::LF_PRECOMP) {
+                bail!("Object files that use {kind:?} are not supported");
+            }
+
+            let mut remapper = Remapper {
+                types: &indexes,
+                items: &indexes,
+                names: NameRemap::None,
+                error: None,
+            };
+            let stream = if kind.is_id_record() {
+                &mut self.ipi
+            } else {
+                &mut self.tpi
+            };
+            let new = remap_record(kind, record, &mut remapper)
+                .and_then(|record| stream.add(record))
+                .with_context(|| format!("Failed to merge type record {old:?} ({kind:?})"))?;
+            indexes.new_indexes.push(new);
+        }
+
+        Ok(indexes)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,184 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::testing::*;
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+use crate::types::{TypeData, TypeModifier, UdtModSrcLine, UdtSrcLine};
+use bstr::ByteSlice;
+use zerocopy::U16;
+
+fn records(builder: &TypeStreamBuilder) -> Vec<(Leaf, Vec<u8>)> {
+    TypesIter::new(builder.type_records_bytes())
+        .map(|r| (r.kind, r.data.to_vec()))
+        .collect()
+}
+
+#[test]
+fn merge_type_streams() {
+    let mut a = TypeBuilder::new();
+    let int_ptr_a = pointer(&mut a, TypeIndex::T_INT4, PTR64);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, int_ptr_a, 0, "p".into());
+    let fields_a = a.field_list(&fields);
+    let s_a = udt(&mut a, Leaf::LF_STRUCTURE, "S", fields_a, 8, 0);
+    let a = type_stream(Stream::TPI, &a.finish());
+
+    // The same types, in a different order and with an extra type at the start.
+    let mut b = TypeBuilder::new();
+    let t_b = udt(&mut b, Leaf::LF_STRUCTURE, "T", TypeIndex(0), 4, 0);
+    let int_ptr_b = pointer(&mut b, TypeIndex::T_INT4, PTR64);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, int_ptr_b, 0, "p".into());
+    let fields_b = b.field_list(&fields);
+    let s_b = udt(&mut b, Leaf::LF_STRUCTURE, "S", fields_b, 8, 0);
+    let s_ptr_b = pointer(&mut b, s_b, PTR64);
+    let b = type_stream(Stream::TPI, &b.finish());
+
+    let mut merger = TypeMerger::new();
+    let map_a = merger.merge_type_stream(&a).unwrap();
+    let map_b = merger.merge_type_stream(&b).unwrap();
+
+    assert_eq!(map_a.len(), 3);
+    assert_eq!(map_b.len(), 5);
+    assert_eq!(merger.tpi().num_types(), 5);
+
+    // Records that were already present are shared.
+    assert_eq!(map_b.map(int_ptr_b), map_a.map(int_ptr_a));
+    assert_eq!(map_b.map(fields_b), map_a.map(fields_a));
+    assert_eq!(map_b.map(s_b), map_a.map(s_a));
+    assert_eq!(map_b.map(t_b), Some(TypeIndex(0x1003)));
+    assert_eq!(map_b.map(s_ptr_b), Some(TypeIndex(0x1004)));
+    assert_eq!(map_b.map(TypeIndex::T_INT4), Some(TypeIndex::T_INT4));
+    assert_eq!(map_b.map(TypeIndex(0x1005)), None);
+
+    // The pointer to S has been rewritten to point to the merged S.
+    let merged = records(merger.tpi());
+    let (kind, data) = &merged[4];
+    assert_eq!(*kind, Leaf::LF_POINTER);
+    match TypeData::parse_bytes(*kind, data).unwrap() {
+        TypeData::Pointer(p) => assert_eq!(p.fixed.ty.get(), TypeIndex(0x1002)),
+        _ => panic!("expected pointer"),
+    }
+
+    let (tpi, _ipi) = merger.finish();
+    let stream = TypeStream::parse(Stream::TPI, tpi.encode_type_stream(None).unwrap()).unwrap();
+    assert_eq!(stream.num_types(), 5);
+}
+
+#[test]
+fn forward_reference_is_error() {
+    let mut t = TypeBuilder::new();
+    pointer(&mut t, TypeIndex(0x1001), PTR64);
+    udt(&mut t, Leaf::LF_STRUCTURE, "S", TypeIndex(0), 4, 0);
+    let t = type_stream(Stream::TPI, &t.finish());
+
+    let mut merger = TypeMerger::new();
+    let err = merger.merge_type_stream(&t).unwrap_err();
+    assert!(format!("{err:#}").contains("not a preceding record"));
+}
+
+#[test]
+fn merge_id_streams() {
+    let mut types = TypeBuilder::new();
+    let s = udt(&mut types, Leaf::LF_STRUCTURE, "S", TypeIndex(0), 4, 0);
+    let types = type_stream(Stream::TPI, &types.finish());
+
+    let mut ids = TypeBuilder::new();
+    let file = ids.string_id(0, b"c:\\src\\s.h".as_bstr());
+    ids.udt_src_line(&UdtSrcLine {
+        ty: s.into(),
+        src: U32::new(file.0),
+        line: U32::new(10),
+    });
+    ids.udt_mod_src_line(&UdtModSrcLine {
+        ty: s.into(),
+        src: U32::new(0x40),
+        line: U32::new(10),
+        imod: U16::new(1),
+    });
+    let ids_bytes = ids.finish();
+    assert_eq!(file, TypeIndex(0x1000));
+
+    let ipi = type_stream(Stream::IPI, &ids_bytes);
+
+    let mut merger = TypeMerger::new();
+    // Shift the struct to a different index, to check that type indexes are remapped.
+    merger
+        .tpi
+        .builder
+        .add_record(Leaf::LF_MODIFIER, &[0x74, 0, 0, 0, 1, 0])
+        .unwrap();
+    let type_map = merger.merge_type_stream(&types).unwrap();
+    assert_eq!(type_map.map(s), Some(TypeIndex(0x1001)));
+
+    let id_map = merger
+        .merge_id_stream(&ipi, &type_map, |name| Ok(NameIndex(name.0 + 4)))
+        .unwrap();
+    assert_eq!(id_map.len(), 3);
+
+    // The source file of LF_UDT_SRC_LINE is an item ID, and the source file of
+    // LF_UDT_MOD_SRC_LINE is a NameIndex.
+    let merged = records(merger.ipi());
+    assert_eq!(merged.len(), 3);
+    match TypeData::parse_bytes(merged[1].0, &merged[1].1).unwrap() {
+        TypeData::UdtSrcLine(r) => {
+            assert_eq!(r.ty.get(), TypeIndex(0x1001));
+            assert_eq!(r.src.get(), 0x1000);
+            assert_eq!(r.line.get(), 10);
+        }
+        _ => panic!("expected LF_UDT_SRC_LINE"),
+    }
+    match TypeData::parse_bytes(merged[2].0, &merged[2].1).unwrap() {
+        TypeData::UdtModSrcLine(r) => {
+            assert_eq!(r.ty.get(), TypeIndex(0x1001));
+            assert_eq!(r.src.get(), 0x44);
+        }
+        _ => panic!("expected LF_UDT_MOD_SRC_LINE"),
+    }
+
+    // Merging the same IPI again does not add any records.
+    merger
+        .merge_id_stream(&ipi, &type_map, |name| Ok(NameIndex(name.0 + 4)))
+        .unwrap();
+    assert_eq!(merger.ipi().num_types(), 3);
+}
+
+#[test]
+fn merge_object_types() {
+    // Object files mix type records and ID records in a single index space.
+    let object = |extra: bool| {
+        let mut t = TypeBuilder::new();
+        if extra {
+            t.string_id(0, b"extra".as_bstr());
+        }
+        let file = t.string_id(0, b"c:\\src\\s.h".as_bstr());
+        t.arg_list(&[TypeIndex::T_INT4]);
+        let ty = t.modifier(&TypeModifier {
+            underlying_type: TypeIndex::T_INT4.into(),
+            attributes: U16::new(1),
+        });
+        t.udt_src_line(&UdtSrcLine {
+            ty: ty.into(),
+            src: U32::new(file.0),
+            line: U32::new(1),
+        });
+        t.finish()
+    };
+
+    let mut merger = TypeMerger::new();
+    let map1 = merger.merge_object_types(&object(false)).unwrap();
+    let map2 = merger.merge_object_types(&object(true)).unwrap();
+
+    assert_eq!(merger.tpi().num_types(), 2);
+    assert_eq!(merger.ipi().num_types(), 3);
+
+    // object(false): string_id, arg_list, modifier, udt_src_line
+    assert_eq!(map1.map(TypeIndex(0x1000)), Some(TypeIndex(0x1000)));
+    assert_eq!(map1.map(TypeIndex(0x1001)), Some(TypeIndex(0x1000)));
+    assert_eq!(map1.map(TypeIndex(0x1002)), Some(TypeIndex(0x1001)));
+    assert_eq!(map1.map(TypeIndex(0x1003)), Some(TypeIndex(0x1001)));
+
+    // object(true): extra, string_id, arg_list, modifier, udt_src_line
+    assert_eq!(map2.map(TypeIndex(0x1000)), Some(TypeIndex(0x1002)));
+    assert_eq!(map2.map(TypeIndex(0x1001)), Some(TypeIndex(0x1000)));
+    assert_eq!(map2.map(TypeIndex(0x1004)), Some(TypeIndex(0x1001)));
+}
@@ -0,0 +1,123 @@
+//! Helpers for building type streams in tests.
+//!
+//! Records are encoded by [`TypeBuilder`]. The functions here only fill in the fixed parts of the
+//! records that tests use most often.
+
+use super::{TypeStream, TypeStreamHeader};
+use crate::Stream;
+use crate::types::builder::TypeBuilder;
+use crate::types::{
+    ArrayFixed, Bitfield, CallingConvention, EnumFixed, Leaf, PointerFixed, Proc, StructFixed,
+    TypeIndex, TypeModifier, UdtPropertiesLe, UnionFixed,
+};
+use zerocopy::{FromZeros, IntoBytes, U16, U32};
+
+pub(crate) const PTR64: u32 = 0x0c | (8 << 13);
+pub(crate) const MODE_LVREF: u32 = 1 << 5;
+pub(crate) const MODE_PMEM: u32 = 2 << 5;
+pub(crate) const MODE_PMFUNC: u32 = 3 << 5;
+pub(crate) const MODE_RVREF: u32 = 4 << 5;
+pub(crate) const PTR_CONST: u32 = 1 << 10;
+
+/// `attr` of a public field.
+pub(crate) const PUBLIC: u16 = 3;
+
+/// Adds an `LF_POINTER` record that is not a pointer to member.
+pub(crate) fn pointer(t: &mut TypeBuilder, ty: TypeIndex, attr: u32) -> TypeIndex {
+    t.pointer(
+        &PointerFixed {
+            ty: ty.into(),
+            attr: U32::new(attr),
+        },
+        &[],
+    )
+}
+
+pub(crate) fn modifier(t: &mut TypeBuilder, ty: TypeIndex, attributes: u16) -> TypeIndex {
+    t.modifier(&TypeModifier {
+        underlying_type: ty.into(),
+        attributes: U16::new(attributes),
+    })
+}
+
+/// Adds an `LF_ARGLIST` record for `args` and an `LF_PROCEDURE` record that uses it.
+pub(crate) fn proc(t: &mut TypeBuilder, ret: TypeIndex, args: &[TypeIndex]) -> TypeIndex {
+    let arg_list = t.arg_list(args);
+    t.proc(&Proc {
+        return_value: ret.into(),
+        call: CallingConvention::NEAR_C.0,
+        reserved: 0,
+        num_params: U16::new(args.len() as u16),
+        arg_list: arg_list.into(),
+    })
+}
+
+pub(crate) fn array(t: &mut TypeBuilder, element: TypeIndex, byte_len: u64) -> TypeIndex {
+    t.array(
+        &ArrayFixed {
+            element_type: element.into(),
+            index_type: TypeIndex::T_UQUAD.into(),
+        },
+        byte_len,
+        "".into(),
+    )
+}
+
+pub(crate) fn bitfield(t: &mut TypeBuilder, ty: TypeIndex, length: u8, position: u8) -> TypeIndex {
+    t.bitfield(&Bitfield {
+        underlying_type: ty.into(),
+        length,
+        position,
+    })
+}
+
+/// Adds an `LF_STRUCTURE`, `LF_CLASS`, `LF_INTERFACE`, or `LF_UNION` record.
+pub(crate) fn udt(
+    t: &mut TypeBuilder,
+    leaf: Leaf,
+    name: &str,
+    fields: TypeIndex,
+    size: u64,
+    property: u16,
+) -> TypeIndex {
+    let property = UdtPropertiesLe(U16::new(property));
+    if leaf == Leaf::LF_UNION {
+        let fixed = UnionFixed {
+            count: U16::new(0),
+            property,
+            fields: fields.into(),
+        };
+        t.union(&fixed, size, name.into(), None)
+    } else {
+        let mut fixed = StructFixed::new_zeroed();
+        fixed.property = property;
+        fixed.field_list = fields.into();
+        t.struct_(leaf, &fixed, size, name.into(), None)
+    }
+}
+
+pub(crate) fn enum_(
+    t: &mut TypeBuilder,
+    name: &str,
+    underlying: TypeIndex,
+    fields: TypeIndex,
+) -> TypeIndex {
+    let fixed = EnumFixed {
+        count: U16::new(0),
+        property: UdtPropertiesLe(U16::new(0)),
+        underlying_type: underlying.into(),
+        fields: fields.into(),
+    };
+    t.enum_(&fixed, name.into(), None)
+}
+
+/// Builds a TPI or IPI stream that contains `records`, which are encoded type records.
+pub(crate) fn type_stream(stream: Stream, records: &[u8]) -> TypeStream<Vec<u8>> {
+    let num_types = crate::types::TypesIter::new(records).count() as u32;
+    let mut header = TypeStreamHeader::empty();
+    header.type_index_end = TypeIndex(TypeIndex::MIN_BEGIN.0 + num_types).into();
+    header.type_record_bytes = U32::new(records.len() as u32);
+    let mut stream_data = header.as_bytes().to_vec();
+    stream_data.extend_from_slice(records);
+    TypeStream::parse(stream, stream_data).unwrap()
+}
@@ -0,0 +1,270 @@
+//! Finds the definitions of UDTs by name.
+//!
+//! Most references to UDTs (in member lists, pointers, and symbols) point to forward references
+//! rather than to definitions. [`UdtIndex`] finds the definition of a UDT, given a forward
+//! reference or a name. If the Type Hash Stream is available, then it is used to find the
+//! candidate records, which only requires decoding a handful of records. Otherwise, an index of
+//! all UDT definitions is built (once) by scanning the entire Type Stream.
+
+use super::TypeStream;
+use super::hash::{TypeHashStream, is_udt_anon_name};
+use crate::hash::hash_u32;
+use crate::names::NamesStream;
+use crate::types::{TypeData, TypeIndex};
+use bstr::{BStr, BString};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Finds the definitions of UDTs (structs, classes, unions, and enums) in a [`TypeStream`].
+///
+/// ```ignore
+/// let tpi = pdb.read_type_stream()?;
+/// let udts = match pdb.read_type_hash_stream(&tpi)? {
+///     Some(hashes) => UdtIndex::with_hash_stream(&tpi, &hashes),
+///     None => UdtIndex::new(&tpi),
+/// };
+/// let definition = udts.resolve_forward_ref(ty);
+/// ```
+pub struct UdtIndex<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    type_stream: &'a TypeStream<StreamData>,
+
+    /// The hash buckets from the Type Hash Stream, if it was provided and is usable.
+    buckets: Option<HashBuckets>,
+
+    /// Maps names to the records that the Hash Adjusters table selects for them.
+    adjusters: HashMap<BString, TypeIndex>,
+
+    /// Maps the key of each UDT definition to its type index. This is only built if there are no
+    /// hash buckets, and is built on first use.
+    definitions: OnceCell<HashMap<UdtKey<'a>, TypeIndex>>,
+}
+
+/// The type records in each hash bucket, stored as a "starts" table and a list of records.
+struct HashBuckets {
+    /// `starts[b]..starts[b + 1]` is the range within `type_indexes` of bucket `b`.
+    starts: Vec<u32>,
+    type_indexes: Vec<TypeIndex>,
+}
+
+/// The key used to match UDT forward references with definitions. This follows the rules that
+/// are used for hashing UDT records: scoped UDTs are identified by their unique name and all other
+/// UDTs are identified by their name.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct UdtKey<'a> {
+    unique: bool,
+    name: &'a BStr,
+}
+
+impl<'a, StreamData> UdtIndex<'a, StreamData>
+where
+    StreamData: AsRef<[u8]>,
+{
+    /// Creates a `UdtIndex` that does not use a Type Hash Stream. The first lookup will scan the
+    /// entire Type Stream.
+    pub fn new(type_stream: &'a TypeStream<StreamData>) -> Self {
+        Self {
+            type_stream,
+            buckets: None,
+            adjusters: HashMap::new(),
+            definitions: OnceCell::new(),
+        }
+    }
+
+    /// Creates a `UdtIndex` that uses the hash values in `hash_stream` to find records.
+    ///
+    /// If `hash_stream` does not contain hash values, or if they are inconsistent with
+    /// `type_stream`, then this falls back to scanning the Type Stream.
+    pub fn with_hash_stream<HashData: AsRef<[u8]>>(
+        type_stream: &'a TypeStream<StreamData>,
+        hash_stream: &TypeHashStream<HashData>,
+    ) -> Self {
+        let mut index = Self::new(type_stream);
+        index.buckets = HashBuckets::new(type_stream, hash_stream);
+        index
+    }
+
+    /// Applies the Hash Adjusters table of `hash_stream`. When more than one definition has the
+    /// same name, the adjusters select the definition that is found. The names of the adjusters
+    /// are stored in the Names Stream.
+    pub fn hash_adjusters<HashData: AsRef<[u8]>, NamesData: AsRef<[u8]>>(
+        mut self,
+        hash_stream: &TypeHashStream<HashData>,
+        names: &NamesStream<NamesData>,
+    ) -> anyhow::Result<Self> {
+        for adj in hash_stream.hash_adjusters() {
+            let name = names.get_string(adj.name)?;
+            self.adjusters.insert(name.to_owned(), adj.type_index);
+        }
+        Ok(self)
+    }
+
+    /// Returns `true` if lookups use the Type Hash Stream.
+    pub fn uses_hash_stream(&self) -> bool {
+        self.buckets.is_some()
+    }
+
+    /// If `type_index` is a forward reference to a UDT, finds the definition of the UDT.
+    ///
+    /// If `type_index` is already the definition of a UDT, this returns `type_index`. Returns
+    /// `None` if `type_index` is not a UDT, or if the UDT has no definition in this stream.
+    pub fn resolve_forward_ref(&self, type_index: TypeIndex) -> Option<TypeIndex> {
+        if self.type_stream.is_primitive(type_index) {
+            return None;
+        }
+        let record = self.type_stream.record(type_index).ok()?;
+        let (fwdref, key) = udt_key(record.parse().ok()?)?;
+        if !fwdref {
+            return Some(type_index);
+        }
+        self.find(key)
+    }
+
+    /// Finds the definition of the UDT named `name`. This does not find UDTs that are identified
+    /// by their unique name, such as UDTs that are defined within a function.
+    ///
+    /// If there is more than one definition then this returns the one selected by the Hash
+    /// Adjusters table, if any, or else the one with the lowest type index.
+    pub fn find_udt_by_name(&self, name: &BStr) -> Option<TypeIndex> {
+        self.find(UdtKey {
+            unique: false,
+            name,
+        })
+    }
+
+    /// Finds the definition of the UDT whose unique (decorated) name is `unique_name`.
+    pub fn find_udt_by_unique_name(&self, unique_name: &BStr) -> Option<TypeIndex> {
+        self.find(UdtKey {
+            unique: true,
+            name: unique_name,
+        })
+    }
+
+    fn find(&self, key: UdtKey<'_>) -> Option<TypeIndex> {
+        if is_udt_anon_name(key.name) {
+            // Anonymous UDTs are not hashed by name, and their names are not unique.
+            return None;
+        }
+
+        if let Some(&type_index) = self.adjusters.get(key.name) {
+            if self.is_definition(type_index, key) {
+                return Some(type_index);
+            }
+        }
+
+        let Some(buckets) = &self.buckets else {
+            return self.definitions().get(&key).copied();
+        };
+
+        let bucket = hash_u32(key.name) % (buckets.starts.len() as u32 - 1);
+        let candidates = &buckets.type_indexes[buckets.starts[bucket as usize] as usize
+            ..buckets.starts[bucket as usize + 1] as usize];
+        candidates
+            .iter()
+            .copied()
+            .find(|&type_index| self.is_definition(type_index, key))
+    }
+
+    /// Checks whether `type_index` is a UDT definition (not a forward reference) for `key`.
+    fn is_definition(&self, type_index: TypeIndex, key: UdtKey<'_>) -> bool {
+        let Ok(record) = self.type_stream.record(type_index) else {
+            return false;
+        };
+        matches!(
+            record.parse().ok().and_then(udt_key),
+            Some((false, k)) if k == key
+        )
+    }
+
+    fn definitions(&self) -> &HashMap<UdtKey<'a>, TypeIndex> {
+        self.definitions.get_or_init(|| {
+            let mut map = HashMap::new();
+            let mut type_index = self.type_stream.type_index_begin();
+            for record in self.type_stream.iter_type_records() {
+                if let Some((false, key)) = record.parse().ok().and_then(udt_key) {
+                    map.entry(key).or_insert(type_index);
+                }
+                type_index.0 += 1;
+            }
+            map
+        })
+    }
+}
+
+impl HashBuckets {
+    fn new<StreamData: AsRef<[u8]>, HashData: AsRef<[u8]>>(
+        type_stream: &TypeStream<StreamData>,
+        hash_stream: &TypeHashStream<HashData>,
+    ) -> Option<Self> {
+        let hash_values = hash_stream.hash_values();
+        if hash_values.is_empty() {
+            return None;
+        }
+        if hash_values.len() != type_stream.num_types() as usize {
+            warn!(
+                "The Type Hash Stream contains {} hash values, but the Type Stream contains {} records",
+                hash_values.len(),
+                type_stream.num_types()
+            );
+            return None;
+        }
+
+        let num_buckets = hash_stream.num_hash_buckets() as usize;
+        let mut starts: Vec<u32> = vec![0; num_buckets + 1];
+        for h in hash_values.iter() {
+            let Some(count) = starts.get_mut(h.get() as usize + 1) else {
+                warn!("The Type Hash Stream contains a hash value that is out of range");
+                return None;
+            };
+            *count += 1;
+        }
+        for i in 1..starts.len() {
+            starts[i] += starts[i - 1];
+        }
+
+        // Fill each bucket in increasing type index order.
+        let mut next: Vec<u32> = starts[..num_buckets].to_vec();
+        let mut type_indexes = vec![TypeIndex(0); hash_values.len()];
+        let type_index_begin = type_stream.type_index_begin();
+        for (i, h) in hash_values.iter().enumerate() {
+            let slot = &mut next[h.get() as usize];
+            type_indexes[*slot as usize] = TypeIndex(type_index_begin.0 + i as u32);
+            *slot += 1;
+        }
+
+        Some(Self {
+            starts,
+            type_indexes,
+        })
+    }
+}
+
+/// If a record is a struct, class, union, or enum, gets whether it is a forward reference and
+/// the key used to match forward references with definitions.
+fn udt_key(data: TypeData<'_>) -> Option<(bool, UdtKey<'_>)> {
+    let (property, name, unique_name) = match data {
+        TypeData::Struct(t) => (t.fixed.property.get(), t.name, t.unique_name),
+        TypeData::Struct2(t) => (t.fixed.udt_properties(), t.name, t.unique_name),
+        TypeData::Union(t) => (t.fixed.property.get(), t.name, t.unique_name),
+        TypeData::Union2(t) => (t.fixed.udt_properties(), t.name, t.unique_name),
+        TypeData::Enum(t) => (t.fixed.property.get(), t.name, t.unique_name),
+        _ => return None,
+    };
+    let key = match unique_name {
+        Some(unique_name) if property.scoped() => UdtKey {
+            unique: true,
+            name: unique_name,
+        },
+        _ => UdtKey {
+            unique: false,
+            name,
+        },
+    };
+    Some((property.fwdref(), key))
+}
+
+#[cfg(test)]
+mod tests;
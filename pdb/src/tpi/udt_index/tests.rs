@@ -0,0 +1,191 @@
+use super::*;
+use crate::Stream;
+use crate::tpi::hash::hash_type_record;
+use crate::tpi::testing::*;
+use crate::types::Leaf;
+use crate::types::builder::{FieldListBuilder, TypeBuilder};
+use crate::types::{Struct2Fixed, StructFixed, UdtPropertiesLe};
+use zerocopy::{FromZeros, I32, U16, U32};
+
+const FWDREF: u16 = 1 << 7;
+const SCOPED_UNIQUE: u16 = (1 << 8) | (1 << 9);
+
+/// Builds a Type Hash Stream for `tpi`, using a small number of buckets so that lookups need to
+/// skip over records that are in the same bucket.
+fn build_hash_stream(
+    tpi: &TypeStream<Vec<u8>>,
+    adjusters: &[(u32, TypeIndex)],
+) -> TypeHashStream<Vec<u8>> {
+    const NUM_BUCKETS: u32 = 3;
+
+    let mut data: Vec<u8> = Vec::new();
+    for record in tpi.iter_type_records() {
+        let h = hash_type_record(record.kind, record.data, record.data).unwrap();
+        data.extend_from_slice(&(h % NUM_BUCKETS).to_le_bytes());
+    }
+    let hash_values_len = data.len();
+
+    // A single index offset entry, for the first record.
+    data.extend_from_slice(&tpi.type_index_begin().0.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+
+    let adj_offset = data.len();
+    for v in [adjusters.len() as u32, 4, 1, (1 << adjusters.len()) - 1, 0] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    for &(name, ti) in adjusters {
+        data.extend_from_slice(&name.to_le_bytes());
+        data.extend_from_slice(&ti.0.to_le_bytes());
+    }
+
+    let mut header = tpi.header().unwrap().clone();
+    header.hash_key_size = U32::new(4);
+    header.num_hash_buckets = U32::new(NUM_BUCKETS);
+    header.hash_value_buffer_offset = I32::new(0);
+    header.hash_value_buffer_length = U32::new(hash_values_len as u32);
+    header.index_offset_buffer_offset = I32::new(hash_values_len as i32);
+    header.index_offset_buffer_length = U32::new(8);
+    header.hash_adj_buffer_offset = I32::new(adj_offset as i32);
+    header.hash_adj_buffer_length = U32::new((data.len() - adj_offset) as u32);
+
+    TypeHashStream::parse(&header, data).unwrap()
+}
+
+fn build_types() -> (TypeStream<Vec<u8>>, [TypeIndex; 7]) {
+    let mut t = TypeBuilder::new();
+    let a_fwd = udt(&mut t, Leaf::LF_STRUCTURE, "A", TypeIndex(0), 0, FWDREF);
+    let b_fwd = udt(&mut t, Leaf::LF_CLASS, "B", TypeIndex(0), 0, FWDREF);
+    let u_fwd = udt(&mut t, Leaf::LF_UNION, "U", TypeIndex(0), 0, FWDREF);
+    let mut fields = FieldListBuilder::new();
+    fields.member(PUBLIC, TypeIndex::T_INT4, 0, "x".into());
+    let fields = t.field_list(&fields);
+    let a = udt(&mut t, Leaf::LF_STRUCTURE, "A", fields, 4, 0);
+    let u = udt(&mut t, Leaf::LF_UNION, "U", fields, 4, 0);
+    for name in ["C", "D", "E", "F", "G"] {
+        udt(&mut t, Leaf::LF_STRUCTURE, name, fields, 4, 0);
+    }
+    // A local type, which is identified by its unique name.
+    let mut fixed = StructFixed::new_zeroed();
+    fixed.property = UdtPropertiesLe(U16::new(SCOPED_UNIQUE));
+    fixed.field_list = fields.into();
+    let local = t.struct_(
+        Leaf::LF_STRUCTURE,
+        &fixed,
+        4,
+        "A".into(),
+        Some(".?AUA@?1??f@@".into()),
+    );
+    let a2 = udt(&mut t, Leaf::LF_STRUCTURE, "A", fields, 4, 0);
+    (
+        type_stream(Stream::TPI, &t.finish()),
+        [a_fwd, b_fwd, u_fwd, a, u, local, a2],
+    )
+}
+
+fn check_lookups(index: &UdtIndex<'_, Vec<u8>>, types: [TypeIndex; 7], expected_a: TypeIndex) {
+    let [a_fwd, b_fwd, u_fwd, a, u, local, _] = types;
+    assert_eq!(index.resolve_forward_ref(a_fwd), Some(expected_a));
+    assert_eq!(index.resolve_forward_ref(u_fwd), Some(u));
+    assert_eq!(index.resolve_forward_ref(b_fwd), None);
+    assert_eq!(index.resolve_forward_ref(a), Some(a));
+    assert_eq!(index.resolve_forward_ref(TypeIndex::T_INT4), None);
+    assert_eq!(index.find_udt_by_name("A".into()), Some(expected_a));
+    assert_eq!(index.find_udt_by_name("G".into()), Some(TypeIndex(a.0 + 6)));
+    assert_eq!(index.find_udt_by_name("B".into()), None);
+    assert_eq!(
+        index.find_udt_by_unique_name(".?AUA@?1??f@@".into()),
+        Some(local)
+    );
+    assert_eq!(index.find_udt_by_unique_name("A".into()), None);
+}
+
+#[test]
+fn without_hash_stream() {
+    let (tpi, types) = build_types();
+    let index = UdtIndex::new(&tpi);
+    assert!(!index.uses_hash_stream());
+    check_lookups(&index, types, types[3]);
+}
+
+#[test]
+fn with_hash_stream() {
+    let (tpi, types) = build_types();
+    let hashes = build_hash_stream(&tpi, &[]);
+    assert_eq!(hashes.hash_values().len(), tpi.num_types() as usize);
+    assert_eq!(hashes.index_offsets().len(), 1);
+    assert!(hashes.hash_adjusters().is_empty());
+
+    let index = UdtIndex::with_hash_stream(&tpi, &hashes);
+    assert!(index.uses_hash_stream());
+    check_lookups(&index, types, types[3]);
+}
+
+#[test]
+fn hash_adjusters() {
+    let (tpi, types) = build_types();
+    let a2 = types[6];
+    let hashes = build_hash_stream(&tpi, &[(1, a2)]);
+    assert_eq!(
+        hashes.hash_adjusters(),
+        &[crate::tpi::hash::HashAdjuster {
+            name: crate::names::NameIndex(1),
+            type_index: a2,
+        }]
+    );
+
+    #[rustfmt::skip]
+    let names_data: &[u8] = &[
+        0xfe, 0xef, 0xfe, 0xef, // signature
+        1, 0, 0, 0,             // version
+        4, 0, 0, 0,             // strings_size
+        0, b'A', 0, 0,          // (ni 0x0001) "A"
+        0, 0, 0, 0,             // num_hashes
+        1, 0, 0, 0,             // num_strings
+    ];
+    let names = NamesStream::parse(names_data).unwrap();
+    let index = UdtIndex::with_hash_stream(&tpi, &hashes)
+        .hash_adjusters(&hashes, &names)
+        .unwrap();
+    check_lookups(&index, types, a2);
+}
+
+/// Adds an `LF_STRUCTURE2` record, which has a 32-bit property field.
+fn struct2(
+    t: &mut TypeBuilder,
+    name: &str,
+    fields: TypeIndex,
+    size: u64,
+    property: u32,
+) -> TypeIndex {
+    let mut fixed = Struct2Fixed::new_zeroed();
+    fixed.property = U32::new(property);
+    fixed.field_list = fields.into();
+    t.struct2(Leaf::LF_STRUCTURE2, &fixed, size, name.into(), None)
+}
+
+#[test]
+fn struct2_forward_refs() {
+    let mut t = TypeBuilder::new();
+    let fwd = struct2(&mut t, "S2", TypeIndex(0), 0, FWDREF as u32 | 0x1_0000);
+    let mut fields = FieldListBuilder::new();
+    fields.member(0, TypeIndex::T_INT4, 0, "x".into());
+    let fields = t.field_list(&fields);
+    let def = struct2(&mut t, "S2", fields, 4, 0x1_0000);
+    let tpi = type_stream(Stream::TPI, &t.finish());
+
+    // The definition hashes by name, so it can be found through the hash stream.
+    let hashes = build_hash_stream(&tpi, &[]);
+    for index in [
+        UdtIndex::new(&tpi),
+        UdtIndex::with_hash_stream(&tpi, &hashes),
+    ] {
+        assert_eq!(index.resolve_forward_ref(fwd), Some(def));
+        assert_eq!(index.find_udt_by_name("S2".into()), Some(def));
+    }
+
+    let record = tpi.record(def).unwrap();
+    assert_eq!(
+        hash_type_record(record.kind, record.data, record.data).unwrap(),
+        crate::hash::hash_u32("S2".as_bytes())
+    );
+}
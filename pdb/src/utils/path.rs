@@ -46,3 +46,74 @@ fn test_path_contains() {
     assert!(!path_contains(r"d:\src", r"e:\src\foo.c"));
     assert!(!path_contains(r"d:\src", r"d:\bar"));
 }
+
+fn is_separator(c: u8) -> bool {
+    c == b'\\' || c == b'/'
+}
+
+/// Normalizes the path of a source file, so that paths can be compared.
+///
+/// This converts ASCII letters to lowercase, converts `/` to `\`, and removes repeated and
+/// trailing separators. Source file paths in PDBs are usually Windows paths, so this is done
+/// regardless of the host platform.
+pub fn normalize_source_path(path: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(path.len());
+    for &c in path {
+        if is_separator(c) {
+            if out.last() != Some(&b'\\') {
+                out.push(b'\\');
+            }
+        } else {
+            out.push(c.to_ascii_lowercase());
+        }
+    }
+    if out.len() > 1 && out.last() == Some(&b'\\') {
+        out.pop();
+    }
+    out
+}
+
+/// Tests whether the path of a source file matches `query`, after normalizing both paths with
+/// [`normalize_source_path`].
+///
+/// The paths match if they are equal, or if `query` is a relative path that names the last
+/// components of `path`. For example, `src/foo.c` and `foo.c` both match `d:\src\foo.c`, but
+/// `rc\foo.c` does not.
+pub fn source_path_matches(path: &[u8], query: &[u8]) -> bool {
+    let path = normalize_source_path(path);
+    let query = normalize_source_path(query);
+    if query.is_empty() {
+        return false;
+    }
+    if path == query {
+        return true;
+    }
+    if query[0] == b'\\' || query.get(1) == Some(&b':') {
+        return false;
+    }
+    path.len() > query.len()
+        && path.ends_with(&query)
+        && path[path.len() - query.len() - 1] == b'\\'
+}
+
+#[test]
+fn test_normalize_source_path() {
+    assert_eq!(normalize_source_path(br"D:\Src\Foo.c"), br"d:\src\foo.c");
+    assert_eq!(normalize_source_path(b"d:/src//foo.c"), br"d:\src\foo.c");
+    assert_eq!(normalize_source_path(br"d:\src\"), br"d:\src");
+    assert_eq!(normalize_source_path(b"/"), br"\");
+}
+
+#[test]
+fn test_source_path_matches() {
+    assert!(source_path_matches(br"d:\src\foo.c", br"D:\SRC\FOO.C"));
+    assert!(source_path_matches(br"d:\src\foo.c", b"d:/src/foo.c"));
+    assert!(source_path_matches(br"d:\src\foo.c", b"foo.c"));
+    assert!(source_path_matches(br"d:\src\foo.c", b"src/foo.c"));
+
+    // negative cases
+    assert!(!source_path_matches(br"d:\src\foo.c", b"rc/foo.c"));
+    assert!(!source_path_matches(br"d:\src\foo.c", br"e:\src\foo.c"));
+    assert!(!source_path_matches(br"d:\src\foo.c", br"\src\foo.c"));
+    assert!(!source_path_matches(br"d:\src\foo.c", b""));
+}
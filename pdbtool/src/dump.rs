@@ -7,7 +7,7 @@ use ms_pdb::codeview::parser::Parser;
 use ms_pdb::dbi::optional_dbg::OptionalDebugHeaderStream;
 use ms_pdb::dbi::{DbiSourcesSubstream, DbiStream, ModuleInfo};
 use ms_pdb::names::NamesStream;
-use ms_pdb::syms::{OffsetSegment, SymData, SymIter, SymKind};
+use ms_pdb::syms::{DebugInfoFormat, OffsetSegment, SymData, SymIter, SymKind};
 use ms_pdb::tpi::TypeStreamKind;
 use ms_pdb::types::TypeIndex;
 use ms_pdb::{Pdb, Stream};
@@ -412,6 +412,26 @@ fn dump_modules(pdb: &Pdb, dbi: &DbiStream, args: ModulesOptions) -> Result<()>
                                         compile.fixed.ver_build.get(),
                                         compile.fixed.ver_qfe.get()
                                     );
+                                    println!(
+                                        "        Language:           {}",
+                                        compile.fixed.language()
+                                    );
+                                    println!(
+                                        "        Toolchain:          {:?}",
+                                        compile.toolchain()
+                                    );
+                                    println!(
+                                        "        Flags:              {:?}",
+                                        compile.fixed.compile_flags()
+                                    );
+                                    let format = DebugInfoFormat::new(
+                                        compile.fixed.compile_flags(),
+                                        module.header().type_server_index() != 0,
+                                    );
+                                    println!(
+                                        "        Debug info:         {}",
+                                        format.option().unwrap_or("none")
+                                    );
                                 }
 
                                 _ => {}
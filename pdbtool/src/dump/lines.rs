@@ -1,6 +1,9 @@
 use super::*;
 use crate::dump_utils::HexStr;
-use ms_pdb::lines::{FileChecksum, FileChecksumsSubsection, LinesSubsection, SubsectionKind};
+use ms_pdb::lines::{
+    CrossScopeExportsSubsection, CrossScopeImportsSubsection, CrossScopeResolver, FileChecksum,
+    FileChecksumsSubsection, LinesSubsection, SubsectionKind,
+};
 use std::collections::HashMap;
 
 /// Dumps C13 Line Data for a given module.
@@ -136,6 +139,33 @@ fn dump_module_lines(
                 }
             }
 
+            SubsectionKind::CROSSSCOPEIMPORTS => {
+                let resolver = CrossScopeResolver::new(pdb);
+                let imports = CrossScopeImportsSubsection::parse(subsection.data)?;
+                for (i, import) in imports.iter().enumerate() {
+                    let module_name = names.get_string(import.module_name())?;
+                    print!("  import {i}: {module_name}");
+                    match resolver.find_module(module_name)? {
+                        Some(module_index) => println!(" (module {module_index})"),
+                        None => println!(" (module not found)"),
+                    }
+                    for local_id in import.local_ids.iter() {
+                        println!("    local id 0x{:08x}", local_id.get());
+                    }
+                }
+            }
+
+            SubsectionKind::CROSSSCOPEEXPORTS => {
+                let exports = CrossScopeExportsSubsection::parse(subsection.data)?;
+                for export in exports.entries.iter() {
+                    println!(
+                        "  export: local id 0x{:08x} -> global id 0x{:08x}",
+                        export.local_id.get(),
+                        export.global_id.get()
+                    );
+                }
+            }
+
             _ => {
                 println!("{:?}", HexDump::new(subsection.data).max(0x200));
             }
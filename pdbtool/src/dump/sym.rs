@@ -2,7 +2,7 @@ use super::*;
 use crate::dump_utils::indent;
 use anyhow::bail;
 use ms_pdb::codeview::arch::{Arch, ArchReg};
-use ms_pdb::syms::SymData;
+use ms_pdb::syms::{MetadataToken, SymData};
 use ms_pdb::tpi::TypeStream;
 use ms_pdb::types::ItemId;
 use tracing::warn;
@@ -40,6 +40,25 @@ pub fn dump_sym(
         }
     }
 
+    /// The `S_MAN*` records contain a metadata token where the `S_ATTR_*` records contain a type
+    /// index.
+    fn attr_ty_ref(
+        out: &mut dyn std::fmt::Write,
+        context: &super::sym::DumpSymsContext,
+        kind: SymKind,
+        ty: TypeIndex,
+    ) {
+        match kind {
+            SymKind::S_MANSLOT
+            | SymKind::S_MANREGISTER
+            | SymKind::S_MANFRAMEREL
+            | SymKind::S_MANREGREL
+            | SymKind::S_MANMANYREG
+            | SymKind::S_MANMANYREG2 => _ = write!(out, "Token {}", MetadataToken(ty.0)),
+            _ => ty_ref(out, context, ty),
+        }
+    }
+
     if context.scope_depth == 0 && kind.starts_scope() {
         writeln!(out)?;
     }
@@ -140,7 +159,7 @@ pub fn dump_sym(
         }
 
         SymData::ManagedProc(proc) => {
-            write!(out, "Token 0x{:x} {}", proc.fixed.token.get(), proc.name)?;
+            write!(out, "Token {} {}", proc.fixed.token(), proc.name)?;
         }
 
         SymData::End => {}
@@ -306,6 +325,208 @@ pub fn dump_sym(
                 section.name
             )?;
         }
+
+        SymData::Compile2(compile2) => {
+            write!(out, "{}", compile2.name)?;
+        }
+
+        SymData::EnvBlock(env) => {
+            writeln!(out)?;
+            for s in env.iter_strings() {
+                writeln!(out, "    {s}")?;
+            }
+        }
+
+        SymData::Register(r) => {
+            let reg = ArchReg::new(context.arch, r.fixed.register.get());
+            write!(out, "{reg}, ")?;
+            ty_ref(out, context, r.fixed.ty.get());
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::BpRel(b) => {
+            write!(out, "bp + {}, ", b.fixed.offset.get())?;
+            ty_ref(out, context, b.fixed.ty.get());
+            write!(out, " {}", b.name)?;
+        }
+
+        SymData::SepCode(sep) => {
+            write!(
+                out,
+                "{} ..+ 0x{:x}, parent: {}",
+                sep.offset_segment(),
+                sep.length.get(),
+                sep.parent_offset_segment()
+            )?;
+        }
+
+        SymData::Export(e) => {
+            write!(
+                out,
+                "ordinal {}, {:?} {}",
+                e.fixed.ordinal.get(),
+                e.flags(),
+                e.name
+            )?;
+        }
+
+        SymData::FileStatic(f) => {
+            ty_ref(out, context, f.fixed.ty.get());
+            write!(out, " {}", f.name)?;
+        }
+
+        SymData::Slot(slot) => {
+            write!(out, "slot {}, ", slot.fixed.slot.get())?;
+            ty_ref(out, context, slot.fixed.ty.get());
+            write!(out, " {}", slot.name)?;
+        }
+
+        SymData::ManyReg(r) => {
+            for &reg in r.regs.iter() {
+                write!(out, "{} ", ArchReg::new(context.arch, reg as u16))?;
+            }
+            ty_ref(out, context, r.ty);
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::ManyReg2(r) => {
+            for reg in r.regs.iter() {
+                write!(out, "{} ", ArchReg::new(context.arch, reg.get()))?;
+            }
+            ty_ref(out, context, r.ty);
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::DefRangeSubField(r) => {
+            write!(
+                out,
+                "program: {}, offset in parent: {}",
+                r.fixed.program.get(),
+                r.fixed.offset_parent.get()
+            )?;
+        }
+
+        SymData::PogoData(pogo) => {
+            write!(
+                out,
+                "invocations: {}, dynamic count: {}",
+                pogo.invocations.get(),
+                pogo.dynamic_count.get()
+            )?;
+        }
+
+        SymData::ModTypeRef(r) => {
+            write!(
+                out,
+                "{:?} 0x{:x} 0x{:x}",
+                r.flags(),
+                r.word0.get(),
+                r.word1.get()
+            )?;
+        }
+
+        SymData::RefMiniPdb(r) => {
+            write!(
+                out,
+                "module {}, {:?} {}",
+                r.fixed.module.get(),
+                r.flags(),
+                r.name
+            )?;
+        }
+
+        SymData::PdbMap(map) => {
+            write!(out, "{} -> {}", map.from, map.to)?;
+        }
+
+        SymData::FastLink(f) => {
+            ty_ref(out, context, f.fixed.ty.get());
+            write!(out, " {}", f.name)?;
+        }
+
+        SymData::Discarded(d) => {
+            write!(
+                out,
+                "type {}, file 0x{:x}, line {}",
+                d.discarded_type(),
+                d.fixed.file_id.get(),
+                d.fixed.line.get()
+            )?;
+        }
+
+        SymData::VFTable32(vft) => {
+            write!(out, "{} ", vft.offset_segment)?;
+            ty_ref(out, context, vft.root.get());
+        }
+
+        SymData::DataHlsl(d) => {
+            ty_ref(out, context, d.fixed.ty.get());
+            write!(out, " {}", d.name)?;
+        }
+
+        SymData::DataHlsl32(d) => {
+            ty_ref(out, context, d.fixed.ty.get());
+            write!(out, " {}", d.name)?;
+        }
+
+        SymData::DataHlsl32Ex(d) => {
+            ty_ref(out, context, d.fixed.ty.get());
+            write!(out, " {}", d.name)?;
+        }
+
+        SymData::DefRangeHlsl(_) => {}
+
+        SymData::AttrSlot(slot) => {
+            write!(out, "slot {}, ", slot.fixed.slot.get())?;
+            attr_ty_ref(out, context, kind, slot.fixed.ty.get());
+            write!(out, " {}", slot.name)?;
+        }
+
+        SymData::AttrRegister(r) => {
+            let reg = ArchReg::new(context.arch, r.fixed.register.get());
+            write!(out, "{reg}, ")?;
+            attr_ty_ref(out, context, kind, r.fixed.ty.get());
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::AttrFrameRel(r) => {
+            write!(out, "frame + {}, ", r.fixed.offset.get())?;
+            attr_ty_ref(out, context, kind, r.fixed.ty.get());
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::AttrRegRel(r) => {
+            let reg = ArchReg::new(context.arch, r.fixed.register.get());
+            write!(out, "{reg} + {}, ", r.fixed.offset.get())?;
+            attr_ty_ref(out, context, kind, r.fixed.ty.get());
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::AttrManyReg(r) => {
+            for &reg in r.regs.iter() {
+                write!(out, "{} ", ArchReg::new(context.arch, reg as u16))?;
+            }
+            attr_ty_ref(out, context, kind, r.ty);
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::AttrManyReg2(r) => {
+            for reg in r.regs.iter() {
+                write!(out, "{} ", ArchReg::new(context.arch, reg.get()))?;
+            }
+            attr_ty_ref(out, context, kind, r.ty);
+            write!(out, " {}", r.name)?;
+        }
+
+        SymData::Oem(oem) => {
+            write!(out, "oem id: ")?;
+            for b in oem.fixed.oem_id.iter() {
+                write!(out, "{b:02x}")?;
+            }
+            write!(out, ", ")?;
+            ty_ref(out, context, oem.fixed.ty.get());
+            write!(out, ", {} bytes", oem.data.len())?;
+        }
     }
 
     writeln!(out)?;
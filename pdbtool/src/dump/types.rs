@@ -1,6 +1,6 @@
 use super::*;
 use ms_pdb::names::NameIndex;
-use ms_pdb::tpi::TypeStreamKind;
+use ms_pdb::tpi::{TypeFormatter, TypeStreamKind};
 use ms_pdb::types::fields::Field;
 use ms_pdb::types::primitive::dump_primitive_type_index;
 use ms_pdb::types::{BUILD_INFO_ARG_NAMES, ItemId, Leaf, TypeData, TypeIndex, UdtProperties};
@@ -87,6 +87,7 @@ pub fn dump_type_stream(
     let mut out = String::new();
 
     let type_stream_start = type_stream.type_records_range().start;
+    let formatter = TypeFormatter::new(type_stream).calling_conventions(true);
 
     for (record_range, ty) in iter {
         out.clear();
@@ -106,6 +107,21 @@ pub fn dump_type_stream(
 
         print!("{out}");
 
+        // Show the C++ declaration of types that are built from other types.
+        if type_stream_kind == TypeStreamKind::TPI
+            && matches!(
+                ty.kind,
+                Leaf::LF_POINTER
+                    | Leaf::LF_MODIFIER
+                    | Leaf::LF_ARRAY
+                    | Leaf::LF_PROCEDURE
+                    | Leaf::LF_MFUNCTION
+                    | Leaf::LF_BITFIELD
+            )
+        {
+            println!("    decl: {}", formatter.format(next_type_index));
+        }
+
         next_type_index.0 += 1;
 
         num_found += 1;
@@ -175,6 +191,21 @@ pub fn dump_type_record(
             }
         }
 
+        TypeData::Struct2(t) => {
+            out_udt_props(out, t.fixed.udt_properties())?;
+            write!(out, " {}", t.name)?;
+            let field_list = t.fixed.field_list.get();
+            if let Some(unique_name) = t.unique_name
+                && unique_name != t.name
+            {
+                write!(out, " (unique: {unique_name})")?;
+            }
+            if field_list.0 != 0 {
+                write!(out, " fields: ")?;
+                ty_ref(out, field_list)?;
+            }
+        }
+
         TypeData::Enum(t) => {
             out_udt_props(out, t.fixed.property.get())?;
             write!(out, " {}", t.name)?;
@@ -195,6 +226,16 @@ pub fn dump_type_record(
             }
         }
 
+        TypeData::Union2(t) => {
+            out_udt_props(out, t.fixed.udt_properties())?;
+            write!(out, " {}", t.name)?;
+            if let Some(unique_name) = t.unique_name
+                && unique_name != t.name
+            {
+                write!(out, " (unique: {unique_name})")?;
+            }
+        }
+
         TypeData::Unknown => {
             write!(out, "<UNKNOWN>")?;
         }
@@ -353,21 +394,16 @@ pub fn dump_type_record(
         }
 
         TypeData::UdtSrcLine(t) => {
-            let src = NameIndex(t.src.get());
             writeln!(out)?;
 
             write!(out, "    ")?;
             ty_ref(out, t.ty.get())?;
             writeln!(out)?;
 
-            let line = t.line.get();
-            if let Some(names) = names {
-                if let Ok(s) = names.get_string(src) {
-                    writeln!(out, "    (line {line:6}) {s}")?;
-                } else {
-                    writeln!(out, "    (line {line:6}) ?? {src:?}")?;
-                }
-            }
+            // The source file is an LF_STRING_ID record, not a NameIndex.
+            write!(out, "    (line {:6}) ", t.line.get())?;
+            dump_item(out, t.src.get())?;
+            writeln!(out)?;
         }
 
         TypeData::SubStrList(t) => {
@@ -406,6 +442,54 @@ pub fn dump_type_record(
                 write!(out, " ")?;
             }
         }
+
+        TypeData::VFTPath(t) => {
+            write!(out, "num_bases: {}", t.bases.len())?;
+            for &base in t.bases.iter() {
+                write!(out, ", ")?;
+                ty_ref(out, base.get())?;
+            }
+        }
+
+        TypeData::Label(t) => {
+            write!(out, "mode: {}", t.mode.get())?;
+        }
+
+        TypeData::DimArray(t) => {
+            write!(out, "{} - ", t.name)?;
+            ty_ref(out, t.fixed.element_type.get())?;
+            write!(out, " dims: ")?;
+            ty_ref(out, t.fixed.dim_info.get())?;
+        }
+
+        TypeData::Precomp(t) => {
+            write!(
+                out,
+                "{} start: {:?} count: {} signature: 0x{:08x}",
+                t.name,
+                t.fixed.start.get(),
+                t.fixed.count.get(),
+                t.fixed.signature.get()
+            )?;
+        }
+
+        TypeData::EndPrecomp(t) => {
+            write!(out, "signature: 0x{:08x}", t.signature.get())?;
+        }
+
+        TypeData::TypeServer2(t) => {
+            write!(
+                out,
+                "{} guid: {} age: {}",
+                t.name,
+                t.fixed.guid(),
+                t.fixed.age.get()
+            )?;
+        }
+
+        TypeData::Managed(t) => {
+            write!(out, "{}", t.name)?;
+        }
     }
 
     writeln!(out)?;
@@ -454,8 +538,10 @@ pub fn dump_type_index_short(
         }
 
         TypeData::Struct(t) => write!(out, "{}", t.name)?,
+        TypeData::Struct2(t) => write!(out, "{}", t.name)?,
         TypeData::Enum(t) => write!(out, "{}", t.name)?,
         TypeData::Union(t) => write!(out, "{}", t.name)?,
+        TypeData::Union2(t) => write!(out, "{}", t.name)?,
         TypeData::Unknown => write!(out, "<UNKNOWN>")?,
 
         TypeData::Pointer(t) => {
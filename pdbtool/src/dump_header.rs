@@ -0,0 +1,50 @@
+use anyhow::Result;
+use ms_pdb::tpi::HeaderGenerator;
+use std::path::Path;
+
+/// Generates a C/C++ header that defines the structs, classes, unions, and enums in the TPI
+/// Stream.
+#[derive(clap::Parser)]
+pub struct DumpHeaderOptions {
+    /// The PDB to read.
+    pub pdb: String,
+
+    /// Only include UDTs whose name matches this regex, and the UDTs that they depend on.
+    /// If not specified, all UDTs are included.
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Emit `static_assert` declarations that check the size of each UDT and the offset of each
+    /// member against the layout stored in the PDB.
+    #[arg(long)]
+    pub static_asserts: bool,
+
+    /// Write the header to this file, instead of stdout.
+    #[arg(long, short)]
+    pub output: Option<String>,
+}
+
+pub fn command(options: DumpHeaderOptions) -> Result<()> {
+    let pdb = ms_pdb::Pdb::open(Path::new(&options.pdb))?;
+    let tpi = pdb.read_type_stream()?;
+
+    let rx = match &options.name {
+        Some(name) => Some(regex::bytes::Regex::new(name)?),
+        None => None,
+    };
+
+    let mut generator = HeaderGenerator::new(&tpi).static_asserts(options.static_asserts);
+    generator.add_types_where(|name| match &rx {
+        Some(rx) => rx.is_match(name),
+        None => true,
+    });
+    let header = generator.generate();
+
+    if let Some(output) = &options.output {
+        std::fs::write(output, header)?;
+    } else {
+        print!("{header}");
+    }
+
+    Ok(())
+}
@@ -15,12 +15,15 @@ mod container;
 mod copy;
 mod counts;
 mod dump;
+mod dump_header;
 mod dump_utils;
 mod find;
 mod glob_pdbs;
 mod hexdump;
 mod pdz;
 mod save;
+mod strip_types;
+mod type_diff;
 mod util;
 
 #[derive(clap::Parser)]
@@ -63,6 +66,7 @@ enum Command {
     Compare(compare::CompareOptions),
     Test,
     Dump(dump::DumpOptions),
+    DumpHeader(dump_header::DumpHeaderOptions),
     Save(save::SaveStreamOptions),
     Find(find::FindOptions),
     FindName(find::FindNameOptions),
@@ -72,6 +76,8 @@ enum Command {
     Hexdump(hexdump::HexdumpOptions),
     PdzEncode(pdz::encode::PdzEncodeOptions),
     Check(check::CheckOptions),
+    StripTypes(strip_types::StripTypesOptions),
+    TypeDiff(type_diff::TypeDiffOptions),
 }
 
 fn main() -> anyhow::Result<()> {
@@ -81,6 +87,7 @@ fn main() -> anyhow::Result<()> {
     match command_with_flags.command {
         Command::AddSrc(args) => addsrc::command(args)?,
         Command::Dump(args) => dump::dump_main(args)?,
+        Command::DumpHeader(args) => dump_header::command(args)?,
         Command::Test => {}
         Command::Copy(args) => copy::copy_command(&args)?,
         Command::Save(args) => save::save_stream(&args)?,
@@ -92,6 +99,8 @@ fn main() -> anyhow::Result<()> {
         Command::Container(args) => container::container_command(&args)?,
         Command::Compare(args) => compare::command(args)?,
         Command::Check(args) => check::command(args)?,
+        Command::StripTypes(args) => strip_types::command(args)?,
+        Command::TypeDiff(args) => type_diff::command(args)?,
     }
 
     Ok(())
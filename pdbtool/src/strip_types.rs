@@ -0,0 +1,75 @@
+use anyhow::Result;
+use ms_pdb::tpi::compact::{CompactTypesOptions, StreamSizes};
+use std::path::Path;
+
+/// Removes type records (and optionally ID records) that are not used by any symbol, and
+/// renumbers the remaining records. Symbols and line data are updated to use the new type
+/// indexes.
+#[derive(clap::Parser)]
+pub struct StripTypesOptions {
+    /// The PDB to modify.
+    pub pdb: String,
+
+    /// Keep the UDT with this name, even if no symbol uses it. May be specified more than once.
+    #[arg(long)]
+    pub keep: Vec<String>,
+
+    /// Also remove ID records (in the IPI) that are not used by any symbol or line data.
+    #[arg(long)]
+    pub strip_ids: bool,
+
+    /// Show what would be removed, but do not modify the PDB.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+pub fn command(options: StripTypesOptions) -> Result<()> {
+    let compact_options = CompactTypesOptions {
+        root_names: options.keep.iter().map(|s| s.as_str().into()).collect(),
+        strip_ids: options.strip_ids,
+    };
+
+    if options.dry_run {
+        let pdb = ms_pdb::Pdb::open(Path::new(&options.pdb))?;
+        let compaction = pdb.plan_type_compaction(&compact_options)?;
+        show_report("TPI", &compaction.report().tpi);
+        show_report("IPI", &compaction.report().ipi);
+        println!("Dry run. The PDB was not modified.");
+        return Ok(());
+    }
+
+    let mut pdb = ms_pdb::Pdb::modify(Path::new(&options.pdb))?;
+    let compaction = pdb.plan_type_compaction(&compact_options)?;
+    show_report("TPI", &compaction.report().tpi);
+    show_report("IPI", &compaction.report().ipi);
+
+    pdb.apply_type_compaction(&compaction)?;
+    pdb.flush_all()?;
+    if pdb.msf_mut_err()?.commit()? {
+        println!("Changes successfully committed to PDB.");
+    } else {
+        println!("No changes were written to disk.");
+    }
+
+    Ok(())
+}
+
+fn show_report(name: &str, sizes: &StreamSizes) {
+    let percent = |before: u64, after: u64| {
+        if before == 0 {
+            0.0
+        } else {
+            100.0 * (before - after) as f64 / before as f64
+        }
+    };
+
+    println!(
+        "{name}: {:>10} -> {:>10} records ({:5.1}% removed), {:>12} -> {:>12} bytes ({:5.1}% removed)",
+        sizes.records_before,
+        sizes.records_after,
+        percent(sizes.records_before as u64, sizes.records_after as u64),
+        sizes.bytes_before,
+        sizes.bytes_after,
+        percent(sizes.bytes_before, sizes.bytes_after),
+    );
+}
@@ -0,0 +1,132 @@
+use anyhow::{Result, bail};
+use ms_pdb::tpi::TypeDiff;
+use ms_pdb::tpi::diff::{BaseShape, MemberShape, UdtChange, UdtDiff, VirtualMethod};
+use std::path::Path;
+
+/// Compares the structs, classes, and unions of two PDBs, ignoring differences in type index
+/// numbering. Reports added and removed UDTs, and changes to the members, sizes, base classes,
+/// and virtual function tables of UDTs.
+#[derive(clap::Parser)]
+pub struct TypeDiffOptions {
+    /// The old PDB.
+    pub old_pdb: String,
+
+    /// The new PDB.
+    pub new_pdb: String,
+
+    /// Only compare the UDT with this name.
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Exit with an error if any differences are found.
+    #[arg(long)]
+    pub check: bool,
+}
+
+pub fn command(options: TypeDiffOptions) -> Result<()> {
+    let old_pdb = ms_pdb::Pdb::open(Path::new(&options.old_pdb))?;
+    let new_pdb = ms_pdb::Pdb::open(Path::new(&options.new_pdb))?;
+    let old_tpi = old_pdb.read_type_stream()?;
+    let new_tpi = new_pdb.read_type_stream()?;
+    let diff = TypeDiff::new(&old_tpi, &new_tpi);
+
+    let same = if let Some(name) = &options.name {
+        let udt = diff.diff_udt(name.as_str().into())?;
+        show_udt_diff(&udt);
+        udt.is_empty()
+    } else {
+        let report = diff.diff_all()?;
+        for name in report.removed.iter() {
+            println!("removed: {name}");
+        }
+        for name in report.added.iter() {
+            println!("added: {name}");
+        }
+        for udt in report.changed.iter() {
+            show_udt_diff(udt);
+        }
+        for name in report.unparseable.iter() {
+            println!("unparseable: {name}");
+        }
+        println!(
+            "{} added, {} removed, {} changed, {} unparseable",
+            report.added.len(),
+            report.removed.len(),
+            report.changed.len(),
+            report.unparseable.len()
+        );
+        report.is_empty()
+    };
+
+    if options.check && !same {
+        bail!("Type definitions are different");
+    }
+
+    Ok(())
+}
+
+fn show_udt_diff(udt: &UdtDiff) {
+    if udt.is_empty() {
+        println!("same: {}", udt.name);
+        return;
+    }
+
+    println!("changed: {}", udt.name);
+    for change in udt.changes.iter() {
+        match change {
+            UdtChange::KindChanged { old, new } => println!("    kind: {old:?} -> {new:?}"),
+            UdtChange::SizeChanged { old, new } => println!("    size: {old} -> {new}"),
+            UdtChange::BasesChanged { old, new } => {
+                println!("    bases: {} -> {}", bases(old), bases(new))
+            }
+            UdtChange::MemberAdded(m) => println!("    + {}", member(m)),
+            UdtChange::MemberRemoved(m) => println!("    - {}", member(m)),
+            UdtChange::MemberRetyped { old, new } => {
+                println!("    {}: type {} -> {}", old.name, old.ty, new.ty)
+            }
+            UdtChange::MemberMoved { old, new } => {
+                println!("    {}: {} -> {}", old.name, location(old), location(new))
+            }
+            UdtChange::VTableChanged { old, new } => {
+                println!("    vtable: {} -> {}", vtable(old), vtable(new))
+            }
+        }
+    }
+}
+
+fn member(m: &MemberShape) -> String {
+    format!("{}: {} {}", m.name, m.ty, location(m))
+}
+
+fn location(m: &MemberShape) -> String {
+    match m.bitfield {
+        Some(b) => format!("at {} bit {}", m.offset, b.position),
+        None => format!("at {}", m.offset),
+    }
+}
+
+fn bases(bases: &[BaseShape]) -> String {
+    if bases.is_empty() {
+        return "(none)".to_string();
+    }
+    let bases: Vec<String> = bases
+        .iter()
+        .map(|b| match b.offset {
+            Some(offset) => format!("{} at {offset}", b.ty),
+            None if b.is_virtual => format!("virtual {}", b.ty),
+            None => b.ty.clone(),
+        })
+        .collect();
+    bases.join(", ")
+}
+
+fn vtable(methods: &[VirtualMethod]) -> String {
+    if methods.is_empty() {
+        return "(none)".to_string();
+    }
+    let methods: Vec<String> = methods
+        .iter()
+        .map(|m| format!("{} at {}", m.name, m.vtable_offset))
+        .collect();
+    methods.join(", ")
+}